@@ -0,0 +1,41 @@
+-- Notification types emitted by the backend
+CREATE TYPE notification_type AS ENUM (
+    'new_follower',
+    'clap',
+    'comment',
+    'reply',
+    'mention',
+    'submission_review'
+);
+
+-- In-app notifications inbox
+CREATE TABLE IF NOT EXISTS notifications (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    notification_type notification_type NOT NULL,
+    article_id UUID REFERENCES articles(id) ON DELETE CASCADE,
+    comment_id UUID REFERENCES comments(id) ON DELETE CASCADE,
+    publication_id UUID REFERENCES publications(id) ON DELETE CASCADE,
+    submission_id UUID REFERENCES publication_submissions(id) ON DELETE CASCADE,
+    -- Number of events folded into this row (claps are aggregated per article)
+    aggregate_count INTEGER NOT NULL DEFAULT 1 CHECK (aggregate_count >= 1),
+    is_read BOOLEAN NOT NULL DEFAULT FALSE,
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_user_id ON notifications(user_id, updated_at DESC);
+CREATE INDEX IF NOT EXISTS idx_notifications_unread ON notifications(user_id) WHERE is_read = FALSE;
+CREATE INDEX IF NOT EXISTS idx_notifications_article_id ON notifications(article_id);
+
+-- Per-type notification preferences (missing rows mean "enabled")
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    notification_type notification_type NOT NULL,
+    in_app_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    email_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, notification_type)
+);
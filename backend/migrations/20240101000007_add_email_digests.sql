@@ -0,0 +1,27 @@
+-- How often a user receives the email digest
+CREATE TYPE digest_frequency AS ENUM ('off', 'daily', 'weekly');
+
+-- Per-user digest settings
+CREATE TABLE IF NOT EXISTS digest_settings (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    frequency digest_frequency NOT NULL DEFAULT 'weekly',
+    -- Lets the recipient unsubscribe from the email without logging in
+    unsubscribe_token VARCHAR(64) NOT NULL UNIQUE
+        DEFAULT replace(gen_random_uuid()::text || gen_random_uuid()::text, '-', ''),
+    -- Start of the next digest window
+    last_sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_digest_settings_due ON digest_settings(frequency, last_sent_at);
+
+-- Tags a user follows
+CREATE TABLE IF NOT EXISTS tag_follows (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tag VARCHAR(100) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS idx_tag_follows_tag ON tag_follows(tag);
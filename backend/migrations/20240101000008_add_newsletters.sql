@@ -0,0 +1,57 @@
+-- Email newsletter: readers (with or without an account) subscribe to an author
+-- or a publication and receive every newly published article
+CREATE TYPE subscriber_status AS ENUM ('pending', 'confirmed', 'unsubscribed');
+CREATE TYPE email_delivery_status AS ENUM ('queued', 'sending', 'sent', 'failed');
+
+CREATE TABLE IF NOT EXISTS newsletter_subscribers (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    author_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    publication_id UUID REFERENCES publications(id) ON DELETE CASCADE,
+    email VARCHAR(255) NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL, -- Set when subscribed while logged in
+    status subscriber_status NOT NULL DEFAULT 'pending',
+    -- Used by both the confirmation and the unsubscribe links
+    token VARCHAR(64) NOT NULL UNIQUE
+        DEFAULT replace(gen_random_uuid()::text || gen_random_uuid()::text, '-', ''),
+    confirmed_at TIMESTAMPTZ,
+    unsubscribed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((author_id IS NULL) <> (publication_id IS NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_newsletter_subscribers_author_email
+    ON newsletter_subscribers(author_id, lower(email)) WHERE author_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_newsletter_subscribers_publication_email
+    ON newsletter_subscribers(publication_id, lower(email)) WHERE publication_id IS NOT NULL;
+
+-- One issue per published article
+CREATE TABLE IF NOT EXISTS newsletter_issues (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    article_id UUID NOT NULL UNIQUE REFERENCES articles(id) ON DELETE CASCADE,
+    recipients_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Per-recipient delivery; also the outgoing mail queue
+CREATE TABLE IF NOT EXISTS newsletter_deliveries (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    issue_id UUID NOT NULL REFERENCES newsletter_issues(id) ON DELETE CASCADE,
+    subscriber_id UUID NOT NULL REFERENCES newsletter_subscribers(id) ON DELETE CASCADE,
+    status email_delivery_status NOT NULL DEFAULT 'queued',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    sent_at TIMESTAMPTZ,
+    opened_at TIMESTAMPTZ,
+    open_count INTEGER NOT NULL DEFAULT 0,
+    clicked_at TIMESTAMPTZ,
+    click_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(issue_id, subscriber_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_newsletter_deliveries_queue
+    ON newsletter_deliveries(next_attempt_at) WHERE status IN ('queued', 'sending');
+CREATE INDEX IF NOT EXISTS idx_newsletter_deliveries_issue ON newsletter_deliveries(issue_id, status);
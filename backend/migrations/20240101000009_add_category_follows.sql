@@ -0,0 +1,9 @@
+-- Categories a user follows (tags are in tag_follows)
+CREATE TABLE IF NOT EXISTS category_follows (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    category VARCHAR(100) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, category)
+);
+
+CREATE INDEX IF NOT EXISTS idx_category_follows_category ON category_follows(category);
@@ -0,0 +1,11 @@
+-- Original URL of cross-posted articles, used as rel=canonical instead of ours
+ALTER TABLE articles ADD COLUMN IF NOT EXISTS canonical_url TEXT;
+
+-- Slugs an article was previously reachable at, so old links can redirect
+CREATE TABLE IF NOT EXISTS article_slug_history (
+    slug VARCHAR(255) PRIMARY KEY,
+    article_id UUID NOT NULL REFERENCES articles(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_article_slug_history_article ON article_slug_history(article_id);
@@ -0,0 +1,27 @@
+-- Jobs that exhausted their retries end up 'dead' (dead-letter); 'failed' now
+-- means the last attempt failed and the job waits for its retry at run_at
+ALTER TYPE job_status ADD VALUE IF NOT EXISTS 'dead';
+
+-- Each queue has its own concurrency limit
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS queue VARCHAR(50) NOT NULL DEFAULT 'default';
+
+DROP INDEX IF EXISTS idx_jobs_due;
+CREATE INDEX IF NOT EXISTS idx_jobs_due ON jobs(queue, run_at) WHERE status IN ('pending', 'failed');
+-- Waiting jobs are cancelled by kind and payload rather than by article_id
+DROP INDEX IF EXISTS idx_jobs_payload_article;
+CREATE INDEX IF NOT EXISTS idx_jobs_waiting_kind ON jobs(kind) WHERE status IN ('pending', 'failed');
+CREATE INDEX IF NOT EXISTS idx_jobs_status_created ON jobs(status, created_at DESC);
+
+-- Cron-style recurring jobs; the instance that claims a due row enqueues the job
+CREATE TABLE IF NOT EXISTS recurring_jobs (
+    name VARCHAR(100) PRIMARY KEY,
+    schedule VARCHAR(100) NOT NULL,
+    next_run_at TIMESTAMPTZ NOT NULL,
+    last_run_at TIMESTAMPTZ,
+    last_job_id UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Operators allowed to use admin endpoints such as the job dashboard
+ALTER TABLE users ADD COLUMN IF NOT EXISTS is_admin BOOLEAN NOT NULL DEFAULT FALSE;
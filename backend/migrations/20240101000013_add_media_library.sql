@@ -0,0 +1,31 @@
+-- Per-user library of uploaded article images
+CREATE TABLE IF NOT EXISTS media (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Largest variant; `variants` lists every size
+    url TEXT NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    variants JSONB NOT NULL DEFAULT '[]',
+    blurhash VARCHAR(64) NOT NULL,
+    dominant_color VARCHAR(7) NOT NULL,
+    alt_text TEXT,
+    caption TEXT,
+    -- Last time an article referenced it; unreferenced media is garbage collected
+    last_referenced_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_media_user_created ON media(user_id, created_at DESC);
+
+-- Media referenced from an article's content or featured image
+CREATE TABLE IF NOT EXISTS article_media (
+    article_id UUID NOT NULL REFERENCES articles(id) ON DELETE CASCADE,
+    media_id UUID NOT NULL REFERENCES media(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (article_id, media_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_article_media_media ON article_media(media_id);
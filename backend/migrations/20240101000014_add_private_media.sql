@@ -0,0 +1,3 @@
+-- Private media is stored under the `private/` storage prefix and only served
+-- through expiring signed URLs
+ALTER TABLE media ADD COLUMN IF NOT EXISTS is_private BOOLEAN NOT NULL DEFAULT FALSE;
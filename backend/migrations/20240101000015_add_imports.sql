@@ -0,0 +1,32 @@
+-- Article imports from other platforms, processed by the `import_articles` job
+CREATE TYPE import_source AS ENUM ('medium', 'wordpress', 'ghost');
+CREATE TYPE import_status AS ENUM ('pending', 'running', 'completed', 'failed');
+
+CREATE TABLE IF NOT EXISTS imports (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    source import_source NOT NULL,
+    status import_status NOT NULL DEFAULT 'pending',
+    -- Uploaded export in private storage; removed once the import finishes
+    file_key TEXT NOT NULL,
+    file_name TEXT NOT NULL,
+    -- Base URL of the old site (Ghost exports only contain relative links)
+    site_url TEXT,
+    -- Import everything as drafts instead of keeping published posts published
+    as_drafts BOOLEAN NOT NULL DEFAULT FALSE,
+    total_items INTEGER NOT NULL DEFAULT 0,
+    imported_count INTEGER NOT NULL DEFAULT 0,
+    skipped_count INTEGER NOT NULL DEFAULT 0,
+    failed_count INTEGER NOT NULL DEFAULT 0,
+    -- Per-post report: [{source_id, title, status, reason, article_id, warnings}]
+    items JSONB NOT NULL DEFAULT '[]',
+    error TEXT,
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_imports_user_created ON imports(user_id, created_at DESC);
+-- Re-imports skip posts whose original URL the author already has
+CREATE INDEX IF NOT EXISTS idx_articles_author_canonical ON articles(author_id, canonical_url) WHERE canonical_url IS NOT NULL;
@@ -0,0 +1,23 @@
+-- Data exports (articles as Markdown, GDPR account data), built by the `export_data` job
+CREATE TYPE export_kind AS ENUM ('articles', 'account');
+CREATE TYPE export_status AS ENUM ('pending', 'running', 'completed', 'failed');
+
+CREATE TABLE IF NOT EXISTS exports (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind export_kind NOT NULL,
+    status export_status NOT NULL DEFAULT 'pending',
+    -- Finished archive in private storage, downloaded through signed URLs
+    file_key TEXT,
+    file_size BIGINT,
+    error TEXT,
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ,
+    -- The archive is deleted after this; a new export can be requested any time
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_exports_user_created ON exports(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_exports_expires ON exports(expires_at) WHERE file_key IS NOT NULL;
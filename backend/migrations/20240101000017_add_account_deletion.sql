@@ -0,0 +1,17 @@
+-- Self-service account deletion with a grace period, carried out by the `delete_account` job
+CREATE TYPE article_deletion_action AS ENUM ('delete', 'anonymize');
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS deletion_requested_at TIMESTAMPTZ,
+    -- The account is deleted at this time unless the request is cancelled first
+    ADD COLUMN IF NOT EXISTS deletion_scheduled_at TIMESTAMPTZ,
+    -- What happens to the user's articles
+    ADD COLUMN IF NOT EXISTS deletion_article_action article_deletion_action;
+
+CREATE INDEX IF NOT EXISTS idx_users_deletion_scheduled ON users(deletion_scheduled_at) WHERE deletion_scheduled_at IS NOT NULL;
+
+-- Placeholder that comments (and anonymized articles) of deleted accounts are moved to,
+-- so threads stay intact. It cannot log in: the password hash is not a valid hash.
+INSERT INTO users (id, email, username, display_name, password_hash, is_banned)
+VALUES ('00000000-0000-0000-0000-000000000000', 'deleted@users.invalid', 'deleted', 'Deleted user', '!', TRUE)
+ON CONFLICT (id) DO NOTHING;
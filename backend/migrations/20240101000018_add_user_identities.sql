@@ -0,0 +1,33 @@
+-- Sign-in with OpenID Connect providers, linked to local accounts
+
+-- Accounts created through a provider have no password
+ALTER TABLE users ALTER COLUMN password_hash DROP NOT NULL;
+UPDATE users SET password_hash = NULL WHERE id = '00000000-0000-0000-0000-000000000000';
+
+CREATE TABLE IF NOT EXISTS user_identities (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Provider id from the OIDC_PROVIDERS configuration
+    provider VARCHAR(50) NOT NULL,
+    -- The provider's stable `sub` claim
+    subject TEXT NOT NULL,
+    email VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_login_at TIMESTAMPTZ,
+    UNIQUE (provider, subject),
+    UNIQUE (user_id, provider)
+);
+
+-- Pending authorization requests: PKCE verifier and nonce, keyed by the `state` parameter
+CREATE TABLE IF NOT EXISTS oidc_login_states (
+    state TEXT PRIMARY KEY,
+    provider VARCHAR(50) NOT NULL,
+    code_verifier TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    -- Set when a signed-in user is linking a provider rather than signing in
+    link_user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_oidc_login_states_expires ON oidc_login_states(expires_at);
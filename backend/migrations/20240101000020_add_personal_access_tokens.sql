@@ -0,0 +1,17 @@
+-- Personal access tokens for scripts and CI, sent as `Authorization: Bearer fbp_...`
+CREATE TABLE IF NOT EXISTS personal_access_tokens (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    -- SHA-256 of the token; the token itself is only shown when it's created
+    token_hash TEXT NOT NULL UNIQUE,
+    -- Start of the token, so users can tell their tokens apart
+    token_prefix VARCHAR(16) NOT NULL,
+    -- read, write:articles, write:comments, admin
+    scopes TEXT[] NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_personal_access_tokens_user ON personal_access_tokens(user_id);
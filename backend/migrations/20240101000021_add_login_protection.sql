@@ -0,0 +1,17 @@
+-- Failed sign-in tracking per account and per IP, with temporary account lockout
+
+ALTER TABLE users
+    -- Password sign-in is refused until then; admins can clear it early
+    ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS failed_logins (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    -- NULL when the email/username matched no account
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    ip_address TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_failed_logins_user ON failed_logins(user_id, created_at) WHERE user_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_failed_logins_ip ON failed_logins(ip_address, created_at);
+CREATE INDEX IF NOT EXISTS idx_failed_logins_created ON failed_logins(created_at);
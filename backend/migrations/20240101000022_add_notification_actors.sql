@@ -0,0 +1,13 @@
+-- Users folded into an aggregated notification, so each actor is counted once
+CREATE TABLE IF NOT EXISTS notification_actors (
+    notification_id UUID NOT NULL REFERENCES notifications(id) ON DELETE CASCADE,
+    actor_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (notification_id, actor_id)
+);
+
+-- Existing clap notifications count their most recent actor
+INSERT INTO notification_actors (notification_id, actor_id)
+SELECT id, actor_id FROM notifications
+WHERE notification_type = 'clap' AND actor_id IS NOT NULL
+ON CONFLICT DO NOTHING;
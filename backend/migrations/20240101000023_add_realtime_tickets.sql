@@ -0,0 +1,9 @@
+-- Short-lived, single-use tickets that authenticate an EventSource stream, so the
+-- session token never appears in a URL
+CREATE TABLE IF NOT EXISTS realtime_tickets (
+    ticket_hash TEXT PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_realtime_tickets_expires ON realtime_tickets(expires_at);
@@ -0,0 +1,7 @@
+-- Workers touch heartbeat_at while a job runs; only jobs whose heartbeat stopped
+-- (crashed instance) are claimed again, however long they have been running
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS heartbeat_at TIMESTAMPTZ;
+
+UPDATE jobs SET heartbeat_at = started_at WHERE status = 'running';
+
+CREATE INDEX IF NOT EXISTS idx_jobs_running_heartbeat ON jobs(queue, heartbeat_at) WHERE status = 'running';
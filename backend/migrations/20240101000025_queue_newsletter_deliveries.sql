@@ -0,0 +1,9 @@
+-- Newsletter deliveries are sent by `send_newsletter` jobs on the email queue, which
+-- own retries and backoff. Deliveries still waiting for the old worker get a job each.
+INSERT INTO jobs (queue, kind, payload, max_attempts)
+SELECT 'email', 'send_newsletter', jsonb_build_object('delivery_id', id), 5
+FROM newsletter_deliveries
+WHERE status IN ('queued', 'sending');
+
+DROP INDEX IF EXISTS idx_newsletter_deliveries_queue;
+ALTER TABLE newsletter_deliveries DROP COLUMN IF EXISTS next_attempt_at;
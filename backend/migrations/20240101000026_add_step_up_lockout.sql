@@ -0,0 +1,5 @@
+-- Wrong passwords and codes on the signed-in 2FA management endpoints; too many
+-- lock those checks for a while so a stolen session can't guess them
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS step_up_failures INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS step_up_locked_until TIMESTAMPTZ;
@@ -0,0 +1,14 @@
+-- Nothing reviews publication submissions yet, so the submission review notification
+-- type and its reference column are dropped until that workflow exists
+DELETE FROM notification_preferences WHERE notification_type = 'submission_review';
+DELETE FROM notifications WHERE notification_type = 'submission_review';
+
+ALTER TABLE notifications DROP COLUMN IF EXISTS submission_id;
+
+ALTER TYPE notification_type RENAME TO notification_type_old;
+CREATE TYPE notification_type AS ENUM ('new_follower', 'clap', 'comment', 'reply', 'mention');
+ALTER TABLE notifications
+    ALTER COLUMN notification_type TYPE notification_type USING notification_type::text::notification_type;
+ALTER TABLE notification_preferences
+    ALTER COLUMN notification_type TYPE notification_type USING notification_type::text::notification_type;
+DROP TYPE notification_type_old;
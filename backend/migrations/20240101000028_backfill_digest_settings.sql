@@ -0,0 +1,4 @@
+-- Digest settings are now created at registration; give existing users theirs
+INSERT INTO digest_settings (user_id)
+SELECT id FROM users
+ON CONFLICT (user_id) DO NOTHING;
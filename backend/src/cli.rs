@@ -0,0 +1,140 @@
+use std::error::Error;
+
+use crate::config::{Config, StorageBackend};
+use crate::database::Database;
+use crate::services::storage::{self, Storage};
+
+// Columns holding a single file URL
+const URL_COLUMNS: &[(&str, &str)] = &[
+    ("users", "avatar_url"),
+    ("publications", "logo_url"),
+    ("publications", "banner_url"),
+    ("articles", "featured_image_url"),
+    ("media", "url"),
+];
+
+// Columns with file URLs embedded in text (Markdown, HTML, JSON)
+const TEXT_COLUMNS: &[(&str, &str)] = &[
+    ("articles", "content"),
+    ("articles", "content_html"),
+    ("media", "variants"),
+];
+
+const USAGE: &str = "Usage: fastblog-backend migrate-storage --from <local|s3> --to <local|s3> [--delete-source]";
+
+// One-off maintenance commands: `fastblog-backend <command> [options]`
+pub async fn run(command: &str, args: &[String], config: &Config, db: &Database) -> Result<(), Box<dyn Error>> {
+    match command {
+        "migrate-storage" => migrate_storage(args, config, db).await,
+        _ => Err(format!("Unknown command '{}'. {}", command, USAGE).into()),
+    }
+}
+
+// Copy every stored file to another backend and point stored URLs at it.
+// Safe to re-run: files already copied are skipped.
+async fn migrate_storage(args: &[String], config: &Config, db: &Database) -> Result<(), Box<dyn Error>> {
+    let mut from = None;
+    let mut to = None;
+    let mut delete_source = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => from = args.next().and_then(|value| StorageBackend::parse(value)),
+            "--to" => to = args.next().and_then(|value| StorageBackend::parse(value)),
+            "--delete-source" => delete_source = true,
+            _ => return Err(format!("Unknown option '{}'. {}", arg, USAGE).into()),
+        }
+    }
+
+    let (Some(from), Some(to)) = (from, to) else {
+        return Err(USAGE.into());
+    };
+    if from == to {
+        return Err("--from and --to must be different backends".into());
+    }
+
+    let source = storage::build(config, from)?;
+    let target = storage::build(config, to)?;
+
+    tracing::info!("Copying files from {:?} to {:?} storage", from, to);
+    let (copied, skipped) = storage::copy_all(source.as_ref(), target.as_ref())
+        .await
+        .map_err(|e| e.to_string())?;
+    tracing::info!("Copied {} files ({} already present)", copied, skipped);
+
+    let updated = rewrite_urls(db, source.as_ref(), target.as_ref(), from == StorageBackend::Local).await?;
+    tracing::info!("Updated {} rows to the new file URLs", updated);
+
+    if delete_source {
+        let objects = source.list("").await.map_err(|e| e.to_string())?;
+        for object in &objects {
+            source.delete(&object.key).await.map_err(|e| e.to_string())?;
+        }
+        tracing::info!("Deleted {} files from {:?} storage", objects.len(), from);
+    }
+
+    Ok(())
+}
+
+// Replace the source's public base URL with the target's. Local storage also
+// rewrites the relative `/uploads/...` paths stored by older versions.
+async fn rewrite_urls(
+    db: &Database,
+    source: &dyn Storage,
+    target: &dyn Storage,
+    legacy_paths: bool,
+) -> Result<u64, Box<dyn Error>> {
+    let from_base = source.public_url("");
+    let to_base = target.public_url("");
+    let mut updated = 0;
+
+    for (table, column) in URL_COLUMNS {
+        let sql = format!(
+            r#"
+            UPDATE {table} SET {column} = CASE
+                WHEN starts_with({column}, $1) THEN $2 || substr({column}, length($1) + 1)
+                WHEN starts_with({column}, '/uploads/') THEN $2 || substr({column}, 10)
+                ELSE $2 || substr({column}, 9)
+            END
+            WHERE starts_with({column}, $1)
+                OR ($3 AND (starts_with({column}, '/uploads/') OR starts_with({column}, 'uploads/')))
+            "#
+        );
+        updated += sqlx::query(&sql)
+            .bind(&from_base)
+            .bind(&to_base)
+            .bind(legacy_paths)
+            .execute(&db.pool)
+            .await?
+            .rows_affected();
+    }
+
+    for (table, column) in TEXT_COLUMNS {
+        // media.variants is JSONB; rewrite its text form
+        let (value, cast) = if *column == "variants" {
+            (format!("{}::text", column), "::jsonb")
+        } else {
+            (column.to_string(), "")
+        };
+        let sql = format!(
+            r#"
+            UPDATE {table} SET {column} = (CASE
+                WHEN $3 THEN replace(replace(replace({value}, $1, $2), '(/uploads/', '(' || $2), '"/uploads/', '"' || $2)
+                ELSE replace({value}, $1, $2)
+            END){cast}
+            WHERE strpos({value}, $1) > 0
+                OR ($3 AND (strpos({value}, '(/uploads/') > 0 OR strpos({value}, '"/uploads/') > 0))
+            "#
+        );
+        updated += sqlx::query(&sql)
+            .bind(&from_base)
+            .bind(&to_base)
+            .bind(legacy_paths)
+            .execute(&db.pool)
+            .await?
+            .rows_affected();
+    }
+
+    Ok(updated)
+}
@@ -4,13 +4,28 @@ use std::env;
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     pub database_url: String,
-    pub jwt_secret: String,
+    pub jwt: JwtConfig,
     pub port: u16,
     pub environment: Environment,
+    // Browser origins allowed to call the API with credentials; `*` allows any origin without them
     pub cors_origins: Vec<String>,
+    pub security_headers: SecurityHeadersConfig,
     pub max_file_size: usize,
     pub redis_url: Option<String>,
     pub smtp_config: Option<SmtpConfig>,
+    pub robots: RobotsConfig,
+    pub storage: StorageConfig,
+    pub oidc_providers: Vec<OidcProviderConfig>,
+    // Frontend page providers send users back to with `code` and `state`; it must be registered with each provider
+    pub oidc_redirect_url: String,
+    // Public URL of the web frontend, used to build links to articles and settings pages
+    pub frontend_url: String,
+    // Public base URL of this API, used for links that must hit the backend directly (emails)
+    pub api_url: String,
+    // Lets imports download images from private addresses (local testing only)
+    pub import_allow_private_networks: bool,
+    // Number of reverse proxies in front of the API that append to X-Forwarded-For (rate limits, login tracking)
+    pub trusted_proxy_hops: usize,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -20,6 +35,95 @@ pub enum Environment {
     Testing,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtConfig {
+    // RSA (RS256) or Ed25519 (EdDSA) private key new session tokens are signed with
+    pub signing_key: Option<JwtKeyConfig>,
+    // Previous signing keys; tokens they issued keep working until they expire
+    pub verification_keys: Vec<JwtKeyConfig>,
+    // HS256 secret used only when no signing key is configured outside production
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtKeyConfig {
+    // Defaults to the key's JWK thumbprint
+    pub kid: Option<String>,
+    pub pem: String,
+}
+
+// Headers added to every response that doesn't set its own
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecurityHeadersConfig {
+    pub content_security_policy: String,
+    // Who may embed our pages in a frame; appended to the CSP as `frame-ancestors`
+    pub frame_ancestors: String,
+    pub referrer_policy: String,
+    // Strict-Transport-Security max-age in seconds; None sends no HSTS header
+    pub hsts_max_age: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RobotsConfig {
+    // When false robots.txt disallows everything (staging, previews)
+    pub allow_indexing: bool,
+    pub disallow: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum StorageBackend {
+    Local,
+    S3,
+}
+
+impl StorageBackend {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "local" => Some(StorageBackend::Local),
+            "s3" => Some(StorageBackend::S3),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageConfig {
+    // Backend new uploads are written to
+    pub backend: StorageBackend,
+    // Directory of the local backend, served under /uploads
+    pub local_root: String,
+    // Base URL public files are served from (e.g. a CDN); defaults depend on the backend
+    pub public_url: Option<String>,
+    // Signs expiring URLs to private files on the local backend
+    pub signing_secret: String,
+    pub s3: Option<S3Config>,
+}
+
+// Any S3-compatible service: AWS, MinIO, R2, ...
+#[derive(Debug, Clone, Deserialize)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    // `endpoint/bucket/key` instead of `bucket.endpoint/key`; MinIO needs this
+    pub path_style: bool,
+}
+
+// An OpenID Connect provider users can sign in with (Google, Keycloak, Auth0, ...)
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcProviderConfig {
+    // Short name used in API paths and stored with linked identities
+    pub id: String,
+    pub display_name: String,
+    // Discovery happens at `{issuer}/.well-known/openid-configuration`
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scopes: String,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct SmtpConfig {
     pub host: String,
@@ -36,8 +140,7 @@ impl Config {
         let database_url = env::var("DATABASE_URL")
             .unwrap_or_else(|_| "postgresql://localhost/fastblog".to_string());
 
-        let jwt_secret = env::var("JWT_SECRET")
-            .unwrap_or_else(|_| "your-super-secret-jwt-key-change-this-in-production".to_string());
+        let jwt_secret = env::var("JWT_SECRET").ok();
 
         let port = env::var("PORT")
             .unwrap_or_else(|_| "3001".to_string())
@@ -53,11 +156,73 @@ impl Config {
             _ => Environment::Development,
         };
 
-        let cors_origins = env::var("CORS_ORIGINS")
+        // JWT_VERIFICATION_KEY_FILES=old.pem,2024-01=older.pem: retired keys, optionally with their kid
+        let jwt = JwtConfig {
+            signing_key: match env::var("JWT_PRIVATE_KEY_FILE") {
+                Ok(path) => Some(JwtKeyConfig {
+                    kid: env::var("JWT_KEY_ID").ok(),
+                    pem: read_key_file(&path)?,
+                }),
+                Err(_) => None,
+            },
+            verification_keys: env::var("JWT_VERIFICATION_KEY_FILES")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .map(|entry| {
+                    let (kid, path) = match entry.split_once('=') {
+                        Some((kid, path)) => (Some(kid.trim().to_string()), path.trim()),
+                        None => (None, entry),
+                    };
+                    Ok(JwtKeyConfig { kid, pem: read_key_file(path)? })
+                })
+                .collect::<Result<_, Box<dyn std::error::Error>>>()?,
+            secret: jwt_secret
+                .clone()
+                .unwrap_or_else(|| "your-super-secret-jwt-key-change-this-in-production".to_string()),
+        };
+
+        // Never sign production tokens with a shared or default secret
+        if matches!(environment, Environment::Production) && jwt.signing_key.is_none() {
+            return Err("JWT_PRIVATE_KEY_FILE must point to an RSA or Ed25519 private key in production".into());
+        }
+
+        let cors_origins: Vec<String> = env::var("CORS_ORIGINS")
             .unwrap_or_else(|_| "http://localhost:3000,http://localhost:3001".to_string())
             .split(',')
-            .map(|s| s.trim().to_string())
+            .map(|s| s.trim().trim_end_matches('/').to_string())
+            .filter(|s| !s.is_empty())
             .collect();
+        if matches!(environment, Environment::Production) && cors_origins.iter().any(|origin| origin == "*") {
+            return Err("CORS_ORIGINS must list the allowed origins in production, not *".into());
+        }
+
+        // HSTS only makes sense behind HTTPS, so it is on by default in production only
+        let security_headers = SecurityHeadersConfig {
+            content_security_policy: env::var("CONTENT_SECURITY_POLICY")
+                .unwrap_or_else(|_| "default-src 'none'".to_string()),
+            frame_ancestors: env::var("FRAME_ANCESTORS").unwrap_or_else(|_| "'none'".to_string()),
+            referrer_policy: env::var("REFERRER_POLICY")
+                .unwrap_or_else(|_| "strict-origin-when-cross-origin".to_string()),
+            hsts_max_age: match env::var("HSTS_MAX_AGE") {
+                Ok(value) if value == "0" => None,
+                Ok(value) => Some(value.parse::<u64>()?),
+                Err(_) if matches!(environment, Environment::Production) => Some(31_536_000),
+                Err(_) => None,
+            },
+        };
+        for value in [
+            &security_headers.content_security_policy,
+            &security_headers.frame_ancestors,
+            &security_headers.referrer_policy,
+        ]
+        .into_iter()
+        .chain(&cors_origins)
+        {
+            axum::http::HeaderValue::from_str(value)
+                .map_err(|_| format!("Invalid header value in configuration: {:?}", value))?;
+        }
 
         let max_file_size = env::var("MAX_FILE_SIZE")
             .unwrap_or_else(|_| "10485760".to_string()) // 10MB default
@@ -65,6 +230,15 @@ impl Config {
 
         let redis_url = env::var("REDIS_URL").ok();
 
+        let frontend_url = env::var("FRONTEND_URL")
+            .unwrap_or_else(|_| "http://localhost:3003".to_string())
+            .trim_end_matches('/')
+            .to_string();
+        let api_url = env::var("API_URL")
+            .unwrap_or_else(|_| "http://localhost:3001".to_string())
+            .trim_end_matches('/')
+            .to_string();
+
         let smtp_config = if let (Ok(host), Ok(port), Ok(username), Ok(password), Ok(from_email)) = (
             env::var("SMTP_HOST"),
             env::var("SMTP_PORT"),
@@ -83,15 +257,111 @@ impl Config {
             None
         };
 
+        // Only production is indexed unless explicitly overridden
+        let robots = RobotsConfig {
+            allow_indexing: env::var("ROBOTS_ALLOW_INDEXING")
+                .map(|v| v == "true")
+                .unwrap_or(matches!(environment, Environment::Production)),
+            disallow: env::var("ROBOTS_DISALLOW")
+                .unwrap_or_else(|_| "/api/,/settings,/drafts,/admin".to_string())
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+        };
+
+        let s3 = if let (Ok(bucket), Ok(access_key_id), Ok(secret_access_key)) = (
+            env::var("S3_BUCKET"),
+            env::var("S3_ACCESS_KEY_ID"),
+            env::var("S3_SECRET_ACCESS_KEY"),
+        ) {
+            let region = env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+            Some(S3Config {
+                endpoint: env::var("S3_ENDPOINT")
+                    .unwrap_or_else(|_| format!("https://s3.{}.amazonaws.com", region))
+                    .trim_end_matches('/')
+                    .to_string(),
+                region,
+                bucket,
+                access_key_id,
+                secret_access_key,
+                path_style: env::var("S3_PATH_STYLE").map(|v| v == "true").unwrap_or(false),
+            })
+        } else {
+            None
+        };
+
+        let signing_secret = match env::var("STORAGE_SIGNING_SECRET").ok().or(jwt_secret) {
+            Some(secret) => secret,
+            None if matches!(environment, Environment::Production) => {
+                return Err("STORAGE_SIGNING_SECRET must be set in production".into());
+            }
+            None => jwt.secret.clone(),
+        };
+
+        let import_allow_private_networks = env::var("IMPORT_ALLOW_PRIVATE_NETWORKS")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        let trusted_proxy_hops = env::var("TRUSTED_PROXY_HOPS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<usize>()?;
+
+        let backend_name = env::var("STORAGE_BACKEND").unwrap_or_else(|_| "local".to_string());
+        let storage = StorageConfig {
+            backend: StorageBackend::parse(&backend_name)
+                .ok_or_else(|| format!("Unknown STORAGE_BACKEND '{}' (expected local or s3)", backend_name))?,
+            local_root: env::var("STORAGE_LOCAL_ROOT").unwrap_or_else(|_| "uploads".to_string()),
+            public_url: env::var("STORAGE_PUBLIC_URL")
+                .ok()
+                .map(|url| url.trim_end_matches('/').to_string()),
+            signing_secret,
+            s3,
+        };
+
+        // OIDC_PROVIDERS=google,keycloak with OIDC_GOOGLE_ISSUER, OIDC_GOOGLE_CLIENT_ID, ... per provider
+        let mut oidc_providers = Vec::new();
+        for id in env::var("OIDC_PROVIDERS").unwrap_or_default().split(',') {
+            let id = id.trim().to_lowercase();
+            if id.is_empty() {
+                continue;
+            }
+            if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return Err(format!("Invalid OIDC provider name '{}'", id).into());
+            }
+            let prefix = format!("OIDC_{}_", id.to_uppercase().replace('-', "_"));
+            let required = |name: &str| {
+                env::var(format!("{}{}", prefix, name)).map_err(|_| format!("{}{} is not set", prefix, name))
+            };
+            oidc_providers.push(OidcProviderConfig {
+                display_name: env::var(format!("{}DISPLAY_NAME", prefix)).unwrap_or_else(|_| id.clone()),
+                issuer: required("ISSUER")?.trim_end_matches('/').to_string(),
+                client_id: required("CLIENT_ID")?,
+                client_secret: required("CLIENT_SECRET")?,
+                scopes: env::var(format!("{}SCOPES", prefix)).unwrap_or_else(|_| "openid email profile".to_string()),
+                id,
+            });
+        }
+        let oidc_redirect_url = env::var("OIDC_REDIRECT_URL")
+            .unwrap_or_else(|_| format!("{}/auth/callback", frontend_url));
+
         Ok(Config {
             database_url,
-            jwt_secret,
+            jwt,
             port,
             environment,
             cors_origins,
+            security_headers,
             max_file_size,
             redis_url,
             smtp_config,
+            robots,
+            storage,
+            oidc_providers,
+            oidc_redirect_url,
+            frontend_url,
+            api_url,
+            import_allow_private_networks,
+            trusted_proxy_hops,
         })
     }
 
@@ -102,4 +372,12 @@ impl Config {
     pub fn is_production(&self) -> bool {
         matches!(self.environment, Environment::Production)
     }
+
+    pub fn oidc_provider(&self, id: &str) -> Option<&OidcProviderConfig> {
+        self.oidc_providers.iter().find(|provider| provider.id == id)
+    }
+}
+
+fn read_key_file(path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    std::fs::read_to_string(path).map_err(|e| format!("Failed to read key file {}: {}", path, e).into())
 }
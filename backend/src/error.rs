@@ -0,0 +1,228 @@
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+use utoipa::ToSchema;
+use validator::ValidationErrors;
+
+use crate::middleware::request_id::current_request_id;
+
+// Error returned by services and handlers. Rendered as RFC 7807 problem details
+// (application/problem+json) with a stable `code` clients can branch on.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("{0}")]
+    BadRequest(String),
+    // Field errors from `validator`
+    #[error("Validation failed")]
+    Validation(#[from] ValidationErrors),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    PayloadTooLarge(String),
+    // An external service (sign-in provider, ...) failed
+    #[error("{0}")]
+    BadGateway(String),
+    #[error("{message}")]
+    RateLimited { message: String, retry_after: u64 },
+    // Account locked after repeated failed sign-ins
+    #[error("{message}")]
+    Locked { message: String, retry_after: u64 },
+    // Logged with the request id; clients only see a generic message
+    #[error("{0}")]
+    Internal(Box<dyn Error + Send + Sync>),
+}
+
+impl AppError {
+    pub fn internal(message: impl Into<String>) -> Self {
+        AppError::Internal(message.into().into())
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::BadGateway(_) => StatusCode::BAD_GATEWAY,
+            AppError::RateLimited { .. } | AppError::Locked { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // Machine-readable error code; problem types are all `about:blank`, so this is what
+    // clients branch on
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Validation(_) => "validation_failed",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::PayloadTooLarge(_) => "payload_too_large",
+            AppError::BadGateway(_) => "bad_gateway",
+            AppError::RateLimited { .. } => "rate_limited",
+            AppError::Locked { .. } => "account_locked",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+}
+
+// Body of every error response (RFC 7807)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: &'static str,
+    pub title: &'static str,
+    pub status: u16,
+    pub detail: String,
+    pub code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    // Same as `detail`
+    pub error: String,
+    // Validation failures, by field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<BTreeMap<String, Vec<FieldError>>>,
+    // Seconds until a rate limited or locked request may be retried
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FieldError {
+    pub code: String,
+    pub message: Option<String>,
+    pub params: BTreeMap<String, Value>,
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let request_id = current_request_id();
+
+        let detail = match &self {
+            AppError::Internal(e) => {
+                tracing::error!(request_id = request_id.as_deref().unwrap_or("-"), "Internal error: {}", e);
+                "An unexpected error occurred".to_string()
+            }
+            other => other.to_string(),
+        };
+
+        let (errors, retry_after, locked) = match &self {
+            AppError::Validation(errors) => (Some(field_errors(errors)), None, None),
+            AppError::RateLimited { retry_after, .. } => (None, Some(*retry_after), Some(false)),
+            AppError::Locked { retry_after, .. } => (None, Some(*retry_after), Some(true)),
+            _ => (None, None, None),
+        };
+
+        let body = ProblemDetails {
+            problem_type: "about:blank",
+            title: status.canonical_reason().unwrap_or("Error"),
+            status: status.as_u16(),
+            code: self.code(),
+            request_id,
+            // Clients written against the old `{"error": "..."}` bodies keep working
+            error: detail.clone(),
+            detail,
+            errors,
+            retry_after,
+            locked,
+        };
+
+        let mut response = (status, Json(body)).into_response();
+        let headers = response.headers_mut();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/problem+json"));
+        if let AppError::RateLimited { retry_after, .. } | AppError::Locked { retry_after, .. } = self {
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        response
+    }
+}
+
+// `{"email": [{"code": "email", "message": "..."}]}`
+fn field_errors(errors: &ValidationErrors) -> BTreeMap<String, Vec<FieldError>> {
+    errors
+        .field_errors()
+        .into_iter()
+        .map(|(field, errors)| {
+            let errors = errors
+                .iter()
+                .map(|error| FieldError {
+                    code: error.code.to_string(),
+                    message: error.message.as_ref().map(|message| message.to_string()),
+                    // `value` is the submitted input, which may be a password
+                    params: error
+                        .params
+                        .iter()
+                        .filter(|(name, _)| *name != "value")
+                        .map(|(name, value)| (name.to_string(), value.clone()))
+                        .collect(),
+                })
+                .collect();
+            (field.to_string(), errors)
+        })
+        .collect()
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        match &e {
+            sqlx::Error::RowNotFound => AppError::NotFound("Resource not found".to_string()),
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                AppError::Conflict("Resource already exists".to_string())
+            }
+            _ => AppError::Internal(e.into()),
+        }
+    }
+}
+
+// Errors from services that still box them; an AppError inside keeps its meaning
+impl From<Box<dyn Error + Send + Sync>> for AppError {
+    fn from(e: Box<dyn Error + Send + Sync>) -> Self {
+        match e.downcast::<AppError>() {
+            Ok(e) => *e,
+            Err(e) => AppError::Internal(e),
+        }
+    }
+}
+
+// Failures of libraries and background services, which only ever surface as 500s
+macro_rules! internal_from {
+    ($($error:ty),* $(,)?) => {
+        $(
+            impl From<$error> for AppError {
+                fn from(e: $error) -> Self {
+                    AppError::Internal(e.into())
+                }
+            }
+        )*
+    };
+}
+
+internal_from!(
+    askama::Error,
+    serde_json::Error,
+    std::io::Error,
+    reqwest::Error,
+    jsonwebtoken::errors::Error,
+    anyhow::Error,
+    tokio::task::JoinError,
+);
@@ -1,14 +1,23 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
     response::Json,
     routing::{get, post},
     Router,
 };
 use serde_json::{json, Value};
+use utoipa::OpenApi;
 use uuid::Uuid;
 
-use crate::AppState;
+use crate::{
+    error::AppError,
+    middleware::auth::AdminUser,
+    models::{
+        job::{JobListResponse, JobQueryParams, JobResponse, JobStatsResponse},
+        user::{AdminDeleteUserQuery, ArticleDeletionAction},
+    },
+    services::{account::AccountService, jobs::JobService, login_protection::LoginProtectionService},
+    AppState,
+};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
@@ -17,6 +26,7 @@ pub fn routes() -> Router<AppState> {
         .route("/users/:user_id", get(get_user_admin).put(update_user_admin).delete(delete_user_admin))
         .route("/users/:user_id/verify", post(verify_user))
         .route("/users/:user_id/ban", post(ban_user).delete(unban_user))
+        .route("/users/:user_id/unlock", post(unlock_user))
         
         // Content moderation
         .route("/articles", get(get_all_articles))
@@ -40,214 +50,495 @@ pub fn routes() -> Router<AppState> {
         // Content reports
         .route("/reports", get(get_content_reports))
         .route("/reports/:report_id", get(get_report).put(resolve_report))
+        
+        // Background jobs
+        .route("/jobs", get(get_jobs))
+        .route("/jobs/stats", get(get_job_stats))
+        .route("/jobs/:job_id", get(get_job))
+        .route("/jobs/:job_id/retry", post(retry_job))
+        .route("/jobs/:job_id/cancel", post(cancel_job))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_all_users,
+    get_user_admin,
+    update_user_admin,
+    delete_user_admin,
+    verify_user,
+    ban_user,
+    unban_user,
+    unlock_user,
+    get_all_articles,
+    feature_article,
+    unfeature_article,
+    moderate_article,
+    get_all_publications,
+    verify_publication,
+    get_analytics_overview,
+    get_user_analytics,
+    get_article_analytics,
+    get_engagement_analytics,
+    get_system_health,
+    get_system_metrics,
+    get_content_reports,
+    get_report,
+    resolve_report,
+    get_jobs,
+    get_job_stats,
+    get_job,
+    retry_job,
+    cancel_job,
+))]
+pub struct AdminApi;
+
+#[utoipa::path(
+    get,
+    path = "/users",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_all_users(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get all users with pagination (admin only)
     Ok(Json(json!({
         "message": "Get all users not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_user_admin(
     State(_state): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get user details for admin (admin only)
     Ok(Json(json!({
         "message": format!("Get user {} admin view not implemented yet", user_id)
     })))
 }
 
+#[utoipa::path(
+    put,
+    path = "/users/{user_id}",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn update_user_admin(
     State(_state): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Update user as admin (admin only)
     Ok(Json(json!({
         "message": format!("Update user {} as admin not implemented yet", user_id)
     })))
 }
 
+// Deletes the account right away (no grace period); `?articles=delete` removes the
+// user's articles instead of anonymizing them
+#[utoipa::path(
+    delete,
+    path = "/users/{user_id}",
+    params(("user_id" = Uuid, Path)),
+    params(AdminDeleteUserQuery),
+    responses((status = 200, description = "Deletion queued", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn delete_user_admin(
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
+    _admin: AdminUser,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // TODO: Delete user account (admin only)
-    Ok(Json(json!({
-        "message": format!("Delete user {} not implemented yet", user_id)
-    })))
+    Query(params): Query<AdminDeleteUserQuery>,
+) -> Result<Json<Value>, AppError> {
+    let account_service = AccountService::new(state.db.pool.clone(), &state.config);
+    let action = params.articles.unwrap_or(ArticleDeletionAction::Anonymize);
+
+    account_service.delete_now(user_id, action).await?;
+    Ok(Json(json!({"message": "User deletion queued"})))
 }
 
+// Lifts a lockout from too many failed sign-ins before it runs out
+#[utoipa::path(
+    post,
+    path = "/users/{user_id}/unlock",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Sign-in lockout lifted", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn unlock_user(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let login_protection = LoginProtectionService::new(state.db.pool.clone(), &state.config);
+
+    login_protection.unlock(user_id).await?;
+    tracing::info!("User {} unlocked by {}", user_id, admin.username);
+    Ok(Json(json!({"message": "User unlocked"})))
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{user_id}/verify",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn verify_user(
     State(_state): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Verify user account (admin only)
     Ok(Json(json!({
         "message": format!("Verify user {} not implemented yet", user_id)
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/users/{user_id}/ban",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn ban_user(
     State(_state): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Ban user account (admin only)
     Ok(Json(json!({
         "message": format!("Ban user {} not implemented yet", user_id)
     })))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/users/{user_id}/ban",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn unban_user(
     State(_state): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Unban user account (admin only)
     Ok(Json(json!({
         "message": format!("Unban user {} not implemented yet", user_id)
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/articles",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_all_articles(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get all articles for moderation (admin only)
     Ok(Json(json!({
         "message": "Get all articles not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/articles/{article_id}/feature",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn feature_article(
     State(_state): State<AppState>,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Feature article on homepage (admin only)
     Ok(Json(json!({
         "message": format!("Feature article {} not implemented yet", article_id)
     })))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/articles/{article_id}/feature",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn unfeature_article(
     State(_state): State<AppState>,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Remove article from featured (admin only)
     Ok(Json(json!({
         "message": format!("Unfeature article {} not implemented yet", article_id)
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/articles/{article_id}/moderate",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn moderate_article(
     State(_state): State<AppState>,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Moderate article (hide, approve, etc.) (admin only)
     Ok(Json(json!({
         "message": format!("Moderate article {} not implemented yet", article_id)
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/publications",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_all_publications(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get all publications for admin (admin only)
     Ok(Json(json!({
         "message": "Get all publications not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/publications/{publication_id}/verify",
+    params(("publication_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn verify_publication(
     State(_state): State<AppState>,
     Path(publication_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Verify publication (admin only)
     Ok(Json(json!({
         "message": format!("Verify publication {} not implemented yet", publication_id)
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/analytics/overview",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_analytics_overview(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get platform analytics overview (admin only)
     Ok(Json(json!({
         "message": "Get analytics overview not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/analytics/users",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_user_analytics(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get user analytics (admin only)
     Ok(Json(json!({
         "message": "Get user analytics not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/analytics/articles",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_article_analytics(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get article analytics (admin only)
     Ok(Json(json!({
         "message": "Get article analytics not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/analytics/engagement",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_engagement_analytics(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get engagement analytics (admin only)
     Ok(Json(json!({
         "message": "Get engagement analytics not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_system_health(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get system health status (admin only)
     Ok(Json(json!({
         "message": "Get system health not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_system_metrics(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get system metrics (admin only)
     Ok(Json(json!({
         "message": "Get system metrics not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/reports",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_content_reports(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get content reports for moderation (admin only)
     Ok(Json(json!({
         "message": "Get content reports not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/reports/{report_id}",
+    params(("report_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_report(
     State(_state): State<AppState>,
     Path(report_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get specific report details (admin only)
     Ok(Json(json!({
         "message": format!("Get report {} not implemented yet", report_id)
     })))
 }
 
+#[utoipa::path(
+    put,
+    path = "/reports/{report_id}",
+    params(("report_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn resolve_report(
     State(_state): State<AppState>,
     Path(report_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Resolve content report (admin only)
     Ok(Json(json!({
         "message": format!("Resolve report {} not implemented yet", report_id)
     })))
 }
+
+#[utoipa::path(
+    get,
+    path = "/jobs",
+    params(JobQueryParams),
+    responses((status = 200, description = "A page of background jobs", body = JobListResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_jobs(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Query(params): Query<JobQueryParams>,
+) -> Result<Json<Value>, AppError> {
+    let job_service = JobService::new(state.db.pool.clone());
+
+    let jobs = job_service.list_jobs(params).await?;
+    Ok(Json(serde_json::to_value(jobs)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs/stats",
+    responses((status = 200, description = "Queue counts and recurring jobs", body = JobStatsResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_job_stats(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Result<Json<Value>, AppError> {
+    let job_service = JobService::new(state.db.pool.clone());
+
+    let stats = job_service.stats().await?;
+    Ok(Json(serde_json::to_value(stats)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs/{job_id}",
+    params(("job_id" = Uuid, Path)),
+    responses((status = 200, description = "The job", body = JobResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_job(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let job_service = JobService::new(state.db.pool.clone());
+
+    let job = job_service.get_job(job_id).await?;
+    Ok(Json(serde_json::to_value(job)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/jobs/{job_id}/retry",
+    params(("job_id" = Uuid, Path)),
+    responses((status = 200, description = "The job, queued again", body = JobResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn retry_job(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let job_service = JobService::new(state.db.pool.clone());
+
+    let job = job_service.retry_job(job_id).await?;
+    tracing::info!("Job {} retried by {}", job_id, admin.username);
+
+    Ok(Json(serde_json::to_value(job)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/jobs/{job_id}/cancel",
+    params(("job_id" = Uuid, Path)),
+    responses((status = 200, description = "The cancelled job", body = JobResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn cancel_job(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let job_service = JobService::new(state.db.pool.clone());
+
+    let job = job_service.cancel_job(job_id).await?;
+    tracing::info!("Job {} cancelled by {}", job_id, admin.username);
+
+    Ok(Json(serde_json::to_value(job)?))
+}
@@ -1,17 +1,28 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
     routing::{get, post, put},
     Router,
 };
 use serde_json::{json, Value};
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 use crate::{
-    models::{Article, ArticleQueryParams, CreateArticleRequest, UpdateArticleRequest, engagement::{ClapRequest, CreateCommentRequest}},
+    error::AppError,
+    models::{
+        Article, ArticleListResponse, ArticleQueryParams, ArticleResponse, ArticleStats, AutoSaveDraftRequest,
+        CreateArticleRequest, FollowedTopicsResponse, ScheduleArticleRequest, SlugLookup, TopicKind, TopicPageResponse,
+        UpdateArticleRequest,
+        engagement::{ClapRequest, CommentResponse, CreateCommentRequest},
+    },
     services::{article::ArticleService, engagement::EngagementService},
-    middleware::auth::OptionalAuthUser,
+    middleware::{
+        auth::{AuthUser, OptionalAuthUser},
+        rate_limit,
+    },
+    util::urlencode,
     AppState,
 };
 
@@ -30,7 +41,8 @@ pub fn routes() -> Router<AppState> {
         .route("/", post(create_article))
         .route("/:article_id", put(update_article).delete(delete_article))
         .route("/:article_id/publish", post(publish_article))
-        .route("/:article_id/clap", post(clap_article))
+        .route("/:article_id/schedule", post(schedule_article).delete(cancel_schedule))
+        .route("/:article_id/clap", post(clap_article).layer(rate_limit::limit(rate_limit::CLAPS)))
         .route("/:article_id/bookmark", post(bookmark_article).delete(unbookmark_article))
         .route("/:article_id/view", post(record_view))
         .route("/:article_id/read", post(record_read))
@@ -46,349 +58,308 @@ pub fn routes() -> Router<AppState> {
         .route("/:article_id/featured", post(toggle_featured))
         .route("/categories", get(get_categories))
         .route("/tags", get(get_tags))
+        .route("/tags/:tag", get(get_tag_page))
+        .route("/tags/:tag/follow", post(follow_tag).delete(unfollow_tag))
+        .route("/categories/:category", get(get_category_page))
+        .route("/categories/:category/follow", post(follow_category).delete(unfollow_category))
+        .route("/topics/following", get(get_followed_topics))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_articles,
+    get_article,
+    get_article_by_slug,
+    get_trending_articles,
+    get_featured_articles,
+    get_comments,
+    get_highlights,
+    create_article,
+    update_article,
+    delete_article,
+    publish_article,
+    schedule_article,
+    cancel_schedule,
+    clap_article,
+    bookmark_article,
+    unbookmark_article,
+    record_view,
+    record_read,
+    create_comment,
+    create_highlight,
+    get_personalized_feed,
+    get_drafts,
+    get_draft,
+    auto_save_draft,
+    get_article_stats,
+    toggle_featured,
+    get_categories,
+    get_tags,
+    get_tag_page,
+    follow_tag,
+    unfollow_tag,
+    get_category_page,
+    follow_category,
+    unfollow_category,
+    get_followed_topics,
+))]
+pub struct ArticlesApi;
+
+#[utoipa::path(
+    get,
+    path = "/",
+    params(ArticleQueryParams),
+    responses((status = 200, description = "A page of articles", body = ArticleListResponse)),
+    security((), ("bearer_auth" = [])),
+)]
 async fn get_articles(
     State(state): State<AppState>,
     OptionalAuthUser(user): OptionalAuthUser,
     Query(params): Query<ArticleQueryParams>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let article_service = ArticleService::new(state.db.pool.clone());
+) -> Result<Json<Value>, AppError> {
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     let user_id = user.map(|u| u.user_id);
     
-    match article_service.get_articles(params, user_id).await {
-        Ok(response) => Ok(Json(serde_json::to_value(response).unwrap())),
-        Err(e) => {
-            tracing::error!("Failed to get articles: {}", e);
-            let error_msg = format!("Failed to get articles: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": error_msg})),
-            ))
-        }
-    }
+    let response = article_service.get_articles(params, user_id).await?;
+    Ok(Json(serde_json::to_value(response)?))
 }
 
+#[utoipa::path(
+    post,
+    path = "/",
+    request_body = CreateArticleRequest,
+    responses((status = 200, description = "The new article", body = ArticleResponse)),
+    security(("bearer_auth" = [])),
+)]
 async fn create_article(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     Json(payload): Json<CreateArticleRequest>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
-    let auth_header = headers
+    let token = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
-
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
-
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
-
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service
+        .authenticate(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
+
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
 
-    let article_service = ArticleService::new(state.db.pool.clone());
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     
-    match article_service.create_article(user_id, payload).await {
-        Ok(response) => Ok(Json(serde_json::to_value(response).unwrap())),
-        Err(e) => {
-            let error_msg = format!("Failed to create article: {}", e);
-            tracing::error!("{}", error_msg);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": error_msg})),
-            ))
-        }
-    }
+    let response = article_service.create_article(user_id, payload).await?;
+    Ok(Json(serde_json::to_value(response)?))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{article_id}",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "The article", body = ArticleResponse)),
+    security((), ("bearer_auth" = [])),
+)]
 async fn get_article(
     State(state): State<AppState>,
     OptionalAuthUser(user): OptionalAuthUser,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let article_service = ArticleService::new(state.db.pool.clone());
+) -> Result<Json<Value>, AppError> {
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     let user_id = user.map(|u| u.user_id);
     
-    match article_service.get_article_by_id(article_id, user_id).await {
-        Ok(response) => Ok(Json(serde_json::to_value(response).unwrap())),
-        Err(e) => {
-            tracing::error!("Failed to get article: {}", e);
-            Err((
-                StatusCode::NOT_FOUND,
-                Json(json!({"error": "Article not found"})),
-            ))
-        }
-    }
+    let response = article_service.get_article_by_id(article_id, user_id).await?;
+    Ok(Json(serde_json::to_value(response)?))
 }
 
+#[utoipa::path(
+    get,
+    path = "/slug/{slug}",
+    params(("slug" = String, Path)),
+    responses(
+        (status = 200, description = "The article", body = ArticleResponse),
+        (status = 301, description = "Renamed article; `Location` has its current slug", body = Value),
+    ),
+    security((), ("bearer_auth" = [])),
+)]
 async fn get_article_by_slug(
     State(state): State<AppState>,
     OptionalAuthUser(user): OptionalAuthUser,
     Path(slug): Path<String>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let article_service = ArticleService::new(state.db.pool.clone());
+) -> Result<Response, AppError> {
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     let user_id = user.map(|u| u.user_id);
     
-    match article_service.get_article_by_slug(&slug, user_id).await {
-        Ok(response) => Ok(Json(serde_json::to_value(response).unwrap())),
-        Err(e) => {
-            tracing::error!("Failed to get article by slug: {}", e);
-            Err((
-                StatusCode::NOT_FOUND,
-                Json(json!({"error": "Article not found"})),
-            ))
+    match article_service.get_article_by_slug(&slug, user_id).await? {
+        SlugLookup::Found(response) => Ok(Json(serde_json::to_value(response)?).into_response()),
+        // Renamed article: permanent redirect so shared links and search rankings follow it
+        SlugLookup::Moved(current_slug) => {
+            let location = format!("/api/v1/articles/slug/{}", urlencode(&current_slug));
+            Ok((
+                StatusCode::MOVED_PERMANENTLY,
+                [(header::LOCATION, location.clone())],
+                Json(json!({
+                    "slug": current_slug,
+                    "location": location
+                })),
+            )
+                .into_response())
         }
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/{article_id}",
+    params(("article_id" = Uuid, Path)),
+    request_body = UpdateArticleRequest,
+    responses((status = 200, description = "The updated article", body = ArticleResponse)),
+    security(("bearer_auth" = [])),
+)]
 async fn update_article(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     Path(article_id): Path<Uuid>,
     Json(payload): Json<UpdateArticleRequest>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
-    let auth_header = headers
+    let token = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
-
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
-
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
-
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service
+        .authenticate(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
+
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
 
-    let article_service = ArticleService::new(state.db.pool.clone());
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     
-    match article_service.update_article(article_id, user_id, payload).await {
-        Ok(response) => Ok(Json(serde_json::to_value(response).unwrap())),
-        Err(e) => {
-            tracing::error!("Failed to update article: {}", e);
-            let status = if e.to_string().contains("not found") {
-                StatusCode::NOT_FOUND
-            } else if e.to_string().contains("Unauthorized") {
-                StatusCode::FORBIDDEN
-            } else {
-                StatusCode::INTERNAL_SERVER_ERROR
-            };
-            Err((status, Json(json!({"error": e.to_string()}))))
-        }
-    }
+    let response = article_service.update_article(article_id, user_id, payload).await?;
+    Ok(Json(serde_json::to_value(response)?))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/{article_id}",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "Article deleted", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn delete_article(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
-    let auth_header = headers
+    let token = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
-
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
-
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
-
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service
+        .authenticate(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
 
-    let article_service = ArticleService::new(state.db.pool.clone());
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
+
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     
-    match article_service.delete_article(article_id, user_id).await {
-        Ok(()) => Ok(Json(json!({"message": "Article deleted successfully"}))),
-        Err(e) => {
-            tracing::error!("Failed to delete article: {}", e);
-            let status = if e.to_string().contains("not found") || e.to_string().contains("unauthorized") {
-                StatusCode::NOT_FOUND
-            } else {
-                StatusCode::INTERNAL_SERVER_ERROR
-            };
-            Err((status, Json(json!({"error": e.to_string()}))))
-        }
-    }
+    article_service.delete_article(article_id, user_id).await?;
+    Ok(Json(json!({"message": "Article deleted successfully"})))
 }
 
+#[utoipa::path(
+    post,
+    path = "/{article_id}/clap",
+    params(("article_id" = Uuid, Path)),
+    request_body = ClapRequest,
+    responses((status = 200, description = "`{total_claps, user_claps, is_clapped}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn clap_article(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     Path(article_id): Path<Uuid>,
     Json(payload): Json<ClapRequest>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
-    let auth_header = headers
+    let token = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
-
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
-
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
-
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service
+        .authenticate(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
 
-    let engagement_service = EngagementService::new(state.db.pool.clone());
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
+
+    let engagement_service = EngagementService::new(state.db.pool.clone(), state.realtime.clone());
     
-    match engagement_service.clap_article(article_id, user_id, payload).await {
-        Ok((total_claps, user_claps, is_clapped)) => {
-            Ok(Json(json!({
-                "total_claps": total_claps,
-                "user_claps": user_claps,
-                "is_clapped": is_clapped
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to clap article: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to clap article"})),
-            ))
-        }
-    }
+    let (total_claps, user_claps, is_clapped) = engagement_service.clap_article(article_id, user_id, payload).await?;
+    Ok(Json(json!({
+        "total_claps": total_claps,
+        "user_claps": user_claps,
+        "is_clapped": is_clapped
+    })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/{article_id}/bookmark",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "`{bookmarked, message}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn bookmark_article(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
-    let auth_header = headers
+    let token = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
-
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
-
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
-
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service
+        .authenticate(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
+
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
 
     // Check if article exists
     let article_exists = sqlx::query_scalar!(
@@ -401,10 +372,7 @@ async fn bookmark_article(
     .unwrap_or(false);
 
     if !article_exists {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(json!({"error": "Article not found"})),
-        ));
+        return Err(AppError::NotFound("Article not found".to_string()));
     }
 
     // Check if already bookmarked
@@ -426,79 +394,57 @@ async fn bookmark_article(
     }
 
     // Add bookmark
-    match sqlx::query!(
+    sqlx::query!(
         "INSERT INTO bookmarks (user_id, article_id, created_at) VALUES ($1, $2, NOW())",
         user_id,
         article_id
     )
     .execute(&state.db.pool)
-    .await
-    {
-        Ok(_) => {
-            // Update bookmarks_count on article
-            let _ = sqlx::query!(
-                "UPDATE articles SET bookmarks_count = bookmarks_count + 1 WHERE id = $1",
-                article_id
-            )
-            .execute(&state.db.pool)
-            .await;
+    .await?;
 
-            Ok(Json(json!({
-                "bookmarked": true,
-                "message": "Article bookmarked successfully"
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to bookmark article: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to bookmark article"})),
-            ))
-        }
-    }
+    // Update bookmarks_count on article
+    let _ = sqlx::query!(
+        "UPDATE articles SET bookmarks_count = bookmarks_count + 1 WHERE id = $1",
+        article_id
+    )
+    .execute(&state.db.pool)
+    .await;
+
+    Ok(Json(json!({
+        "bookmarked": true,
+        "message": "Article bookmarked successfully"
+    })))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/{article_id}/bookmark",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "`{bookmarked, message}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn unbookmark_article(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
-    let auth_header = headers
+    let token = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
-
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
-
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
-
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service
+        .authenticate(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
+
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
 
     // Remove bookmark
     let result = sqlx::query!(
@@ -507,223 +453,189 @@ async fn unbookmark_article(
         article_id
     )
     .execute(&state.db.pool)
-    .await;
+    .await?;
 
-    match result {
-        Ok(res) => {
-            if res.rows_affected() > 0 {
-                // Update bookmarks_count on article
-                let _ = sqlx::query!(
-                    "UPDATE articles SET bookmarks_count = GREATEST(bookmarks_count - 1, 0) WHERE id = $1",
-                    article_id
-                )
-                .execute(&state.db.pool)
-                .await;
-
-                Ok(Json(json!({
-                    "bookmarked": false,
-                    "message": "Bookmark removed successfully"
-                })))
-            } else {
-                Ok(Json(json!({
-                    "bookmarked": false,
-                    "message": "Bookmark not found"
-                })))
-            }
-        }
-        Err(e) => {
-            tracing::error!("Failed to remove bookmark: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to remove bookmark"})),
-            ))
-        }
+    if result.rows_affected() > 0 {
+        // Update bookmarks_count on article
+        let _ = sqlx::query!(
+            "UPDATE articles SET bookmarks_count = GREATEST(bookmarks_count - 1, 0) WHERE id = $1",
+            article_id
+        )
+        .execute(&state.db.pool)
+        .await;
+
+        Ok(Json(json!({
+            "bookmarked": false,
+            "message": "Bookmark removed successfully"
+        })))
+    } else {
+        Ok(Json(json!({
+            "bookmarked": false,
+            "message": "Bookmark not found"
+        })))
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/{article_id}/view",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn record_view(
     State(_state): State<AppState>,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Record article view for analytics
     Ok(Json(json!({
         "message": format!("Record view for article {} not implemented yet", article_id)
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/{article_id}/read",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "Read recorded", body = Value)),
+    security((), ("bearer_auth" = [])),
+)]
 async fn record_read(
     State(state): State<AppState>,
     Path(article_id): Path<Uuid>,
     OptionalAuthUser(user): OptionalAuthUser,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let article_service = ArticleService::new(state.db.pool.clone());
+) -> Result<Json<Value>, AppError> {
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     let user_id = user.map(|u| u.user_id);
     
-    match article_service.record_read(article_id, user_id).await {
-        Ok(_) => {
-            Ok(Json(json!({
-                "message": "Read recorded successfully"
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to record read: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to record read"})),
-            ))
-        }
-    }
+    article_service.record_read(article_id, user_id).await?;
+    Ok(Json(json!({
+        "message": "Read recorded successfully"
+    })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{article_id}/comments",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "`{comments}` with nested replies", body = Value)),
+    security((), ("bearer_auth" = [])),
+)]
 async fn get_comments(
     State(state): State<AppState>,
     OptionalAuthUser(user): OptionalAuthUser,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let engagement_service = EngagementService::new(state.db.pool.clone());
+) -> Result<Json<Value>, AppError> {
+    let engagement_service = EngagementService::new(state.db.pool.clone(), state.realtime.clone());
     let user_id = user.map(|u| u.user_id);
     
-    match engagement_service.get_comments(article_id, user_id).await {
-        Ok(comments) => {
-            Ok(Json(json!({
-                "comments": comments
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to get comments: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get comments"})),
-            ))
-        }
-    }
+    let comments = engagement_service.get_comments(article_id, user_id).await?;
+    Ok(Json(json!({
+        "comments": comments
+    })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/{article_id}/comments",
+    params(("article_id" = Uuid, Path)),
+    request_body = CreateCommentRequest,
+    responses((status = 200, description = "The new comment", body = CommentResponse)),
+    security(("bearer_auth" = [])),
+)]
 async fn create_comment(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     Path(article_id): Path<Uuid>,
     Json(payload): Json<CreateCommentRequest>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
-    let auth_header = headers
+    let token = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
-
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
-
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
-
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service
+        .authenticate(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
+
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
 
-    let engagement_service = EngagementService::new(state.db.pool.clone());
+    let engagement_service = EngagementService::new(state.db.pool.clone(), state.realtime.clone());
     
-    match engagement_service.create_comment(article_id, user_id, payload).await {
-        Ok(comment) => {
-            Ok(Json(serde_json::to_value(comment).unwrap()))
-        }
-        Err(e) => {
-            tracing::error!("Failed to create comment: {}", e);
-            let status = if e.to_string().contains("not found") {
-                StatusCode::NOT_FOUND
-            } else {
-                StatusCode::INTERNAL_SERVER_ERROR
-            };
-            Err((
-                status,
-                Json(json!({"error": e.to_string()})),
-            ))
-        }
-    }
+    let comment = engagement_service.create_comment(article_id, user_id, payload).await?;
+    Ok(Json(serde_json::to_value(comment)?))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{article_id}/highlights",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_highlights(
     State(_state): State<AppState>,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get highlights for article (user-specific if authenticated)
     Ok(Json(json!({
         "message": format!("Get highlights for article {} not implemented yet", article_id)
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/{article_id}/highlights",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn create_highlight(
     State(_state): State<AppState>,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Create highlight on article (requires auth middleware)
     Ok(Json(json!({
         "message": format!("Create highlight on article {} not implemented yet", article_id)
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/feed",
+    params(
+        ("page" = Option<i64>, Query, description = "Defaults to 1"),
+        ("limit" = Option<i64>, Query, description = "Defaults to 20"),
+    ),
+    responses((status = 200, description = "Articles from followed authors and topics", body = ArticleListResponse)),
+    security(("bearer_auth" = [])),
+)]
 async fn get_personalized_feed(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     Query(params): Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
-    let auth_header = headers
+    let token = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
-
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
-
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
-
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service
+        .authenticate(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
+
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
 
     let page = params.get("page")
         .and_then(|p| p.parse::<i64>().ok())
@@ -732,25 +644,28 @@ async fn get_personalized_feed(
         .and_then(|l| l.parse::<i64>().ok())
         .unwrap_or(20);
 
-    let article_service = ArticleService::new(state.db.pool.clone());
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     
-    match article_service.get_user_feed(user_id, Some(page), Some(limit)).await {
-        Ok(response) => Ok(Json(serde_json::to_value(response).unwrap())),
-        Err(e) => {
-            tracing::error!("Failed to get user feed: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get feed"})),
-            ))
-        }
-    }
+    let response = article_service.get_user_feed(user_id, Some(page), Some(limit)).await?;
+    Ok(Json(serde_json::to_value(response)?))
 }
 
+#[utoipa::path(
+    get,
+    path = "/trending",
+    params(
+        ("page" = Option<i64>, Query, description = "Defaults to 1"),
+        ("limit" = Option<i64>, Query, description = "Defaults to 20"),
+        ("time_window" = Option<i32>, Query, description = "Hours to look back; defaults to 168"),
+    ),
+    responses((status = 200, description = "Trending articles", body = ArticleListResponse)),
+    security((), ("bearer_auth" = [])),
+)]
 async fn get_trending_articles(
     State(state): State<AppState>,
     OptionalAuthUser(user): OptionalAuthUser,
     Query(params): Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     let page = params.get("page")
         .and_then(|p| p.parse::<i64>().ok())
         .unwrap_or(1);
@@ -761,70 +676,56 @@ async fn get_trending_articles(
         .and_then(|t| t.parse::<i32>().ok())
         .unwrap_or(168); // Default: 7 days
 
-    let article_service = ArticleService::new(state.db.pool.clone());
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     let user_id = user.map(|u| u.user_id);
     
-    match article_service.get_trending_articles(user_id, Some(page), Some(limit), Some(time_window)).await {
-        Ok(response) => Ok(Json(serde_json::to_value(response).unwrap())),
-        Err(e) => {
-            tracing::error!("Failed to get trending articles: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get trending articles"})),
-            ))
-        }
-    }
+    let response = article_service
+        .get_trending_articles(user_id, Some(page), Some(limit), Some(time_window))
+        .await?;
+    Ok(Json(serde_json::to_value(response)?))
 }
 
+#[utoipa::path(
+    get,
+    path = "/featured",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_featured_articles(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get featured/staff-picked articles
     Ok(Json(json!({
         "message": "Get featured articles not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/drafts",
+    responses((status = 200, description = "`{drafts, total}`: the signed-in user's drafts", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn get_drafts(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
-    let auth_header = headers
+    let token = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
-
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
-
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
-
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service
+        .authenticate(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
+
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
 
     // Get drafts for this user
     let drafts = sqlx::query_as::<_, Article>(
@@ -837,7 +738,7 @@ async fn get_drafts(
             COALESCE(categories, ARRAY[]::TEXT[])::TEXT[] as categories, 
             reading_time_minutes, 
             claps_count, comments_count, bookmarks_count, views_count, reads_count,
-            published_at, created_at, updated_at, last_auto_save, auto_save_version
+            published_at, created_at, updated_at, last_auto_save, auto_save_version, canonical_url, scheduled_at
         FROM articles 
         WHERE author_id = $1 AND status = 'draft'
         ORDER BY updated_at DESC, created_at DESC
@@ -846,86 +747,63 @@ async fn get_drafts(
     )
     .bind(user_id)
     .fetch_all(&state.db.pool)
-    .await;
+    .await?;
 
-    match drafts {
-        Ok(articles) => {
-            let draft_list: Vec<Value> = articles
-                .into_iter()
-                .map(|article| {
-                    json!({
-                        "id": article.id,
-                        "title": article.title,
-                        "subtitle": article.subtitle,
-                        "excerpt": article.excerpt,
-                        "updated_at": article.updated_at,
-                        "created_at": article.created_at,
-                        "last_auto_save": article.last_auto_save,
-                    })
-                })
-                .collect();
-            
-            Ok(Json(json!({
-                "drafts": draft_list,
-                "total": draft_list.len()
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to get drafts: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": format!("Failed to get drafts: {}", e)})),
-            ))
-        }
-    }
+    let draft_list: Vec<Value> = drafts
+        .into_iter()
+        .map(|article| {
+            json!({
+                "id": article.id,
+                "title": article.title,
+                "subtitle": article.subtitle,
+                "excerpt": article.excerpt,
+                "updated_at": article.updated_at,
+                "created_at": article.created_at,
+                "last_auto_save": article.last_auto_save,
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "drafts": draft_list,
+        "total": draft_list.len()
+    })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/drafts/{article_id}",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "The draft", body = ArticleResponse)),
+    security(("bearer_auth" = [])),
+)]
 async fn get_draft(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
-    let auth_header = headers
+    let token = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
-
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
-
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
-
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
 
-    let article_service = ArticleService::new(state.db.pool.clone());
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service
+        .authenticate(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
+
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
+
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     
     // Get draft article (must be draft and owned by user)
-    let article = match sqlx::query_as::<_, Article>(
+    let article = sqlx::query_as::<_, Article>(
         r#"
         SELECT 
             id, title, subtitle, content, content_html, excerpt, featured_image_url, 
@@ -935,7 +813,7 @@ async fn get_draft(
             COALESCE(categories, ARRAY[]::TEXT[])::TEXT[] as categories, 
             reading_time_minutes, 
             claps_count, comments_count, bookmarks_count, views_count, reads_count,
-            published_at, created_at, updated_at, last_auto_save, auto_save_version
+            published_at, created_at, updated_at, last_auto_save, auto_save_version, canonical_url, scheduled_at
         FROM articles 
         WHERE id = $1 AND author_id = $2 AND status = 'draft'
         "#,
@@ -943,293 +821,237 @@ async fn get_draft(
     .bind(article_id)
     .bind(user_id)
     .fetch_optional(&state.db.pool)
-    .await
-    {
-        Ok(article) => article,
-        Err(e) => {
-            tracing::error!("Database error fetching draft: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to fetch draft"})),
-            ));
-        }
-    };
-
-    match article {
-        Some(article) => {
-            match article_service.get_article_response(&article, Some(user_id)).await {
-                Ok(response) => Ok(Json(serde_json::to_value(response).unwrap())),
-                Err(e) => {
-                    tracing::error!("Failed to get draft response: {}", e);
-                    Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(json!({"error": format!("Failed to get draft: {}", e)})),
-                    ))
-                }
-            }
-        }
-        None => Err((
-            StatusCode::NOT_FOUND,
-            Json(json!({"error": "Draft not found or unauthorized"})),
-        )),
-    }
+    .await?
+    .ok_or_else(|| AppError::NotFound("Draft not found or unauthorized".to_string()))?;
+
+    let response = article_service.get_article_response(&article, Some(user_id)).await?;
+    Ok(Json(serde_json::to_value(response)?))
 }
 
+#[utoipa::path(
+    post,
+    path = "/{article_id}/publish",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "The published article", body = ArticleResponse)),
+    security(("bearer_auth" = [])),
+)]
 async fn publish_article(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
-    let auth_header = headers
+    let token = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
-
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
-
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
-
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
 
-    let article_service = ArticleService::new(state.db.pool.clone());
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service
+        .authenticate(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
+
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
+
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     
-    match article_service.publish_article(article_id, user_id).await {
-        Ok(response) => Ok(Json(serde_json::to_value(response).unwrap())),
-        Err(e) => {
-            tracing::error!("Failed to publish article: {}", e);
-            let status = if e.to_string().contains("not found") || e.to_string().contains("unauthorized") {
-                StatusCode::NOT_FOUND
-            } else {
-                StatusCode::INTERNAL_SERVER_ERROR
-            };
-            Err((status, Json(json!({"error": e.to_string()}))))
-        }
-    }
+    let response = article_service.publish_article(article_id, user_id).await?;
+    Ok(Json(serde_json::to_value(response)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{article_id}/schedule",
+    params(("article_id" = Uuid, Path)),
+    request_body = ScheduleArticleRequest,
+    responses((status = 200, description = "The scheduled article", body = ArticleResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn schedule_article(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(article_id): Path<Uuid>,
+    Json(payload): Json<ScheduleArticleRequest>,
+) -> Result<Json<Value>, AppError> {
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
+
+    let response = article_service.schedule_article(article_id, user.user_id, payload).await?;
+    Ok(Json(serde_json::to_value(response)?))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/{article_id}/schedule",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "The article, back to draft", body = ArticleResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn cancel_schedule(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(article_id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
+
+    let response = article_service.cancel_schedule(article_id, user.user_id).await?;
+    Ok(Json(serde_json::to_value(response)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/draft/auto-save",
+    request_body = AutoSaveDraftRequest,
+    responses((status = 200, description = "`{message, article_id, auto_saved_at}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn auto_save_draft(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
-    Json(payload): Json<crate::models::AutoSaveDraftRequest>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    Json(payload): Json<AutoSaveDraftRequest>,
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
-    let auth_header = headers
+    let token = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
-
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
-
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
-
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service
+        .authenticate(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
+
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
 
-    let article_service = ArticleService::new(state.db.pool.clone());
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     
-    match article_service.auto_save_draft(user_id, &payload).await {
-        Ok(article_id) => {
-            Ok(Json(json!({
-                "message": "Draft auto-saved successfully",
-                "article_id": article_id,
-                "auto_saved_at": chrono::Utc::now()
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to auto-save draft: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to auto-save draft"})),
-            ))
-        }
-    }
+    let article_id = article_service.auto_save_draft(user_id, &payload).await?;
+    Ok(Json(json!({
+        "message": "Draft auto-saved successfully",
+        "article_id": article_id,
+        "auto_saved_at": chrono::Utc::now()
+    })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{article_id}/stats",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "View, read and clap counts", body = ArticleStats)),
+)]
 async fn get_article_stats(
     State(state): State<AppState>,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let article_service = ArticleService::new(state.db.pool.clone());
+) -> Result<Json<Value>, AppError> {
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     
-    match article_service.get_article_stats(article_id).await {
-        Ok(stats) => {
-            Ok(Json(serde_json::to_value(stats).unwrap()))
-        }
-        Err(e) => {
-            tracing::error!("Failed to get article stats: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get article stats"})),
-            ))
-        }
-    }
+    let stats = article_service.get_article_stats(article_id).await?;
+    Ok(Json(serde_json::to_value(stats)?))
 }
 
+#[utoipa::path(
+    post,
+    path = "/{article_id}/featured",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "`{message, is_featured}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn toggle_featured(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
-    let auth_header = headers
+    let token = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
-        .and_then(|header| header.strip_prefix("Bearer "));
-
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
-
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
-
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service
+        .authenticate(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
 
-    let article_service = ArticleService::new(state.db.pool.clone());
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
+
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
     
-    match article_service.toggle_featured(article_id, Some(user_id)).await {
-        Ok(is_featured) => {
-            Ok(Json(json!({
-                "message": if is_featured { "Article featured successfully" } else { "Article unfeatured successfully" },
-                "is_featured": is_featured
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to toggle featured: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to toggle featured status"})),
-            ))
-        }
-    }
+    let is_featured = article_service.toggle_featured(article_id, Some(user_id)).await?;
+    Ok(Json(json!({
+        "message": if is_featured { "Article featured successfully" } else { "Article unfeatured successfully" },
+        "is_featured": is_featured
+    })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/categories",
+    responses((status = 200, description = "`{categories}` of published articles", body = Value)),
+)]
 async fn get_categories(
     State(state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Get unique categories from published articles
     let categories = sqlx::query_scalar!(
         "SELECT DISTINCT unnest(categories) as category FROM articles WHERE status = 'published' AND categories IS NOT NULL AND array_length(categories, 1) > 0 ORDER BY category"
     )
     .fetch_all(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to get categories: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": "Failed to get categories"})),
-        )
-    })?;
+    .await?;
     
     Ok(Json(json!({
         "categories": categories
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/tags",
+    responses((status = 200, description = "`{tags}` with article and follower counts", body = Value)),
+)]
 async fn get_tags(
     State(state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Get unique tags from published articles with count
     let tags_with_count = sqlx::query!(
         r#"
         SELECT 
-            unnest(tags) as tag,
-            COUNT(*) as article_count
-        FROM articles 
-        WHERE status = 'published' AND tags IS NOT NULL AND array_length(tags, 1) > 0 
-        GROUP BY tag 
-        ORDER BY article_count DESC, tag
+            t.tag as "tag!",
+            t.article_count as "article_count!",
+            (SELECT COUNT(*) FROM tag_follows tf WHERE tf.tag = t.tag) as "follower_count!"
+        FROM (
+            SELECT unnest(tags) as tag, COUNT(*) as article_count
+            FROM articles 
+            WHERE status = 'published' AND tags IS NOT NULL AND array_length(tags, 1) > 0 
+            GROUP BY tag 
+        ) t
+        ORDER BY t.article_count DESC, t.tag
         LIMIT 100
         "#
     )
     .fetch_all(&state.db.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to get tags: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": "Failed to get tags"})),
-        )
-    })?;
+    .await?;
     
     let tags: Vec<serde_json::Value> = tags_with_count
         .into_iter()
         .map(|row| {
             json!({
                 "tag": row.tag,
-                "article_count": row.article_count
+                "article_count": row.article_count,
+                "follower_count": row.follower_count
             })
         })
         .collect();
@@ -1238,3 +1060,169 @@ async fn get_tags(
         "tags": tags
     })))
 }
+
+#[utoipa::path(
+    get,
+    path = "/tags/{tag}",
+    params(("tag" = String, Path)),
+    params(ArticleQueryParams),
+    responses((status = 200, description = "The tag and its articles", body = TopicPageResponse)),
+    security((), ("bearer_auth" = [])),
+)]
+async fn get_tag_page(
+    State(state): State<AppState>,
+    OptionalAuthUser(user): OptionalAuthUser,
+    Path(tag): Path<String>,
+    Query(params): Query<ArticleQueryParams>,
+) -> Result<Json<Value>, AppError> {
+    get_topic_page(&state, user, TopicKind::Tag, &tag, params).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/categories/{category}",
+    params(("category" = String, Path)),
+    params(ArticleQueryParams),
+    responses((status = 200, description = "The category and its articles", body = TopicPageResponse)),
+    security((), ("bearer_auth" = [])),
+)]
+async fn get_category_page(
+    State(state): State<AppState>,
+    OptionalAuthUser(user): OptionalAuthUser,
+    Path(category): Path<String>,
+    Query(params): Query<ArticleQueryParams>,
+) -> Result<Json<Value>, AppError> {
+    get_topic_page(&state, user, TopicKind::Category, &category, params).await
+}
+
+async fn get_topic_page(
+    state: &AppState,
+    user: Option<AuthUser>,
+    kind: TopicKind,
+    name: &str,
+    params: ArticleQueryParams,
+) -> Result<Json<Value>, AppError> {
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
+    let user_id = user.map(|u| u.user_id);
+
+    let response = article_service
+        .get_topic_page(kind, name.trim(), params.page, params.limit, user_id)
+        .await?;
+    Ok(Json(serde_json::to_value(response)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/tags/{tag}/follow",
+    params(("tag" = String, Path)),
+    responses((status = 200, description = "`{message, name, kind, is_following}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn follow_tag(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(tag): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    follow_topic(&state, user, TopicKind::Tag, &tag).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tags/{tag}/follow",
+    params(("tag" = String, Path)),
+    responses((status = 200, description = "`{message, name, kind, is_following}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn unfollow_tag(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(tag): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    unfollow_topic(&state, user, TopicKind::Tag, &tag).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/categories/{category}/follow",
+    params(("category" = String, Path)),
+    responses((status = 200, description = "`{message, name, kind, is_following}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn follow_category(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(category): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    follow_topic(&state, user, TopicKind::Category, &category).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/categories/{category}/follow",
+    params(("category" = String, Path)),
+    responses((status = 200, description = "`{message, name, kind, is_following}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn unfollow_category(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(category): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    unfollow_topic(&state, user, TopicKind::Category, &category).await
+}
+
+async fn follow_topic(
+    state: &AppState,
+    user: AuthUser,
+    kind: TopicKind,
+    name: &str,
+) -> Result<Json<Value>, AppError> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > 100 {
+        return Err(AppError::BadRequest("Invalid topic name".to_string()));
+    }
+
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
+
+    article_service.follow_topic(user.user_id, kind, name).await?;
+    Ok(Json(json!({
+        "message": "Topic followed",
+        "name": name,
+        "kind": kind,
+        "is_following": true
+    })))
+}
+
+async fn unfollow_topic(
+    state: &AppState,
+    user: AuthUser,
+    kind: TopicKind,
+    name: &str,
+) -> Result<Json<Value>, AppError> {
+    let name = name.trim();
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
+
+    article_service.unfollow_topic(user.user_id, kind, name).await?;
+    Ok(Json(json!({
+        "message": "Topic unfollowed",
+        "name": name,
+        "kind": kind,
+        "is_following": false
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/topics/following",
+    responses((status = 200, description = "Tags and categories the user follows", body = FollowedTopicsResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_followed_topics(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
+
+    let response = article_service.get_followed_topics(user.user_id).await?;
+    Ok(Json(serde_json::to_value(response)?))
+}
@@ -1,198 +1,435 @@
 use axum::{
-    extract::State,
-    http::{StatusCode, HeaderMap},
-    response::Json,
+    extract::{Path, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Json, Response},
     routing::{post, get},
     Router,
 };
 use serde_json::{json, Value};
+use utoipa::OpenApi;
 use validator::Validate;
 
 use crate::{
-    models::{CreateUserRequest, LoginRequest, AuthResponse, UserResponse},
-    services::auth::AuthService,
+    error::AppError,
+    middleware::{
+        auth::AuthUser,
+        rate_limit::{self, ClientIp},
+    },
+    models::{
+        CreateUserRequest, LoginRequest, AuthResponse, UserResponse,
+        identity::{OidcAuthorizationResponse, OidcCallbackRequest},
+        two_factor::{
+            DisableTwoFactorRequest, LoginResponse, RecoveryCodesResponse, TwoFactorCodeRequest,
+            TwoFactorSetupResponse, TwoFactorStatusResponse, TwoFactorVerifyRequest,
+        },
+    },
+    services::{
+        auth::AuthService,
+        jwt,
+        oidc::OidcService,
+        two_factor::TwoFactorService,
+    },
     AppState,
 };
 
+// Other services verify our tokens with these keys; served at the site root
+pub fn well_known_routes() -> Router<AppState> {
+    Router::new()
+        .route("/.well-known/jwks.json", get(jwks))
+}
+
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/register", post(register))
         .route("/login", post(login))
+        .route("/forgot-password", post(forgot_password))
+        .route("/reset-password", post(reset_password))
+        .route("/oidc/callback", post(oidc_callback))
+        .route("/2fa/verify", post(verify_two_factor))
+        .route("/2fa/confirm", post(confirm_two_factor))
+        .route("/2fa/disable", post(disable_two_factor))
+        .route("/2fa/recovery-codes", post(regenerate_recovery_codes))
+        // Per-IP limit on the endpoints brute-force and spam scripts go after: everything that
+        // signs in, completes a sign-in or checks a password or one-time code
+        .route_layer(rate_limit::limit(rate_limit::AUTH))
         .route("/refresh", post(refresh_token))
         .route("/logout", post(logout))
         .route("/me", get(get_current_user_simple))
         .route("/verify-email", post(verify_email))
-        .route("/forgot-password", post(forgot_password))
-        .route("/reset-password", post(reset_password))
+        .route("/oidc/providers", get(list_oidc_providers))
+        .route("/oidc/:provider/authorize", post(start_oidc_login))
+        .route("/2fa", get(get_two_factor_status))
+        .route("/2fa/setup", post(setup_two_factor))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    register,
+    login,
+    forgot_password,
+    refresh_token,
+    logout,
+    get_current_user_simple,
+    verify_email,
+    reset_password,
+    list_oidc_providers,
+    start_oidc_login,
+    oidc_callback,
+    get_two_factor_status,
+    setup_two_factor,
+    confirm_two_factor,
+    disable_two_factor,
+    regenerate_recovery_codes,
+    verify_two_factor,
+))]
+pub struct AuthApi;
+
+#[derive(OpenApi)]
+#[openapi(paths(jwks))]
+pub struct WellKnownApi;
+
+// Short cache so verifiers see a rotated-in key soon after it starts signing
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    responses((status = 200, description = "Public keys that verify session tokens (JWK Set)", body = Value)),
+)]
+async fn jwks() -> Response {
+    (
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(jwt::keys().jwks()),
+    )
+        .into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/register",
+    request_body = CreateUserRequest,
+    responses((status = 200, description = "Account created and signed in", body = AuthResponse)),
+)]
 async fn register(
     State(state): State<AppState>,
     Json(payload): Json<CreateUserRequest>,
-) -> Result<Json<AuthResponse>, (StatusCode, Json<Value>)> {
-    // Validate input
-    if let Err(errors) = payload.validate() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": "Validation failed",
-                "details": errors
-            })),
-        ));
-    }
+) -> Result<Json<AuthResponse>, AppError> {
+    payload.validate()?;
 
-    let auth_service = AuthService::new(&state.db, &state.config);
+    let auth_service = AuthService::new(&state.db);
     
-    match auth_service.register(payload).await {
-        Ok(response) => Ok(Json(response)),
-        Err(e) => {
-            tracing::error!("Registration failed: {}", e);
-            Err((
-                StatusCode::BAD_REQUEST,
-                Json(json!({
-                    "error": "Registration failed",
-                    "message": e.to_string()
-                })),
-            ))
-        }
-    }
+    Ok(Json(auth_service.register(payload).await?))
 }
 
+#[utoipa::path(
+    post,
+    path = "/login",
+    request_body = LoginRequest,
+    responses((status = 200, description = "A session token, or a challenge when the account has two-factor authentication", body = LoginResponse)),
+)]
 async fn login(
     State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<AuthResponse>, (StatusCode, Json<Value>)> {
+) -> Result<Json<LoginResponse>, AppError> {
     // Validate input (password is required, email/username field just needs to be non-empty)
     if payload.email.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": "Validation failed",
-                "message": "Email or username is required"
-            })),
-        ));
+        return Err(AppError::BadRequest("Email or username is required".to_string()));
     }
     
     if payload.password.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": "Validation failed",
-                "message": "Password is required"
-            })),
-        ));
+        return Err(AppError::BadRequest("Password is required".to_string()));
     }
 
-    let auth_service = AuthService::new(&state.db, &state.config);
+    let auth_service = AuthService::new(&state.db);
     
-    match auth_service.login(payload).await {
+    match auth_service.login(&state.config, payload, &client_ip.to_string()).await {
         Ok(response) => Ok(Json(response)),
         Err(e) => {
-            tracing::error!("Login failed: {}", e);
-            Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({
-                    "error": "Authentication failed",
-                    "message": "Invalid email/username or password"
-                })),
-            ))
+            if let AppError::RateLimited { message, .. } | AppError::Locked { message, .. } = &e {
+                tracing::warn!("Login throttled for {}: {}", client_ip, message);
+            }
+            Err(e)
         }
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/oidc/providers",
+    responses((status = 200, description = "`{providers}` that can be signed in with", body = Value)),
+)]
+async fn list_oidc_providers(
+    State(state): State<AppState>,
+) -> Json<Value> {
+    let oidc_service = OidcService::new(&state.db, &state.config);
+
+    Json(json!({ "providers": oidc_service.list_providers() }))
+}
+
+// Returns the provider's authorization URL to send the browser to
+#[utoipa::path(
+    post,
+    path = "/oidc/{provider}/authorize",
+    params(("provider" = String, Path, description = "Provider id from /oidc/providers")),
+    responses((status = 200, description = "Where to send the browser", body = OidcAuthorizationResponse)),
+)]
+async fn start_oidc_login(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let oidc_service = OidcService::new(&state.db, &state.config);
+
+    let response = oidc_service.start(&provider, None).await?;
+    Ok(Json(serde_json::to_value(response)?))
+}
+
+// The frontend posts the `code` and `state` the provider redirected back with
+#[utoipa::path(
+    post,
+    path = "/oidc/callback",
+    request_body = OidcCallbackRequest,
+    responses((status = 200, description = "A session token, or a two-factor challenge", body = LoginResponse)),
+)]
+async fn oidc_callback(
+    State(state): State<AppState>,
+    Json(payload): Json<OidcCallbackRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let oidc_service = OidcService::new(&state.db, &state.config);
+
+    Ok(Json(oidc_service.login(&payload.code, &payload.state).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/2fa",
+    responses((status = 200, description = "Two-factor status of the account", body = TwoFactorStatusResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_two_factor_status(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let two_factor_service = TwoFactorService::new(&state.db);
+
+    let status = two_factor_service.status(user.user_id).await?;
+    Ok(Json(serde_json::to_value(status)?))
+}
+
+// New secret and provisioning URI; 2FA is only turned on by /2fa/confirm
+#[utoipa::path(
+    post,
+    path = "/2fa/setup",
+    responses((status = 200, description = "New secret; not active until confirmed", body = TwoFactorSetupResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn setup_two_factor(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let two_factor_service = TwoFactorService::new(&state.db);
+
+    let setup = two_factor_service.setup(user.user_id).await?;
+    Ok(Json(serde_json::to_value(setup)?))
+}
+
+// `{"code": "123456"}` from the authenticator; returns the recovery codes, shown only this once
+#[utoipa::path(
+    post,
+    path = "/2fa/confirm",
+    request_body = TwoFactorCodeRequest,
+    responses((status = 200, description = "Two-factor enabled; the recovery codes are shown only this once", body = RecoveryCodesResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn confirm_two_factor(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<Json<Value>, AppError> {
+    let Some(code) = payload.code else {
+        return Err(AppError::BadRequest("A two-factor code is required".to_string()));
+    };
+
+    let two_factor_service = TwoFactorService::new(&state.db);
+
+    let codes = two_factor_service.confirm(user.user_id, &code).await?;
+    Ok(Json(serde_json::to_value(codes)?))
+}
+
+// Needs the password and a current code (or a recovery code)
+#[utoipa::path(
+    post,
+    path = "/2fa/disable",
+    request_body = DisableTwoFactorRequest,
+    responses((status = 200, description = "Two-factor disabled", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn disable_two_factor(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<DisableTwoFactorRequest>,
+) -> Result<Json<Value>, AppError> {
+    let auth_service = AuthService::new(&state.db);
+    let two_factor_service = TwoFactorService::new(&state.db);
+
+    let password_check = auth_service.confirm_password(user.user_id, &payload.password);
+    if !two_factor_service.guard_step_up(user.user_id, password_check).await? {
+        return Err(AppError::Forbidden("Incorrect password".to_string()));
+    }
+
+    check_second_factor(&two_factor_service, &user, payload.code.as_deref(), payload.recovery_code.as_deref()).await?;
+
+    two_factor_service.disable(user.user_id).await?;
+    Ok(Json(json!({"message": "Two-factor authentication disabled"})))
+}
+
+// Replaces all recovery codes; needs a current code
+#[utoipa::path(
+    post,
+    path = "/2fa/recovery-codes",
+    request_body = TwoFactorCodeRequest,
+    responses((status = 200, description = "Replacement recovery codes", body = RecoveryCodesResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<Json<Value>, AppError> {
+    let two_factor_service = TwoFactorService::new(&state.db);
+
+    check_second_factor(&two_factor_service, &user, payload.code.as_deref(), payload.recovery_code.as_deref()).await?;
+
+    let codes = two_factor_service.replace_recovery_codes(user.user_id).await?;
+    Ok(Json(serde_json::to_value(codes)?))
+}
+
+// Second login step: the challenge token from /login plus a code or recovery code
+#[utoipa::path(
+    post,
+    path = "/2fa/verify",
+    request_body = TwoFactorVerifyRequest,
+    responses((status = 200, description = "Signed in", body = AuthResponse)),
+)]
+async fn verify_two_factor(
+    State(state): State<AppState>,
+    Json(payload): Json<TwoFactorVerifyRequest>,
+) -> Result<Json<AuthResponse>, AppError> {
+    let auth_service = AuthService::new(&state.db);
+
+    let response = auth_service
+        .login_with_two_factor(
+            &payload.challenge_token,
+            payload.code.as_deref(),
+            payload.recovery_code.as_deref(),
+        )
+        .await?;
+    Ok(Json(response))
+}
+
+async fn check_second_factor(
+    two_factor_service: &TwoFactorService<'_>,
+    user: &AuthUser,
+    code: Option<&str>,
+    recovery_code: Option<&str>,
+) -> Result<(), AppError> {
+    let code_check = two_factor_service.verify(user.user_id, code, recovery_code);
+    if !two_factor_service.guard_step_up(user.user_id, code_check).await? {
+        return Err(AppError::Forbidden("Invalid two-factor code".to_string()));
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/refresh",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn refresh_token(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Implement token refresh logic
     Ok(Json(json!({
         "message": "Token refresh not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/logout",
+    responses((status = 200, description = "Signed out", body = Value)),
+)]
 async fn logout(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Implement logout logic (blacklist token)
     Ok(Json(json!({
         "message": "Logged out successfully"
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/me",
+    responses((status = 200, description = "The signed-in user", body = UserResponse)),
+    security(("bearer_auth" = [])),
+)]
 async fn get_current_user_simple(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<UserResponse>, (StatusCode, Json<Value>)> {
+) -> Result<Json<UserResponse>, AppError> {
     // Extract token from Authorization header
     let auth_header = headers.get("authorization")
         .and_then(|header| header.to_str().ok())
-        .ok_or_else(|| {
-            (
-                StatusCode::UNAUTHORIZED,
-                Json(json!({
-                    "error": "Authorization header required"
-                }))
-            )
-        })?;
+        .ok_or_else(|| AppError::Unauthorized("Authorization header required".to_string()))?;
 
     let token = auth_header.strip_prefix("Bearer ")
-        .ok_or_else(|| {
-            (
-                StatusCode::UNAUTHORIZED,
-                Json(json!({
-                    "error": "Invalid authorization format"
-                }))
-            )
-        })?;
-
-    let auth_service = AuthService::new(&state.db, &state.config);
+        .ok_or_else(|| AppError::Unauthorized("Invalid authorization format".to_string()))?;
+
+    let auth_service = AuthService::new(&state.db);
     
     // Verify token and get claims
-    let claims = auth_service.verify_token(token).await
-        .map_err(|_| {
-            (
-                StatusCode::UNAUTHORIZED,
-                Json(json!({
-                    "error": "Invalid or expired token"
-                }))
-            )
-        })?;
+    let claims = auth_service.authenticate(token).await?;
     
     // Get user by ID from claims
-    match auth_service.get_user_by_id(&claims.sub).await {
-        Ok(user) => Ok(Json(user.into())),
-        Err(e) => {
-            tracing::error!("Failed to get user: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "error": "Failed to get user information"
-                }))
-            ))
-        }
-    }
+    let user = auth_service.get_user_by_id(&claims.sub).await?;
+    Ok(Json(user.into()))
 }
 
+#[utoipa::path(
+    post,
+    path = "/verify-email",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn verify_email(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Implement email verification
     Ok(Json(json!({
         "message": "Email verification not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/forgot-password",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn forgot_password(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Implement forgot password
     Ok(Json(json!({
         "message": "Forgot password not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/reset-password",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn reset_password(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Implement reset password
     Ok(Json(json!({
         "message": "Reset password not implemented yet"
@@ -0,0 +1,123 @@
+use axum::{
+    extract::{Query, State},
+    response::{Html, Json},
+    routing::get,
+    Router,
+};
+use serde_json::{json, Value};
+use utoipa::OpenApi;
+
+use super::render_notice;
+use crate::{
+    error::AppError,
+    middleware::auth::AuthUser,
+    models::digest::{DigestPreviewResponse, DigestSettingsResponse, UnsubscribeQuery, UpdateDigestSettingsRequest},
+    services::digest::{self, DigestService},
+    AppState,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/settings", get(get_settings).put(update_settings))
+        .route("/preview", get(preview_digest))
+        // GET from the email footer, POST for one-click List-Unsubscribe (RFC 8058)
+        .route("/unsubscribe", get(unsubscribe_page).post(unsubscribe_one_click))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_settings, update_settings, preview_digest, unsubscribe_page, unsubscribe_one_click))]
+pub struct DigestApi;
+
+#[utoipa::path(
+    get,
+    path = "/settings",
+    responses((status = 200, description = "Digest frequency", body = DigestSettingsResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_settings(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let digest_service = DigestService::new(state.db.pool.clone(), &state.config);
+
+    let settings = digest_service.get_settings(user.user_id).await?;
+    Ok(Json(serde_json::to_value(settings)?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/settings",
+    request_body = UpdateDigestSettingsRequest,
+    responses((status = 200, description = "`{message, settings}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn update_settings(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<UpdateDigestSettingsRequest>,
+) -> Result<Json<Value>, AppError> {
+    let digest_service = DigestService::new(state.db.pool.clone(), &state.config);
+
+    let settings = digest_service.update_settings(user.user_id, payload.frequency).await?;
+    Ok(Json(json!({
+        "message": "Digest settings updated",
+        "settings": settings
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/preview",
+    responses((status = 200, description = "The articles the next digest would contain", body = DigestPreviewResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn preview_digest(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let digest_service = DigestService::new(state.db.pool.clone(), &state.config);
+
+    let preview = digest_service.preview(user.user_id).await?;
+    Ok(Json(serde_json::to_value(preview)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/unsubscribe",
+    params(UnsubscribeQuery),
+    responses((status = 200, description = "Confirmation page", content_type = "text/html", body = String)),
+)]
+async fn unsubscribe_page(
+    State(state): State<AppState>,
+    Query(query): Query<UnsubscribeQuery>,
+) -> Result<Html<String>, AppError> {
+    let digest_service = DigestService::new(state.db.pool.clone(), &state.config);
+
+    digest_service.unsubscribe(&query.token).await?;
+
+    render_notice(
+        "You've been unsubscribed",
+        "You won't receive FastBlog digest emails anymore.",
+        &digest::settings_url(&state.config),
+        "Manage email settings",
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/unsubscribe",
+    params(UnsubscribeQuery),
+    responses((status = 200, description = "Unsubscribed (RFC 8058 one-click)", body = Value)),
+)]
+async fn unsubscribe_one_click(
+    State(state): State<AppState>,
+    Query(query): Query<UnsubscribeQuery>,
+) -> Result<Json<Value>, AppError> {
+    let digest_service = DigestService::new(state.db.pool.clone(), &state.config);
+
+    digest_service.unsubscribe(&query.token).await?;
+
+    Ok(Json(json!({
+        "message": "Unsubscribed from email digests"
+    })))
+}
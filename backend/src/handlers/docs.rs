@@ -0,0 +1,238 @@
+use axum::{
+    http::{header, HeaderValue},
+    Router,
+};
+use tower_http::set_header::SetResponseHeaderLayer;
+use utoipa::{
+    openapi::{
+        security::{Http, HttpAuthScheme, SecurityScheme},
+        tag::TagBuilder,
+        ContentBuilder, Ref, ResponseBuilder,
+    },
+    OpenApi,
+};
+use utoipa_swagger_ui::SwaggerUi;
+
+use super::{
+    admin, articles, auth, digest, engagement, exports, feeds, files, imports, media, newsletter, notifications,
+    realtime, search, seo, tokens, upload, users,
+};
+use crate::{
+    error::{FieldError, ProblemDetails},
+    AppState,
+};
+
+pub const OPENAPI_PATH: &str = "/api/v1/openapi.json";
+
+// The bundled UI loads its own scripts, styles and inline SVG icons; everything else
+// stays as locked down as the API's default CSP
+const DOCS_CSP: &str = "default-src 'none'; script-src 'self'; style-src 'self' 'unsafe-inline'; \
+                        img-src 'self' data:; connect-src 'self'; frame-ancestors 'none'";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "FastBlog API",
+        description = "Errors are RFC 7807 problem details (`application/problem+json`) with a stable `code`."
+    ),
+    components(schemas(ProblemDetails, FieldError))
+)]
+struct ApiDoc;
+
+// Everything nested under /api/v1 in `api_routes`: prefix, tag description and the
+// module's documented operations. The tag is the prefix without its slash.
+fn api_modules() -> Vec<(&'static str, &'static str, utoipa::openapi::OpenApi)> {
+    vec![
+        ("/auth", "Registration, sign-in, two-factor authentication and OIDC", auth::AuthApi::openapi()),
+        ("/users", "Profiles, follows and account settings", users::UsersApi::openapi()),
+        ("/articles", "Articles, drafts, comments and topics", articles::ArticlesApi::openapi()),
+        ("/engagement", "Comments, highlights and reading lists", engagement::EngagementApi::openapi()),
+        ("/search", "Full-text search", search::SearchApi::openapi()),
+        ("/admin", "Moderation and background jobs", admin::AdminApi::openapi()),
+        ("/upload", "Avatar uploads", upload::UploadApi::openapi()),
+        ("/media", "Article image uploads and the media library", media::MediaApi::openapi()),
+        ("/files", "Signed, expiring links to private files", files::FilesApi::openapi()),
+        ("/imports", "Imports from Medium, WordPress and Ghost", imports::ImportsApi::openapi()),
+        ("/exports", "Markdown and account data exports", exports::ExportsApi::openapi()),
+        ("/tokens", "Personal access tokens", tokens::TokensApi::openapi()),
+        ("/notifications", "Notification inbox and preferences", notifications::NotificationsApi::openapi()),
+        ("/realtime", "Live notifications and article counts (Server-Sent Events)", realtime::RealtimeApi::openapi()),
+        ("/digest", "Email digest settings", digest::DigestApi::openapi()),
+        ("/newsletter", "Author and publication newsletters", newsletter::NewsletterApi::openapi()),
+        ("/feeds", "RSS, Atom and JSON Feed syndication", feeds::FeedsApi::openapi()),
+        ("/seo", "Open Graph, Twitter Card and JSON-LD metadata", seo::SeoApi::openapi()),
+    ]
+}
+
+// Routes outside /api/v1, documented at their own paths: tag, tag description and operations
+fn root_modules() -> Vec<(&'static str, &'static str, utoipa::openapi::OpenApi)> {
+    vec![
+        ("health", "Liveness check", crate::HealthApi::openapi()),
+        ("crawlers", "robots.txt and XML sitemaps", seo::CrawlerApi::openapi()),
+        ("well-known", "Public keys that verify session tokens", auth::WellKnownApi::openapi()),
+    ]
+}
+
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    let mut doc = ApiDoc::openapi();
+    let mut tags = Vec::new();
+
+    for (prefix, description, mut api) in api_modules() {
+        let tag = &prefix[1..];
+        tag_operations(&mut api, tag);
+        tags.push(TagBuilder::new().name(tag).description(Some(description)).build());
+
+        // `/` is the prefix itself, like in the router
+        doc = doc.nest_with_path_composer(format!("/api/v1{}", prefix), api, |base, path| {
+            if path == "/" {
+                base.to_string()
+            } else {
+                format!("{}{}", base, path)
+            }
+        });
+    }
+    for (tag, description, mut api) in root_modules() {
+        tag_operations(&mut api, tag);
+        tags.push(TagBuilder::new().name(tag).description(Some(description)).build());
+        doc.merge(api);
+    }
+    doc.tags = Some(tags);
+
+    // Any operation can fail with a problem+json body
+    let problem = ResponseBuilder::new()
+        .description("Error, as RFC 7807 problem details")
+        .content(
+            "application/problem+json",
+            ContentBuilder::new().schema(Some(Ref::from_schema_name("ProblemDetails"))).build(),
+        )
+        .build();
+    for item in doc.paths.paths.values_mut() {
+        for operation in operations(item) {
+            operation
+                .responses
+                .responses
+                .entry("default".to_string())
+                .or_insert_with(|| problem.clone().into());
+        }
+    }
+
+    // Session JWTs and personal access tokens
+    if let Some(components) = doc.components.as_mut() {
+        components.add_security_scheme("bearer_auth", SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)));
+    }
+
+    doc
+}
+
+fn tag_operations(api: &mut utoipa::openapi::OpenApi, tag: &str) {
+    for item in api.paths.paths.values_mut() {
+        for operation in operations(item) {
+            operation.tags = Some(vec![tag.to_string()]);
+            // Handler names repeat across modules (`unsubscribe_page`, ...)
+            operation.operation_id = operation.operation_id.take().map(|id| format!("{}_{}", tag, id));
+        }
+    }
+}
+
+fn operations(item: &mut utoipa::openapi::PathItem) -> impl Iterator<Item = &mut utoipa::openapi::path::Operation> {
+    [
+        &mut item.get,
+        &mut item.put,
+        &mut item.post,
+        &mut item.delete,
+        &mut item.options,
+        &mut item.head,
+        &mut item.patch,
+        &mut item.trace,
+    ]
+    .into_iter()
+    .filter_map(Option::as_mut)
+}
+
+// The OpenAPI document and the Swagger UI at /docs
+pub fn routes() -> Router<AppState> {
+    Router::from(SwaggerUi::new("/docs").url(OPENAPI_PATH, openapi())).layer(SetResponseHeaderLayer::overriding(
+        header::CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static(DOCS_CSP),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use regex::Regex;
+
+    use super::{openapi, OPENAPI_PATH};
+
+    // Served as-is rather than as API operations: the Swagger UI with its document, and stored files
+    const UNDOCUMENTED: &[&str] = &["/docs", OPENAPI_PATH, "/uploads/"];
+
+    // Every (method, path) of the router the server runs, so a route added without a
+    // `#[utoipa::path]` (or left out of its module's OpenApi struct) fails here. axum can't
+    // list its routes, but its Debug output maps each route id to a method router and a path.
+    fn routed_operations() -> BTreeSet<(String, String)> {
+        let router = format!("{:?}", crate::app_routes("uploads"));
+        // Nested routers are flattened into the main path router; the fallback router has no routes
+        let router = &router[..router.find("fallback_router").unwrap()];
+        let (endpoints, paths) = router.split_once("node: Node").unwrap();
+
+        let path = Regex::new(r#"RouteId\((\d+)\): "([^"]+)""#).unwrap();
+        let endpoint = Regex::new(r"RouteId\((\d+)\): MethodRouter\(MethodRouter \{ (.*?), fallback:").unwrap();
+        let method = Regex::new(r"\b(get|post|put|patch|delete): (?:BoxedHandler|Route)\b").unwrap();
+        let param = Regex::new(r"[:*](\w+)").unwrap();
+
+        let paths: HashMap<&str, String> = path
+            .captures_iter(paths)
+            .map(|route| (route.get(1).unwrap().as_str(), param.replace_all(&route[2], "{$1}").into_owned()))
+            .collect();
+
+        let mut operations = BTreeSet::new();
+        for routed in endpoint.captures_iter(endpoints) {
+            let path = &paths[&routed[1]];
+            if UNDOCUMENTED.iter().any(|prefix| path.starts_with(prefix)) {
+                continue;
+            }
+            for handler in method.captures_iter(&routed[2]) {
+                operations.insert((handler[1].to_string(), path.clone()));
+            }
+        }
+        operations
+    }
+
+    fn documented_operations() -> BTreeSet<(String, String)> {
+        let doc = serde_json::to_value(openapi()).unwrap();
+        let mut operations = BTreeSet::new();
+        for (path, item) in doc["paths"].as_object().unwrap() {
+            for method in item.as_object().unwrap().keys() {
+                operations.insert((method.clone(), path.clone()));
+            }
+        }
+        operations
+    }
+
+    #[test]
+    fn every_route_is_documented() {
+        let routed = routed_operations();
+        assert!(routed.len() > 100, "found only {} routes", routed.len());
+
+        let documented = documented_operations();
+        let undocumented: Vec<_> = routed.difference(&documented).collect();
+        assert!(undocumented.is_empty(), "routes missing from the OpenAPI document: {:?}", undocumented);
+
+        let stale: Vec<_> = documented.difference(&routed).collect();
+        assert!(stale.is_empty(), "documented operations without a route: {:?}", stale);
+    }
+
+    #[test]
+    fn operation_ids_are_unique() {
+        let doc = serde_json::to_value(openapi()).unwrap();
+        let mut seen = BTreeSet::new();
+        for item in doc["paths"].as_object().unwrap().values() {
+            for operation in item.as_object().unwrap().values() {
+                let id = operation["operationId"].as_str().unwrap().to_string();
+                assert!(seen.insert(id.clone()), "duplicate operationId {}", id);
+            }
+        }
+    }
+}
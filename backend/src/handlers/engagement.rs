@@ -1,20 +1,20 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
     response::Json,
     routing::{get, post},
     Router,
 };
 use serde_json::{json, Value};
+use utoipa::OpenApi;
 use uuid::Uuid;
 
-use crate::AppState;
+use crate::{error::AppError, middleware::rate_limit, AppState};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         // Comments management
         .route("/comments/:comment_id", get(get_comment).put(update_comment).delete(delete_comment))
-        .route("/comments/:comment_id/clap", post(clap_comment))
+        .route("/comments/:comment_id/clap", post(clap_comment).layer(rate_limit::limit(rate_limit::CLAPS)))
         .route("/comments/:comment_id/replies", get(get_comment_replies).post(create_comment_reply))
         
         // Highlights management
@@ -30,178 +30,307 @@ pub fn routes() -> Router<AppState> {
         .route("/stats/article/:article_id", get(get_article_engagement_stats))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_comment,
+    update_comment,
+    delete_comment,
+    clap_comment,
+    get_comment_replies,
+    create_comment_reply,
+    get_highlight,
+    update_highlight,
+    delete_highlight,
+    get_reading_lists,
+    create_reading_list,
+    get_reading_list,
+    update_reading_list,
+    delete_reading_list,
+    add_article_to_list,
+    remove_article_from_list,
+    get_user_engagement_stats,
+    get_article_engagement_stats,
+))]
+pub struct EngagementApi;
+
+#[utoipa::path(
+    get,
+    path = "/comments/{comment_id}",
+    params(("comment_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_comment(
     State(_state): State<AppState>,
     Path(comment_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get comment by ID
     Ok(Json(json!({
         "message": format!("Get comment {} not implemented yet", comment_id)
     })))
 }
 
+#[utoipa::path(
+    put,
+    path = "/comments/{comment_id}",
+    params(("comment_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn update_comment(
     State(_state): State<AppState>,
     Path(comment_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Update comment (requires auth middleware + ownership check)
     Ok(Json(json!({
         "message": format!("Update comment {} not implemented yet", comment_id)
     })))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/comments/{comment_id}",
+    params(("comment_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn delete_comment(
     State(_state): State<AppState>,
     Path(comment_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Delete comment (requires auth middleware + ownership check)
     Ok(Json(json!({
         "message": format!("Delete comment {} not implemented yet", comment_id)
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/comments/{comment_id}/clap",
+    params(("comment_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn clap_comment(
     State(_state): State<AppState>,
     Path(comment_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Clap comment (requires auth middleware)
     Ok(Json(json!({
         "message": format!("Clap comment {} not implemented yet", comment_id)
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/comments/{comment_id}/replies",
+    params(("comment_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_comment_replies(
     State(_state): State<AppState>,
     Path(comment_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get replies to comment
     Ok(Json(json!({
         "message": format!("Get replies for comment {} not implemented yet", comment_id)
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/comments/{comment_id}/replies",
+    params(("comment_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn create_comment_reply(
     State(_state): State<AppState>,
     Path(comment_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Create reply to comment (requires auth middleware)
     Ok(Json(json!({
         "message": format!("Create reply to comment {} not implemented yet", comment_id)
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/highlights/{highlight_id}",
+    params(("highlight_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_highlight(
     State(_state): State<AppState>,
     Path(highlight_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get highlight by ID (requires auth middleware + ownership check)
     Ok(Json(json!({
         "message": format!("Get highlight {} not implemented yet", highlight_id)
     })))
 }
 
+#[utoipa::path(
+    put,
+    path = "/highlights/{highlight_id}",
+    params(("highlight_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn update_highlight(
     State(_state): State<AppState>,
     Path(highlight_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Update highlight note (requires auth middleware + ownership check)
     Ok(Json(json!({
         "message": format!("Update highlight {} not implemented yet", highlight_id)
     })))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/highlights/{highlight_id}",
+    params(("highlight_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn delete_highlight(
     State(_state): State<AppState>,
     Path(highlight_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Delete highlight (requires auth middleware + ownership check)
     Ok(Json(json!({
         "message": format!("Delete highlight {} not implemented yet", highlight_id)
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/reading-lists",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_reading_lists(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get user's reading lists (requires auth middleware)
     Ok(Json(json!({
         "message": "Get reading lists not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/reading-lists",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn create_reading_list(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Create reading list (requires auth middleware)
     Ok(Json(json!({
         "message": "Create reading list not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/reading-lists/{list_id}",
+    params(("list_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_reading_list(
     State(_state): State<AppState>,
     Path(list_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get reading list by ID
     Ok(Json(json!({
         "message": format!("Get reading list {} not implemented yet", list_id)
     })))
 }
 
+#[utoipa::path(
+    put,
+    path = "/reading-lists/{list_id}",
+    params(("list_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn update_reading_list(
     State(_state): State<AppState>,
     Path(list_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Update reading list (requires auth middleware + ownership check)
     Ok(Json(json!({
         "message": format!("Update reading list {} not implemented yet", list_id)
     })))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/reading-lists/{list_id}",
+    params(("list_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn delete_reading_list(
     State(_state): State<AppState>,
     Path(list_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Delete reading list (requires auth middleware + ownership check)
     Ok(Json(json!({
         "message": format!("Delete reading list {} not implemented yet", list_id)
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/reading-lists/{list_id}/articles",
+    params(("list_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn add_article_to_list(
     State(_state): State<AppState>,
     Path(list_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Add article to reading list (requires auth middleware + ownership check)
     Ok(Json(json!({
         "message": format!("Add article to reading list {} not implemented yet", list_id)
     })))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/reading-lists/{list_id}/articles",
+    params(("list_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn remove_article_from_list(
     State(_state): State<AppState>,
     Path(list_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Remove article from reading list (requires auth middleware + ownership check)
     Ok(Json(json!({
         "message": format!("Remove article from reading list {} not implemented yet", list_id)
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/stats/user/{user_id}",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_user_engagement_stats(
     State(_state): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get engagement statistics for user
     Ok(Json(json!({
         "message": format!("Get engagement stats for user {} not implemented yet", user_id)
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/stats/article/{article_id}",
+    params(("article_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_article_engagement_stats(
     State(_state): State<AppState>,
     Path(article_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get engagement statistics for article
     Ok(Json(json!({
         "message": format!("Get engagement stats for article {} not implemented yet", article_id)
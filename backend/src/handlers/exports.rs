@@ -0,0 +1,85 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde_json::Value;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    middleware::auth::AuthUser,
+    models::export::{CreateExportRequest, ExportListResponse, ExportResponse},
+    services::export::ExportService,
+    AppState,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_exports).post(create_export))
+        .route("/:export_id", get(get_export))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(list_exports, create_export, get_export))]
+pub struct ExportsApi;
+
+// `{"kind": "articles"}` for a Markdown zip, `{"kind": "account"}` for all account data as JSON
+#[utoipa::path(
+    post,
+    path = "/",
+    request_body = CreateExportRequest,
+    responses((status = 200, description = "The queued export", body = ExportResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn create_export(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<CreateExportRequest>,
+) -> Result<Json<Value>, AppError> {
+    let export_service = ExportService::new(state.db.pool.clone());
+
+    let export = export_service
+        .create_export(state.storage.as_ref(), user.user_id, payload.kind)
+        .await?;
+    Ok(Json(serde_json::to_value(export)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    responses((status = 200, description = "The user's exports", body = ExportListResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn list_exports(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let export_service = ExportService::new(state.db.pool.clone());
+
+    let response = export_service.list_exports(state.storage.as_ref(), user.user_id).await?;
+    Ok(Json(serde_json::to_value(response)?))
+}
+
+// Status, and a fresh signed `download_url` once the export is ready
+#[utoipa::path(
+    get,
+    path = "/{export_id}",
+    params(("export_id" = Uuid, Path)),
+    responses((status = 200, description = "Status, with a signed `download_url` once ready", body = ExportResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_export(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(export_id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let export_service = ExportService::new(state.db.pool.clone());
+
+    let export = export_service
+        .get_export(state.storage.as_ref(), user.user_id, export_id)
+        .await?;
+    Ok(Json(serde_json::to_value(export)?))
+}
@@ -0,0 +1,175 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono::{DateTime, Utc};
+use utoipa::OpenApi;
+
+use crate::{
+    error::AppError,
+    services::feed::{Feed, FeedFormat, FeedScope, FeedService},
+    AppState,
+};
+
+// The trailing segment picks the format: rss.xml, atom.xml or feed.json
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/:file", get(site_feed))
+        .route("/users/:username/:file", get(author_feed))
+        .route("/tags/:tag/:file", get(tag_feed))
+        .route("/categories/:category/:file", get(category_feed))
+        .route("/publications/:slug/:file", get(publication_feed))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(site_feed, author_feed, tag_feed, category_feed, publication_feed))]
+pub struct FeedsApi;
+
+#[utoipa::path(
+    get,
+    path = "/{file}",
+    params(("file" = String, Path, description = "`rss.xml`, `atom.xml` or `feed.json`")),
+    responses(
+        (status = 200, description = "RSS 2.0, Atom 1.0 or JSON Feed 1.1, picked by the file name"),
+        (status = 304, description = "Unchanged since `If-None-Match` / `If-Modified-Since`"),
+    ),
+)]
+async fn site_feed(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(file): Path<String>,
+) -> Result<Response, AppError> {
+    serve_feed(&state, &headers, FeedScope::Site, &file).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{username}/{file}",
+    params(("username" = String, Path), ("file" = String, Path, description = "`rss.xml`, `atom.xml` or `feed.json`")),
+    responses(
+        (status = 200, description = "RSS 2.0, Atom 1.0 or JSON Feed 1.1, picked by the file name"),
+        (status = 304, description = "Unchanged since `If-None-Match` / `If-Modified-Since`"),
+    ),
+)]
+async fn author_feed(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((username, file)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    serve_feed(&state, &headers, FeedScope::Author(username), &file).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/tags/{tag}/{file}",
+    params(("tag" = String, Path), ("file" = String, Path, description = "`rss.xml`, `atom.xml` or `feed.json`")),
+    responses(
+        (status = 200, description = "RSS 2.0, Atom 1.0 or JSON Feed 1.1, picked by the file name"),
+        (status = 304, description = "Unchanged since `If-None-Match` / `If-Modified-Since`"),
+    ),
+)]
+async fn tag_feed(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((tag, file)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    serve_feed(&state, &headers, FeedScope::Tag(tag), &file).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/categories/{category}/{file}",
+    params(("category" = String, Path), ("file" = String, Path, description = "`rss.xml`, `atom.xml` or `feed.json`")),
+    responses(
+        (status = 200, description = "RSS 2.0, Atom 1.0 or JSON Feed 1.1, picked by the file name"),
+        (status = 304, description = "Unchanged since `If-None-Match` / `If-Modified-Since`"),
+    ),
+)]
+async fn category_feed(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((category, file)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    serve_feed(&state, &headers, FeedScope::Category(category), &file).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/publications/{slug}/{file}",
+    params(("slug" = String, Path), ("file" = String, Path, description = "`rss.xml`, `atom.xml` or `feed.json`")),
+    responses(
+        (status = 200, description = "RSS 2.0, Atom 1.0 or JSON Feed 1.1, picked by the file name"),
+        (status = 304, description = "Unchanged since `If-None-Match` / `If-Modified-Since`"),
+    ),
+)]
+async fn publication_feed(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((slug, file)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    serve_feed(&state, &headers, FeedScope::Publication(slug), &file).await
+}
+
+async fn serve_feed(
+    state: &AppState,
+    headers: &HeaderMap,
+    scope: FeedScope,
+    file: &str,
+) -> Result<Response, AppError> {
+    let format = FeedFormat::from_file_name(file).ok_or_else(|| {
+        AppError::NotFound("Unknown feed format, use rss.xml, atom.xml or feed.json".to_string())
+    })?;
+
+    let feed_service = FeedService::new(state.db.pool.clone(), &state.config);
+    let feed = feed_service
+        .get_feed(&scope)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Feed not found".to_string()))?;
+
+    let etag = feed.etag(format);
+    let last_modified = http_date(feed.updated);
+
+    let mut response = if is_not_modified(headers, &etag, &feed) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        let body = feed.render(format)?;
+        ([(header::CONTENT_TYPE, format.content_type())], body).into_response()
+    };
+
+    let response_headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response_headers.insert(header::ETAG, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&last_modified) {
+        response_headers.insert(header::LAST_MODIFIED, value);
+    }
+    response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("public, max-age=300"));
+
+    Ok(response)
+}
+
+// If-None-Match takes precedence over If-Modified-Since (RFC 9110 13.2.2)
+fn is_not_modified(headers: &HeaderMap, etag: &str, feed: &Feed) -> bool {
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        // Weak comparison: W/ prefixes are ignored
+        let current = etag.trim_start_matches("W/");
+        return if_none_match
+            .split(',')
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == current);
+    }
+
+    headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+        .is_some_and(|since| feed.updated.timestamp() <= since.timestamp())
+}
+
+// IMF-fixdate, e.g. "Sun, 06 Nov 1994 08:49:37 GMT"
+fn http_date(date: DateTime<Utc>) -> String {
+    date.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
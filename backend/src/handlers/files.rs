@@ -0,0 +1,101 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderName, HeaderValue},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use tower_http::{services::ServeDir, set_header::SetResponseHeaderLayer};
+use utoipa::{IntoParams, OpenApi};
+
+use crate::{
+    error::AppError,
+    middleware::security_headers::USER_CONTENT_CSP,
+    services::{media::MEDIA_PREFIX, storage, user::AVATAR_PREFIX},
+    AppState,
+};
+
+// Signed links to private files of the local backend; S3 serves its own signed URLs
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/*key", get(signed_file))
+        .layer(SetResponseHeaderLayer::overriding(
+            header::CONTENT_SECURITY_POLICY,
+            HeaderValue::from_static(USER_CONTENT_CSP),
+        ))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(signed_file))]
+pub struct FilesApi;
+
+// Article images uploaded before the media library, linked from stored article content
+const LEGACY_ARTICLE_PREFIX: &str = "articles";
+
+// Public files of the local backend under /uploads. Only the public prefixes are
+// mounted, so `private/` is only reachable through signed URLs. Files are embedded by
+// the frontend on another origin, so they are served with their own sandboxing CSP.
+pub fn local_routes(root: &str) -> Router<AppState> {
+    let mut prefixes = vec![AVATAR_PREFIX, MEDIA_PREFIX, LEGACY_ARTICLE_PREFIX];
+    // The media library shares the legacy directory
+    prefixes.sort_unstable();
+    prefixes.dedup();
+
+    prefixes
+        .into_iter()
+        .fold(Router::new(), |router, prefix| {
+            router.nest_service(&format!("/{}", prefix), ServeDir::new(format!("{}/{}", root, prefix)))
+        })
+        .layer(SetResponseHeaderLayer::overriding(
+            header::CONTENT_SECURITY_POLICY,
+            HeaderValue::from_static(USER_CONTENT_CSP),
+        ))
+        .layer(SetResponseHeaderLayer::overriding(
+            HeaderName::from_static("cross-origin-resource-policy"),
+            HeaderValue::from_static("cross-origin"),
+        ))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SignedFileParams {
+    expires: i64,
+    signature: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/{key}",
+    params(("key" = String, Path, description = "Storage key, may contain `/`"), SignedFileParams),
+    responses((status = 200, description = "The file contents, with its content type")),
+)]
+async fn signed_file(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Query(params): Query<SignedFileParams>,
+) -> Result<Response, AppError> {
+    if !state.storage.verify_signature(&key, params.expires, &params.signature) {
+        return Err(AppError::Forbidden("Invalid or expired link".to_string()));
+    }
+
+    let data = state
+        .storage
+        .get(&key)
+        .await?
+        .ok_or_else(|| AppError::NotFound("File not found".to_string()))?;
+
+    // Browsers may keep the file until the link expires, but shared caches must not
+    let max_age = (params.expires - Utc::now().timestamp()).max(0);
+    let content_type = HeaderValue::from_str(&storage::content_type(&key))
+        .unwrap_or(HeaderValue::from_static("application/octet-stream"));
+    let cache_control = HeaderValue::from_str(&format!("private, max-age={}", max_age))
+        .unwrap_or(HeaderValue::from_static("private, no-cache"));
+
+    Ok((
+        [(header::CONTENT_TYPE, content_type), (header::CACHE_CONTROL, cache_control)],
+        data,
+    )
+        .into_response())
+}
@@ -0,0 +1,143 @@
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde_json::Value;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    middleware::auth::AuthUser,
+    models::import::{ImportListResponse, ImportResponse, ImportSource},
+    services::import::{ImportService, MAX_IMPORT_SIZE},
+    AppState,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_imports).post(create_import))
+        .route("/:import_id", get(get_import))
+        // Exports with many posts can be large
+        .layer(DefaultBodyLimit::max(MAX_IMPORT_SIZE + 64 * 1024))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(list_imports, create_import, get_import))]
+pub struct ImportsApi;
+
+// Multipart fields: `file` (required), `source` (medium, wordpress or ghost; detected
+// when omitted), `site_url` (the old blog, for relative links) and `drafts` ("true"
+// to import everything as drafts)
+#[utoipa::path(
+    post,
+    path = "/",
+    request_body(content_type = "multipart/form-data", description = "`file` (required), `source`, `site_url` and `drafts`"),
+    responses((status = 200, description = "The queued import", body = ImportResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn create_import(
+    State(state): State<AppState>,
+    user: AuthUser,
+    mut multipart: Multipart,
+) -> Result<Json<Value>, AppError> {
+    let mut file = None;
+    let mut source = None;
+    let mut site_url = None;
+    let mut as_drafts = false;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| AppError::BadRequest("Invalid multipart data".to_string()))?
+    {
+        let name = field.name().unwrap_or("").to_string();
+        match name.as_str() {
+            "file" => {
+                let file_name = field.file_name().unwrap_or("export").to_string();
+                let bytes = field
+                    .bytes()
+                    .await
+                    .map_err(|_| AppError::BadRequest("Failed to read file data".to_string()))?;
+                file = Some((file_name, bytes.to_vec()));
+            }
+            "source" | "site_url" | "drafts" => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|_| AppError::BadRequest(format!("Invalid {} field", name)))?;
+                match name.as_str() {
+                    "source" => {
+                        let parsed = serde_json::from_value::<ImportSource>(Value::String(text.trim().to_lowercase()));
+                        source = Some(parsed.map_err(|_| {
+                            AppError::BadRequest("Source must be medium, wordpress or ghost".to_string())
+                        })?);
+                    }
+                    "site_url" => site_url = Some(text),
+                    _ => as_drafts = text.trim() == "true",
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some((file_name, data)) = file else {
+        return Err(AppError::BadRequest("No file found".to_string()));
+    };
+
+    if data.len() > MAX_IMPORT_SIZE {
+        return Err(AppError::PayloadTooLarge("File too large. Maximum size is 100MB".to_string()));
+    }
+
+    let import_service = ImportService::new(state.db.pool.clone(), &state.config);
+
+    let import = import_service
+        .create_import(
+            state.storage.as_ref(),
+            user.user_id,
+            file_name,
+            data,
+            source,
+            site_url,
+            as_drafts,
+        )
+        .await?;
+    Ok(Json(serde_json::to_value(import)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    responses((status = 200, description = "The user's imports", body = ImportListResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn list_imports(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let import_service = ImportService::new(state.db.pool.clone(), &state.config);
+
+    let response = import_service.list_imports(user.user_id).await?;
+    Ok(Json(serde_json::to_value(response)?))
+}
+
+// Progress and the per-post report (imported, skipped with a reason, failed)
+#[utoipa::path(
+    get,
+    path = "/{import_id}",
+    params(("import_id" = Uuid, Path)),
+    responses((status = 200, description = "Progress and the per-post report", body = ImportResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_import(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(import_id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let import_service = ImportService::new(state.db.pool.clone(), &state.config);
+
+    let import = import_service.get_import(user.user_id, import_id).await?;
+    Ok(Json(serde_json::to_value(import)?))
+}
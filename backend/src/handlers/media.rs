@@ -0,0 +1,184 @@
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde_json::{json, Value};
+use utoipa::OpenApi;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    middleware::auth::AuthUser,
+    models::media::{MediaListResponse, MediaQueryParams, MediaResponse, UpdateMediaRequest},
+    services::{image::MAX_UPLOAD_SIZE, media::MediaService},
+    AppState,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_media_library).post(upload_media))
+        .route("/:media_id", get(get_media).put(update_media).delete(delete_media))
+        // Allow full-size photos; they are scaled down on upload
+        .layer(DefaultBodyLimit::max(MAX_UPLOAD_SIZE + 64 * 1024))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(upload_media, get_media_library, get_media, update_media, delete_media))]
+pub struct MediaApi;
+
+// Multipart fields: `file` (required), `alt_text`, `caption`, `private` ("true" for signed URLs only)
+#[utoipa::path(
+    post,
+    path = "/",
+    request_body(content_type = "multipart/form-data", description = "`file` (required), `alt_text`, `caption` and `private`"),
+    responses((status = 200, description = "The uploaded image and its variants", body = MediaResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn upload_media(
+    State(state): State<AppState>,
+    user: AuthUser,
+    mut multipart: Multipart,
+) -> Result<Json<Value>, AppError> {
+    let mut data = None;
+    let mut is_private = false;
+    let mut details = UpdateMediaRequest {
+        alt_text: None,
+        caption: None,
+    };
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| AppError::BadRequest("Invalid multipart data".to_string()))?
+    {
+        let name = field.name().unwrap_or("").to_string();
+        match name.as_str() {
+            "file" => {
+                let bytes = field
+                    .bytes()
+                    .await
+                    .map_err(|_| AppError::BadRequest("Failed to read file data".to_string()))?;
+                data = Some(bytes.to_vec());
+            }
+            "alt_text" | "caption" | "private" => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|_| AppError::BadRequest(format!("Invalid {} field", name)))?;
+                match name.as_str() {
+                    "alt_text" => details.alt_text = Some(text),
+                    "caption" => details.caption = Some(text),
+                    _ => is_private = text.trim() == "true",
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some(data) = data else {
+        return Err(AppError::BadRequest("No file found".to_string()));
+    };
+
+    if data.len() > MAX_UPLOAD_SIZE {
+        return Err(AppError::PayloadTooLarge("File too large. Maximum size is 20MB".to_string()));
+    }
+
+    details.validate()?;
+
+    let media_service = MediaService::new(state.db.pool.clone());
+
+    let media = media_service
+        .upload(
+            state.storage.as_ref(),
+            user.user_id,
+            data,
+            details.alt_text,
+            details.caption,
+            is_private,
+        )
+        .await?;
+    Ok(Json(serde_json::to_value(media)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    params(MediaQueryParams),
+    responses((status = 200, description = "A page of the user's media library", body = MediaListResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_media_library(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(params): Query<MediaQueryParams>,
+) -> Result<Json<Value>, AppError> {
+    let media_service = MediaService::new(state.db.pool.clone());
+
+    let response = media_service.list_media(state.storage.as_ref(), user.user_id, params).await?;
+    Ok(Json(serde_json::to_value(response)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{media_id}",
+    params(("media_id" = Uuid, Path)),
+    responses((status = 200, description = "The media item", body = MediaResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_media(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(media_id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let media_service = MediaService::new(state.db.pool.clone());
+
+    let media = media_service.get_media(state.storage.as_ref(), user.user_id, media_id).await?;
+    Ok(Json(serde_json::to_value(media)?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/{media_id}",
+    params(("media_id" = Uuid, Path)),
+    request_body = UpdateMediaRequest,
+    responses((status = 200, description = "The updated media item", body = MediaResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn update_media(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(media_id): Path<Uuid>,
+    Json(payload): Json<UpdateMediaRequest>,
+) -> Result<Json<Value>, AppError> {
+    payload.validate()?;
+
+    let media_service = MediaService::new(state.db.pool.clone());
+
+    let media = media_service
+        .update_media(state.storage.as_ref(), user.user_id, media_id, payload)
+        .await?;
+    Ok(Json(serde_json::to_value(media)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{media_id}",
+    params(("media_id" = Uuid, Path)),
+    responses((status = 200, description = "Media deleted", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn delete_media(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(media_id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let media_service = MediaService::new(state.db.pool.clone());
+
+    media_service
+        .delete_media(state.storage.as_ref(), user.user_id, media_id)
+        .await?;
+    Ok(Json(json!({"message": "Media deleted successfully"})))
+}
@@ -5,3 +5,46 @@ pub mod engagement;
 pub mod search;
 pub mod admin;
 pub mod upload;
+pub mod media;
+pub mod files;
+pub mod notifications;
+pub mod realtime;
+pub mod digest;
+pub mod newsletter;
+pub mod feeds;
+pub mod seo;
+
+use askama::Template;
+use axum::response::Html;
+
+use crate::error::AppError;
+
+// Minimal HTML page for links opened from emails (confirmations, unsubscribes)
+#[derive(Template)]
+#[template(path = "notice.html")]
+struct NoticeTemplate<'a> {
+    heading: &'a str,
+    message: &'a str,
+    link_url: &'a str,
+    link_label: &'a str,
+}
+
+pub fn render_notice(
+    heading: &str,
+    message: &str,
+    link_url: &str,
+    link_label: &str,
+) -> Result<Html<String>, AppError> {
+    let page = NoticeTemplate {
+        heading,
+        message,
+        link_url,
+        link_label,
+    }
+    .render()?;
+    Ok(Html(page))
+}
+pub mod imports;
+pub mod exports;
+pub mod tokens;
+pub mod docs;
@@ -0,0 +1,306 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{Html, IntoResponse, Json, Redirect, Response},
+    routing::{get, post},
+    Router,
+};
+use serde_json::{json, Value};
+use utoipa::OpenApi;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::render_notice;
+use crate::{
+    error::AppError,
+    middleware::auth::{AuthUser, OptionalAuthUser},
+    models::newsletter::{NewsletterQueryParams, NewsletterTokenQuery, SubscribeRequest, SubscriberListResponse, TrackClickQuery},
+    services::newsletter::{NewsletterService, NewsletterTarget},
+    AppState,
+};
+
+// 1x1 transparent GIF used as the open-tracking pixel
+const TRACKING_PIXEL: &[u8] = &[
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        // Public subscription flow (double opt-in)
+        .route("/authors/:username/subscribe", post(subscribe_to_author))
+        .route("/publications/:slug/subscribe", post(subscribe_to_publication))
+        .route("/confirm", get(confirm_subscription))
+        // GET from the email footer, POST for one-click List-Unsubscribe (RFC 8058)
+        .route("/unsubscribe", get(unsubscribe_page).post(unsubscribe_one_click))
+
+        // Open/click tracking
+        .route("/track/:delivery_id/open", get(track_open))
+        .route("/track/:delivery_id/click", get(track_click))
+
+        // Auth required: newsletter management
+        .route("/subscribers", get(get_subscribers))
+        .route("/issues", get(get_issues))
+        .route("/issues/:issue_id/deliveries", get(get_deliveries))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    subscribe_to_author,
+    subscribe_to_publication,
+    confirm_subscription,
+    unsubscribe_page,
+    unsubscribe_one_click,
+    track_open,
+    track_click,
+    get_subscribers,
+    get_issues,
+    get_deliveries,
+))]
+pub struct NewsletterApi;
+
+#[utoipa::path(
+    post,
+    path = "/authors/{username}/subscribe",
+    params(("username" = String, Path)),
+    request_body = SubscribeRequest,
+    responses((status = 200, description = "`{message, status}`; `pending` until the emailed link is opened", body = Value)),
+    security((), ("bearer_auth" = [])),
+)]
+async fn subscribe_to_author(
+    State(state): State<AppState>,
+    OptionalAuthUser(user): OptionalAuthUser,
+    Path(username): Path<String>,
+    Json(payload): Json<SubscribeRequest>,
+) -> Result<Json<Value>, AppError> {
+    let newsletter_service = NewsletterService::new(state.db.pool.clone(), &state.config);
+    payload.validate()?;
+    let target = newsletter_service.resolve_author(&username).await?;
+
+    subscribe(&newsletter_service, target, user, payload).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/publications/{slug}/subscribe",
+    params(("slug" = String, Path)),
+    request_body = SubscribeRequest,
+    responses((status = 200, description = "`{message, status}`; `pending` until the emailed link is opened", body = Value)),
+    security((), ("bearer_auth" = [])),
+)]
+async fn subscribe_to_publication(
+    State(state): State<AppState>,
+    OptionalAuthUser(user): OptionalAuthUser,
+    Path(slug): Path<String>,
+    Json(payload): Json<SubscribeRequest>,
+) -> Result<Json<Value>, AppError> {
+    let newsletter_service = NewsletterService::new(state.db.pool.clone(), &state.config);
+    payload.validate()?;
+    let target = newsletter_service.resolve_publication(&slug).await?;
+
+    subscribe(&newsletter_service, target, user, payload).await
+}
+
+async fn subscribe(
+    newsletter_service: &NewsletterService<'_>,
+    (target, newsletter_name): (NewsletterTarget, String),
+    user: Option<AuthUser>,
+    payload: SubscribeRequest,
+) -> Result<Json<Value>, AppError> {
+    let user_id = user.map(|u| u.user_id);
+
+    let created = newsletter_service
+        .subscribe(target, &newsletter_name, &payload.email, user_id)
+        .await?;
+
+    if created {
+        Ok(Json(json!({
+            "message": "Check your inbox to confirm your subscription",
+            "status": "pending"
+        })))
+    } else {
+        Ok(Json(json!({
+            "message": "You're already subscribed",
+            "status": "confirmed"
+        })))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/confirm",
+    params(NewsletterTokenQuery),
+    responses((status = 200, description = "Confirmation page", content_type = "text/html", body = String)),
+)]
+async fn confirm_subscription(
+    State(state): State<AppState>,
+    Query(query): Query<NewsletterTokenQuery>,
+) -> Result<Html<String>, AppError> {
+    let newsletter_service = NewsletterService::new(state.db.pool.clone(), &state.config);
+
+    newsletter_service.confirm(&query.token).await?;
+
+    render_notice(
+        "Subscription confirmed",
+        "You'll get new stories by email as soon as they're published.",
+        &state.config.frontend_url,
+        "Keep reading on FastBlog",
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/unsubscribe",
+    params(NewsletterTokenQuery),
+    responses((status = 200, description = "Confirmation page", content_type = "text/html", body = String)),
+)]
+async fn unsubscribe_page(
+    State(state): State<AppState>,
+    Query(query): Query<NewsletterTokenQuery>,
+) -> Result<Html<String>, AppError> {
+    let newsletter_service = NewsletterService::new(state.db.pool.clone(), &state.config);
+
+    newsletter_service.unsubscribe(&query.token).await?;
+
+    render_notice(
+        "You've been unsubscribed",
+        "You won't receive this newsletter anymore.",
+        &state.config.frontend_url,
+        "Back to FastBlog",
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/unsubscribe",
+    params(NewsletterTokenQuery),
+    responses((status = 200, description = "Unsubscribed (RFC 8058 one-click)", body = Value)),
+)]
+async fn unsubscribe_one_click(
+    State(state): State<AppState>,
+    Query(query): Query<NewsletterTokenQuery>,
+) -> Result<Json<Value>, AppError> {
+    let newsletter_service = NewsletterService::new(state.db.pool.clone(), &state.config);
+
+    newsletter_service.unsubscribe(&query.token).await?;
+
+    Ok(Json(json!({
+        "message": "Unsubscribed from newsletter"
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/track/{delivery_id}/open",
+    params(("delivery_id" = Uuid, Path)),
+    responses((status = 200, description = "1x1 tracking pixel", content_type = "image/gif")),
+)]
+async fn track_open(
+    State(state): State<AppState>,
+    Path(delivery_id): Path<Uuid>,
+) -> Response {
+    let newsletter_service = NewsletterService::new(state.db.pool.clone(), &state.config);
+
+    // Tracking must never break the email, so failures are only logged
+    if let Err(e) = newsletter_service.record_open(delivery_id).await {
+        tracing::warn!("Failed to record newsletter open: {}", e);
+    }
+
+    (
+        [
+            (header::CONTENT_TYPE, "image/gif"),
+            (header::CACHE_CONTROL, "no-store, max-age=0"),
+        ],
+        TRACKING_PIXEL,
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/track/{delivery_id}/click",
+    params(("delivery_id" = Uuid, Path), TrackClickQuery),
+    responses((status = 303, description = "Redirect to the link, or to the frontend for foreign URLs")),
+)]
+async fn track_click(
+    State(state): State<AppState>,
+    Path(delivery_id): Path<Uuid>,
+    Query(query): Query<TrackClickQuery>,
+) -> Redirect {
+    let newsletter_service = NewsletterService::new(state.db.pool.clone(), &state.config);
+
+    if let Err(e) = newsletter_service.record_click(delivery_id).await {
+        tracing::warn!("Failed to record newsletter click: {}", e);
+    }
+
+    // Only redirect to our own frontend so this can't be used as an open redirect
+    let frontend_url = &state.config.frontend_url;
+    let is_own_url = query.url == *frontend_url || query.url.starts_with(&format!("{}/", frontend_url));
+    if is_own_url {
+        Redirect::to(&query.url)
+    } else {
+        Redirect::to(frontend_url)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/subscribers",
+    params(NewsletterQueryParams),
+    responses((status = 200, description = "A page of subscribers", body = SubscriberListResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_subscribers(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(params): Query<NewsletterQueryParams>,
+) -> Result<Json<Value>, AppError> {
+    let newsletter_service = NewsletterService::new(state.db.pool.clone(), &state.config);
+    let target = newsletter_service.managed_target(user.user_id, params.publication_id).await?;
+
+    let response = newsletter_service.list_subscribers(target, params).await?;
+    Ok(Json(serde_json::to_value(response)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/issues",
+    params(NewsletterQueryParams),
+    responses((status = 200, description = "`{issues}` with open and click counts", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_issues(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(params): Query<NewsletterQueryParams>,
+) -> Result<Json<Value>, AppError> {
+    let newsletter_service = NewsletterService::new(state.db.pool.clone(), &state.config);
+    let target = newsletter_service.managed_target(user.user_id, params.publication_id).await?;
+
+    let issues = newsletter_service.list_issues(target, params).await?;
+    Ok(Json(json!({
+        "issues": issues
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/issues/{issue_id}/deliveries",
+    params(("issue_id" = Uuid, Path), NewsletterQueryParams),
+    responses((status = 200, description = "`{deliveries}` of the issue", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_deliveries(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(issue_id): Path<Uuid>,
+    Query(params): Query<NewsletterQueryParams>,
+) -> Result<Json<Value>, AppError> {
+    let newsletter_service = NewsletterService::new(state.db.pool.clone(), &state.config);
+
+    let deliveries = newsletter_service.list_deliveries(user.user_id, issue_id, params).await?;
+    Ok(Json(json!({
+        "deliveries": deliveries
+    })))
+}
@@ -0,0 +1,152 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde_json::{json, Value};
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    middleware::auth::AuthUser,
+    models::notification::{NotificationListResponse, NotificationQueryParams, UpdateNotificationPreferencesRequest},
+    services::notification::NotificationService,
+    AppState,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_notifications))
+        .route("/unread-count", get(get_unread_count))
+        .route("/read-all", post(mark_all_as_read))
+        .route("/preferences", get(get_preferences).put(update_preferences))
+        .route("/:notification_id/read", post(mark_as_read))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_notifications,
+    get_unread_count,
+    mark_all_as_read,
+    get_preferences,
+    update_preferences,
+    mark_as_read,
+))]
+pub struct NotificationsApi;
+
+#[utoipa::path(
+    get,
+    path = "/",
+    params(NotificationQueryParams),
+    responses((status = 200, description = "A page of notifications", body = NotificationListResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_notifications(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(params): Query<NotificationQueryParams>,
+) -> Result<Json<Value>, AppError> {
+    let notification_service = NotificationService::new(state.db.pool.clone(), state.realtime.clone());
+
+    let response = notification_service.get_notifications(user.user_id, params).await?;
+    Ok(Json(serde_json::to_value(response)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/unread-count",
+    responses((status = 200, description = "`{unread_count}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_unread_count(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let notification_service = NotificationService::new(state.db.pool.clone(), state.realtime.clone());
+
+    let unread_count = notification_service.get_unread_count(user.user_id).await?;
+    Ok(Json(json!({
+        "unread_count": unread_count
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{notification_id}/read",
+    params(("notification_id" = Uuid, Path)),
+    responses((status = 200, description = "Notification marked as read", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn mark_as_read(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(notification_id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let notification_service = NotificationService::new(state.db.pool.clone(), state.realtime.clone());
+
+    notification_service.mark_as_read(user.user_id, notification_id).await?;
+    Ok(Json(json!({
+        "message": "Notification marked as read",
+        "is_read": true
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/read-all",
+    responses((status = 200, description = "`{message, updated}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn mark_all_as_read(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let notification_service = NotificationService::new(state.db.pool.clone(), state.realtime.clone());
+
+    let updated = notification_service.mark_all_as_read(user.user_id).await?;
+    Ok(Json(json!({
+        "message": "All notifications marked as read",
+        "updated": updated
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/preferences",
+    responses((status = 200, description = "`{preferences}` per notification type", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_preferences(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let notification_service = NotificationService::new(state.db.pool.clone(), state.realtime.clone());
+
+    let preferences = notification_service.get_preferences(user.user_id).await?;
+    Ok(Json(json!({
+        "preferences": preferences
+    })))
+}
+
+#[utoipa::path(
+    put,
+    path = "/preferences",
+    request_body = UpdateNotificationPreferencesRequest,
+    responses((status = 200, description = "`{message, preferences}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn update_preferences(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<UpdateNotificationPreferencesRequest>,
+) -> Result<Json<Value>, AppError> {
+    let notification_service = NotificationService::new(state.db.pool.clone(), state.realtime.clone());
+
+    let preferences = notification_service.update_preferences(user.user_id, &payload.preferences).await?;
+    Ok(Json(json!({
+        "message": "Notification preferences updated",
+        "preferences": preferences
+    })))
+}
@@ -0,0 +1,135 @@
+use axum::{
+    extract::{Query, State},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json,
+    },
+    routing::{get, post},
+    Router,
+};
+use futures::{stream, Stream, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+use utoipa::{IntoParams, OpenApi};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{AuthUser, OptionalAuthUser},
+    services::{
+        notification::NotificationService,
+        realtime::StreamTicketService,
+    },
+    AppState,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/stream", get(stream_events))
+        .route("/ticket", post(create_ticket))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(stream_events, create_ticket))]
+pub struct RealtimeApi;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct StreamQuery {
+    // Article the reader is viewing; enables live clap/comment counts
+    article_id: Option<Uuid>,
+    // EventSource can't send headers, so it authenticates with a ticket from `POST /ticket`
+    ticket: Option<String>,
+}
+
+// A single-use ticket for opening the stream, valid for a minute
+#[utoipa::path(
+    post,
+    path = "/ticket",
+    responses((status = 200, description = "`ticket` and `expires_at`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn create_ticket(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let ticket_service = StreamTicketService::new(state.db.pool.clone());
+
+    let (ticket, expires_at) = ticket_service.issue(user.user_id).await?;
+    Ok(Json(json!({
+        "ticket": ticket,
+        "expires_at": expires_at
+    })))
+}
+
+// Server-Sent Events stream of the caller's notifications and, when `article_id` is
+// given, live engagement counts for that article
+#[utoipa::path(
+    get,
+    path = "/stream",
+    params(StreamQuery),
+    responses((status = 200, description = "`ready`, then notification and article count events", content_type = "text/event-stream", body = String)),
+    security(("bearer_auth" = [])),
+)]
+async fn stream_events(
+    State(state): State<AppState>,
+    OptionalAuthUser(user): OptionalAuthUser,
+    Query(query): Query<StreamQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, AppError> {
+    let user_id = match (user, query.ticket.as_deref()) {
+        (Some(user), _) => user.user_id,
+        (None, Some(ticket)) => StreamTicketService::new(state.db.pool.clone()).redeem(ticket).await?,
+        (None, None) => {
+            return Err(AppError::Unauthorized("Authentication required".to_string()));
+        }
+    };
+    let article_id = query.article_id;
+
+    // Subscribe before reading the initial state so no event falls in between
+    let receiver = state.realtime.subscribe();
+
+    let notification_service = NotificationService::new(state.db.pool.clone(), state.realtime.clone());
+    let unread_count = notification_service.get_unread_count(user_id).await.unwrap_or(0);
+
+    let article_counts = match article_id {
+        Some(article_id) => sqlx::query!(
+            "SELECT claps_count, comments_count FROM articles WHERE id = $1 AND status = 'published'",
+            article_id
+        )
+        .fetch_optional(&state.db.pool)
+        .await
+        .ok()
+        .flatten()
+        .map(|counts| json!({
+            "article_id": article_id,
+            "claps_count": counts.claps_count,
+            "comments_count": counts.comments_count
+        })),
+        None => None,
+    };
+
+    let ready = Event::default().event("ready").json_data(json!({
+        "unread_count": unread_count,
+        "article_counts": article_counts
+    }));
+
+    let events = stream::unfold(receiver, move |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) if event.is_visible_to(user_id, article_id) => {
+                    let sse_event = Event::default().event(event.name()).json_data(&event);
+                    return Some((sse_event, receiver));
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Realtime stream for user {} skipped {} events", user_id, skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Ok(Sse::new(stream::once(async { ready }).chain(events)).keep_alive(KeepAlive::default()))
+}
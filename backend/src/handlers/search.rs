@@ -1,14 +1,14 @@
 use axum::{
     extract::{Query, State},
-    http::StatusCode,
     response::Json,
     routing::get,
     Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 
-use crate::{AppState, services::search::SearchService};
+use crate::{error::AppError, services::search::SearchService, AppState};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
@@ -20,7 +20,19 @@ pub fn routes() -> Router<AppState> {
         .route("/suggestions", get(get_search_suggestions))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(OpenApi)]
+#[openapi(paths(
+    search,
+    search_articles,
+    search_users,
+    search_publications,
+    search_tags,
+    get_search_suggestions,
+))]
+pub struct SearchApi;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct SearchQuery {
     pub q: String,
     pub page: Option<i64>,
@@ -29,7 +41,7 @@ pub struct SearchQuery {
     pub filter: Option<SearchFilter>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub enum SearchSortBy {
     #[serde(rename = "relevance")]
     Relevance,
@@ -41,7 +53,7 @@ pub enum SearchSortBy {
     Claps,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub enum SearchFilter {
     #[serde(rename = "articles")]
     Articles,
@@ -53,7 +65,7 @@ pub enum SearchFilter {
     Tags,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SearchResponse {
     pub query: String,
     pub total_results: i64,
@@ -62,7 +74,7 @@ pub struct SearchResponse {
     pub filters: SearchFilters,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SearchResults {
     pub articles: Vec<SearchArticleResult>,
     pub users: Vec<SearchUserResult>,
@@ -70,7 +82,7 @@ pub struct SearchResults {
     pub tags: Vec<SearchTagResult>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SearchArticleResult {
     pub id: String,
     pub title: String,
@@ -86,7 +98,7 @@ pub struct SearchArticleResult {
     pub relevance_score: f32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SearchUserResult {
     pub id: String,
     pub username: String,
@@ -99,7 +111,7 @@ pub struct SearchUserResult {
     pub relevance_score: f32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SearchPublicationResult {
     pub id: String,
     pub name: String,
@@ -111,7 +123,7 @@ pub struct SearchPublicationResult {
     pub relevance_score: f32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SearchTagResult {
     pub name: String,
     pub articles_count: i64,
@@ -119,7 +131,7 @@ pub struct SearchTagResult {
     pub relevance_score: f32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SearchAuthorResult {
     pub id: String,
     pub username: String,
@@ -127,100 +139,91 @@ pub struct SearchAuthorResult {
     pub avatar_url: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SearchFilters {
     pub available_tags: Vec<String>,
     pub date_ranges: Vec<String>,
     pub reading_time_ranges: Vec<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/",
+    params(SearchQuery),
+    responses((status = 200, description = "Matching articles, users, publications and tags", body = SearchResponse)),
+)]
 async fn search(
     State(state): State<AppState>,
     Query(query): Query<SearchQuery>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     if query.q.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({"error": "Search query cannot be empty"})),
-        ));
+        return Err(AppError::BadRequest("Search query cannot be empty".to_string()));
     }
 
     let search_service = SearchService::new(state.db.pool.clone());
     
-    match search_service.global_search(&query).await {
-        Ok(results) => Ok(Json(serde_json::to_value(results).unwrap())),
-        Err(e) => {
-            tracing::error!("Search error: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Search failed"})),
-            ))
-        }
-    }
+    let results = search_service.global_search(&query).await?;
+    Ok(Json(serde_json::to_value(results)?))
 }
 
+#[utoipa::path(
+    get,
+    path = "/articles",
+    params(SearchQuery),
+    responses((status = 200, description = "`{query, results, total_results}` with article results", body = Value)),
+)]
 async fn search_articles(
     State(state): State<AppState>,
     Query(query): Query<SearchQuery>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     if query.q.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({"error": "Search query cannot be empty"})),
-        ));
+        return Err(AppError::BadRequest("Search query cannot be empty".to_string()));
     }
 
     let search_service = SearchService::new(state.db.pool.clone());
     
-    match search_service.search_articles(&query).await {
-        Ok(results) => Ok(Json(json!({
-            "query": query.q,
-            "results": results,
-            "total_results": results.len()
-        }))),
-        Err(e) => {
-            tracing::error!("Article search error: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Article search failed"})),
-            ))
-        }
-    }
+    let results = search_service.search_articles(&query).await?;
+    Ok(Json(json!({
+        "query": query.q,
+        "results": results,
+        "total_results": results.len()
+    })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/users",
+    params(SearchQuery),
+    responses((status = 200, description = "`{query, results, total_results}` with user results", body = Value)),
+)]
 async fn search_users(
     State(state): State<AppState>,
     Query(query): Query<SearchQuery>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     if query.q.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({"error": "Search query cannot be empty"})),
-        ));
+        return Err(AppError::BadRequest("Search query cannot be empty".to_string()));
     }
 
     let search_service = SearchService::new(state.db.pool.clone());
     
-    match search_service.search_users(&query).await {
-        Ok(results) => Ok(Json(json!({
-            "query": query.q,
-            "results": results,
-            "total_results": results.len()
-        }))),
-        Err(e) => {
-            tracing::error!("User search error: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "User search failed"})),
-            ))
-        }
-    }
+    let results = search_service.search_users(&query).await?;
+    Ok(Json(json!({
+        "query": query.q,
+        "results": results,
+        "total_results": results.len()
+    })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/publications",
+    params(SearchQuery),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn search_publications(
     State(_state): State<AppState>,
     Query(query): Query<SearchQuery>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Implement publication search
     Ok(Json(json!({
         "message": "Publication search not implemented yet",
@@ -228,39 +231,40 @@ async fn search_publications(
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/tags",
+    params(SearchQuery),
+    responses((status = 200, description = "`{query, results, total_results}` with tag results", body = Value)),
+)]
 async fn search_tags(
     State(state): State<AppState>,
     Query(query): Query<SearchQuery>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     if query.q.trim().is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({"error": "Search query cannot be empty"})),
-        ));
+        return Err(AppError::BadRequest("Search query cannot be empty".to_string()));
     }
 
     let search_service = SearchService::new(state.db.pool.clone());
     
-    match search_service.search_tags(&query).await {
-        Ok(results) => Ok(Json(json!({
-            "query": query.q,
-            "results": results,
-            "total_results": results.len()
-        }))),
-        Err(e) => {
-            tracing::error!("Tag search error: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Tag search failed"})),
-            ))
-        }
-    }
+    let results = search_service.search_tags(&query).await?;
+    Ok(Json(json!({
+        "query": query.q,
+        "results": results,
+        "total_results": results.len()
+    })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/suggestions",
+    params(SearchQuery),
+    responses((status = 200, description = "`{query, suggestions}` for autocomplete", body = Value)),
+)]
 async fn get_search_suggestions(
     State(state): State<AppState>,
     Query(query): Query<SearchQuery>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     if query.q.trim().is_empty() {
         return Ok(Json(json!({
             "query": query.q,
@@ -270,17 +274,9 @@ async fn get_search_suggestions(
 
     let search_service = SearchService::new(state.db.pool.clone());
     
-    match search_service.get_search_suggestions(&query.q).await {
-        Ok(suggestions) => Ok(Json(json!({
-            "query": query.q,
-            "suggestions": suggestions
-        }))),
-        Err(e) => {
-            tracing::error!("Search suggestions error: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get search suggestions"})),
-            ))
-        }
-    }
+    let suggestions = search_service.get_search_suggestions(&query.q).await?;
+    Ok(Json(json!({
+        "query": query.q,
+        "suggestions": suggestions
+    })))
 }
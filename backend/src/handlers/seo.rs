@@ -0,0 +1,140 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use serde_json::json;
+use utoipa::OpenApi;
+
+use crate::{
+    error::AppError,
+    models::seo::ArticleSeoResponse,
+    services::{
+        article::ArticleService,
+        seo::{SeoService, SitemapKind},
+    },
+    util::urlencode,
+    AppState,
+};
+
+// Crawlers expect these at the site root; the frontend proxies them here
+pub fn crawler_routes() -> Router<AppState> {
+    Router::new()
+        .route("/robots.txt", get(robots_txt))
+        .route("/sitemap.xml", get(sitemap_index))
+        .route("/sitemaps/:file", get(sitemap))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/articles/:slug", get(get_article_metadata))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_article_metadata))]
+pub struct SeoApi;
+
+#[derive(OpenApi)]
+#[openapi(paths(robots_txt, sitemap_index, sitemap))]
+pub struct CrawlerApi;
+
+#[utoipa::path(
+    get,
+    path = "/robots.txt",
+    responses((status = 200, description = "Crawler rules, pointing at the sitemap index", content_type = "text/plain")),
+)]
+
+async fn robots_txt(State(state): State<AppState>) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8"),
+            (header::CACHE_CONTROL, "public, max-age=3600"),
+        ],
+        SeoService::robots_txt(&state.config),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/sitemap.xml",
+    responses((status = 200, description = "Sitemap index listing every sitemap page", content_type = "application/xml")),
+)]
+async fn sitemap_index(State(state): State<AppState>) -> Result<Response, AppError> {
+    let seo_service = SeoService::new(state.db.pool.clone(), &state.config);
+
+    let body = seo_service.sitemap_index().await?;
+    Ok(xml_response(body))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sitemaps/{file}",
+    params(("file" = String, Path, description = "A file name from the sitemap index, e.g. `articles-1.xml`")),
+    responses((status = 200, description = "One sitemap page", content_type = "application/xml")),
+)]
+async fn sitemap(
+    State(state): State<AppState>,
+    Path(file): Path<String>,
+) -> Result<Response, AppError> {
+    let not_found = || AppError::NotFound("Sitemap not found".to_string());
+    let (kind, page) = SitemapKind::from_file_name(&file).ok_or_else(not_found)?;
+
+    let seo_service = SeoService::new(state.db.pool.clone(), &state.config);
+
+    let body = seo_service.sitemap(kind, page).await?.ok_or_else(not_found)?;
+    Ok(xml_response(body))
+}
+
+fn xml_response(body: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "application/xml; charset=utf-8"),
+            (header::CACHE_CONTROL, "public, max-age=3600"),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/articles/{slug}",
+    params(("slug" = String, Path)),
+    responses(
+        (status = 200, description = "Meta tags and JSON-LD for the article page", body = ArticleSeoResponse),
+        (status = 301, description = "Renamed article; `Location` has its current slug", body = Value),
+    ),
+)]
+async fn get_article_metadata(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Response, AppError> {
+    let seo_service = SeoService::new(state.db.pool.clone(), &state.config);
+
+    match seo_service.article_metadata(&slug).await {
+        Ok(metadata) => Ok(Json(serde_json::to_value(metadata)?).into_response()),
+        Err(AppError::NotFound(message)) => {
+            // Follow renamed articles like the article endpoint does
+            let article_service = ArticleService::new(state.db.pool.clone(), &state.config);
+            let current_slug = article_service
+                .resolve_historic_slug(&slug, None)
+                .await?
+                .ok_or(AppError::NotFound(message))?;
+
+            let location = format!("/api/v1/seo/articles/{}", urlencode(&current_slug));
+            Ok((
+                StatusCode::MOVED_PERMANENTLY,
+                [(header::LOCATION, location.clone())],
+                Json(json!({
+                    "slug": current_slug,
+                    "location": location
+                })),
+            )
+                .into_response())
+        }
+        Err(e) => Err(e),
+    }
+}
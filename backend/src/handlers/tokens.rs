@@ -0,0 +1,91 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+    routing::{delete, get},
+    Router,
+};
+use serde_json::{json, Value};
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    middleware::auth::{AdminUser, AuthUser},
+    models::access_token::{AccessTokenListResponse, CreateAccessTokenRequest, CreatedAccessTokenResponse, TokenScope},
+    services::access_token::AccessTokenService,
+    AppState,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_tokens).post(create_token))
+        .route("/:token_id", delete(revoke_token))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(list_tokens, create_token, revoke_token))]
+pub struct TokensApi;
+
+// `{"name": "CI", "scopes": ["read", "write:articles"], "expires_in_days": 30}`;
+// the token is only returned here
+#[utoipa::path(
+    post,
+    path = "/",
+    request_body = CreateAccessTokenRequest,
+    responses((status = 200, description = "The new token; its secret is only returned here", body = CreatedAccessTokenResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn create_token(
+    State(state): State<AppState>,
+    user: AuthUser,
+    admin: Option<AdminUser>,
+    Json(payload): Json<CreateAccessTokenRequest>,
+) -> Result<Json<Value>, AppError> {
+    if payload.scopes.contains(&TokenScope::Admin) && admin.is_none() {
+        return Err(AppError::Forbidden(
+            "Admin tokens can only be created by admins signed in with two-factor authentication".to_string(),
+        ));
+    }
+
+    let token_service = AccessTokenService::new(state.db.pool.clone());
+
+    let token = token_service
+        .create_token(user.user_id, &payload.name, &payload.scopes, payload.expires_in_days)
+        .await?;
+    Ok(Json(serde_json::to_value(token)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    responses((status = 200, description = "The user's tokens, without secrets", body = AccessTokenListResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn list_tokens(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let token_service = AccessTokenService::new(state.db.pool.clone());
+
+    let response = token_service.list_tokens(user.user_id).await?;
+    Ok(Json(serde_json::to_value(response)?))
+}
+
+// Takes effect immediately
+#[utoipa::path(
+    delete,
+    path = "/{token_id}",
+    params(("token_id" = Uuid, Path)),
+    responses((status = 200, description = "Token revoked", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn revoke_token(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(token_id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let token_service = AccessTokenService::new(state.db.pool.clone());
+
+    token_service.revoke_token(user.user_id, token_id).await?;
+    Ok(Json(json!({"message": "Token revoked"})))
+}
@@ -1,26 +1,39 @@
 use axum::{
     extract::{Multipart, State},
-    http::StatusCode,
     response::Json,
 };
+use chrono::Utc;
 use serde_json::{json, Value};
-use std::path::Path;
-use tokio::fs;
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 use crate::{
-    services::user::UserService,
+    error::AppError,
+    services::{
+        image,
+        jobs::{Job, JobService},
+        user::{UserService, AVATAR_PREFIX},
+    },
     AppState,
 };
 
-const MAX_FILE_SIZE: usize = 5 * 1024 * 1024; // 5MB
-const ALLOWED_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp"];
-
+// Served under /upload; the router is `upload_routes` in main.rs
+#[derive(OpenApi)]
+#[openapi(paths(upload_avatar, delete_avatar))]
+pub struct UploadApi;
+
+#[utoipa::path(
+    post,
+    path = "/avatar",
+    request_body(content_type = "multipart/form-data", description = "`avatar`: JPEG, PNG, GIF or WebP, up to 20MB"),
+    responses((status = 200, description = "`{message, avatar_url, image}` with the resized variants", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 pub async fn upload_avatar(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     mut multipart: Multipart,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
     tracing::debug!("Upload avatar - All headers: {:?}", headers);
     
@@ -37,138 +50,94 @@ pub async fn upload_avatar(
         }
         Some(header) => {
             tracing::warn!("Invalid authorization header format: {}", header);
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid authorization format"})),
-            ));
+            return Err(AppError::Unauthorized("Invalid authorization format".to_string()));
         }
         None => {
             tracing::warn!("No authorization header found");
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
+            return Err(AppError::Unauthorized("Authentication required".to_string()));
         }
     };
 
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = match auth_service.authenticate(token).await {
         Ok(claims) => claims,
         Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
+            return Err(AppError::Unauthorized("Invalid token".to_string()));
         }
     };
 
     let user_id: uuid::Uuid = match claims.sub.parse() {
         Ok(id) => id,
         Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
+            return Err(AppError::Unauthorized("Invalid user ID in token".to_string()));
         }
     };
-    // Create uploads directory if it doesn't exist
-    let uploads_dir = "uploads/avatars";
-    if let Err(_) = fs::create_dir_all(uploads_dir).await {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": "Failed to create uploads directory"})),
-        ));
-    }
-
-    while let Some(field) = multipart.next_field().await.map_err(|_| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(json!({"error": "Invalid multipart data"})),
-        )
-    })? {
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| AppError::BadRequest("Invalid multipart data".to_string()))?
+    {
         let name = field.name().unwrap_or("");
         
         if name == "avatar" {
-            let filename = field.file_name().unwrap_or("").to_string();
-            
-            // Validate file extension
-            let extension = Path::new(&filename)
-                .extension()
-                .and_then(|ext| ext.to_str())
-                .unwrap_or("")
-                .to_lowercase();
-            
-            if !ALLOWED_EXTENSIONS.contains(&extension.as_str()) {
-                return Err((
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({"error": "Invalid file type. Allowed: jpg, jpeg, png, gif, webp"})),
-                ));
-            }
-
             // Get file data
-            let data = field.bytes().await.map_err(|_| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({"error": "Failed to read file data"})),
-                )
-            })?;
+            let data = field
+                .bytes()
+                .await
+                .map_err(|_| AppError::BadRequest("Failed to read file data".to_string()))?;
 
             // Check file size
-            if data.len() > MAX_FILE_SIZE {
-                return Err((
-                    StatusCode::PAYLOAD_TOO_LARGE,
-                    Json(json!({"error": "File too large. Maximum size is 5MB"})),
-                ));
+            if data.len() > image::MAX_UPLOAD_SIZE {
+                return Err(AppError::PayloadTooLarge("File too large. Maximum size is 20MB".to_string()));
             }
 
-            // Generate unique filename
-            let file_id = Uuid::new_v4();
-            let new_filename = format!("{}.{}", file_id, extension);
-            let file_path = format!("{}/{}", uploads_dir, new_filename);
-
-            // Save file
-            if let Err(_) = fs::write(&file_path, &data).await {
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(json!({"error": "Failed to save file"})),
-                ));
+            // Validate by content, strip metadata and build the size variants
+            let processed = tokio::task::spawn_blocking(move || image::process(&data, &image::AVATAR)).await??;
+
+            let (uploaded, keys) = image::save(state.storage.as_ref(), &processed, AVATAR_PREFIX, Uuid::new_v4()).await?;
+
+            // AVIF copies are encoded in the background
+            if let Err(e) = JobService::enqueue(&state.db.pool, &Job::EncodeAvif { keys: keys.clone() }, Utc::now()).await {
+                tracing::warn!("Failed to queue AVIF encoding: {}", e);
             }
 
-            // Update user avatar URL in database
-            let avatar_url = format!("/uploads/avatars/{}", new_filename);
+            // Update user avatar URL in database; the previous avatar's files are
+            // removed by the cleanup_orphaned_avatars job
             let user_service = UserService::new(state.db.pool.clone());
             
-            match user_service.update_avatar(&user_id, &avatar_url).await {
+            match user_service.update_avatar(&user_id, &uploaded.url).await {
                 Ok(_) => {
                     return Ok(Json(json!({
                         "message": "Avatar uploaded successfully",
-                        "avatar_url": avatar_url
+                        "avatar_url": uploaded.url,
+                        "image": uploaded
                     })));
                 }
                 Err(e) => {
-                    // Clean up uploaded file if database update fails
-                    let _ = fs::remove_file(&file_path).await;
+                    // Clean up uploaded files if database update fails
+                    if let Some(key) = keys.first() {
+                        image::remove_variants(state.storage.as_ref(), key).await;
+                    }
                     
-                    tracing::error!("Failed to update user avatar: {}", e);
-                    return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(json!({"error": "Failed to update user avatar"})),
-                    ));
+                    return Err(e);
                 }
             }
         }
     }
 
-    Err((
-        StatusCode::BAD_REQUEST,
-        Json(json!({"error": "No avatar file found"})),
-    ))
+    Err(AppError::BadRequest("No avatar file found".to_string()))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/avatar",
+    responses((status = 200, description = "Avatar removed", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 pub async fn delete_avatar(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
     let auth_header = headers
         .get("authorization")
@@ -178,72 +147,46 @@ pub async fn delete_avatar(
     let token = match auth_header {
         Some(token) => token,
         None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
+            return Err(AppError::Unauthorized("Authentication required".to_string()));
         }
     };
 
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = match auth_service.authenticate(token).await {
         Ok(claims) => claims,
         Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
+            return Err(AppError::Unauthorized("Invalid token".to_string()));
         }
     };
 
     let user_id: uuid::Uuid = match claims.sub.parse() {
         Ok(id) => id,
         Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
+            return Err(AppError::Unauthorized("Invalid user ID in token".to_string()));
         }
     };
     let user_service = UserService::new(state.db.pool.clone());
     
     // Get current user data to find avatar file
-    match user_service.get_user_by_id(&user_id).await {
-        Ok(Some(current_user)) => {
-            // Remove avatar URL from database
-            match user_service.update_avatar(&user_id, "").await {
-                Ok(_) => {
-                    // Try to delete the physical file if it exists
-                    if let Some(avatar_url) = current_user.avatar_url {
-                        if avatar_url.starts_with("/uploads/avatars/") {
-                            let file_path = format!(".{}", avatar_url);
-                            let _ = fs::remove_file(&file_path).await;
-                        }
-                    }
-                    
-                    Ok(Json(json!({
-                        "message": "Avatar deleted successfully"
-                    })))
-                }
-                Err(e) => {
-                    tracing::error!("Failed to delete user avatar: {}", e);
-                    Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(json!({"error": "Failed to delete avatar"})),
-                    ))
-                }
-            }
-        }
-        Ok(None) => Err((
-            StatusCode::NOT_FOUND,
-            Json(json!({"error": "User not found"})),
-        )),
-        Err(e) => {
-            tracing::error!("Failed to get user: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get user data"})),
-            ))
-        }
+    let current_user = user_service
+        .get_user_by_id(&user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    // Remove avatar URL from database
+    user_service.update_avatar(&user_id, "").await?;
+
+    // Try to delete the physical files (all size variants) if they exist
+    if let Some(key) = current_user
+        .avatar_url
+        .as_deref()
+        .and_then(|url| state.storage.key_from_url(url))
+        .filter(|key| key.starts_with(&format!("{}/", AVATAR_PREFIX)))
+    {
+        image::remove_variants(state.storage.as_ref(), &key).await;
     }
+
+    Ok(Json(json!({
+        "message": "Avatar deleted successfully"
+    })))
 }
@@ -1,17 +1,23 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
     response::Json,
-    routing::{get, post},
+    routing::{delete, get, post},
     Router,
 };
 use serde::Deserialize;
 use serde_json::{json, Value};
+use utoipa::{OpenApi, ToSchema};
 use uuid::Uuid;
 
 use crate::{
+    error::AppError,
     middleware::auth::AuthUser,
-    services::user::UserService,
+    models::{
+        identity::{LinkIdentityRequest, LinkedIdentitiesResponse, LinkedIdentity, OidcAuthorizationResponse, OidcCallbackRequest},
+        user::{AccountDeletionResponse, DeleteAccountRequest},
+        ArticleListResponse, AuthorStats, User, UserResponse,
+    },
+    services::{account::AccountService, auth::AuthService, oidc::OidcService, user::UserService},
     AppState,
 };
 
@@ -20,6 +26,10 @@ pub fn routes() -> Router<AppState> {
         .route("/profile", get(get_profile).put(update_profile))
         .route("/profile/:username", get(get_user_profile_by_username))
         .route("/me/bookmarks", get(get_my_bookmarks))
+        .route("/me/deletion", get(get_account_deletion).post(request_account_deletion).delete(cancel_account_deletion))
+        .route("/me/identities", get(list_identities).post(start_identity_link))
+        .route("/me/identities/callback", post(link_identity))
+        .route("/me/identities/:identity_id", delete(unlink_identity))
         .route("/:user_id", get(get_user_by_id))
         .route("/:user_id/follow", post(follow_user).delete(unfollow_user))
         .route("/:user_id/follow-status", get(get_follow_status))
@@ -33,205 +43,232 @@ pub fn routes() -> Router<AppState> {
         .route("/recommendations", get(get_user_recommendations))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_profile,
+    update_profile,
+    get_user_profile_by_username,
+    get_my_bookmarks,
+    get_account_deletion,
+    request_account_deletion,
+    cancel_account_deletion,
+    list_identities,
+    start_identity_link,
+    link_identity,
+    unlink_identity,
+    get_user_by_id,
+    follow_user,
+    unfollow_user,
+    get_follow_status,
+    get_followers,
+    get_following,
+    get_user_articles,
+    get_user_bookmarks,
+    get_reading_lists,
+    get_user_stats,
+    search_users,
+    get_user_recommendations,
+))]
+pub struct UsersApi;
+
+#[utoipa::path(
+    get,
+    path = "/profile",
+    responses((status = 200, description = "The signed-in user", body = User)),
+    security(("bearer_auth" = [])),
+)]
 async fn get_profile(
     State(state): State<AppState>,
     user: AuthUser,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     let user_service = UserService::new(state.db.pool.clone());
     
-    match user_service.get_user_by_id(&user.user_id).await {
-        Ok(Some(user_data)) => {
-            Ok(Json(serde_json::to_value(user_data).unwrap()))
-        }
-        Ok(None) => {
-            Err((
-                StatusCode::NOT_FOUND,
-                Json(json!({"error": "User not found"})),
-            ))
-        }
-        Err(e) => {
-            tracing::error!("Failed to get user profile: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get user profile"})),
-            ))
-        }
-    }
+    let user_data = user_service
+        .get_user_by_id(&user.user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+    Ok(Json(serde_json::to_value(user_data)?))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 struct UpdateProfileRequest {
     display_name: Option<String>,
     bio: Option<String>,
 }
 
+#[utoipa::path(
+    put,
+    path = "/profile",
+    request_body = UpdateProfileRequest,
+    responses((status = 200, description = "Profile updated", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn update_profile(
     State(state): State<AppState>,
     user: AuthUser,
     Json(request): Json<UpdateProfileRequest>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     let user_service = UserService::new(state.db.pool.clone());
     
-    match user_service.update_profile(
+    user_service.update_profile(
         &user.user_id,
         request.display_name.as_deref(),
         request.bio.as_deref(),
-    ).await {
-        Ok(_) => {
-            Ok(Json(json!({
-                "message": "Profile updated successfully"
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to update profile: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to update profile"})),
-            ))
-        }
-    }
+    ).await?;
+
+    Ok(Json(json!({
+        "message": "Profile updated successfully"
+    })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/profile/{username}",
+    params(("username" = String, Path)),
+    responses((status = 200, description = "Public profile", body = UserResponse)),
+)]
 async fn get_user_profile_by_username(
     State(state): State<AppState>,
     Path(username): Path<String>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     let user_service = UserService::new(state.db.pool.clone());
     
-    match user_service.get_user_profile(&username).await {
-        Ok(Some(user_profile)) => {
-            Ok(Json(serde_json::to_value(user_profile).unwrap()))
-        }
-        Ok(None) => {
-            Err((
-                StatusCode::NOT_FOUND,
-                Json(json!({"error": "User not found"})),
-            ))
-        }
-        Err(e) => {
-            tracing::error!("Failed to get user profile: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get user profile"})),
-            ))
-        }
-    }
+    let user_profile = user_service
+        .get_user_profile(&state.config, &username)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+    Ok(Json(serde_json::to_value(user_profile)?))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{user_id}",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_user_by_id(
     State(_state): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get user by ID
     Ok(Json(json!({
         "message": format!("Get user {} not implemented yet", user_id)
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/{user_id}/follow",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Now following the user", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn follow_user(
     State(state): State<AppState>,
     user: AuthUser,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Can't follow yourself
     if user.user_id == user_id {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({"error": "Cannot follow yourself"})),
-        ));
+        return Err(AppError::BadRequest("Cannot follow yourself".to_string()));
     }
 
     let user_service = UserService::new(state.db.pool.clone());
     
-    match user_service.follow_user(&user.user_id, &user_id).await {
-        Ok(_) => {
-            Ok(Json(json!({
-                "message": "Successfully followed user",
-                "is_following": true
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to follow user: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to follow user"})),
-            ))
-        }
-    }
+    user_service.follow_user(&state.realtime, &user.user_id, &user_id).await?;
+
+    Ok(Json(json!({
+        "message": "Successfully followed user",
+        "is_following": true
+    })))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/{user_id}/follow",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "No longer following the user", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn unfollow_user(
     State(state): State<AppState>,
     user: AuthUser,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     let user_service = UserService::new(state.db.pool.clone());
     
-    match user_service.unfollow_user(&user.user_id, &user_id).await {
-        Ok(_) => {
-            Ok(Json(json!({
-                "message": "Successfully unfollowed user",
-                "is_following": false
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to unfollow user: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to unfollow user"})),
-            ))
-        }
-    }
+    user_service.unfollow_user(&user.user_id, &user_id).await?;
+
+    Ok(Json(json!({
+        "message": "Successfully unfollowed user",
+        "is_following": false
+    })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{user_id}/follow-status",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "`{is_following}`", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn get_follow_status(
     State(state): State<AppState>,
     user: AuthUser,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     let user_service = UserService::new(state.db.pool.clone());
     
-    match user_service.is_following(&user.user_id, &user_id).await {
-        Ok(is_following) => {
-            Ok(Json(json!({
-                "is_following": is_following
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to check follow status: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to check follow status"})),
-            ))
-        }
-    }
+    let is_following = user_service.is_following(&user.user_id, &user_id).await?;
+
+    Ok(Json(json!({
+        "is_following": is_following
+    })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{user_id}/followers",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_followers(
     State(_state): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get user followers
     Ok(Json(json!({
         "message": format!("Get followers for user {} not implemented yet", user_id)
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{user_id}/following",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_following(
     State(_state): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get users that this user is following
     Ok(Json(json!({
         "message": format!("Get following for user {} not implemented yet", user_id)
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{user_id}/articles",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "The user's published articles", body = ArticleListResponse)),
+)]
 async fn get_user_articles(
     State(state): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let article_service = crate::services::article::ArticleService::new(state.db.pool.clone());
+) -> Result<Json<Value>, AppError> {
+    let article_service = crate::services::article::ArticleService::new(state.db.pool.clone(), &state.config);
     
     // Create query params to get articles by this user
     let params = crate::models::ArticleQueryParams {
@@ -242,60 +279,35 @@ async fn get_user_articles(
         ..Default::default()
     };
     
-    match article_service.get_articles(params, None).await {
-        Ok(response) => {
-            Ok(Json(serde_json::to_value(response).unwrap()))
-        }
-        Err(e) => {
-            tracing::error!("Failed to get user articles: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get user articles"})),
-            ))
-        }
-    }
+    let response = article_service.get_articles(params, None).await?;
+    Ok(Json(serde_json::to_value(response)?))
 }
 
+#[utoipa::path(
+    get,
+    path = "/me/bookmarks",
+    responses((status = 200, description = "`{articles, total}`: the signed-in user's bookmarked articles", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn get_my_bookmarks(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // Manual auth check
     let auth_header = headers
         .get("authorization")
         .and_then(|header| header.to_str().ok())
         .and_then(|header| header.strip_prefix("Bearer "));
 
-    let token = match auth_header {
-        Some(token) => token,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Authentication required"})),
-            ));
-        }
-    };
+    let token = auth_header.ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
 
-    let auth_service = crate::services::auth::AuthService::new(&state.db, &state.config);
-    let claims = match auth_service.verify_token(token).await {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid token"})),
-            ));
-        }
-    };
+    let auth_service = crate::services::auth::AuthService::new(&state.db);
+    let claims = auth_service.authenticate(token).await?;
 
-    let user_id: uuid::Uuid = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({"error": "Invalid user ID in token"})),
-            ));
-        }
-    };
+    let user_id: uuid::Uuid = claims
+        .sub
+        .parse()
+        .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?;
 
     // Get bookmarked articles for this user
     let bookmarks = sqlx::query!(
@@ -316,124 +328,254 @@ async fn get_my_bookmarks(
         user_id
     )
     .fetch_all(&state.db.pool)
-    .await;
-
-    match bookmarks {
-        Ok(rows) => {
-            let articles: Vec<Value> = rows
-                .into_iter()
-                .map(|row| {
-                    json!({
-                        "id": row.id,
-                        "title": row.title,
-                        "subtitle": row.subtitle,
-                        "excerpt": row.excerpt,
-                        "slug": row.slug,
-                        "featured_image_url": row.featured_image_url,
-                        "reading_time_minutes": row.reading_time_minutes,
-                        "claps_count": row.claps_count,
-                        "comments_count": row.comments_count,
-                        "views_count": row.views_count,
-                        "published_at": row.published_at,
-                        "created_at": row.created_at,
-                        "bookmarked_at": row.bookmarked_at,
-                        "author": {
-                            "id": row.author_id,
-                            "username": row.author_username,
-                            "display_name": row.author_display_name,
-                            "avatar_url": row.author_avatar_url
-                        }
-                    })
-                })
-                .collect();
-
-            Ok(Json(json!({
-                "articles": articles,
-                "total": articles.len()
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to get bookmarks: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get bookmarks"})),
-            ))
-        }
-    }
+    .await?;
+
+    let articles: Vec<Value> = bookmarks
+        .into_iter()
+        .map(|row| {
+            json!({
+                "id": row.id,
+                "title": row.title,
+                "subtitle": row.subtitle,
+                "excerpt": row.excerpt,
+                "slug": row.slug,
+                "featured_image_url": row.featured_image_url,
+                "reading_time_minutes": row.reading_time_minutes,
+                "claps_count": row.claps_count,
+                "comments_count": row.comments_count,
+                "views_count": row.views_count,
+                "published_at": row.published_at,
+                "created_at": row.created_at,
+                "bookmarked_at": row.bookmarked_at,
+                "author": {
+                    "id": row.author_id,
+                    "username": row.author_username,
+                    "display_name": row.author_display_name,
+                    "avatar_url": row.author_avatar_url
+                }
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "articles": articles,
+        "total": articles.len()
+    })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{user_id}/bookmarks",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_user_bookmarks(
     State(_state): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get user bookmarked articles (requires auth middleware for privacy)
     Ok(Json(json!({
         "message": format!("Get bookmarks for user {} not implemented yet", user_id)
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{user_id}/reading-lists",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn get_reading_lists(
     State(_state): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Get user reading lists
     Ok(Json(json!({
         "message": format!("Get reading lists for user {} not implemented yet", user_id)
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/search",
+    responses((status = 200, description = "Not implemented yet", body = Value)),
+)]
 async fn search_users(
     State(_state): State<AppState>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     // TODO: Search users by username/name
     Ok(Json(json!({
         "message": "Search users not implemented yet"
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{user_id}/stats",
+    params(("user_id" = Uuid, Path)),
+    responses((status = 200, description = "Totals across the user's articles", body = AuthorStats)),
+)]
 async fn get_user_stats(
     State(state): State<AppState>,
     Path(user_id): Path<Uuid>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let article_service = crate::services::article::ArticleService::new(state.db.pool.clone());
+) -> Result<Json<Value>, AppError> {
+    let article_service = crate::services::article::ArticleService::new(state.db.pool.clone(), &state.config);
     
-    match article_service.get_author_stats(user_id).await {
-        Ok(stats) => {
-            Ok(Json(serde_json::to_value(stats).unwrap()))
-        }
-        Err(e) => {
-            tracing::error!("Failed to get user stats: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get user statistics"})),
-            ))
-        }
-    }
+    let stats = article_service.get_author_stats(user_id).await?;
+    Ok(Json(serde_json::to_value(stats)?))
 }
 
+#[utoipa::path(
+    get,
+    path = "/recommendations",
+    params(("limit" = Option<i64>, Query, description = "Defaults to 10")),
+    responses((status = 200, description = "`{recommendations}`: users to follow", body = Value)),
+    security(("bearer_auth" = [])),
+)]
 async fn get_user_recommendations(
     State(state): State<AppState>,
     user: AuthUser,
     Query(params): Query<std::collections::HashMap<String, String>>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<Json<Value>, AppError> {
     let limit = params.get("limit")
         .and_then(|l| l.parse::<i64>().ok())
         .unwrap_or(10);
 
     let user_service = UserService::new(state.db.pool.clone());
     
-    match user_service.get_user_recommendations(&user.user_id, Some(limit)).await {
-        Ok(recommendations) => {
-            Ok(Json(json!({
-                "recommendations": recommendations
-            })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to get user recommendations: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": "Failed to get recommendations"})),
-            ))
-        }
+    let recommendations = user_service.get_user_recommendations(&state.config, &user.user_id, Some(limit)).await?;
+
+    Ok(Json(json!({
+        "recommendations": recommendations
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/me/deletion",
+    responses((status = 200, description = "Pending account deletion, if any", body = AccountDeletionResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn get_account_deletion(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let account_service = AccountService::new(state.db.pool.clone(), &state.config);
+
+    let deletion = account_service.get_deletion(user.user_id).await?;
+    Ok(Json(serde_json::to_value(deletion)?))
+}
+
+// Deletes the account after a grace period, with `articles` set to "delete" or "anonymize"
+#[utoipa::path(
+    post,
+    path = "/me/deletion",
+    request_body = DeleteAccountRequest,
+    responses((status = 200, description = "Deletion scheduled after the grace period", body = AccountDeletionResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn request_account_deletion(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<DeleteAccountRequest>,
+) -> Result<Json<Value>, AppError> {
+    let auth_service = AuthService::new(&state.db);
+
+    if !auth_service.confirm_password(user.user_id, &payload.password).await? {
+        return Err(AppError::Forbidden("Incorrect password".to_string()));
     }
+
+    let account_service = AccountService::new(state.db.pool.clone(), &state.config);
+
+    let deletion = account_service.request_deletion(user.user_id, payload.articles).await?;
+    Ok(Json(serde_json::to_value(deletion)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/me/deletion",
+    responses((status = 200, description = "Deletion cancelled", body = AccountDeletionResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn cancel_account_deletion(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let account_service = AccountService::new(state.db.pool.clone(), &state.config);
+
+    let deletion = account_service.cancel_deletion(user.user_id).await?;
+    Ok(Json(serde_json::to_value(deletion)?))
+}
+
+// Sign-in providers linked to the account
+#[utoipa::path(
+    get,
+    path = "/me/identities",
+    responses((status = 200, description = "Linked sign-in providers", body = LinkedIdentitiesResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn list_identities(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Value>, AppError> {
+    let oidc_service = OidcService::new(&state.db, &state.config);
+
+    let response = oidc_service.list_identities(user.user_id).await?;
+    Ok(Json(serde_json::to_value(response)?))
+}
+
+// Like signing in, but the callback links the provider account to this user
+#[utoipa::path(
+    post,
+    path = "/me/identities",
+    request_body = LinkIdentityRequest,
+    responses((status = 200, description = "Where to send the browser", body = OidcAuthorizationResponse)),
+    security(("bearer_auth" = [])),
+)]
+async fn start_identity_link(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<LinkIdentityRequest>,
+) -> Result<Json<Value>, AppError> {
+    let oidc_service = OidcService::new(&state.db, &state.config);
+
+    let response = oidc_service.start(&payload.provider, Some(user.user_id)).await?;
+    Ok(Json(serde_json::to_value(response)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/me/identities/callback",
+    request_body = OidcCallbackRequest,
+    responses((status = 200, description = "Provider account linked", body = LinkedIdentity)),
+    security(("bearer_auth" = [])),
+)]
+async fn link_identity(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<OidcCallbackRequest>,
+) -> Result<Json<Value>, AppError> {
+    let oidc_service = OidcService::new(&state.db, &state.config);
+
+    let identity = oidc_service.link(user.user_id, &payload.code, &payload.state).await?;
+    Ok(Json(serde_json::to_value(identity)?))
+}
+
+// Refused when it's the account's only way to sign in
+#[utoipa::path(
+    delete,
+    path = "/me/identities/{identity_id}",
+    params(("identity_id" = Uuid, Path)),
+    responses((status = 200, description = "Provider account unlinked", body = Value)),
+    security(("bearer_auth" = [])),
+)]
+async fn unlink_identity(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(identity_id): Path<Uuid>,
+) -> Result<Json<Value>, AppError> {
+    let oidc_service = OidcService::new(&state.db, &state.config);
+
+    oidc_service.unlink(user.user_id, identity_id).await?;
+    Ok(Json(json!({"message": "Account unlinked"})))
 }
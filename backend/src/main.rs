@@ -1,36 +1,52 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{DefaultBodyLimit, State},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
     response::Json,
     routing::{get, post},
     Router,
 };
-use tower_http::services::ServeDir;
 use serde_json::{json, Value};
+use std::future::IntoFuture;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 use tower::ServiceBuilder;
 use tower_http::{
     compression::CompressionLayer,
-    cors::{Any, CorsLayer},
+    cors::{AllowOrigin, Any, CorsLayer},
     trace::TraceLayer,
 };
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use utoipa::OpenApi;
 
+mod cli;
 mod config;
 mod database;
+mod error;
 mod handlers;
 mod middleware;
 mod models;
 mod services;
+mod util;
 
 use config::Config;
 use database::Database;
+use error::AppError;
+
+// How long shutdown waits for open HTTP connections
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+// How long shutdown waits for running background jobs
+const JOB_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
 
 pub type AppState = Arc<AppStateInner>;
 
 pub struct AppStateInner {
     pub db: Database,
     pub config: Config,
+    pub mailer: Arc<dyn services::mailer::Mailer>,
+    pub storage: Arc<dyn services::storage::Storage>,
+    pub realtime: Arc<services::realtime::RealtimeHub>,
 }
 
 #[tokio::main]
@@ -46,6 +62,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Load configuration
     let config = Config::from_env()?;
+
+    // Parse the token signing keys up front so a bad key stops startup
+    services::jwt::init(&config.jwt).map_err(|e| format!("Invalid JWT key configuration: {}", e))?;
     
     // Initialize database
     let db = Database::new(&config.database_url).await?;
@@ -53,8 +72,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Run migrations
     db.migrate().await?;
 
+    // Maintenance commands run instead of the server, e.g. `migrate-storage --from local --to s3`
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(command) = args.first() {
+        return cli::run(command, &args[1..], &config, &db).await;
+    }
+
+    let realtime = Arc::new(services::realtime::RealtimeHub::new());
+
+    // Fan realtime events out and share rate limit counters through Redis when running multiple instances
+    if let Some(redis_url) = &config.redis_url {
+        realtime.connect_redis(redis_url).await?;
+        services::rate_limit::limiter().connect_redis(redis_url).await?;
+    }
+    services::rate_limit::limiter().spawn_cleanup();
+
+    let mailer = services::mailer::from_config(&config)?;
+    let storage = services::storage::from_config(&config)?;
+
+    // Cancelled on SIGINT/SIGTERM: stops the server and the job runner
+    let shutdown = CancellationToken::new();
+    tokio::spawn(wait_for_shutdown_signal(shutdown.clone()));
+
+    // Run background jobs (publishing, emails, newsletters, digests, maintenance)
+    let jobs = services::jobs::JobRunner::start(
+        db.pool.clone(),
+        config.clone(),
+        mailer.clone(),
+        storage.clone(),
+        shutdown.clone(),
+    );
+
     // Create application state
-    let state = Arc::new(AppStateInner { db, config });
+    let state = Arc::new(AppStateInner { db, config, mailer, storage, realtime });
 
     // Build the application router
     let app = create_app(state.clone());
@@ -65,37 +115,168 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("🚀 FastBlog server starting on port {}", state.config.port);
     tracing::info!("📖 API Documentation: http://localhost:{}/docs", state.config.port);
     
-    axum::serve(listener, app).await?;
+    // Peer addresses feed per-IP rate limits and failed login tracking
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+        .into_future();
+
+    // Open SSE streams never finish on their own, so only wait a little for connections to close
+    tokio::select! {
+        result = server => result?,
+        _ = async {
+            shutdown.cancelled().await;
+            tokio::time::sleep(SHUTDOWN_TIMEOUT).await;
+        } => tracing::warn!("Closing remaining connections after {:?}", SHUTDOWN_TIMEOUT),
+    }
+
+    // Let running jobs finish; unfinished ones are picked up again after a restart
+    tracing::info!("Waiting for running background jobs");
+    if tokio::time::timeout(JOB_SHUTDOWN_TIMEOUT, jobs.wait()).await.is_err() {
+        tracing::warn!("Background jobs did not finish within {:?}", JOB_SHUTDOWN_TIMEOUT);
+    }
 
     Ok(())
 }
 
+async fn wait_for_shutdown_signal(shutdown: CancellationToken) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+
+    tracing::info!("Shutdown signal received");
+    shutdown.cancel();
+}
+
 fn create_app(state: AppState) -> Router {
-    Router::new()
-        // Health check
-        .route("/health", get(health_check))
+    let cors = cors_layer(&state.config);
+
+    app_routes(&state.config.storage.local_root)
+        // Unknown paths get a problem+json 404 like every other error
+        .fallback(not_found)
+        
+        // Resolve the bearer token (if any) so handlers can use the AuthUser extractors
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::auth::optional_auth_middleware,
+        ))
         
-        // Static file serving for uploads
-        .nest_service("/uploads", ServeDir::new("uploads"))
+        // Client address for rate limits and login tracking, honouring TRUSTED_PROXY_HOPS
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::rate_limit::resolve_client_ip,
+        ))
         
-        // API routes
-        .nest("/api/v1", api_routes())
+        // CSP, HSTS and friends, unless a route set its own
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::security_headers::security_headers,
+        ))
+        
+        // X-Request-Id on every response; error bodies and logs carry the same id
+        .layer(axum::middleware::from_fn(middleware::request_id::request_id))
         
         // Add middleware
         .layer(
             ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
+                .layer(TraceLayer::new_for_http().make_span_with(request_span))
                 .layer(CompressionLayer::new())
-                .layer(
-                    CorsLayer::new()
-                        .allow_origin(Any)
-                        .allow_methods(Any)
-                        .allow_headers(Any),
-                ),
+                .layer(cors),
         )
         .with_state(state)
 }
 
+// Every route the server answers; the docs tests check it against the OpenAPI document
+fn app_routes(local_root: &str) -> Router<AppState> {
+    Router::new()
+        // Health check
+        .route("/health", get(health_check))
+        
+        // Public files of the local storage backend
+        .nest("/uploads", handlers::files::local_routes(local_root))
+        
+        // robots.txt and sitemaps
+        .merge(handlers::seo::crawler_routes())
+        
+        // Public token verification keys (JWKS)
+        .merge(handlers::auth::well_known_routes())
+        
+        // OpenAPI document at /api/v1/openapi.json and the Swagger UI at /docs
+        .merge(handlers::docs::routes())
+        
+        // API routes, with the default per-IP read and per-user write limits
+        .nest("/api/v1", api_routes().layer(middleware::rate_limit::limit(middleware::rate_limit::API)))
+}
+
+// Logs the path without the query string, which can carry unsubscribe tokens,
+// signed file links and stream tickets
+fn request_span(request: &axum::http::Request<axum::body::Body>) -> tracing::Span {
+    tracing::debug_span!(
+        "request",
+        method = %request.method(),
+        path = %request.uri().path(),
+        version = ?request.version(),
+    )
+}
+
+// Only the configured origins may call the API from a browser, with cookies and
+// Authorization headers. `*` allows any origin but then credentials are not allowed.
+fn cors_layer(config: &Config) -> CorsLayer {
+    let layer = CorsLayer::new()
+        .allow_methods([
+            Method::GET,
+            Method::HEAD,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+            Method::OPTIONS,
+        ])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, header::ACCEPT])
+        .expose_headers([
+            HeaderName::from_static("ratelimit-limit"),
+            HeaderName::from_static("ratelimit-remaining"),
+            HeaderName::from_static("ratelimit-reset"),
+            header::RETRY_AFTER,
+            middleware::request_id::REQUEST_ID_HEADER.clone(),
+        ])
+        .max_age(Duration::from_secs(3600));
+
+    if config.cors_origins.iter().any(|origin| origin == "*") {
+        return layer.allow_origin(Any);
+    }
+
+    let origins: Vec<HeaderValue> = config
+        .cors_origins
+        .iter()
+        .filter_map(|origin| HeaderValue::from_str(origin).ok())
+        .collect();
+    layer.allow_origin(AllowOrigin::list(origins)).allow_credentials(true)
+}
+
 fn api_routes() -> Router<AppState> {
     Router::new()
         // Authentication routes
@@ -119,6 +300,39 @@ fn api_routes() -> Router<AppState> {
         // Upload routes
         .nest("/upload", upload_routes())
         
+        // Article image uploads and the per-user media library
+        .nest("/media", handlers::media::routes())
+        
+        // Signed, expiring links to private files
+        .nest("/files", handlers::files::routes())
+        
+        // Article imports from Medium, WordPress and Ghost exports
+        .nest("/imports", handlers::imports::routes())
+        
+        // Markdown and account data (GDPR) exports
+        .nest("/exports", handlers::exports::routes())
+        
+        // Personal access tokens for scripts and CI
+        .nest("/tokens", handlers::tokens::routes())
+        
+        // Notification inbox
+        .nest("/notifications", handlers::notifications::routes())
+        
+        // Live notifications and article counts (Server-Sent Events)
+        .nest("/realtime", handlers::realtime::routes())
+        
+        // Email digest settings and unsubscribe links
+        .nest("/digest", handlers::digest::routes())
+        
+        // Author and publication email newsletters
+        .nest("/newsletter", handlers::newsletter::routes())
+        
+        // RSS, Atom and JSON Feed syndication
+        .nest("/feeds", handlers::feeds::routes())
+        
+        // Open Graph, Twitter Card and JSON-LD metadata for SSR
+        .nest("/seo", handlers::seo::routes())
+        
 
 }
 
@@ -126,8 +340,19 @@ fn upload_routes() -> Router<AppState> {
     Router::new()
         .route("/avatar", post(handlers::upload::upload_avatar))
         .route("/avatar", axum::routing::delete(handlers::upload::delete_avatar))
+        // Allow full-size phone photos; they are scaled down on upload
+        .layer(DefaultBodyLimit::max(services::image::MAX_UPLOAD_SIZE + 64 * 1024))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(health_check))]
+struct HealthApi;
+
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses((status = 200, description = "Service name, version and current time", body = Value)),
+)]
 async fn health_check(State(_state): State<AppState>) -> Result<Json<Value>, StatusCode> {
     Ok(Json(json!({
         "status": "ok",
@@ -135,4 +360,8 @@ async fn health_check(State(_state): State<AppState>) -> Result<Json<Value>, Sta
         "version": env!("CARGO_PKG_VERSION"),
         "timestamp": chrono::Utc::now().to_rfc3339()
     })))
-}
\ No newline at end of file
+}
+
+async fn not_found() -> AppError {
+    AppError::NotFound("Route not found".to_string())
+}
@@ -1,14 +1,16 @@
 use axum::{
     extract::{Request, State, FromRequestParts},
-    http::{header::AUTHORIZATION, StatusCode, request::Parts},
+    http::{header::AUTHORIZATION, Method, request::Parts},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
     async_trait,
 };
-use serde_json::json;
 use uuid::Uuid;
 
-use crate::{services::auth::{AuthService, Claims}, AppState};
+use crate::{error::AppError, models::access_token::TokenScope, services::auth::{AuthService, Claims}, AppState};
+
+// Managing credentials and the account itself needs a real sign-in
+const SESSION_ONLY_PATHS: &[&str] = &["/tokens", "/auth", "/users/me/identities", "/users/me/deletion", "/exports"];
 
 // Auth extractor for required authentication
 #[derive(Debug, Clone)]
@@ -23,26 +25,19 @@ impl<S> FromRequestParts<S> for AuthUser
 where
     S: Send + Sync,
 {
-    type Rejection = (StatusCode, axum::Json<serde_json::Value>);
+    type Rejection = AppError;
 
     async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
         let claims = parts
             .extensions
             .get::<Claims>()
-            .ok_or_else(|| {
-                (
-                    StatusCode::UNAUTHORIZED,
-                    axum::Json(json!({"error": "Authentication required"})),
-                )
-            })?;
+            .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
 
         Ok(AuthUser {
-            user_id: claims.sub.parse().map_err(|_| {
-                (
-                    StatusCode::UNAUTHORIZED,
-                    axum::Json(json!({"error": "Invalid user ID in token"})),
-                )
-            })?,
+            user_id: claims
+                .sub
+                .parse()
+                .map_err(|_| AppError::Unauthorized("Invalid user ID in token".to_string()))?,
             username: claims.username.clone(),
             user_type: format!("{:?}", claims.user_type),
         })
@@ -76,11 +71,48 @@ where
     }
 }
 
+// Admin extractor: an authenticated user with users.is_admin set, signed in with two-factor authentication
+#[derive(Debug, Clone)]
+pub struct AdminUser(pub AuthUser);
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+        let signed_in_with_mfa = parts.extensions.get::<Claims>().is_some_and(|claims| claims.mfa);
+
+        let admin = sqlx::query!(
+            r#"SELECT is_admin, totp_enabled_at IS NOT NULL as "two_factor!" FROM users WHERE id = $1"#,
+            user.user_id
+        )
+        .fetch_optional(&state.db.pool)
+        .await?;
+
+        let Some(admin) = admin.filter(|admin| admin.is_admin) else {
+            return Err(AppError::Forbidden("Admin access required".to_string()));
+        };
+        if !admin.two_factor {
+            return Err(AppError::Forbidden(
+                "Two-factor authentication is required for admin accounts".to_string(),
+            ));
+        }
+        if !signed_in_with_mfa {
+            return Err(AppError::Forbidden(
+                "Sign in again with two-factor authentication to use admin features".to_string(),
+            ));
+        }
+
+        Ok(AdminUser(user))
+    }
+}
+
 pub async fn auth_middleware(
     State(state): State<AppState>,
     mut request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
+) -> Result<Response, AppError> {
     let auth_header = request
         .headers()
         .get(AUTHORIZATION)
@@ -92,21 +124,25 @@ pub async fn auth_middleware(
         }
         _ => {
             tracing::warn!("Missing or invalid Authorization header");
-            return Err(StatusCode::UNAUTHORIZED);
+            return Err(AppError::Unauthorized("Authentication required".to_string()));
         }
     };
 
-    let auth_service = AuthService::new(&state.db, &state.config);
+    let auth_service = AuthService::new(&state.db);
 
-    match auth_service.verify_token(token).await {
+    match auth_service.authenticate(token).await {
         Ok(claims) => {
+            if let Err(message) = check_token_scope(&claims, request.method(), request.uri().path()) {
+                tracing::warn!("Access token rejected: {}", message);
+                return Err(AppError::Forbidden(message));
+            }
             // Add user info to request extensions
             request.extensions_mut().insert(claims);
             Ok(next.run(request).await)
         }
         Err(e) => {
             tracing::warn!("Token verification failed: {}", e);
-            Err(StatusCode::UNAUTHORIZED)
+            Err(AppError::Unauthorized("Invalid or expired token".to_string()))
         }
     }
 }
@@ -124,9 +160,12 @@ pub async fn optional_auth_middleware(
 
     if let Some(header) = auth_header {
         if let Some(token) = header.strip_prefix("Bearer ") {
-            let auth_service = AuthService::new(&state.db, &state.config);
+            let auth_service = AuthService::new(&state.db);
             
-            if let Ok(claims) = auth_service.verify_token(token).await {
+            if let Ok(claims) = auth_service.authenticate(token).await {
+                if let Err(message) = check_token_scope(&claims, request.method(), request.uri().path()) {
+                    return AppError::Forbidden(message).into_response();
+                }
                 request.extensions_mut().insert(claims);
             }
         }
@@ -134,3 +173,54 @@ pub async fn optional_auth_middleware(
 
     next.run(request).await
 }
+
+// Personal access tokens only reach the endpoints their scopes cover
+fn check_token_scope(claims: &Claims, method: &Method, path: &str) -> Result<(), String> {
+    let Some(scopes) = &claims.scopes else {
+        return Ok(());
+    };
+    let Some(path) = path.strip_prefix("/api/v1") else {
+        return Ok(());
+    };
+
+    match required_scope(method, path) {
+        Some(scope) if scopes.contains(&scope) => Ok(()),
+        Some(scope) => Err(format!("This access token lacks the {} scope", scope.as_str())),
+        None => Err("Access tokens can't be used for this endpoint".to_string()),
+    }
+}
+
+// None: sessions only
+fn required_scope(method: &Method, path: &str) -> Option<TokenScope> {
+    let path = path.trim_end_matches('/');
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    let is_read = matches!(*method, Method::GET | Method::HEAD);
+
+    if is_read && path == "/auth/me" {
+        return Some(TokenScope::Read);
+    }
+    if SESSION_ONLY_PATHS
+        .iter()
+        .any(|prefix| path == *prefix || path.starts_with(&format!("{}/", prefix)))
+    {
+        return None;
+    }
+    if segments.first() == Some(&"admin") {
+        return Some(TokenScope::Admin);
+    }
+    if is_read {
+        return Some(TokenScope::Read);
+    }
+
+    match segments.as_slice() {
+        ["engagement", "comments", ..] | ["articles", _, "comments"] => Some(TokenScope::WriteComments),
+        ["articles"]
+        | ["articles", "draft", "auto-save"]
+        | ["articles", _, "publish" | "schedule"]
+        | ["media", ..]
+        | ["imports", ..] => Some(TokenScope::WriteArticles),
+        ["realtime", "ticket"] => Some(TokenScope::Read),
+        ["articles", _] if matches!(*method, Method::PUT | Method::DELETE) => Some(TokenScope::WriteArticles),
+        _ => None,
+    }
+}
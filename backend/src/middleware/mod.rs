@@ -1,2 +1,4 @@
 pub mod auth;
 pub mod rate_limit;
+pub mod request_id;
+pub mod security_headers;
@@ -1,110 +1,277 @@
 use axum::{
-    extract::{ConnectInfo, Request},
-    http::StatusCode,
-    middleware::Next,
-    response::Response,
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::{request::Parts, Extensions, HeaderMap, HeaderValue, StatusCode},
+    middleware::{from_fn, FromFnLayer, Next},
+    response::{IntoResponse, Response},
 };
-use dashmap::DashMap;
+use futures::future::BoxFuture;
 use std::{
-    net::SocketAddr,
-    sync::Arc,
-    time::{Duration, Instant},
+    net::{IpAddr, SocketAddr},
+    time::Duration,
 };
-use tokio::time::sleep;
 
-#[derive(Clone)]
-pub struct RateLimiter {
-    requests: Arc<DashMap<String, Vec<Instant>>>,
-    max_requests: usize,
-    window_duration: Duration,
+use crate::{
+    error::AppError,
+    services::{
+        auth::Claims,
+        rate_limit::{limiter, Decision, Quota},
+    },
+    AppState,
+};
+
+// Address of the client: the peer address, or with TRUSTED_PROXY_HOPS set, the X-Forwarded-For
+// entry added by the outermost trusted proxy. Entries to its left come from the client and can't
+// be trusted. Resolved once per request by `resolve_client_ip`.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<ClientIp>()
+            .copied()
+            .ok_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+// Stores the client address for rate limits and the ClientIp extractor
+pub async fn resolve_client_ip(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    if let Some(ip) = client_ip(state.config.trusted_proxy_hops, request.headers(), request.extensions()) {
+        request.extensions_mut().insert(ClientIp(ip));
+    }
+    next.run(request).await
+}
+
+fn client_ip(trusted_proxy_hops: usize, headers: &HeaderMap, extensions: &Extensions) -> Option<IpAddr> {
+    if trusted_proxy_hops > 0 {
+        // Each proxy appends the address it received the request from, so with N proxies
+        // the client is the Nth entry from the right
+        let entries: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect();
+        let forwarded = entries
+            .len()
+            .checked_sub(trusted_proxy_hops)
+            .and_then(|index| entries[index].trim().parse().ok());
+        if forwarded.is_some() {
+            return forwarded;
+        }
+    }
+
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateMethods {
+    All,
+    // GET and HEAD
+    Reads,
+    Writes,
 }
 
-impl RateLimiter {
-    pub fn new(max_requests: usize, window_duration: Duration) -> Self {
-        let rate_limiter = Self {
-            requests: Arc::new(DashMap::new()),
-            max_requests,
-            window_duration,
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateKey {
+    Ip,
+    // Signed-in user, or the IP for anonymous requests
+    User,
+}
+
+// A declarative limit, attached to routes with `limit`
+#[derive(Debug)]
+pub struct RatePolicy {
+    // Part of the counter key and logged when the limit is hit
+    pub name: &'static str,
+    pub methods: RateMethods,
+    pub key: RateKey,
+    pub quota: Quota,
+    // Count each path separately, e.g. claps per article rather than claps overall
+    pub per_path: bool,
+}
+
+const fn per_minute(limit: u32) -> Quota {
+    Quota { limit, period: Duration::from_secs(60) }
+}
+
+// Every API request: reads per IP, writes per user
+pub const API: &[RatePolicy] = &[
+    RatePolicy { name: "reads", methods: RateMethods::Reads, key: RateKey::Ip, quota: per_minute(300), per_path: false },
+    RatePolicy { name: "writes", methods: RateMethods::Writes, key: RateKey::User, quota: per_minute(60), per_path: false },
+];
+
+// Login, registration, password reset, 2FA code checks and the OIDC callback, per IP and endpoint
+pub const AUTH: &[RatePolicy] = &[
+    RatePolicy { name: "auth", methods: RateMethods::All, key: RateKey::Ip, quota: per_minute(5), per_path: true },
+];
+
+// Clapping is one request per click; keep one reader from flooding an article's counts
+pub const CLAPS: &[RatePolicy] = &[
+    RatePolicy { name: "claps", methods: RateMethods::Writes, key: RateKey::User, quota: per_minute(30), per_path: true },
+];
+
+// Layer enforcing `policies` on a router or route. Responses carry the RateLimit-* headers
+// of the tightest matching policy; admin tokens are exempt.
+pub fn limit(
+    policies: &'static [RatePolicy],
+) -> FromFnLayer<impl FnMut(Request, Next) -> BoxFuture<'static, Response> + Clone + Send + 'static, (), (Request,)> {
+    from_fn(move |request: Request, next: Next| -> BoxFuture<'static, Response> {
+        Box::pin(enforce(policies, request, next))
+    })
+}
+
+async fn enforce(policies: &'static [RatePolicy], request: Request, next: Next) -> Response {
+    let claims = request.extensions().get::<Claims>();
+    if claims.is_some_and(|claims| claims.admin) {
+        return next.run(request).await;
+    }
+
+    let user_id = claims.map(|claims| claims.sub.clone());
+    let ip = request
+        .extensions()
+        .get::<ClientIp>()
+        .map(|ClientIp(ip)| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let is_read = request.method() == axum::http::Method::GET || request.method() == axum::http::Method::HEAD;
+
+    let mut tightest: Option<Decision> = None;
+    for policy in policies {
+        let applies = match policy.methods {
+            RateMethods::All => true,
+            RateMethods::Reads => is_read,
+            RateMethods::Writes => !is_read,
         };
+        if !applies {
+            continue;
+        }
 
-        // Cleanup task to remove old entries
-        let requests_clone = rate_limiter.requests.clone();
-        let window_duration_clone = window_duration;
-        
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(60));
-            loop {
-                interval.tick().await;
-                let now = Instant::now();
-                
-                requests_clone.retain(|_, timestamps| {
-                    timestamps.retain(|&timestamp| now.duration_since(timestamp) < window_duration_clone);
-                    !timestamps.is_empty()
-                });
-            }
-        });
+        let subject = match (policy.key, &user_id) {
+            (RateKey::User, Some(user_id)) => format!("user:{}", user_id),
+            _ => format!("ip:{}", ip),
+        };
+        let mut key = format!("{}:{}", policy.name, subject);
+        if policy.per_path {
+            key.push(':');
+            key.push_str(request.uri().path());
+        }
 
-        rate_limiter
+        let decision = limiter().check(&key, &policy.quota).await;
+        if !decision.allowed {
+            tracing::warn!("Rate limit '{}' exceeded by {}", policy.name, subject);
+            let mut response = AppError::RateLimited {
+                message: "Too many requests".to_string(),
+                retry_after: decision.retry_after,
+            }
+            .into_response();
+            set_headers(&mut response, &decision);
+            return response;
+        }
+        if tightest.is_none_or(|current| decision.remaining < current.remaining) {
+            tightest = Some(decision);
+        }
     }
 
-    pub fn check_rate_limit(&self, identifier: &str) -> bool {
-        let now = Instant::now();
-        
-        let mut entry = self.requests.entry(identifier.to_string()).or_insert_with(Vec::new);
-        
-        // Remove old requests outside the window
-        entry.retain(|&timestamp| now.duration_since(timestamp) < self.window_duration);
-        
-        if entry.len() >= self.max_requests {
-            false
-        } else {
-            entry.push(now);
-            true
+    let mut response = next.run(request).await;
+    if let Some(decision) = tightest {
+        // A route-specific layer further in may have reported a tighter policy already
+        let reported = response
+            .headers()
+            .get("ratelimit-remaining")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u32>().ok());
+        if reported.is_none_or(|remaining| decision.remaining < remaining) {
+            set_headers(&mut response, &decision);
         }
     }
+    response
 }
 
-pub async fn rate_limit_middleware(
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    request: Request,
-    next: Next,
-) -> Result<Response, StatusCode> {
-    // Create a simple rate limiter: 100 requests per minute per IP
-    static RATE_LIMITER: once_cell::sync::Lazy<RateLimiter> = once_cell::sync::Lazy::new(|| {
-        RateLimiter::new(100, Duration::from_secs(60))
-    });
-
-    let client_ip = addr.ip().to_string();
-    
-    if !RATE_LIMITER.check_rate_limit(&client_ip) {
-        tracing::warn!("Rate limit exceeded for IP: {}", client_ip);
-        return Err(StatusCode::TOO_MANY_REQUESTS);
+// IETF RateLimit header fields (draft-ietf-httpapi-ratelimit-headers)
+fn set_headers(response: &mut Response, decision: &Decision) {
+    let headers = response.headers_mut();
+    headers.insert("ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert("ratelimit-reset", HeaderValue::from(decision.reset));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(forwarded_for: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in forwarded_for {
+            headers.append("x-forwarded-for", HeaderValue::from_str(value).unwrap());
+        }
+        headers
     }
 
-    Ok(next.run(request).await)
-}
+    fn peer() -> Extensions {
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 443))));
+        extensions
+    }
 
-// Stricter rate limiting for authentication endpoints
-pub async fn auth_rate_limit_middleware(
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    request: Request,
-    next: Next,
-) -> Result<Response, StatusCode> {
-    // 5 auth attempts per minute per IP
-    static AUTH_RATE_LIMITER: once_cell::sync::Lazy<RateLimiter> = once_cell::sync::Lazy::new(|| {
-        RateLimiter::new(5, Duration::from_secs(60))
-    });
-
-    let client_ip = addr.ip().to_string();
-    
-    if !AUTH_RATE_LIMITER.check_rate_limit(&client_ip) {
-        tracing::warn!("Auth rate limit exceeded for IP: {}", client_ip);
-        
-        // Add a small delay to slow down brute force attacks
-        sleep(Duration::from_millis(1000)).await;
-        
-        return Err(StatusCode::TOO_MANY_REQUESTS);
+    fn ip(value: &str) -> Option<IpAddr> {
+        Some(value.parse().unwrap())
     }
 
-    Ok(next.run(request).await)
+    #[test]
+    fn uses_the_peer_address_without_trusted_proxies() {
+        let headers = headers(&["203.0.113.7"]);
+
+        assert_eq!(client_ip(0, &headers, &peer()), ip("10.0.0.1"));
+        assert_eq!(client_ip(0, &headers, &Extensions::new()), None);
+    }
+
+    #[test]
+    fn picks_the_entry_added_by_the_outermost_trusted_proxy() {
+        let headers = headers(&["198.51.100.1, 203.0.113.7, 192.0.2.10"]);
+
+        assert_eq!(client_ip(1, &headers, &peer()), ip("192.0.2.10"));
+        assert_eq!(client_ip(2, &headers, &peer()), ip("203.0.113.7"));
+        assert_eq!(client_ip(3, &headers, &peer()), ip("198.51.100.1"));
+    }
+
+    #[test]
+    fn ignores_spoofed_entries_left_of_the_trusted_hops() {
+        // The client sent its own X-Forwarded-For; the one proxy appended the real address
+        let headers = headers(&["1.2.3.4, 5.6.7.8, 203.0.113.7"]);
+
+        assert_eq!(client_ip(1, &headers, &peer()), ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn reads_entries_across_repeated_headers() {
+        let headers = headers(&["1.2.3.4", "203.0.113.7, 192.0.2.10"]);
+
+        assert_eq!(client_ip(1, &headers, &peer()), ip("192.0.2.10"));
+        assert_eq!(client_ip(2, &headers, &peer()), ip("203.0.113.7"));
+        assert_eq!(client_ip(3, &headers, &peer()), ip("1.2.3.4"));
+    }
+
+    #[test]
+    fn falls_back_to_the_peer_when_the_header_is_short_missing_or_invalid() {
+        assert_eq!(client_ip(2, &headers(&["203.0.113.7"]), &peer()), ip("10.0.0.1"));
+        assert_eq!(client_ip(1, &headers(&[]), &peer()), ip("10.0.0.1"));
+        assert_eq!(client_ip(1, &headers(&["1.2.3.4, not-an-ip"]), &peer()), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn accepts_ipv6_entries() {
+        let headers = headers(&["2001:db8::1 , 203.0.113.7"]);
+
+        assert_eq!(client_ip(2, &headers, &peer()), ip("2001:db8::1"));
+    }
 }
@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::AppState;
+
+// Policy for stored uploads: images and media render, but an uploaded HTML or SVG file
+// can't run scripts or reach the API with the reader's cookies
+pub const USER_CONTENT_CSP: &str = "default-src 'none'; img-src 'self' data:; media-src 'self'; style-src 'unsafe-inline'; sandbox";
+
+// Adds the configured security headers to every response. Headers a route sets itself
+// (e.g. the uploads CSP) are kept.
+pub async fn security_headers(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    let config = &state.config.security_headers;
+    let headers = response.headers_mut();
+
+    let csp = if config.content_security_policy.contains("frame-ancestors") {
+        config.content_security_policy.clone()
+    } else {
+        format!("{}; frame-ancestors {}", config.content_security_policy, config.frame_ancestors)
+    };
+    set_default(headers, header::CONTENT_SECURITY_POLICY, &csp);
+    set_default(headers, header::X_CONTENT_TYPE_OPTIONS, "nosniff");
+    set_default(headers, header::REFERRER_POLICY, &config.referrer_policy);
+
+    // Legacy equivalent of frame-ancestors for browsers without CSP level 2
+    match config.frame_ancestors.as_str() {
+        "'none'" => set_default(headers, header::X_FRAME_OPTIONS, "DENY"),
+        "'self'" => set_default(headers, header::X_FRAME_OPTIONS, "SAMEORIGIN"),
+        _ => {}
+    }
+
+    if let Some(max_age) = config.hsts_max_age {
+        set_default(
+            headers,
+            header::STRICT_TRANSPORT_SECURITY,
+            &format!("max-age={}; includeSubDomains", max_age),
+        );
+    }
+
+    response
+}
+
+fn set_default(headers: &mut HeaderMap, name: HeaderName, value: &str) {
+    if headers.contains_key(&name) {
+        return;
+    }
+    if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(name, value);
+    }
+}
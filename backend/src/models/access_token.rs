@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum TokenScope {
+    // Every GET endpoint the user can reach
+    #[serde(rename = "read")]
+    Read,
+    // Create, edit, publish and schedule articles; media and imports
+    #[serde(rename = "write:articles")]
+    WriteArticles,
+    #[serde(rename = "write:comments")]
+    WriteComments,
+    // Admin endpoints; only admins can create these
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl TokenScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::Read => "read",
+            TokenScope::WriteArticles => "write:articles",
+            TokenScope::WriteComments => "write:comments",
+            TokenScope::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "read" => Some(TokenScope::Read),
+            "write:articles" => Some(TokenScope::WriteArticles),
+            "write:comments" => Some(TokenScope::WriteComments),
+            "admin" => Some(TokenScope::Admin),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateAccessTokenRequest {
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    // Defaults to 90 days, at most a year
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccessTokenResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Vec<TokenScope>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedAccessTokenResponse {
+    // Shown only this once
+    pub token: String,
+    #[serde(flatten)]
+    pub access_token: AccessTokenResponse,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccessTokenListResponse {
+    pub tokens: Vec<AccessTokenResponse>,
+}
@@ -1,10 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct Article {
     pub id: Uuid,
     pub title: String,
@@ -33,18 +34,21 @@ pub struct Article {
     pub updated_at: DateTime<Utc>,
     pub last_auto_save: Option<DateTime<Utc>>, // New field for auto-save tracking
     pub auto_save_version: i32, // New field for auto-save versioning
+    pub canonical_url: Option<String>, // Original URL for cross-posted articles
+    pub scheduled_at: Option<DateTime<Utc>>, // Publish time while status is Scheduled
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::Type)]
 #[sqlx(type_name = "article_status", rename_all = "lowercase")]
 pub enum ArticleStatus {
     Draft,
     Published,
     Unlisted,
     Archived,
+    Scheduled,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct CreateArticleRequest {
     #[validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters"))]
     pub title: String,
@@ -72,10 +76,17 @@ pub struct CreateArticleRequest {
     
     pub paywall_position: Option<i32>,
     
+    // Original URL when the article is cross-posted from elsewhere
+    #[validate(url(message = "Invalid canonical URL"))]
+    pub canonical_url: Option<String>,
+    
+    // Publish automatically at this (future) time instead of now
+    pub scheduled_at: Option<DateTime<Utc>>,
+    
     pub status: Option<ArticleStatus>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct UpdateArticleRequest {
     #[validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters"))]
     pub title: Option<String>,
@@ -103,11 +114,17 @@ pub struct UpdateArticleRequest {
     
     pub paywall_position: Option<i32>,
     
+    // An empty string removes the canonical URL
+    pub canonical_url: Option<String>,
+    
+    // Publish automatically at this (future) time instead of now
+    pub scheduled_at: Option<DateTime<Utc>>,
+    
     pub status: Option<ArticleStatus>,
 }
 
 // New model for auto-save draft
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct AutoSaveDraftRequest {
     pub article_id: Option<Uuid>, // None for new articles
     pub title: Option<String>,
@@ -122,7 +139,7 @@ pub struct AutoSaveDraftRequest {
 }
 
 // New model for article statistics
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ArticleStats {
     pub article_id: Uuid,
     pub title: String,
@@ -137,7 +154,7 @@ pub struct ArticleStats {
 }
 
 // New model for author statistics
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct AuthorStats {
     pub total_articles: i32,
     pub total_views: i64,
@@ -150,7 +167,7 @@ pub struct AuthorStats {
     pub top_articles: Vec<ArticleStats>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ArticleResponse {
     pub id: Uuid,
     pub title: String,
@@ -172,15 +189,29 @@ pub struct ArticleResponse {
     pub bookmarks_count: i32,
     pub views_count: i64,
     pub published_at: Option<DateTime<Utc>>,
+    pub scheduled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub user_interactions: Option<UserInteractions>,
     pub share_url: String,
     pub share_title: String,
     pub share_description: String,
+    pub canonical_url: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ScheduleArticleRequest {
+    pub scheduled_at: DateTime<Utc>,
+}
+
+// Result of looking an article up by slug
+pub enum SlugLookup {
+    Found(Box<ArticleResponse>),
+    // The slug is historic; holds the article's current slug
+    Moved(String),
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ArticleListResponse {
     pub articles: Vec<ArticleResponse>,
     pub total: u64,
@@ -188,7 +219,7 @@ pub struct ArticleListResponse {
     pub offset: i64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ArticleAuthor {
     pub id: Uuid,
     pub username: String,
@@ -199,7 +230,7 @@ pub struct ArticleAuthor {
     pub is_verified: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ArticlePublication {
     pub id: Uuid,
     pub name: String,
@@ -208,7 +239,7 @@ pub struct ArticlePublication {
     pub followers_count: i32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UserInteractions {
     pub has_clapped: bool,
     pub clap_count: i32,
@@ -216,7 +247,8 @@ pub struct UserInteractions {
     pub is_following_author: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ArticleQueryParams {
     pub page: Option<i64>,
     pub limit: Option<i64>,
@@ -229,7 +261,7 @@ pub struct ArticleQueryParams {
     pub search: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub enum ArticleSortBy {
     #[serde(rename = "recent")]
     Recent,
@@ -258,3 +290,32 @@ impl Default for ArticleQueryParams {
         }
     }
 }
+
+// Tags and categories readers can follow
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TopicKind {
+    Tag,
+    Category,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TopicResponse {
+    pub name: String,
+    pub kind: TopicKind,
+    pub article_count: i64,
+    pub follower_count: i64,
+    pub is_following: Option<bool>, // Only for authenticated requests
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TopicPageResponse {
+    pub topic: TopicResponse,
+    pub articles: ArticleListResponse,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FollowedTopicsResponse {
+    pub tags: Vec<String>,
+    pub categories: Vec<String>,
+}
@@ -0,0 +1,73 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "digest_frequency", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DigestFrequency {
+    Off,
+    Daily,
+    Weekly,
+}
+
+impl DigestFrequency {
+    // Length of one digest window, None when digests are disabled
+    pub fn period(&self) -> Option<Duration> {
+        match self {
+            DigestFrequency::Off => None,
+            DigestFrequency::Daily => Some(Duration::days(1)),
+            DigestFrequency::Weekly => Some(Duration::weeks(1)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DigestSettingsResponse {
+    pub frequency: DigestFrequency,
+    pub last_sent_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateDigestSettingsRequest {
+    pub frequency: DigestFrequency,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UnsubscribeQuery {
+    pub token: String,
+}
+
+// Why an article made it into the digest
+#[derive(Debug, Clone, Copy, Serialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestSource {
+    Author,
+    Publication,
+    Topic,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DigestArticle {
+    pub id: Uuid,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub excerpt: Option<String>,
+    pub url: String,
+    pub author_name: String,
+    pub publication_name: Option<String>,
+    pub matched_topic: Option<String>,
+    pub source: DigestSource,
+    pub reading_time_minutes: i32,
+    pub published_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DigestPreviewResponse {
+    pub subject: String,
+    pub articles: Vec<DigestArticle>,
+    pub html: String,
+    pub text: String,
+}
@@ -1,11 +1,12 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
 // Claps (1 clap per user per article - toggle behavior)
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct Clap {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -15,14 +16,14 @@ pub struct Clap {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct ClapRequest {
     #[validate(range(min = 1, max = 1, message = "Clap count must be 1"))]
     pub clap_count: i32,
 }
 
 // Comments
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct Comment {
     pub id: Uuid,
     pub article_id: Uuid,
@@ -37,20 +38,20 @@ pub struct Comment {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct CreateCommentRequest {
     #[validate(length(min = 1, max = 2000, message = "Comment must be between 1 and 2000 characters"))]
     pub content: String,
     pub parent_id: Option<Uuid>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct UpdateCommentRequest {
     #[validate(length(min = 1, max = 2000, message = "Comment must be between 1 and 2000 characters"))]
     pub content: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CommentResponse {
     pub id: Uuid,
     pub content: String,
@@ -63,10 +64,11 @@ pub struct CommentResponse {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub user_interactions: Option<CommentInteractions>,
+    #[schema(no_recursion)]
     pub replies: Option<Vec<CommentResponse>>, // For nested replies
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CommentAuthor {
     pub id: Uuid,
     pub username: String,
@@ -75,14 +77,14 @@ pub struct CommentAuthor {
     pub is_verified: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CommentInteractions {
     pub has_clapped: bool,
     pub clap_count: i32,
 }
 
 // Bookmarks
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct Bookmark {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -91,7 +93,7 @@ pub struct Bookmark {
 }
 
 // Highlights (text selections with notes)
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct Highlight {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -104,7 +106,7 @@ pub struct Highlight {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct CreateHighlightRequest {
     #[validate(length(min = 1, max = 1000, message = "Selected text must be between 1 and 1000 characters"))]
     pub selected_text: String,
@@ -119,7 +121,7 @@ pub struct CreateHighlightRequest {
     pub end_position: i32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct HighlightResponse {
     pub id: Uuid,
     pub selected_text: String,
@@ -131,7 +133,7 @@ pub struct HighlightResponse {
 }
 
 // Article Views (for analytics)
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct ArticleView {
     pub id: Uuid,
     pub article_id: Uuid,
@@ -143,14 +145,14 @@ pub struct ArticleView {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct RecordViewRequest {
     pub reading_time_seconds: Option<i32>,
     pub scroll_percentage: Option<f32>,
 }
 
 // Reading Lists (collections of bookmarked articles)
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct ReadingList {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -162,7 +164,7 @@ pub struct ReadingList {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct CreateReadingListRequest {
     #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
     pub name: String,
@@ -173,7 +175,7 @@ pub struct CreateReadingListRequest {
     pub is_public: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct ReadingListArticle {
     pub reading_list_id: Uuid,
     pub article_id: Uuid,
@@ -181,7 +183,7 @@ pub struct ReadingListArticle {
 }
 
 // Engagement Analytics
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct EngagementStats {
     pub total_claps: i64,
     pub total_comments: i64,
@@ -0,0 +1,49 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "export_kind", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ExportKind {
+    // Zip of the user's articles as Markdown with their images
+    Articles,
+    // Everything stored about the account as JSON (GDPR Art. 15/20)
+    Account,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "export_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ExportStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateExportRequest {
+    pub kind: ExportKind,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExportResponse {
+    pub id: Uuid,
+    pub kind: ExportKind,
+    pub status: ExportStatus,
+    pub file_size: Option<i64>,
+    // Signed link, only while the finished archive is kept
+    pub download_url: Option<String>,
+    pub error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExportListResponse {
+    pub exports: Vec<ExportResponse>,
+}
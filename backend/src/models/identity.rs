@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OidcProviderResponse {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LinkIdentityRequest {
+    pub provider: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OidcAuthorizationResponse {
+    // Send the browser here; the provider redirects back to the OIDC redirect URL
+    pub authorization_url: String,
+    // Also in the URL; keep it to check the `state` the provider sends back
+    pub state: String,
+}
+
+// Query parameters the provider appended to the redirect URL
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct OidcCallbackRequest {
+    pub code: String,
+    pub state: String,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct LinkedIdentity {
+    pub id: Uuid,
+    pub provider: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LinkedIdentitiesResponse {
+    pub identities: Vec<LinkedIdentity>,
+    // Whether the account can also sign in with a password
+    pub has_password: bool,
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImageVariantResponse {
+    pub size: u32,
+    pub width: u32,
+    pub height: u32,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UploadedImageResponse {
+    // Largest variant
+    pub url: String,
+    // Source dimensions after EXIF rotation and cropping
+    pub width: u32,
+    pub height: u32,
+    pub blurhash: String,
+    pub dominant_color: String,
+    pub variants: Vec<ImageVariantResponse>,
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "import_source", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ImportSource {
+    // Export zip with one HTML file per post
+    Medium,
+    // WXR (WordPress eXtended RSS) XML
+    Wordpress,
+    // JSON export from Labs
+    Ghost,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "import_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    Pending,
+    Running,
+    Completed,
+    // The export couldn't be read at all; see `error`
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportItemStatus {
+    Imported,
+    Skipped,
+    Failed,
+}
+
+// One post of the export in the import report
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportItem {
+    // File name or post id in the export
+    pub source_id: String,
+    pub title: String,
+    pub status: ImportItemStatus,
+    pub reason: Option<String>,
+    pub article_id: Option<Uuid>,
+    // Problems that didn't stop the import, e.g. images that couldn't be downloaded
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportResponse {
+    pub id: Uuid,
+    pub source: ImportSource,
+    pub status: ImportStatus,
+    pub file_name: String,
+    pub as_drafts: bool,
+    pub total_items: i32,
+    pub imported_count: i32,
+    pub skipped_count: i32,
+    pub failed_count: i32,
+    // Only included for a single import
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<Vec<ImportItem>>,
+    pub error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportListResponse {
+    pub imports: Vec<ImportResponse>,
+}
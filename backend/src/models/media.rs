@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::image::ImageVariantResponse;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MediaResponse {
+    pub id: Uuid,
+    pub url: String,
+    pub width: i32,
+    pub height: i32,
+    pub size_bytes: i64,
+    pub variants: Vec<ImageVariantResponse>,
+    pub blurhash: String,
+    pub dominant_color: String,
+    pub alt_text: Option<String>,
+    pub caption: Option<String>,
+    // Private media is only reachable through the expiring signed URLs returned here
+    pub is_private: bool,
+    // Articles whose content or featured image uses it
+    pub usage_count: i64,
+    // False while a published or scheduled article uses it
+    pub deletable: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MediaListResponse {
+    pub media: Vec<MediaResponse>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MediaQueryParams {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    // Only media no article uses
+    pub unused: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct UpdateMediaRequest {
+    // "" clears the field
+    #[validate(length(max = 500, message = "Alt text cannot exceed 500 characters"))]
+    pub alt_text: Option<String>,
+
+    #[validate(length(max = 1000, message = "Caption cannot exceed 1000 characters"))]
+    pub caption: Option<String>,
+}
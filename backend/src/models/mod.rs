@@ -2,6 +2,18 @@ pub mod user;
 pub mod article;
 pub mod engagement;
 pub mod publication;
+pub mod notification;
+pub mod digest;
+pub mod newsletter;
+pub mod seo;
+pub mod job;
+pub mod image;
+pub mod media;
+pub mod import;
+pub mod export;
+pub mod identity;
+pub mod two_factor;
+pub mod access_token;
 
 pub use user::*;
 pub use article::*;
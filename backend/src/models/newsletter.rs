@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "subscriber_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SubscriberStatus {
+    Pending,
+    Confirmed,
+    Unsubscribed,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "email_delivery_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EmailDeliveryStatus {
+    Queued,
+    Sending,
+    Sent,
+    Failed,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct SubscribeRequest {
+    #[validate(email(message = "Invalid email format"))]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NewsletterTokenQuery {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TrackClickQuery {
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NewsletterQueryParams {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    // Defaults to the caller's own author newsletter
+    pub publication_id: Option<Uuid>,
+    pub status: Option<SubscriberStatus>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SubscriberResponse {
+    pub id: Uuid,
+    pub email: String,
+    pub status: SubscriberStatus,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub unsubscribed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SubscriberListResponse {
+    pub subscribers: Vec<SubscriberResponse>,
+    pub total: u64,
+    pub confirmed_count: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NewsletterIssueResponse {
+    pub id: Uuid,
+    pub article_id: Uuid,
+    pub article_title: String,
+    pub recipients_count: i32,
+    pub queued_count: i64,
+    pub sent_count: i64,
+    pub failed_count: i64,
+    pub opened_count: i64,
+    pub clicked_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NewsletterDeliveryResponse {
+    pub id: Uuid,
+    pub email: String,
+    pub status: EmailDeliveryStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub opened_at: Option<DateTime<Utc>>,
+    pub open_count: i32,
+    pub clicked_at: Option<DateTime<Utc>>,
+    pub click_count: i32,
+}
@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, sqlx::Type, PartialEq, Eq, Hash)]
+#[sqlx(type_name = "notification_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationType {
+    NewFollower,
+    Clap,
+    Comment,
+    Reply,
+    Mention,
+}
+
+impl NotificationType {
+    pub const ALL: [NotificationType; 5] = [
+        NotificationType::NewFollower,
+        NotificationType::Clap,
+        NotificationType::Comment,
+        NotificationType::Reply,
+        NotificationType::Mention,
+    ];
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NotificationResponse {
+    pub id: Uuid,
+    pub notification_type: NotificationType,
+    pub message: String,
+    pub actor: Option<NotificationActor>,
+    pub article: Option<NotificationArticle>,
+    pub comment_id: Option<Uuid>,
+    pub publication_id: Option<Uuid>,
+    pub aggregate_count: i32,
+    pub is_read: bool,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NotificationActor {
+    pub id: Uuid,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NotificationArticle {
+    pub id: Uuid,
+    pub title: String,
+    pub slug: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NotificationListResponse {
+    pub notifications: Vec<NotificationResponse>,
+    pub total: u64,
+    pub unread_count: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NotificationQueryParams {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub unread_only: Option<bool>,
+}
+
+// Notification preferences
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NotificationPreference {
+    pub notification_type: NotificationType,
+    pub in_app_enabled: bool,
+    pub email_enabled: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateNotificationPreferencesRequest {
+    pub preferences: Vec<NotificationPreference>,
+}
@@ -1,10 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct Publication {
     pub id: Uuid,
     pub name: String,
@@ -24,7 +25,7 @@ pub struct Publication {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct PublicationMember {
     pub id: Uuid,
     pub publication_id: Uuid,
@@ -35,7 +36,7 @@ pub struct PublicationMember {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::Type)]
 #[sqlx(type_name = "publication_role", rename_all = "lowercase")]
 pub enum PublicationRole {
     Owner,
@@ -43,7 +44,7 @@ pub enum PublicationRole {
     Writer,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct CreatePublicationRequest {
     #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
     pub name: String,
@@ -67,7 +68,7 @@ pub struct CreatePublicationRequest {
     pub is_accepting_submissions: Option<bool>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct UpdatePublicationRequest {
     #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
     pub name: Option<String>,
@@ -87,7 +88,7 @@ pub struct UpdatePublicationRequest {
     pub is_accepting_submissions: Option<bool>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PublicationResponse {
     pub id: Uuid,
     pub name: String,
@@ -107,7 +108,7 @@ pub struct PublicationResponse {
     pub user_interactions: Option<PublicationInteractions>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PublicationOwner {
     pub id: Uuid,
     pub username: String,
@@ -115,14 +116,14 @@ pub struct PublicationOwner {
     pub avatar_url: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PublicationInteractions {
     pub is_following: bool,
     pub is_member: bool,
     pub member_role: Option<PublicationRole>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PublicationMemberResponse {
     pub id: Uuid,
     pub user: PublicationMemberUser,
@@ -131,7 +132,7 @@ pub struct PublicationMemberResponse {
     pub joined_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PublicationMemberUser {
     pub id: Uuid,
     pub username: String,
@@ -142,19 +143,19 @@ pub struct PublicationMemberUser {
     pub followers_count: i32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct InviteMemberRequest {
     pub email: String,
     pub role: PublicationRole,
     pub message: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateMemberRoleRequest {
     pub role: PublicationRole,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct PublicationFollow {
     pub user_id: Uuid,
     pub publication_id: Uuid,
@@ -162,7 +163,7 @@ pub struct PublicationFollow {
 }
 
 // Publication submission (for writers to submit to publications)
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct PublicationSubmission {
     pub id: Uuid,
     pub publication_id: Uuid,
@@ -175,7 +176,7 @@ pub struct PublicationSubmission {
     pub reviewed_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::Type)]
 #[sqlx(type_name = "submission_status", rename_all = "lowercase")]
 pub enum SubmissionStatus {
     Pending,
@@ -184,13 +185,13 @@ pub enum SubmissionStatus {
     RevisionRequested,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct SubmitArticleRequest {
     pub article_id: Uuid,
     pub message: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct ReviewSubmissionRequest {
     pub status: SubmissionStatus,
     pub editor_notes: Option<String>,
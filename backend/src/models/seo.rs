@@ -0,0 +1,32 @@
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::ToSchema;
+
+// A single `<meta>` tag; Open Graph tags use the `property` attribute, Twitter tags `name`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MetaTag {
+    pub key: String,
+    pub content: String,
+}
+
+impl MetaTag {
+    pub fn new(key: &str, content: impl Into<String>) -> Self {
+        Self {
+            key: key.to_string(),
+            content: content.into(),
+        }
+    }
+}
+
+// Everything the SSR frontend needs to render an article's `<head>`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ArticleSeoResponse {
+    pub title: String,
+    pub description: String,
+    pub canonical_url: String,
+    pub image_url: Option<String>,
+    pub open_graph: Vec<MetaTag>,
+    pub twitter: Vec<MetaTag>,
+    // schema.org `Article`, to embed as application/ld+json
+    pub json_ld: Value,
+}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::AuthResponse;
+
+// Login answers with a token right away, or with a challenge when the account has 2FA
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Authenticated(AuthResponse),
+    TwoFactorRequired(TwoFactorChallenge),
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TwoFactorChallenge {
+    pub two_factor_required: bool,
+    // Exchanged for the real token at /auth/2fa/verify
+    pub challenge_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TwoFactorVerifyRequest {
+    pub challenge_token: String,
+    // Either a code from the authenticator app or one of the recovery codes
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TwoFactorStatusResponse {
+    pub enabled: bool,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub recovery_codes_remaining: i64,
+    // Admin accounts can't do without
+    pub required: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TwoFactorSetupResponse {
+    // Base32, for manual entry
+    pub secret: String,
+    // otpauth:// URI, usually shown as a QR code
+    pub provisioning_uri: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TwoFactorCodeRequest {
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DisableTwoFactorRequest {
+    // Current password; accounts without one leave it out
+    #[serde(default)]
+    pub password: String,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+// Shown once; only hashes are stored
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
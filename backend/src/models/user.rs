@@ -1,10 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct User {
     pub user_id: Uuid, // Alias for id in queries
     pub email: String,
@@ -20,7 +21,7 @@ pub struct User {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::Type, PartialEq)]
 #[sqlx(type_name = "user_type", rename_all = "lowercase")]
 pub enum UserType {
     Free,
@@ -29,7 +30,7 @@ pub enum UserType {
     Publication,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct CreateUserRequest {
     #[validate(email(message = "Invalid email format"))]
     pub email: String,
@@ -45,7 +46,7 @@ pub struct CreateUserRequest {
     pub display_name: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct UpdateUserRequest {
     #[validate(length(max = 100, message = "Display name cannot exceed 100 characters"))]
     pub display_name: Option<String>,
@@ -57,7 +58,7 @@ pub struct UpdateUserRequest {
     pub avatar_url: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct LoginRequest {
     // Can be either email or username
     #[validate(length(min = 1, message = "Email or username is required"))]
@@ -69,7 +70,7 @@ pub struct LoginRequest {
     pub remember_me: Option<bool>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UserResponse {
     pub id: Uuid,
     pub email: String,
@@ -87,20 +88,52 @@ pub struct UserResponse {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct AuthResponse {
     pub user: UserResponse,
     pub token: String,
     pub expires_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, FromRow)]
 pub struct UserFollow {
     pub follower_id: Uuid,
     pub following_id: Uuid,
     pub created_at: DateTime<Utc>,
 }
 
+// What happens to a deleted account's articles
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "article_deletion_action", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ArticleDeletionAction {
+    Delete,
+    // Keep them, credited to the "Deleted user" placeholder
+    Anonymize,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DeleteAccountRequest {
+    // Current password, to confirm; accounts without one leave it out
+    #[serde(default)]
+    pub password: String,
+    pub articles: ArticleDeletionAction,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminDeleteUserQuery {
+    pub articles: Option<ArticleDeletionAction>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountDeletionResponse {
+    pub scheduled: bool,
+    pub requested_at: Option<DateTime<Utc>>,
+    pub scheduled_at: Option<DateTime<Utc>>,
+    pub articles: Option<ArticleDeletionAction>,
+}
+
 impl From<User> for UserResponse {
     fn from(user: User) -> Self {
         Self {
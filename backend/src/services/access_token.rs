@@ -0,0 +1,194 @@
+use chrono::{Duration, Utc};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        access_token::{AccessTokenListResponse, AccessTokenResponse, CreatedAccessTokenResponse, TokenScope},
+        UserType,
+    },
+    services::auth::Claims,
+};
+
+// Tells access tokens apart from JWTs (and makes leaked tokens easy to scan for)
+pub const ACCESS_TOKEN_PREFIX: &str = "fbp_";
+const DEFAULT_EXPIRY_DAYS: i64 = 90;
+const MAX_EXPIRY_DAYS: i64 = 365;
+const MAX_TOKENS_PER_USER: i64 = 50;
+// Characters of the token kept for display, prefix included
+const DISPLAY_PREFIX_LENGTH: usize = 12;
+
+pub struct AccessTokenService {
+    db: PgPool,
+}
+
+impl AccessTokenService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    pub async fn create_token(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        scopes: &[TokenScope],
+        expires_in_days: Option<i64>,
+    ) -> Result<CreatedAccessTokenResponse, AppError> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > 100 {
+            return Err(AppError::BadRequest("Token name must be between 1 and 100 characters".to_string()));
+        }
+        if scopes.is_empty() {
+            return Err(AppError::BadRequest("At least one scope is required".to_string()));
+        }
+        let expires_in_days = expires_in_days.unwrap_or(DEFAULT_EXPIRY_DAYS);
+        if !(1..=MAX_EXPIRY_DAYS).contains(&expires_in_days) {
+            return Err(AppError::BadRequest(format!("Tokens must expire within 1 to {} days", MAX_EXPIRY_DAYS)));
+        }
+
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM personal_access_tokens WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_one(&self.db)
+        .await?;
+        if count >= MAX_TOKENS_PER_USER {
+            return Err(AppError::Conflict(format!(
+                "Token limit reached ({}); revoke unused tokens first",
+                MAX_TOKENS_PER_USER
+            )));
+        }
+
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token = format!("{}{}", ACCESS_TOKEN_PREFIX, hex::encode(bytes));
+
+        let mut scope_names: Vec<String> = scopes.iter().map(|scope| scope.as_str().to_string()).collect();
+        scope_names.sort();
+        scope_names.dedup();
+
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO personal_access_tokens (user_id, name, token_hash, token_prefix, scopes, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, name, token_prefix, scopes, expires_at, last_used_at, created_at
+            "#,
+            user_id,
+            name,
+            hash_token(&token),
+            &token[..DISPLAY_PREFIX_LENGTH],
+            &scope_names,
+            Utc::now() + Duration::days(expires_in_days)
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(CreatedAccessTokenResponse {
+            token,
+            access_token: AccessTokenResponse {
+                id: row.id,
+                name: row.name,
+                token_prefix: row.token_prefix,
+                scopes: parse_scopes(&row.scopes),
+                expires_at: row.expires_at,
+                last_used_at: row.last_used_at,
+                created_at: row.created_at,
+            },
+        })
+    }
+
+    pub async fn list_tokens(&self, user_id: Uuid) -> Result<AccessTokenListResponse, AppError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, name, token_prefix, scopes, expires_at, last_used_at, created_at
+            FROM personal_access_tokens
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let tokens = rows
+            .into_iter()
+            .map(|row| AccessTokenResponse {
+                id: row.id,
+                name: row.name,
+                token_prefix: row.token_prefix,
+                scopes: parse_scopes(&row.scopes),
+                expires_at: row.expires_at,
+                last_used_at: row.last_used_at,
+                created_at: row.created_at,
+            })
+            .collect();
+
+        Ok(AccessTokenListResponse { tokens })
+    }
+
+    pub async fn revoke_token(&self, user_id: Uuid, token_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!(
+            "DELETE FROM personal_access_tokens WHERE id = $1 AND user_id = $2",
+            token_id,
+            user_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Token not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    // Claims for a request made with an access token; None if it's unknown or expired
+    pub async fn authenticate(&self, token: &str) -> Result<Option<Claims>, AppError> {
+        let Some(row) = sqlx::query!(
+            r#"
+            SELECT t.id, t.user_id, t.scopes, t.expires_at, t.created_at, t.last_used_at,
+                u.username, u.user_type as "user_type: UserType"
+            FROM personal_access_tokens t
+            JOIN users u ON u.id = t.user_id
+            WHERE t.token_hash = $1 AND t.expires_at > NOW()
+            "#,
+            hash_token(token)
+        )
+        .fetch_optional(&self.db)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        // At most one write a minute per token
+        if row.last_used_at.is_none_or(|last_used| last_used < Utc::now() - Duration::minutes(1)) {
+            sqlx::query!("UPDATE personal_access_tokens SET last_used_at = NOW() WHERE id = $1", row.id)
+                .execute(&self.db)
+                .await?;
+        }
+
+        let scopes = parse_scopes(&row.scopes);
+        Ok(Some(Claims {
+            sub: row.user_id.to_string(),
+            username: row.username,
+            user_type: row.user_type,
+            exp: row.expires_at.timestamp() as usize,
+            iat: row.created_at.timestamp() as usize,
+            // Admin tokens can only be created from a two-factor session
+            mfa: scopes.contains(&TokenScope::Admin),
+            admin: scopes.contains(&TokenScope::Admin),
+            scopes: Some(scopes),
+        }))
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn parse_scopes(scopes: &[String]) -> Vec<TokenScope> {
+    scopes.iter().filter_map(|scope| TokenScope::parse(scope)).collect()
+}
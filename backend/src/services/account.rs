@@ -0,0 +1,316 @@
+use askama::Template;
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::user::{AccountDeletionResponse, ArticleDeletionAction},
+    services::{
+        image,
+        jobs::{Job, JobService},
+        mailer::EmailMessage,
+        storage::Storage,
+    },
+};
+
+// Days between a deletion request and the deletion; the user can cancel until then
+pub const DELETION_GRACE_DAYS: i32 = 14;
+
+// The "Deleted user" placeholder account (see the add_account_deletion migration)
+pub const DELETED_USER_ID: Uuid = Uuid::nil();
+
+#[derive(Template)]
+#[template(path = "email/account_deletion.html")]
+struct DeletionHtmlTemplate<'a> {
+    name: &'a str,
+    scheduled_date: &'a str,
+    anonymize_articles: bool,
+    settings_url: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "email/account_deletion.txt")]
+struct DeletionTextTemplate<'a> {
+    name: &'a str,
+    scheduled_date: &'a str,
+    anonymize_articles: bool,
+    settings_url: &'a str,
+}
+
+pub struct AccountService<'a> {
+    db: PgPool,
+    config: &'a Config,
+}
+
+impl<'a> AccountService<'a> {
+    pub fn new(db: PgPool, config: &'a Config) -> Self {
+        Self { db, config }
+    }
+
+    // Schedule the account for deletion after the grace period and confirm by email.
+    // Requesting again keeps the original date but updates the article choice.
+    pub async fn request_deletion(
+        &self,
+        user_id: Uuid,
+        action: ArticleDeletionAction,
+    ) -> Result<AccountDeletionResponse, AppError> {
+        if user_id == DELETED_USER_ID {
+            return Err(AppError::Forbidden("This account cannot be deleted".to_string()));
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        let user = sqlx::query!(
+            r#"
+            UPDATE users
+            SET deletion_requested_at = COALESCE(deletion_requested_at, NOW()),
+                deletion_scheduled_at = COALESCE(deletion_scheduled_at, NOW() + make_interval(days => $2)),
+                deletion_article_action = $3,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING email, username, display_name, deletion_scheduled_at as "scheduled_at!",
+                (deletion_requested_at = NOW()) as "is_new!"
+            "#,
+            user_id,
+            DELETION_GRACE_DAYS,
+            action as ArticleDeletionAction
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        if user.is_new {
+            JobService::enqueue(&mut *tx, &Job::DeleteAccount { user_id }, user.scheduled_at).await?;
+
+            let name = user.display_name.as_deref().unwrap_or(&user.username);
+            let scheduled_date = user.scheduled_at.format("%B %-d, %Y").to_string();
+            let settings_url = format!("{}/settings/account", self.config.frontend_url);
+            let anonymize_articles = action == ArticleDeletionAction::Anonymize;
+            let message = EmailMessage {
+                to: user.email,
+                subject: format!("Your FastBlog account will be deleted on {}", scheduled_date),
+                html_body: DeletionHtmlTemplate {
+                    name,
+                    scheduled_date: &scheduled_date,
+                    anonymize_articles,
+                    settings_url: &settings_url,
+                }
+                .render()?,
+                text_body: DeletionTextTemplate {
+                    name,
+                    scheduled_date: &scheduled_date,
+                    anonymize_articles,
+                    settings_url: &settings_url,
+                }
+                .render()?,
+                headers: Vec::new(),
+            };
+            JobService::enqueue(&mut *tx, &Job::SendEmail { message }, Utc::now()).await?;
+        }
+
+        tx.commit().await?;
+
+        self.get_deletion(user_id).await
+    }
+
+    pub async fn cancel_deletion(&self, user_id: Uuid) -> Result<AccountDeletionResponse, AppError> {
+        // The queued job finds nothing scheduled and does nothing
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET deletion_requested_at = NULL, deletion_scheduled_at = NULL, deletion_article_action = NULL,
+                updated_at = NOW()
+            WHERE id = $1 AND deletion_scheduled_at IS NOT NULL
+            "#,
+            user_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("No account deletion is scheduled".to_string()));
+        }
+
+        self.get_deletion(user_id).await
+    }
+
+    pub async fn get_deletion(&self, user_id: Uuid) -> Result<AccountDeletionResponse, AppError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT deletion_requested_at, deletion_scheduled_at,
+                deletion_article_action as "deletion_article_action: ArticleDeletionAction"
+            FROM users WHERE id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        Ok(AccountDeletionResponse {
+            scheduled: row.deletion_scheduled_at.is_some(),
+            requested_at: row.deletion_requested_at,
+            scheduled_at: row.deletion_scheduled_at,
+            articles: row.deletion_article_action,
+        })
+    }
+
+    // Admin deletion: no grace period
+    pub async fn delete_now(&self, user_id: Uuid, action: ArticleDeletionAction) -> Result<(), AppError> {
+        if user_id == DELETED_USER_ID {
+            return Err(AppError::Forbidden("This account cannot be deleted".to_string()));
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET deletion_requested_at = COALESCE(deletion_requested_at, NOW()), deletion_scheduled_at = NOW(),
+                deletion_article_action = $2, updated_at = NOW()
+            WHERE id = $1
+            "#,
+            user_id,
+            action as ArticleDeletionAction
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("User not found".to_string()));
+        }
+
+        JobService::enqueue(&mut *tx, &Job::DeleteAccount { user_id }, Utc::now()).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // Run by the delete_account job once the grace period is over. Returns false when
+    // the deletion was cancelled or moved to a later date in the meantime.
+    pub async fn delete_account(&self, storage: &dyn Storage, user_id: Uuid) -> Result<bool, AppError> {
+        let mut tx = self.db.begin().await?;
+
+        let Some(user) = sqlx::query!(
+            r#"
+            SELECT avatar_url, deletion_article_action as "action: ArticleDeletionAction"
+            FROM users
+            WHERE id = $1 AND id <> $2 AND deletion_scheduled_at <= NOW() + INTERVAL '1 minute'
+            FOR UPDATE
+            "#,
+            user_id,
+            DELETED_USER_ID
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(false);
+        };
+        let action = user.action.unwrap_or(ArticleDeletionAction::Anonymize);
+
+        // Users whose follower/following counts change
+        let affected: Vec<Uuid> = sqlx::query_scalar!(
+            r#"
+            SELECT following_id as "id!" FROM user_follows WHERE follower_id = $1
+            UNION
+            SELECT follower_id FROM user_follows WHERE following_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // Comments stay in their threads, credited to the placeholder
+        sqlx::query!(
+            "UPDATE comments SET user_id = $2 WHERE user_id = $1",
+            user_id,
+            DELETED_USER_ID
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Publications can't be left without an owner; an admin can hand them over later
+        sqlx::query!(
+            "UPDATE publications SET owner_id = $2, updated_at = NOW() WHERE owner_id = $1",
+            user_id,
+            DELETED_USER_ID
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if action == ArticleDeletionAction::Anonymize {
+            // Kept articles keep their images
+            sqlx::query!(
+                r#"
+                UPDATE media SET user_id = $2, updated_at = NOW()
+                WHERE user_id = $1
+                    AND id IN (SELECT media_id FROM article_media am JOIN articles a ON a.id = am.article_id
+                               WHERE a.author_id = $1)
+                "#,
+                user_id,
+                DELETED_USER_ID
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query!(
+                "UPDATE articles SET author_id = $2 WHERE author_id = $1",
+                user_id,
+                DELETED_USER_ID
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // Files of everything deleted below along with the user
+        let media_urls = sqlx::query_scalar!("SELECT url FROM media WHERE user_id = $1", user_id)
+            .fetch_all(&mut *tx)
+            .await?;
+        let private_files = sqlx::query_scalar!(
+            r#"
+            SELECT file_key as "file_key!" FROM exports WHERE user_id = $1 AND file_key IS NOT NULL
+            UNION ALL
+            SELECT file_key FROM imports WHERE user_id = $1 AND status IN ('pending', 'running')
+            "#,
+            user_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // Deleting the user cascades to their follows, claps, bookmarks, highlights, reading
+        // lists, notifications, settings, media rows and (unless anonymized) articles
+        sqlx::query!("DELETE FROM users WHERE id = $1", user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Recount rather than trust the triggers' running totals
+        sqlx::query!(
+            r#"
+            UPDATE users u
+            SET followers_count = (SELECT COUNT(*) FROM user_follows WHERE following_id = u.id),
+                following_count = (SELECT COUNT(*) FROM user_follows WHERE follower_id = u.id)
+            WHERE u.id = ANY($1)
+            "#,
+            &affected
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        for url in user.avatar_url.iter().chain(media_urls.iter()) {
+            if let Some(key) = storage.key_from_url(url) {
+                image::remove_variants(storage, &key).await;
+            }
+        }
+        for key in private_files {
+            if let Err(e) = storage.delete(&key).await {
+                tracing::warn!("Failed to delete {} of deleted user {}: {}", key, user_id, e);
+            }
+        }
+
+        Ok(true)
+    }
+}
@@ -1,28 +1,35 @@
 use chrono::Utc;
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
-use std::error::Error;
 use ammonia::clean;
 use regex::Regex;
 use lazy_static::lazy_static;
+use reqwest::Url;
 
+use crate::config::Config;
+use crate::error::AppError;
 use crate::models::{
     Article, ArticleStatus, CreateArticleRequest, UpdateArticleRequest,
-    ArticleResponse, ArticleListResponse, ArticleQueryParams
+    ArticleResponse, ArticleListResponse, ArticleQueryParams,
+    FollowedTopicsResponse, ScheduleArticleRequest, SlugLookup, TopicKind, TopicPageResponse, TopicResponse
 };
+use crate::services::jobs::{Job, JobService};
+use crate::services::media::MediaService;
+use crate::services::newsletter::NewsletterService;
 
 lazy_static! {
     static ref SLUG_REGEX: Regex = Regex::new(r"[^a-zA-Z0-9\-]").unwrap();
     static ref HTML_TAG_REGEX: Regex = Regex::new(r"<[^>]*>").unwrap();
 }
 
-pub struct ArticleService {
+pub struct ArticleService<'a> {
     db: PgPool,
+    config: &'a Config,
 }
 
-impl ArticleService {
-    pub fn new(db: PgPool) -> Self {
-        Self { db }
+impl<'a> ArticleService<'a> {
+    pub fn new(db: PgPool, config: &'a Config) -> Self {
+        Self { db, config }
     }
 
     // Generate URL-friendly slug from title
@@ -70,11 +77,51 @@ impl ArticleService {
         clean(content)
     }
 
+    // Canonical URLs must be absolute http(s) links; blank means none
+    fn normalize_canonical_url(&self, url: &str) -> Result<Option<String>, AppError> {
+        let url = url.trim();
+        if url.is_empty() {
+            return Ok(None);
+        }
+
+        match Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(Some(parsed.to_string())),
+            _ => Err(AppError::BadRequest("Invalid canonical URL".to_string())),
+        }
+    }
+
     pub async fn create_article(
         &self,
         author_id: Uuid,
         request: CreateArticleRequest,
-    ) -> Result<ArticleResponse, Box<dyn Error + Send + Sync>> {
+    ) -> Result<ArticleResponse, AppError> {
+        let (article_id, status) = self.insert_article(author_id, request, None).await?;
+        if matches!(status, ArticleStatus::Published) {
+            self.on_published(article_id).await;
+        }
+
+        // Fetch and return the created article
+        self.get_article_by_id(article_id, Some(author_id)).await
+    }
+
+    // Articles brought over from another platform keep their original publish date.
+    // Nothing is announced to followers or newsletter subscribers.
+    pub async fn import_article(
+        &self,
+        author_id: Uuid,
+        request: CreateArticleRequest,
+        published_at: Option<chrono::DateTime<Utc>>,
+    ) -> Result<Uuid, AppError> {
+        let (article_id, _) = self.insert_article(author_id, request, published_at).await?;
+        Ok(article_id)
+    }
+
+    async fn insert_article(
+        &self,
+        author_id: Uuid,
+        request: CreateArticleRequest,
+        original_published_at: Option<chrono::DateTime<Utc>>,
+    ) -> Result<(Uuid, ArticleStatus), AppError> {
         let article_id = Uuid::new_v4();
         let slug = self.generate_slug(&request.title);
         let content_html = self.sanitize_html(&request.content);
@@ -87,17 +134,34 @@ impl ArticleService {
 
         let tags: Vec<String> = request.tags.unwrap_or_default();
         let categories: Vec<String> = request.categories.unwrap_or_default();
+        let canonical_url = match &request.canonical_url {
+            Some(url) => self.normalize_canonical_url(url)?,
+            None => None,
+        };
         
-        // Use status from request, or default to Draft
-        let status = request.status.unwrap_or(ArticleStatus::Draft);
+        // Use status from request, or default to Draft; a publish time means Scheduled
+        let status = match request.scheduled_at {
+            Some(scheduled_at) => {
+                self.validate_schedule(scheduled_at)?;
+                ArticleStatus::Scheduled
+            }
+            None if matches!(request.status, Some(ArticleStatus::Scheduled)) => {
+                return Err(AppError::BadRequest(
+                    "scheduled_at is required to schedule an article".to_string(),
+                ));
+            }
+            None => request.status.unwrap_or(ArticleStatus::Draft),
+        };
         
         // Set published_at only if status is Published
         let published_at = if matches!(status, ArticleStatus::Published) {
-            Some(now)
+            Some(original_published_at.unwrap_or(now))
         } else {
             None
         };
 
+        let mut tx = self.db.begin().await?;
+
         // Insert article
         sqlx::query!(
             r#"
@@ -105,10 +169,12 @@ impl ArticleService {
                 id, title, subtitle, content, content_html, excerpt,
                 featured_image_url, author_id, publication_id, status,
                 is_member_only, paywall_position, slug, tags, categories,
-                reading_time_minutes, created_at, updated_at, published_at, is_featured, reads_count, views_count, claps_count, comments_count, bookmarks_count, last_auto_save, auto_save_version
+                reading_time_minutes, created_at, updated_at, published_at, is_featured, reads_count, views_count, claps_count, comments_count, bookmarks_count, last_auto_save, auto_save_version,
+                canonical_url, scheduled_at
             ) VALUES (
                 $1, $2, $3, $4, $5, $6, $7, $8, $9, $10::article_status,
-                $11, $12, $13, $14, $15, $16, $17, $18, $19, FALSE, 0, 0, 0, 0, 0, $17, 1
+                $11, $12, $13, $14, $15, $16, $17, $18, $19, FALSE, 0, 0, 0, 0, 0, $17, 1,
+                $20, $21
             )
             "#,
             article_id,
@@ -120,7 +186,7 @@ impl ArticleService {
             request.featured_image_url,
             author_id,
             request.publication_id,
-            status as ArticleStatus,
+            status.clone() as ArticleStatus,
             request.is_member_only.unwrap_or(false),
             request.paywall_position,
             unique_slug,
@@ -129,20 +195,29 @@ impl ArticleService {
             reading_time,
             now,
             now,
-            published_at
+            published_at,
+            canonical_url,
+            request.scheduled_at
         )
-        .execute(&self.db)
+        .execute(&mut *tx)
         .await?;
 
-        // Fetch and return the created article
-        self.get_article_by_id(article_id, Some(author_id)).await
+        if let Some(scheduled_at) = request.scheduled_at {
+            JobService::enqueue(&mut *tx, &Job::PublishArticle { article_id }, scheduled_at).await?;
+        }
+
+        MediaService::sync_article_references(&mut *tx, article_id).await?;
+
+        tx.commit().await?;
+
+        Ok((article_id, status))
     }
 
     pub async fn get_article_by_id(
         &self,
         article_id: Uuid,
         user_id: Option<Uuid>,
-    ) -> Result<ArticleResponse, Box<dyn Error + Send + Sync>> {
+    ) -> Result<ArticleResponse, AppError> {
         let article = sqlx::query_as::<_, Article>(
             r#"
             SELECT 
@@ -154,7 +229,7 @@ impl ArticleService {
                 COALESCE(categories, ARRAY[]::TEXT[])::TEXT[] as categories, 
                 reading_time_minutes,
                 claps_count, comments_count, bookmarks_count, views_count, reads_count,
-                published_at, created_at, updated_at, last_auto_save, auto_save_version
+                published_at, created_at, updated_at, last_auto_save, auto_save_version, canonical_url, scheduled_at
             FROM articles 
             WHERE id = $1 AND (status = 'published' OR author_id = $2)
             "#,
@@ -166,7 +241,7 @@ impl ArticleService {
 
         match article {
             Some(article) => self.get_article_response(&article, user_id).await,
-            None => Err("Article not found".into()),
+            None => Err(AppError::NotFound("Article not found".to_string())),
         }
     }
 
@@ -174,7 +249,7 @@ impl ArticleService {
         &self,
         slug: &str,
         user_id: Option<Uuid>,
-    ) -> Result<ArticleResponse, Box<dyn Error + Send + Sync>> {
+    ) -> Result<SlugLookup, AppError> {
         let article = sqlx::query_as::<_, Article>(
             r#"
             SELECT 
@@ -186,7 +261,7 @@ impl ArticleService {
                 COALESCE(categories, ARRAY[]::TEXT[])::TEXT[] as categories, 
                 reading_time_minutes,
                 claps_count, comments_count, bookmarks_count, views_count, reads_count,
-                published_at, created_at, updated_at, last_auto_save, auto_save_version
+                published_at, created_at, updated_at, last_auto_save, auto_save_version, canonical_url, scheduled_at
             FROM articles 
             WHERE slug = $1 AND (status = 'published' OR author_id = $2)
             "#,
@@ -196,35 +271,96 @@ impl ArticleService {
         .fetch_optional(&self.db)
         .await?;
 
-        match article {
-            Some(article) => self.get_article_response(&article, user_id).await,
-            None => Err("Article not found".into()),
+        if let Some(article) = article {
+            return Ok(SlugLookup::Found(Box::new(self.get_article_response(&article, user_id).await?)));
+        }
+
+        match self.resolve_historic_slug(slug, user_id).await? {
+            Some(current_slug) => Ok(SlugLookup::Moved(current_slug)),
+            None => Err(AppError::NotFound("Article not found".to_string())),
         }
     }
 
+    // Current slug of an article that used to live at `slug`, if the caller may see it
+    pub async fn resolve_historic_slug(
+        &self,
+        slug: &str,
+        user_id: Option<Uuid>,
+    ) -> Result<Option<String>, AppError> {
+        let current_slug = sqlx::query_scalar!(
+            r#"
+            SELECT a.slug
+            FROM article_slug_history h
+            JOIN articles a ON a.id = h.article_id
+            WHERE h.slug = $1 AND (a.status = 'published' OR a.author_id = $2)
+            "#,
+            slug,
+            user_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(current_slug)
+    }
+
     pub async fn update_article(
         &self,
         article_id: Uuid,
         author_id: Uuid,
         request: UpdateArticleRequest,
-    ) -> Result<ArticleResponse, Box<dyn Error + Send + Sync>> {
+    ) -> Result<ArticleResponse, AppError> {
         // Check ownership
-        let existing = sqlx::query!("SELECT author_id FROM articles WHERE id = $1", article_id)
+        let existing = sqlx::query!("SELECT author_id, slug FROM articles WHERE id = $1", article_id)
             .fetch_optional(&self.db)
             .await?;
 
-        match existing {
-            Some(row) if row.author_id == author_id => {},
-            Some(_) => return Err("Unauthorized to update this article".into()),
-            None => return Err("Article not found".into()),
+        let current_slug = match existing {
+            Some(row) if row.author_id == author_id => row.slug,
+            Some(_) => return Err(AppError::Forbidden("Unauthorized to update this article".to_string())),
+            None => return Err(AppError::NotFound("Article not found".to_string())),
+        };
+
+        // Everything that can be rejected is checked before the first write, and the changes
+        // are applied in one transaction, so a rejected field never leaves the others saved
+        if let Some(scheduled_at) = request.scheduled_at {
+            self.validate_schedule(scheduled_at)?;
+        }
+        let canonical_url = match &request.canonical_url {
+            Some(url) => Some(self.normalize_canonical_url(url)?),
+            None => None,
+        };
+        let new_slug = match &request.title {
+            Some(title) => Some(self.ensure_unique_slug(&self.generate_slug(title), Some(article_id)).await?),
+            None => None,
+        };
+
+        let mut tx = self.db.begin().await?;
+
+        if let (Some(title), Some(new_slug)) = (&request.title, &new_slug) {
+            sqlx::query!(
+                "UPDATE articles SET title = $1, slug = $2, updated_at = NOW() WHERE id = $3 AND author_id = $4",
+                title, new_slug, article_id, author_id
+            ).execute(&mut *tx).await?;
+
+            // Keep the old slug reachable; reclaiming a previous slug drops it from history
+            if *new_slug != current_slug {
+                sqlx::query!(
+                    "INSERT INTO article_slug_history (slug, article_id) VALUES ($1, $2) ON CONFLICT (slug) DO NOTHING",
+                    current_slug, article_id
+                ).execute(&mut *tx).await?;
+
+                sqlx::query!(
+                    "DELETE FROM article_slug_history WHERE slug = $1 AND article_id = $2",
+                    new_slug, article_id
+                ).execute(&mut *tx).await?;
+            }
         }
 
-        // Simple update for now - in production, use a proper query builder
-        if let Some(title) = &request.title {
+        if let Some(canonical_url) = canonical_url {
             sqlx::query!(
-                "UPDATE articles SET title = $1, updated_at = NOW() WHERE id = $2 AND author_id = $3",
-                title, article_id, author_id
-            ).execute(&self.db).await?;
+                "UPDATE articles SET canonical_url = $1, updated_at = NOW() WHERE id = $2 AND author_id = $3",
+                canonical_url, article_id, author_id
+            ).execute(&mut *tx).await?;
         }
 
         if let Some(content) = &request.content {
@@ -234,9 +370,27 @@ impl ArticleService {
             sqlx::query!(
                 "UPDATE articles SET content = $1, content_html = $2, reading_time_minutes = $3, updated_at = NOW() WHERE id = $4 AND author_id = $5",
                 content, content_html, reading_time, article_id, author_id
-            ).execute(&self.db).await?;
+            ).execute(&mut *tx).await?;
+        }
+
+        // "" removes the featured image
+        if let Some(featured_image_url) = &request.featured_image_url {
+            sqlx::query!(
+                "UPDATE articles SET featured_image_url = NULLIF($1, ''), updated_at = NOW() WHERE id = $2 AND author_id = $3",
+                featured_image_url, article_id, author_id
+            ).execute(&mut *tx).await?;
+        }
+
+        if request.content.is_some() || request.featured_image_url.is_some() {
+            MediaService::sync_article_references(&mut *tx, article_id).await?;
+        }
+
+        if let Some(scheduled_at) = request.scheduled_at {
+            self.set_schedule(&mut tx, article_id, author_id, scheduled_at).await?;
         }
 
+        tx.commit().await?;
+
         self.get_article_by_id(article_id, Some(author_id)).await
     }
 
@@ -244,7 +398,7 @@ impl ArticleService {
         &self,
         article_id: Uuid,
         author_id: Uuid,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    ) -> Result<(), AppError> {
         let result = sqlx::query!(
             "DELETE FROM articles WHERE id = $1 AND author_id = $2",
             article_id,
@@ -254,7 +408,7 @@ impl ArticleService {
         .await?;
 
         if result.rows_affected() == 0 {
-            return Err("Article not found or unauthorized".into());
+            return Err(AppError::NotFound("Article not found or unauthorized".to_string()));
         }
 
         Ok(())
@@ -264,31 +418,160 @@ impl ArticleService {
         &self,
         article_id: Uuid,
         author_id: Uuid,
-    ) -> Result<ArticleResponse, Box<dyn Error + Send + Sync>> {
+    ) -> Result<ArticleResponse, AppError> {
+        let mut tx = self.db.begin().await?;
+
+        // Publishing a scheduled article early replaces its schedule
         let result = sqlx::query!(
             r#"
             UPDATE articles 
-            SET status = 'published', published_at = NOW(), updated_at = NOW()
-            WHERE id = $1 AND author_id = $2 AND status = 'draft'
+            SET status = 'published', published_at = NOW(), scheduled_at = NULL, updated_at = NOW()
+            WHERE id = $1 AND author_id = $2 AND status IN ('draft', 'scheduled')
             "#,
             article_id,
             author_id
         )
-        .execute(&self.db)
+        .execute(&mut *tx)
         .await?;
 
         if result.rows_affected() == 0 {
-            return Err("Article not found, unauthorized, or already published".into());
+            return Err(AppError::NotFound(
+                "Article not found, unauthorized, or already published".to_string(),
+            ));
         }
 
+        JobService::cancel_pending(&mut *tx, &Job::PublishArticle { article_id }).await?;
+        tx.commit().await?;
+
+        self.on_published(article_id).await;
+
+        self.get_article_by_id(article_id, Some(author_id)).await
+    }
+
+    // Schedule a draft, or move the publish time of an already scheduled article
+    pub async fn schedule_article(
+        &self,
+        article_id: Uuid,
+        author_id: Uuid,
+        request: ScheduleArticleRequest,
+    ) -> Result<ArticleResponse, AppError> {
+        self.validate_schedule(request.scheduled_at)?;
+
+        let mut tx = self.db.begin().await?;
+        self.set_schedule(&mut tx, article_id, author_id, request.scheduled_at).await?;
+        tx.commit().await?;
+
         self.get_article_by_id(article_id, Some(author_id)).await
     }
 
+    // Moves the article to `scheduled` and replaces its publish job, as part of the caller's transaction
+    async fn set_schedule(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        article_id: Uuid,
+        author_id: Uuid,
+        scheduled_at: chrono::DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE articles
+            SET status = 'scheduled', scheduled_at = $3, updated_at = NOW()
+            WHERE id = $1 AND author_id = $2 AND status IN ('draft', 'scheduled')
+            "#,
+            article_id,
+            author_id,
+            scheduled_at
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound(
+                "Article not found, unauthorized, or already published".to_string(),
+            ));
+        }
+
+        JobService::cancel_pending(&mut **tx, &Job::PublishArticle { article_id }).await?;
+        JobService::enqueue(&mut **tx, &Job::PublishArticle { article_id }, scheduled_at).await?;
+
+        Ok(())
+    }
+
+    // Turn a scheduled article back into a draft
+    pub async fn cancel_schedule(
+        &self,
+        article_id: Uuid,
+        author_id: Uuid,
+    ) -> Result<ArticleResponse, AppError> {
+        let mut tx = self.db.begin().await?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE articles
+            SET status = 'draft', scheduled_at = NULL, updated_at = NOW()
+            WHERE id = $1 AND author_id = $2 AND status = 'scheduled'
+            "#,
+            article_id,
+            author_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound(
+                "Article not found, unauthorized, or not scheduled".to_string(),
+            ));
+        }
+
+        JobService::cancel_pending(&mut *tx, &Job::PublishArticle { article_id }).await?;
+        tx.commit().await?;
+
+        self.get_article_by_id(article_id, Some(author_id)).await
+    }
+
+    // Called by the job runner; a no-op if the schedule was cancelled or moved meanwhile
+    pub async fn publish_scheduled(&self, article_id: Uuid) -> Result<bool, AppError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE articles
+            SET status = 'published', published_at = NOW(), scheduled_at = NULL, updated_at = NOW()
+            WHERE id = $1 AND status = 'scheduled' AND scheduled_at <= NOW()
+            "#,
+            article_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        self.on_published(article_id).await;
+        Ok(true)
+    }
+
+    fn validate_schedule(&self, scheduled_at: chrono::DateTime<Utc>) -> Result<(), AppError> {
+        if scheduled_at <= Utc::now() {
+            return Err(AppError::BadRequest("scheduled_at must be in the future".to_string()));
+        }
+        Ok(())
+    }
+
+    // Side effects of an article going live; they must not fail the publish itself
+    async fn on_published(&self, article_id: Uuid) {
+        // Email the article to newsletter subscribers
+        match NewsletterService::new(self.db.clone(), self.config).queue_article(article_id).await {
+            Ok(queued) if queued > 0 => tracing::info!("Queued article {} for {} newsletter subscribers", article_id, queued),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to queue newsletter for article {}: {}", article_id, e),
+        }
+    }
+
     pub async fn get_articles(
         &self,
         params: ArticleQueryParams,
         user_id: Option<Uuid>,
-    ) -> Result<ArticleListResponse, Box<dyn Error + Send + Sync>> {
+    ) -> Result<ArticleListResponse, AppError> {
         let limit = params.limit.unwrap_or(20).min(100);
         let page = params.page.unwrap_or(1).max(1);
         let offset = (page - 1) * limit;
@@ -307,7 +590,7 @@ impl ArticleService {
                         COALESCE(categories, ARRAY[]::TEXT[])::TEXT[] as categories, 
                         reading_time_minutes, 
                         claps_count, comments_count, bookmarks_count, views_count, reads_count,
-                        published_at, created_at, updated_at, last_auto_save, auto_save_version
+                        published_at, created_at, updated_at, last_auto_save, auto_save_version, canonical_url, scheduled_at
                     FROM articles 
                     WHERE status = 'published' AND author_id = $1
                     ORDER BY created_at DESC
@@ -345,7 +628,7 @@ impl ArticleService {
                     COALESCE(categories, ARRAY[]::TEXT[])::TEXT[] as categories, 
                     reading_time_minutes, 
                     claps_count, comments_count, bookmarks_count, views_count, reads_count,
-                    published_at, created_at, updated_at, last_auto_save, auto_save_version
+                    published_at, created_at, updated_at, last_auto_save, auto_save_version, canonical_url, scheduled_at
                 FROM articles 
                 WHERE status = 'published' AND $1 = ANY(categories)
                 ORDER BY created_at DESC
@@ -379,7 +662,7 @@ impl ArticleService {
                     COALESCE(categories, ARRAY[]::TEXT[])::TEXT[] as categories, 
                     reading_time_minutes, 
                     claps_count, comments_count, bookmarks_count, views_count, reads_count,
-                    published_at, created_at, updated_at, last_auto_save, auto_save_version
+                    published_at, created_at, updated_at, last_auto_save, auto_save_version, canonical_url, scheduled_at
                 FROM articles 
                 WHERE status = 'published' AND $1 = ANY(tags)
                 ORDER BY created_at DESC
@@ -413,7 +696,7 @@ impl ArticleService {
                     COALESCE(categories, ARRAY[]::TEXT[])::TEXT[] as categories, 
                     reading_time_minutes, 
                     claps_count, comments_count, bookmarks_count, views_count, reads_count,
-                    published_at, created_at, updated_at, last_auto_save, auto_save_version
+                    published_at, created_at, updated_at, last_auto_save, auto_save_version, canonical_url, scheduled_at
                 FROM articles 
                 WHERE status = 'published'
                 ORDER BY created_at DESC
@@ -449,19 +732,26 @@ impl ArticleService {
         &self,
         base_slug: &str,
         exclude_id: Option<Uuid>,
-    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+    ) -> Result<String, AppError> {
         let mut slug = base_slug.to_string();
         let mut counter = 1;
 
         loop {
+            // Historic slugs of other articles stay reserved so their redirects keep working
             let count = if let Some(id) = exclude_id {
                 sqlx::query_scalar!(
-                    "SELECT COUNT(*) FROM articles WHERE slug = $1 AND id != $2",
+                    r#"
+                    SELECT (SELECT COUNT(*) FROM articles WHERE slug = $1 AND id != $2)
+                        + (SELECT COUNT(*) FROM article_slug_history WHERE slug = $1 AND article_id != $2)
+                    "#,
                     slug, id
                 ).fetch_one(&self.db).await?.unwrap_or(0)
             } else {
                 sqlx::query_scalar!(
-                    "SELECT COUNT(*) FROM articles WHERE slug = $1",
+                    r#"
+                    SELECT (SELECT COUNT(*) FROM articles WHERE slug = $1)
+                        + (SELECT COUNT(*) FROM article_slug_history WHERE slug = $1)
+                    "#,
                     slug
                 ).fetch_one(&self.db).await?.unwrap_or(0)
             };
@@ -481,7 +771,7 @@ impl ArticleService {
         &self,
         article: &Article,
         user_id: Option<Uuid>,
-    ) -> Result<ArticleResponse, Box<dyn Error + Send + Sync>> {
+    ) -> Result<ArticleResponse, AppError> {
         // Get author info
         let author = sqlx::query!(
             "SELECT username, display_name, avatar_url, bio, followers_count, is_verified FROM users WHERE id = $1",
@@ -489,15 +779,18 @@ impl ArticleService {
         )
         .fetch_optional(&self.db)
         .await?
-        .ok_or_else(|| format!("Author not found for article {}", article.id))?;
+        .ok_or_else(|| AppError::internal(format!("Author not found for article {}", article.id)))?;
 
         // Get user interactions if user is logged in
         let user_interactions = if let Some(uid) = user_id {
-            use crate::services::engagement::EngagementService;
-            let engagement_service = EngagementService::new(self.db.clone());
-            
-            let user_clap_count = engagement_service.get_user_clap_count(article.id, uid).await.unwrap_or(0);
-            let has_clapped = user_clap_count > 0;
+            let has_clapped = sqlx::query_scalar!(
+                "SELECT EXISTS(SELECT 1 FROM claps WHERE user_id = $1 AND article_id = $2)",
+                uid,
+                article.id
+            )
+            .fetch_one(&self.db)
+            .await?
+            .unwrap_or(false);
             
             // Check if user is following author
             let is_following_author = sqlx::query_scalar!(
@@ -521,7 +814,7 @@ impl ArticleService {
             
             Some(crate::models::UserInteractions {
                 has_clapped,
-                clap_count: user_clap_count,
+                clap_count: if has_clapped { 1 } else { 0 },
                 has_bookmarked,
                 is_following_author,
             })
@@ -530,8 +823,8 @@ impl ArticleService {
         };
 
         // Generate share URL and metadata
-        let frontend_url = std::env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:3003".to_string());
-        let share_url = format!("{}/article/{}", frontend_url, article.slug);
+        let share_url = format!("{}/article/{}", self.config.frontend_url, article.slug);
+        let canonical_url = article.canonical_url.clone().unwrap_or_else(|| share_url.clone());
         let share_title = article.title.clone();
         let share_description = article.excerpt.clone()
             .or_else(|| article.subtitle.clone())
@@ -575,12 +868,14 @@ impl ArticleService {
             bookmarks_count: article.bookmarks_count,
             views_count: article.views_count,
             published_at: article.published_at,
+            scheduled_at: article.scheduled_at,
             created_at: article.created_at,
             updated_at: article.updated_at,
             user_interactions,
             share_url,
             share_title,
             share_description,
+            canonical_url,
         })
     }
 
@@ -588,7 +883,7 @@ impl ArticleService {
         &self,
         author_id: Uuid,
         request: &crate::models::AutoSaveDraftRequest,
-    ) -> Result<Uuid, Box<dyn Error + Send + Sync>> {
+    ) -> Result<Uuid, AppError> {
         let now = chrono::Utc::now();
         
         if let Some(article_id) = request.article_id {
@@ -627,6 +922,8 @@ impl ArticleService {
             )
             .execute(&self.db)
             .await?;
+
+            MediaService::sync_article_references(&self.db, article_id).await?;
             
             Ok(article_id)
         } else {
@@ -661,6 +958,8 @@ impl ArticleService {
             )
             .execute(&self.db)
             .await?;
+
+            MediaService::sync_article_references(&self.db, article_id).await?;
             
             Ok(article_id)
         }
@@ -669,7 +968,7 @@ impl ArticleService {
     pub async fn get_article_stats(
         &self,
         article_id: Uuid,
-    ) -> Result<crate::models::ArticleStats, Box<dyn Error + Send + Sync>> {
+    ) -> Result<crate::models::ArticleStats, AppError> {
         let article = sqlx::query!(
             r#"
             SELECT 
@@ -706,7 +1005,7 @@ impl ArticleService {
     pub async fn get_author_stats(
         &self,
         author_id: Uuid,
-    ) -> Result<crate::models::AuthorStats, Box<dyn Error + Send + Sync>> {
+    ) -> Result<crate::models::AuthorStats, AppError> {
         // Get basic stats
         let stats = sqlx::query!(
             r#"
@@ -816,7 +1115,7 @@ impl ArticleService {
         })
     }
 
-    pub async fn record_view(&self, article_id: Uuid, _user_id: Option<Uuid>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    pub async fn record_view(&self, article_id: Uuid, _user_id: Option<Uuid>) -> Result<(), AppError> {
         // Increment view count
         sqlx::query!(
             "UPDATE articles SET views_count = views_count + 1 WHERE id = $1",
@@ -830,7 +1129,7 @@ impl ArticleService {
         Ok(())
     }
 
-    pub async fn record_read(&self, article_id: Uuid, _user_id: Option<Uuid>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    pub async fn record_read(&self, article_id: Uuid, _user_id: Option<Uuid>) -> Result<(), AppError> {
         // Increment read count
         sqlx::query!(
             "UPDATE articles SET reads_count = reads_count + 1 WHERE id = $1",
@@ -844,7 +1143,7 @@ impl ArticleService {
         Ok(())
     }
 
-    pub async fn toggle_featured(&self, article_id: Uuid, _user_id: Option<Uuid>) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    pub async fn toggle_featured(&self, article_id: Uuid, _user_id: Option<Uuid>) -> Result<bool, AppError> {
         // Get current featured status
         let current_status: bool = sqlx::query_scalar!(
             "SELECT is_featured FROM articles WHERE id = $1",
@@ -874,49 +1173,26 @@ impl ArticleService {
         user_id: Uuid,
         page: Option<i64>,
         limit: Option<i64>,
-    ) -> Result<ArticleListResponse, Box<dyn Error + Send + Sync>> {
+    ) -> Result<ArticleListResponse, AppError> {
         let limit = limit.unwrap_or(20).min(100);
         let page = page.unwrap_or(1).max(1);
         let offset = (page - 1) * limit;
 
-        // Get articles from users that this user follows
-        let limit_i64 = limit as i64;
-        let offset_i64 = offset as i64;
-        let articles = sqlx::query_as::<_, Article>(
-            r#"
-            SELECT 
-                a.id, a.title, a.subtitle, a.content, a.content_html, a.excerpt, 
-                a.featured_image_url, a.author_id, a.publication_id, 
-                a.status, 
-                a.is_member_only, a.is_featured, a.paywall_position, a.slug, 
-                COALESCE(a.tags, ARRAY[]::TEXT[])::TEXT[] as tags, 
-                COALESCE(a.categories, ARRAY[]::TEXT[])::TEXT[] as categories, 
-                a.reading_time_minutes, 
-                a.claps_count, a.comments_count, a.bookmarks_count, a.views_count, a.reads_count,
-                a.published_at, a.created_at, a.updated_at, a.last_auto_save, a.auto_save_version
-            FROM articles a
-            INNER JOIN user_follows uf ON a.author_id = uf.following_id
-            WHERE uf.follower_id = $1 
-                AND a.status = 'published'
-                AND a.published_at IS NOT NULL
-            ORDER BY a.published_at DESC
-            LIMIT $2 OFFSET $3
-            "#,
-        )
-        .bind(user_id)
-        .bind(limit_i64)
-        .bind(offset_i64)
-        .fetch_all(&self.db)
-        .await?;
+        let articles = self.feed_articles(user_id, None, limit, offset).await?;
 
         let total = sqlx::query_scalar!(
             r#"
             SELECT COUNT(*)
             FROM articles a
-            INNER JOIN user_follows uf ON a.author_id = uf.following_id
-            WHERE uf.follower_id = $1 
-                AND a.status = 'published'
+            WHERE a.status = 'published'
                 AND a.published_at IS NOT NULL
+                AND a.author_id <> $1
+                AND (
+                    EXISTS (SELECT 1 FROM user_follows uf WHERE uf.follower_id = $1 AND uf.following_id = a.author_id)
+                    OR EXISTS (SELECT 1 FROM publication_follows pf WHERE pf.user_id = $1 AND pf.publication_id = a.publication_id)
+                    OR EXISTS (SELECT 1 FROM tag_follows tf WHERE tf.user_id = $1 AND tf.tag = ANY(a.tags))
+                    OR EXISTS (SELECT 1 FROM category_follows cf WHERE cf.user_id = $1 AND cf.category = ANY(a.categories))
+                )
             "#,
             user_id
         )
@@ -942,6 +1218,179 @@ impl ArticleService {
         })
     }
 
+    // Blend articles from followed authors, publications, tags and categories, newest first;
+    // each article appears once whatever the number of reasons it matched. Also backs the
+    // email digest, which passes `since` to only get articles published after its last run.
+    pub async fn feed_articles(
+        &self,
+        user_id: Uuid,
+        since: Option<chrono::DateTime<Utc>>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Article>, AppError> {
+        let articles = sqlx::query_as::<_, Article>(
+            r#"
+            SELECT 
+                a.id, a.title, a.subtitle, a.content, a.content_html, a.excerpt, 
+                a.featured_image_url, a.author_id, a.publication_id, 
+                a.status, 
+                a.is_member_only, a.is_featured, a.paywall_position, a.slug, 
+                COALESCE(a.tags, ARRAY[]::TEXT[])::TEXT[] as tags, 
+                COALESCE(a.categories, ARRAY[]::TEXT[])::TEXT[] as categories, 
+                a.reading_time_minutes, 
+                a.claps_count, a.comments_count, a.bookmarks_count, a.views_count, a.reads_count,
+                a.published_at, a.created_at, a.updated_at, a.last_auto_save, a.auto_save_version, a.canonical_url, a.scheduled_at
+            FROM articles a
+            WHERE a.status = 'published'
+                AND a.published_at IS NOT NULL
+                AND ($4::TIMESTAMPTZ IS NULL OR a.published_at > $4)
+                AND a.author_id <> $1
+                AND (
+                    EXISTS (SELECT 1 FROM user_follows uf WHERE uf.follower_id = $1 AND uf.following_id = a.author_id)
+                    OR EXISTS (SELECT 1 FROM publication_follows pf WHERE pf.user_id = $1 AND pf.publication_id = a.publication_id)
+                    OR EXISTS (SELECT 1 FROM tag_follows tf WHERE tf.user_id = $1 AND tf.tag = ANY(a.tags))
+                    OR EXISTS (SELECT 1 FROM category_follows cf WHERE cf.user_id = $1 AND cf.category = ANY(a.categories))
+                )
+            ORDER BY a.published_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .bind(offset)
+        .bind(since)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(articles)
+    }
+
+    // Follow a tag or category; returns false if it was already followed
+    pub async fn follow_topic(&self, user_id: Uuid, kind: TopicKind, name: &str) -> Result<bool, AppError> {
+        let result = match kind {
+            TopicKind::Tag => sqlx::query!(
+                "INSERT INTO tag_follows (user_id, tag) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                user_id,
+                name
+            )
+            .execute(&self.db)
+            .await?,
+            TopicKind::Category => sqlx::query!(
+                "INSERT INTO category_follows (user_id, category) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                user_id,
+                name
+            )
+            .execute(&self.db)
+            .await?,
+        };
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn unfollow_topic(&self, user_id: Uuid, kind: TopicKind, name: &str) -> Result<(), AppError> {
+        match kind {
+            TopicKind::Tag => sqlx::query!(
+                "DELETE FROM tag_follows WHERE user_id = $1 AND tag = $2",
+                user_id,
+                name
+            )
+            .execute(&self.db)
+            .await?,
+            TopicKind::Category => sqlx::query!(
+                "DELETE FROM category_follows WHERE user_id = $1 AND category = $2",
+                user_id,
+                name
+            )
+            .execute(&self.db)
+            .await?,
+        };
+
+        Ok(())
+    }
+
+    // Tag or category page: counts, follow state and its published articles
+    pub async fn get_topic_page(
+        &self,
+        kind: TopicKind,
+        name: &str,
+        page: Option<i64>,
+        limit: Option<i64>,
+        user_id: Option<Uuid>,
+    ) -> Result<TopicPageResponse, AppError> {
+        let (follower_count, is_following) = match kind {
+            TopicKind::Tag => {
+                let row = sqlx::query!(
+                    r#"
+                    SELECT
+                        COUNT(*) as "follower_count!",
+                        COALESCE(BOOL_OR(user_id = $2), FALSE) as "is_following!"
+                    FROM tag_follows
+                    WHERE tag = $1
+                    "#,
+                    name,
+                    user_id
+                )
+                .fetch_one(&self.db)
+                .await?;
+                (row.follower_count, row.is_following)
+            }
+            TopicKind::Category => {
+                let row = sqlx::query!(
+                    r#"
+                    SELECT
+                        COUNT(*) as "follower_count!",
+                        COALESCE(BOOL_OR(user_id = $2), FALSE) as "is_following!"
+                    FROM category_follows
+                    WHERE category = $1
+                    "#,
+                    name,
+                    user_id
+                )
+                .fetch_one(&self.db)
+                .await?;
+                (row.follower_count, row.is_following)
+            }
+        };
+
+        let params = ArticleQueryParams {
+            page,
+            limit,
+            tag: (kind == TopicKind::Tag).then(|| name.to_string()),
+            category: (kind == TopicKind::Category).then(|| name.to_string()),
+            ..Default::default()
+        };
+        let articles = self.get_articles(params, user_id).await?;
+
+        Ok(TopicPageResponse {
+            topic: TopicResponse {
+                name: name.to_string(),
+                kind,
+                article_count: articles.total as i64,
+                follower_count,
+                is_following: user_id.map(|_| is_following),
+            },
+            articles,
+        })
+    }
+
+    pub async fn get_followed_topics(&self, user_id: Uuid) -> Result<FollowedTopicsResponse, AppError> {
+        let tags = sqlx::query_scalar!(
+            "SELECT tag FROM tag_follows WHERE user_id = $1 ORDER BY tag",
+            user_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let categories = sqlx::query_scalar!(
+            "SELECT category FROM category_follows WHERE user_id = $1 ORDER BY category",
+            user_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(FollowedTopicsResponse { tags, categories })
+    }
+
     // Get trending articles based on engagement (claps, comments, views, reads)
     pub async fn get_trending_articles(
         &self,
@@ -949,7 +1398,7 @@ impl ArticleService {
         page: Option<i64>,
         limit: Option<i64>,
         time_window_hours: Option<i32>,
-    ) -> Result<ArticleListResponse, Box<dyn Error + Send + Sync>> {
+    ) -> Result<ArticleListResponse, AppError> {
         let limit = limit.unwrap_or(20).min(100);
         let page = page.unwrap_or(1).max(1);
         let offset = (page - 1) * limit;
@@ -971,7 +1420,7 @@ impl ArticleService {
                 COALESCE(a.categories, ARRAY[]::TEXT[])::TEXT[] as categories, 
                 a.reading_time_minutes, 
                 a.claps_count, a.comments_count, a.bookmarks_count, a.views_count, a.reads_count,
-                a.published_at, a.created_at, a.updated_at, a.last_auto_save, a.auto_save_version
+                a.published_at, a.created_at, a.updated_at, a.last_auto_save, a.auto_save_version, a.canonical_url, a.scheduled_at
             FROM articles a
             WHERE a.status = 'published'
                 AND a.published_at IS NOT NULL
@@ -3,7 +3,6 @@ use argon2::{
     Argon2,
 };
 use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use rand_core::OsRng;
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
@@ -12,9 +11,19 @@ use uuid::Uuid;
 use crate::{
     config::Config,
     database::Database,
-    models::{AuthResponse, CreateUserRequest, LoginRequest, User, UserType},
+    error::AppError,
+    models::{access_token::TokenScope, two_factor::LoginResponse, AuthResponse, CreateUserRequest, LoginRequest, User, UserType},
+    services::{
+        access_token::{AccessTokenService, ACCESS_TOKEN_PREFIX},
+        jwt,
+        login_protection::LoginProtectionService,
+        two_factor::TwoFactorService,
+    },
 };
 
+/// How recent a provider sign-in must be to stand in for a password
+const PROVIDER_REAUTH_MINUTES: i32 = 10;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String, // User ID
@@ -22,19 +31,27 @@ pub struct Claims {
     pub user_type: UserType,
     pub exp: usize, // Expiration time
     pub iat: usize, // Issued at
+    // Signed in with a second factor
+    #[serde(default)]
+    pub mfa: bool,
+    // Admin signed in with a second factor, or an access token with the admin scope
+    #[serde(default)]
+    pub admin: bool,
+    // Set for personal access tokens; sessions can do everything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<TokenScope>>,
 }
 
 pub struct AuthService<'a> {
     db: &'a Database,
-    config: &'a Config,
 }
 
 impl<'a> AuthService<'a> {
-    pub fn new(db: &'a Database, config: &'a Config) -> Self {
-        Self { db, config }
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
     }
 
-    pub async fn register(&self, request: CreateUserRequest) -> Result<AuthResponse, Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn register(&self, request: CreateUserRequest) -> Result<AuthResponse, AppError> {
         // Check if user already exists
         let existing_user = sqlx::query("SELECT id FROM users WHERE email = $1 OR username = $2")
             .bind(&request.email)
@@ -43,7 +60,7 @@ impl<'a> AuthService<'a> {
             .await?;
 
         if existing_user.is_some() {
-            return Err("User with this email or username already exists".into());
+            return Err(AppError::Conflict("User with this email or username already exists".to_string()));
         }
 
         // Hash password
@@ -53,7 +70,9 @@ impl<'a> AuthService<'a> {
         let user_id = Uuid::new_v4();
         let now = Utc::now();
 
-        // First insert the user
+        // First insert the user along with their default digest settings
+        let mut tx = self.db.pool.begin().await?;
+
         sqlx::query(
             r#"
             INSERT INTO users (
@@ -74,9 +93,15 @@ impl<'a> AuthService<'a> {
         .bind(0i32)
         .bind(now)
         .bind(now)
-        .execute(&self.db.pool)
+        .execute(&mut *tx)
         .await?;
 
+        sqlx::query!("INSERT INTO digest_settings (user_id) VALUES ($1)", user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
         // Then fetch the user
         let user = sqlx::query_as!(
             User,
@@ -98,7 +123,13 @@ impl<'a> AuthService<'a> {
         })
     }
 
-    pub async fn login(&self, request: LoginRequest) -> Result<AuthResponse, Box<dyn std::error::Error + Send + Sync>> {
+    // Password sign-in; failures are tracked per account and per IP (see LoginProtectionService)
+    pub async fn login(
+        &self,
+        config: &Config,
+        request: LoginRequest,
+        ip_address: &str,
+    ) -> Result<LoginResponse, AppError> {
         // Find user by email or username
         // Check if input looks like an email (contains @)
         let is_email = request.email.contains('@');
@@ -127,7 +158,13 @@ impl<'a> AuthService<'a> {
             .await?
         };
 
-        let user = user.ok_or("Invalid email/username or password")?;
+        let protection = LoginProtectionService::new(self.db.pool.clone(), config);
+        protection.check(user.as_ref().map(|user| user.user_id), ip_address).await?;
+
+        let Some(user) = user else {
+            protection.record_failure(None, ip_address).await?;
+            return Err(AppError::Unauthorized("Invalid email/username or password".to_string()));
+        };
 
         // Get password hash
         let password_hash_row = sqlx::query("SELECT password_hash FROM users WHERE id = $1")
@@ -135,13 +172,58 @@ impl<'a> AuthService<'a> {
             .fetch_one(&self.db.pool)
             .await?;
         
-        let password_hash: String = password_hash_row.get("password_hash");
+        // Accounts created through a sign-in provider have no password
+        let password_hash: Option<String> = password_hash_row.get("password_hash");
 
         // Verify password
-        if !self.verify_password(&request.password, &password_hash)? {
-            return Err("Invalid email/username or password".into());
+        let password_matches = match password_hash {
+            Some(password_hash) => self.verify_password(&request.password, &password_hash)?,
+            None => false,
+        };
+        if !password_matches {
+            protection.record_failure(Some(user.user_id), ip_address).await?;
+            return Err(AppError::Unauthorized("Invalid email/username or password".to_string()));
         }
+        protection.record_success(user.user_id).await?;
 
+        // Generate JWT token with appropriate duration based on remember_me
+        let remember_me = request.remember_me.unwrap_or(false);
+        self.finish_login(user, remember_me).await
+    }
+
+    // Sign in a user authenticated by a linked OpenID Connect provider
+    pub async fn login_with_identity(&self, user_id: Uuid) -> Result<LoginResponse, AppError> {
+        let user = self.get_user_by_id(&user_id.to_string()).await?;
+        self.finish_login(user, false).await
+    }
+
+    // Second login step for accounts with two-factor authentication
+    pub async fn login_with_two_factor(
+        &self,
+        challenge_token: &str,
+        code: Option<&str>,
+        recovery_code: Option<&str>,
+    ) -> Result<AuthResponse, AppError> {
+        let (user_id, remember_me) = TwoFactorService::new(self.db)
+            .complete_challenge(challenge_token, code, recovery_code)
+            .await?;
+        let user = self.get_user_by_id(&user_id.to_string()).await?;
+
+        self.start_session(user, remember_me, true).await
+    }
+
+    // The first factor checked out: issue a token, or a challenge when 2FA is enabled
+    async fn finish_login(&self, user: User, remember_me: bool) -> Result<LoginResponse, AppError> {
+        let two_factor = TwoFactorService::new(self.db);
+        if two_factor.is_enabled(user.user_id).await? {
+            let challenge = two_factor.create_challenge(user.user_id, remember_me).await?;
+            return Ok(LoginResponse::TwoFactorRequired(challenge));
+        }
+
+        Ok(LoginResponse::Authenticated(self.start_session(user, remember_me, false).await?))
+    }
+
+    async fn start_session(&self, user: User, remember_me: bool, mfa: bool) -> Result<AuthResponse, AppError> {
         // Update last login
         sqlx::query("UPDATE users SET updated_at = $1 WHERE id = $2")
             .bind(Utc::now())
@@ -149,9 +231,11 @@ impl<'a> AuthService<'a> {
             .execute(&self.db.pool)
             .await?;
 
-        // Generate JWT token with appropriate duration based on remember_me
-        let remember_me = request.remember_me.unwrap_or(false);
-        let (token, expires_at) = self.generate_token_with_duration(&user, remember_me)?;
+        let is_admin = sqlx::query_scalar!("SELECT is_admin FROM users WHERE id = $1", user.user_id)
+            .fetch_one(&self.db.pool)
+            .await?;
+
+        let (token, expires_at) = self.generate_token_with_duration(&user, remember_me, mfa, is_admin && mfa)?;
 
         Ok(AuthResponse {
             user: user.into(),
@@ -160,18 +244,27 @@ impl<'a> AuthService<'a> {
         })
     }
 
-    pub async fn verify_token(&self, token: &str) -> Result<Claims, Box<dyn std::error::Error + Send + Sync>> {
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(self.config.jwt_secret.as_ref()),
-            &Validation::default(),
-        )?;
+    // A bearer token: a session JWT or a personal access token
+    pub async fn authenticate(&self, token: &str) -> Result<Claims, AppError> {
+        if token.starts_with(ACCESS_TOKEN_PREFIX) {
+            return AccessTokenService::new(self.db.pool.clone())
+                .authenticate(token)
+                .await?
+                .ok_or_else(|| AppError::Unauthorized("Invalid or expired access token".to_string()));
+        }
+
+        self.verify_token(token).await
+    }
 
-        Ok(token_data.claims)
+    pub async fn verify_token(&self, token: &str) -> Result<Claims, AppError> {
+        jwt::keys().verify(token).map_err(|e| {
+            tracing::debug!("Rejected session token: {}", e);
+            AppError::Unauthorized("Invalid or expired token".to_string())
+        })
     }
 
-    pub async fn get_user_by_id(&self, user_id: &str) -> Result<User, Box<dyn std::error::Error + Send + Sync>> {
-        let user_uuid = Uuid::parse_str(user_id)?;
+    pub async fn get_user_by_id(&self, user_id: &str) -> Result<User, AppError> {
+        let user_uuid = Uuid::parse_str(user_id).map_err(|_| AppError::NotFound("User not found".to_string()))?;
         
         let user = sqlx::query_as!(
             User,
@@ -180,34 +273,67 @@ impl<'a> AuthService<'a> {
                FROM users WHERE id = $1"#,
             user_uuid
         )
-        .fetch_one(&self.db.pool)
-        .await?;
+        .fetch_optional(&self.db.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
         Ok(user)
     }
 
-    fn hash_password(&self, password: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    // Re-authentication for sensitive account changes. Accounts that only sign in through
+    // a provider have no password to confirm, so they need a fresh provider sign-in instead.
+    pub async fn confirm_password(&self, user_id: Uuid, password: &str) -> Result<bool, AppError> {
+        let password_hash = sqlx::query_scalar!("SELECT password_hash FROM users WHERE id = $1", user_id)
+            .fetch_optional(&self.db.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        match password_hash {
+            Some(password_hash) => self.verify_password(password, &password_hash),
+            None => {
+                let recent_login = sqlx::query_scalar!(
+                    r#"SELECT EXISTS(
+                        SELECT 1 FROM user_identities
+                        WHERE user_id = $1 AND last_login_at > NOW() - make_interval(mins => $2)
+                    ) as "exists!""#,
+                    user_id,
+                    PROVIDER_REAUTH_MINUTES
+                )
+                .fetch_one(&self.db.pool)
+                .await?;
+
+                if !recent_login {
+                    return Err(AppError::Forbidden(
+                        "Sign in again with your identity provider to confirm this change".to_string(),
+                    ));
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    fn hash_password(&self, password: &str) -> Result<String, AppError> {
         let salt = SaltString::generate(&mut OsRng);
         let argon2 = Argon2::default();
         let password_hash = argon2
             .hash_password(password.as_bytes(), &salt)
-            .map_err(|e| format!("Password hashing failed: {}", e))?
+            .map_err(|e| AppError::internal(format!("Password hashing failed: {}", e)))?
             .to_string();
         Ok(password_hash)
     }
 
-    fn verify_password(&self, password: &str, hash: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    fn verify_password(&self, password: &str, hash: &str) -> Result<bool, AppError> {
         let parsed_hash = PasswordHash::new(hash)
-            .map_err(|e| format!("Invalid password hash: {}", e))?;
+            .map_err(|e| AppError::internal(format!("Invalid password hash: {}", e)))?;
         let argon2 = Argon2::default();
         Ok(argon2.verify_password(password.as_bytes(), &parsed_hash).is_ok())
     }
 
-    fn generate_token(&self, user: &User) -> Result<(String, chrono::DateTime<Utc>), Box<dyn std::error::Error + Send + Sync>> {
-        self.generate_token_with_duration(user, false)
+    fn generate_token(&self, user: &User) -> Result<(String, chrono::DateTime<Utc>), AppError> {
+        self.generate_token_with_duration(user, false, false, false)
     }
 
-    fn generate_token_with_duration(&self, user: &User, remember_me: bool) -> Result<(String, chrono::DateTime<Utc>), Box<dyn std::error::Error + Send + Sync>> {
+    fn generate_token_with_duration(&self, user: &User, remember_me: bool, mfa: bool, admin: bool) -> Result<(String, chrono::DateTime<Utc>), AppError> {
         let now = Utc::now();
         // If remember_me is true, token expires in 30 days, otherwise 24 hours
         let expires_at = if remember_me {
@@ -222,13 +348,12 @@ impl<'a> AuthService<'a> {
             user_type: user.user_type.clone(),
             exp: expires_at.timestamp() as usize,
             iat: now.timestamp() as usize,
+            mfa,
+            admin,
+            scopes: None,
         };
 
-        let token = encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(self.config.jwt_secret.as_ref()),
-        )?;
+        let token = jwt::keys().sign(&claims)?;
 
         Ok((token, expires_at))
     }
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc};
+use std::error::Error;
+
+// Five-field cron expression (minute hour day-of-month month day-of-week), evaluated in UTC.
+// Fields accept `*`, numbers, ranges `a-b`, steps `*/n` / `a-b/n` and comma-separated lists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    // Standard cron: when both day fields are restricted, either may match
+    day_of_month_any: bool,
+    day_of_week_any: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("Invalid cron expression '{}': expected 5 fields", expression).into());
+        }
+
+        // Sunday may be written as 0 or 7
+        let mut days_of_week = parse_field(fields[4], 0, 7)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days_of_month: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            days_of_week,
+            day_of_month_any: fields[2] == "*",
+            day_of_week_any: fields[4] == "*",
+        })
+    }
+
+    // First matching minute strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.duration_trunc(Duration::minutes(1)).ok()? + Duration::minutes(1);
+
+        // Four years covers every valid combination (Feb 29 included)
+        let limit = after + Duration::days(366 * 4);
+        while time <= limit {
+            if !has(self.months, time.month()) {
+                let (year, month) = if time.month() == 12 { (time.year() + 1, 1) } else { (time.year(), time.month() + 1) };
+                time = time
+                    .with_day(1)?
+                    .with_hour(0)?
+                    .with_minute(0)?
+                    .with_year(year)?
+                    .with_month(month)?;
+                continue;
+            }
+            if !self.matches_day(time) {
+                time = time.with_hour(0)?.with_minute(0)? + Duration::days(1);
+                continue;
+            }
+            if !has(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+                continue;
+            }
+            if !has(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+                continue;
+            }
+            return Some(time);
+        }
+
+        None
+    }
+
+    fn matches_day(&self, time: DateTime<Utc>) -> bool {
+        let day_of_month = has(self.days_of_month, time.day());
+        let day_of_week = has(self.days_of_week, time.weekday().num_days_from_sunday());
+
+        match (self.day_of_month_any, self.day_of_week_any) {
+            (true, true) => true,
+            (true, false) => day_of_week,
+            (false, true) => day_of_month,
+            (false, false) => day_of_month || day_of_week,
+        }
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, Box<dyn Error + Send + Sync>> {
+    let invalid = || format!("Invalid cron field '{}'", field);
+    let mut set = 0u64;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid().into());
+        }
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                start.parse::<u32>().map_err(|_| invalid())?,
+                end.parse::<u32>().map_err(|_| invalid())?,
+            )
+        } else {
+            let value = range.parse::<u32>().map_err(|_| invalid())?;
+            // `5/15` means "from 5 every 15"
+            (value, if part.contains('/') { max } else { value })
+        };
+
+        if start < min || end > max || start > end {
+            return Err(invalid().into());
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+
+    Ok(set)
+}
@@ -0,0 +1,455 @@
+use askama::Template;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::error::Error;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::digest::{
+        DigestArticle, DigestFrequency, DigestPreviewResponse, DigestSettingsResponse, DigestSource,
+    },
+    services::{
+        article::ArticleService,
+        mailer::{EmailMessage, Mailer},
+    },
+};
+
+// Users claimed per run of the send_digests job
+const BATCH_SIZE: i64 = 100;
+// Newest feed articles the digest picks from
+const MAX_CANDIDATES: i64 = 200;
+// Articles included in a single digest
+const MAX_ARTICLES: usize = 15;
+
+#[derive(Template)]
+#[template(path = "email/digest.html")]
+struct DigestHtmlTemplate<'a> {
+    subject: &'a str,
+    recipient_name: &'a str,
+    period_label: &'a str,
+    sections: &'a [DigestSection<'a>],
+    settings_url: &'a str,
+    unsubscribe_url: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "email/digest.txt")]
+struct DigestTextTemplate<'a> {
+    recipient_name: &'a str,
+    period_label: &'a str,
+    sections: &'a [DigestSection<'a>],
+    settings_url: &'a str,
+    unsubscribe_url: &'a str,
+}
+
+
+struct DigestSection<'a> {
+    heading: &'static str,
+    articles: Vec<&'a DigestArticle>,
+}
+
+// A user whose digest window has closed
+struct DueDigest {
+    user_id: Uuid,
+    frequency: DigestFrequency,
+    unsubscribe_token: String,
+    window_start: DateTime<Utc>,
+}
+
+struct Recipient {
+    email: String,
+    name: String,
+}
+
+pub struct DigestService<'a> {
+    db: PgPool,
+    config: &'a Config,
+}
+
+impl<'a> DigestService<'a> {
+    pub fn new(db: PgPool, config: &'a Config) -> Self {
+        Self { db, config }
+    }
+
+    pub async fn get_settings(&self, user_id: Uuid) -> Result<DigestSettingsResponse, AppError> {
+        self.ensure_settings(user_id).await?;
+
+        let settings = sqlx::query!(
+            r#"
+            SELECT frequency as "frequency: DigestFrequency", last_sent_at
+            FROM digest_settings
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(DigestSettingsResponse {
+            frequency: settings.frequency,
+            last_sent_at: settings.last_sent_at,
+        })
+    }
+
+    pub async fn update_settings(
+        &self,
+        user_id: Uuid,
+        frequency: DigestFrequency,
+    ) -> Result<DigestSettingsResponse, AppError> {
+        let settings = sqlx::query!(
+            r#"
+            INSERT INTO digest_settings (user_id, frequency)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id)
+            DO UPDATE SET frequency = EXCLUDED.frequency, updated_at = NOW()
+            RETURNING frequency as "frequency: DigestFrequency", last_sent_at
+            "#,
+            user_id,
+            frequency as DigestFrequency
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(DigestSettingsResponse {
+            frequency: settings.frequency,
+            last_sent_at: settings.last_sent_at,
+        })
+    }
+
+    pub async fn unsubscribe(&self, token: &str) -> Result<(), AppError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE digest_settings
+            SET frequency = 'off', updated_at = NOW()
+            WHERE unsubscribe_token = $1
+            "#,
+            token
+        )
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Unsubscribe link is invalid".to_string()));
+        }
+
+        Ok(())
+    }
+
+    // Render what the user's next digest would look like right now
+    pub async fn preview(&self, user_id: Uuid) -> Result<DigestPreviewResponse, AppError> {
+        self.ensure_settings(user_id).await?;
+
+        let settings = sqlx::query!(
+            r#"
+            SELECT frequency as "frequency: DigestFrequency", unsubscribe_token, last_sent_at
+            FROM digest_settings
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        // Disabled digests preview the weekly window
+        let frequency = match settings.frequency {
+            DigestFrequency::Off => DigestFrequency::Weekly,
+            frequency => frequency,
+        };
+        let period = frequency.period().unwrap_or_default();
+        let window_start = settings.last_sent_at.min(Utc::now() - period);
+
+        let recipient = self.get_recipient(user_id).await?;
+        let articles = self.compile(user_id, window_start).await?;
+        let message = self.render(&recipient, frequency, &settings.unsubscribe_token, &articles)?;
+
+        Ok(DigestPreviewResponse {
+            subject: message.subject,
+            articles,
+            html: message.html_body,
+            text: message.text_body,
+        })
+    }
+
+    // Claim every due digest (safe across instances), compile and send it
+    pub async fn send_due_digests(&self, mailer: &dyn Mailer) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let mut sent = 0;
+        loop {
+            let due = self.claim_due_digests().await?;
+            if due.is_empty() {
+                break;
+            }
+
+            for digest in &due {
+                match self.send_digest(mailer, digest).await {
+                    Ok(true) => sent += 1,
+                    Ok(false) => {}
+                    Err(e) => {
+                        tracing::warn!("Failed to send digest to user {}: {}", digest.user_id, e);
+                        // Put the window back so the next run retries it
+                        if let Err(e) = self.release(digest).await {
+                            tracing::error!("Failed to reset digest window for user {}: {}", digest.user_id, e);
+                        }
+                    }
+                }
+            }
+
+            if (due.len() as i64) < BATCH_SIZE {
+                break;
+            }
+        }
+
+        Ok(sent)
+    }
+
+    async fn send_digest(&self, mailer: &dyn Mailer, digest: &DueDigest) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let articles = self.compile(digest.user_id, digest.window_start).await?;
+        if articles.is_empty() {
+            return Ok(false);
+        }
+
+        let recipient = self.get_recipient(digest.user_id).await?;
+        let message = self.render(&recipient, digest.frequency, &digest.unsubscribe_token, &articles)?;
+        mailer.send(&message).await?;
+
+        Ok(true)
+    }
+
+    async fn ensure_settings(&self, user_id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+        sqlx::query!(
+            "INSERT INTO digest_settings (user_id) VALUES ($1) ON CONFLICT (user_id) DO NOTHING",
+            user_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn claim_due_digests(&self) -> Result<Vec<DueDigest>, Box<dyn Error + Send + Sync>> {
+        let rows = sqlx::query!(
+            r#"
+            WITH due AS (
+                SELECT ds.user_id, ds.last_sent_at
+                FROM digest_settings ds
+                JOIN users u ON u.id = ds.user_id
+                WHERE u.is_banned = FALSE
+                    AND (
+                        (ds.frequency = 'daily' AND ds.last_sent_at <= NOW() - INTERVAL '1 day')
+                        OR (ds.frequency = 'weekly' AND ds.last_sent_at <= NOW() - INTERVAL '7 days')
+                    )
+                ORDER BY ds.last_sent_at
+                LIMIT $1
+                FOR UPDATE OF ds SKIP LOCKED
+            )
+            UPDATE digest_settings ds
+            SET last_sent_at = NOW()
+            FROM due
+            WHERE ds.user_id = due.user_id
+            RETURNING ds.user_id, ds.frequency as "frequency: DigestFrequency",
+                ds.unsubscribe_token, due.last_sent_at as window_start
+            "#,
+            BATCH_SIZE
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| DueDigest {
+                user_id: row.user_id,
+                frequency: row.frequency,
+                unsubscribe_token: row.unsubscribe_token,
+                window_start: row.window_start,
+            })
+            .collect())
+    }
+
+    async fn release(&self, digest: &DueDigest) -> Result<(), Box<dyn Error + Send + Sync>> {
+        sqlx::query!(
+            "UPDATE digest_settings SET last_sent_at = $2 WHERE user_id = $1",
+            digest.user_id,
+            digest.window_start
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_recipient(&self, user_id: Uuid) -> Result<Recipient, Box<dyn Error + Send + Sync>> {
+        let user = sqlx::query!(
+            "SELECT email, username, display_name FROM users WHERE id = $1",
+            user_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or("User not found")?;
+
+        Ok(Recipient {
+            email: user.email,
+            name: user.display_name.unwrap_or(user.username),
+        })
+    }
+
+    // New articles since `since` from the user's feed, best first
+    pub async fn compile(&self, user_id: Uuid, since: DateTime<Utc>) -> Result<Vec<DigestArticle>, Box<dyn Error + Send + Sync>> {
+        let mut articles = ArticleService::new(self.db.clone(), self.config)
+            .feed_articles(user_id, Some(since), MAX_CANDIDATES, 0)
+            .await?;
+        articles.sort_by(|a, b| b.claps_count.cmp(&a.claps_count).then(b.published_at.cmp(&a.published_at)));
+        articles.truncate(MAX_ARTICLES);
+
+        // Who wrote each article and why it's in this user's feed
+        let ids: Vec<Uuid> = articles.iter().map(|article| article.id).collect();
+        let details = sqlx::query!(
+            r#"
+            SELECT
+                a.id,
+                u.username, u.display_name,
+                p.name as "publication_name?",
+                EXISTS (
+                    SELECT 1 FROM user_follows uf
+                    WHERE uf.follower_id = $1 AND uf.following_id = a.author_id
+                ) as "from_author!",
+                EXISTS (
+                    SELECT 1 FROM publication_follows pf
+                    WHERE pf.user_id = $1 AND pf.publication_id = a.publication_id
+                ) as "from_publication!",
+                COALESCE(
+                    (
+                        SELECT tf.tag FROM tag_follows tf
+                        WHERE tf.user_id = $1 AND tf.tag = ANY(a.tags)
+                        ORDER BY tf.tag
+                        LIMIT 1
+                    ),
+                    (
+                        SELECT cf.category FROM category_follows cf
+                        WHERE cf.user_id = $1 AND cf.category = ANY(a.categories)
+                        ORDER BY cf.category
+                        LIMIT 1
+                    )
+                ) as matched_topic
+            FROM articles a
+            JOIN users u ON u.id = a.author_id
+            LEFT JOIN publications p ON p.id = a.publication_id
+            WHERE a.id = ANY($2)
+            "#,
+            user_id,
+            &ids
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut details: HashMap<Uuid, _> = details.into_iter().map(|row| (row.id, row)).collect();
+        let frontend_url = &self.config.frontend_url;
+
+        Ok(articles
+            .into_iter()
+            .filter_map(|article| {
+                let row = details.remove(&article.id)?;
+
+                // Prefer the most personal reason when several apply
+                let source = if row.from_author {
+                    DigestSource::Author
+                } else if row.from_publication {
+                    DigestSource::Publication
+                } else {
+                    DigestSource::Topic
+                };
+
+                Some(DigestArticle {
+                    id: article.id,
+                    title: article.title,
+                    subtitle: article.subtitle,
+                    excerpt: article.excerpt,
+                    url: format!("{}/article/{}", frontend_url, article.slug),
+                    author_name: row.display_name.unwrap_or(row.username),
+                    publication_name: row.publication_name,
+                    matched_topic: row.matched_topic,
+                    source,
+                    reading_time_minutes: article.reading_time_minutes,
+                    published_at: article.published_at?,
+                })
+            })
+            .collect())
+    }
+
+    fn render(
+        &self,
+        recipient: &Recipient,
+        frequency: DigestFrequency,
+        unsubscribe_token: &str,
+        articles: &[DigestArticle],
+    ) -> Result<EmailMessage, Box<dyn Error + Send + Sync>> {
+        let period_label = match frequency {
+            DigestFrequency::Daily => "daily",
+            _ => "weekly",
+        };
+
+        let sections: Vec<DigestSection> = [
+            (DigestSource::Author, "From writers you follow"),
+            (DigestSource::Publication, "From publications you follow"),
+            (DigestSource::Topic, "In topics you follow"),
+        ]
+        .into_iter()
+        .map(|(source, heading)| DigestSection {
+            heading,
+            articles: articles.iter().filter(|a| a.source == source).collect(),
+        })
+        .filter(|section| !section.articles.is_empty())
+        .collect();
+
+        let subject = match articles.first() {
+            Some(top) if articles.len() > 1 => {
+                format!("{} and {} more new stories for you", top.title, articles.len() - 1)
+            }
+            Some(top) => format!("{} - new on FastBlog", top.title),
+            None => "Your FastBlog digest".to_string(),
+        };
+
+        let settings_url = settings_url(self.config);
+        let unsubscribe_url = format!(
+            "{}/api/v1/digest/unsubscribe?token={}",
+            self.config.api_url,
+            unsubscribe_token
+        );
+
+        let html_body = DigestHtmlTemplate {
+            subject: &subject,
+            recipient_name: &recipient.name,
+            period_label,
+            sections: &sections,
+            settings_url: &settings_url,
+            unsubscribe_url: &unsubscribe_url,
+        }
+        .render()?;
+
+        let text_body = DigestTextTemplate {
+            recipient_name: &recipient.name,
+            period_label,
+            sections: &sections,
+            settings_url: &settings_url,
+            unsubscribe_url: &unsubscribe_url,
+        }
+        .render()?;
+
+        Ok(EmailMessage {
+            to: recipient.email.clone(),
+            subject,
+            html_body,
+            text_body,
+            headers: vec![
+                ("List-Unsubscribe".to_string(), format!("<{}>", unsubscribe_url)),
+                ("List-Unsubscribe-Post".to_string(), "List-Unsubscribe=One-Click".to_string()),
+            ],
+        })
+    }
+}
+
+// Frontend page where users manage their email settings
+pub fn settings_url(config: &Config) -> String {
+    format!("{}/settings/notifications", config.frontend_url)
+}
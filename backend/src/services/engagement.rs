@@ -1,20 +1,24 @@
 use sqlx::PgPool;
+use std::sync::Arc;
 use uuid::Uuid;
-use std::error::Error;
 use chrono::Utc;
 use ammonia::clean;
 
+use crate::error::AppError;
 use crate::models::engagement::{
     ClapRequest, CreateCommentRequest, Comment, CommentResponse, CommentAuthor, CommentInteractions
 };
+use crate::services::notification::NotificationService;
+use crate::services::realtime::{RealtimeEvent, RealtimeHub};
 
 pub struct EngagementService {
     db: PgPool,
+    realtime: Arc<RealtimeHub>,
 }
 
 impl EngagementService {
-    pub fn new(db: PgPool) -> Self {
-        Self { db }
+    pub fn new(db: PgPool, realtime: Arc<RealtimeHub>) -> Self {
+        Self { db, realtime }
     }
 
     // Clap/unclap an article (toggle behavior - max 1 clap per user)
@@ -23,7 +27,7 @@ impl EngagementService {
         article_id: Uuid,
         user_id: Uuid,
         request: ClapRequest,
-    ) -> Result<(i64, i32, bool), Box<dyn Error + Send + Sync>> {
+    ) -> Result<(i64, i32, bool), AppError> {
         // Check if user already clapped
         let existing = sqlx::query!(
             "SELECT clap_count FROM claps WHERE user_id = $1 AND article_id = $2",
@@ -72,24 +76,36 @@ impl EngagementService {
         .execute(&self.db)
         .await?;
 
+        self.broadcast_article_counts(article_id).await;
+
+        if is_clapped {
+            let notification_service = NotificationService::new(self.db.clone(), self.realtime.clone());
+            if let Err(e) = notification_service.notify_clap(article_id, user_id).await {
+                tracing::warn!("Failed to create clap notification: {}", e);
+            }
+        }
+
         Ok((total_claps, new_clap_count, is_clapped))
     }
 
-    // Get user's clap status for an article (returns 1 if clapped, 0 if not)
-    pub async fn get_user_clap_count(
-        &self,
-        article_id: Uuid,
-        user_id: Uuid,
-    ) -> Result<i32, Box<dyn Error + Send + Sync>> {
-        let result = sqlx::query!(
-            "SELECT clap_count FROM claps WHERE user_id = $1 AND article_id = $2",
-            user_id,
+    // Push live clap/comment counts to readers viewing the article
+    async fn broadcast_article_counts(&self, article_id: Uuid) {
+        let counts = sqlx::query!(
+            "SELECT claps_count, comments_count FROM articles WHERE id = $1",
             article_id
         )
         .fetch_optional(&self.db)
-        .await?;
-
-        Ok(if result.is_some() { 1 } else { 0 })
+        .await;
+
+        match counts {
+            Ok(Some(counts)) => self.realtime.publish(RealtimeEvent::ArticleCounts {
+                article_id,
+                claps_count: counts.claps_count,
+                comments_count: counts.comments_count,
+            }),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to load article counts for broadcast: {}", e),
+        }
     }
 
     // Get comments for an article
@@ -97,7 +113,7 @@ impl EngagementService {
         &self,
         article_id: Uuid,
         user_id: Option<Uuid>,
-    ) -> Result<Vec<CommentResponse>, Box<dyn Error + Send + Sync>> {
+    ) -> Result<Vec<CommentResponse>, AppError> {
         let comments = sqlx::query_as!(
             Comment,
             r#"
@@ -122,7 +138,7 @@ impl EngagementService {
             )
             .fetch_optional(&self.db)
             .await?
-            .ok_or_else(|| format!("Author not found for comment {}", comment.id))?;
+            .ok_or_else(|| AppError::internal(format!("Author not found for comment {}", comment.id)))?;
 
             // Get user interactions if logged in
             let user_interactions = if let Some(uid) = user_id {
@@ -176,7 +192,7 @@ impl EngagementService {
         &self,
         parent_id: Uuid,
         user_id: Option<Uuid>,
-    ) -> Result<Vec<CommentResponse>, Box<dyn Error + Send + Sync>> {
+    ) -> Result<Vec<CommentResponse>, AppError> {
         let replies = sqlx::query_as!(
             Comment,
             r#"
@@ -200,7 +216,7 @@ impl EngagementService {
             )
             .fetch_optional(&self.db)
             .await?
-            .ok_or_else(|| format!("Author not found for reply {}", reply.id))?;
+            .ok_or_else(|| AppError::internal(format!("Author not found for reply {}", reply.id)))?;
 
             let user_interactions = if let Some(uid) = user_id {
                 Some(CommentInteractions {
@@ -242,7 +258,7 @@ impl EngagementService {
         article_id: Uuid,
         user_id: Uuid,
         request: CreateCommentRequest,
-    ) -> Result<CommentResponse, Box<dyn Error + Send + Sync>> {
+    ) -> Result<CommentResponse, AppError> {
         // Check if article exists and get author_id
         let article = sqlx::query!(
             "SELECT author_id FROM articles WHERE id = $1",
@@ -250,7 +266,7 @@ impl EngagementService {
         )
         .fetch_optional(&self.db)
         .await?
-        .ok_or_else(|| "Article not found".to_string())?;
+        .ok_or_else(|| AppError::NotFound("Article not found".to_string()))?;
 
         // Sanitize HTML
         let content_html = clean(&request.content);
@@ -314,6 +330,17 @@ impl EngagementService {
         .execute(&self.db)
         .await?;
 
+        self.broadcast_article_counts(article_id).await;
+
+        // Notify article author, parent comment author and mentioned users
+        let notification_service = NotificationService::new(self.db.clone(), self.realtime.clone());
+        if let Err(e) = notification_service
+            .notify_comment(article_id, comment_id, user_id, request.parent_id, &request.content)
+            .await
+        {
+            tracing::warn!("Failed to create comment notifications: {}", e);
+        }
+
         // Get author info
         let author = sqlx::query!(
             "SELECT id, username, display_name, avatar_url, is_verified FROM users WHERE id = $1",
@@ -321,7 +348,7 @@ impl EngagementService {
         )
         .fetch_optional(&self.db)
         .await?
-        .ok_or_else(|| "User not found".to_string())?;
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
         Ok(CommentResponse {
             id: comment_id,
@@ -0,0 +1,904 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::io::{Cursor, Write};
+use std::time::Duration;
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::error::AppError;
+use crate::models::{
+    export::{ExportKind, ExportListResponse, ExportResponse, ExportStatus},
+    ArticleStatus,
+};
+use crate::services::{
+    jobs::{Job, JobService},
+    storage::{Storage, PRIVATE_PREFIX},
+};
+use crate::util::decode_entities;
+
+// Finished archives are kept this long
+const EXPORT_RETENTION_DAYS: i32 = 7;
+// Lifetime of the signed download link in a response
+const DOWNLOAD_URL_TTL: Duration = Duration::from_secs(60 * 60);
+
+lazy_static! {
+    static ref IMG_SRC_REGEX: Regex = Regex::new(r#"(?i)<img\b[^>]*?\ssrc\s*=\s*"([^"]+)""#).unwrap();
+    // Sanitized HTML: tags, comments and the text between them
+    static ref HTML_TOKEN_REGEX: Regex =
+        Regex::new(r#"(?s)<!--.*?-->|<(/?)([a-zA-Z][a-zA-Z0-9]*)((?:[^>"']|"[^"]*"|'[^']*')*)>|[^<]+|<"#).unwrap();
+    static ref ATTR_REGEX: Regex = Regex::new(r#"([a-zA-Z_:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    static ref WHITESPACE_REGEX: Regex = Regex::new(r"\s+").unwrap();
+}
+
+pub struct ExportService {
+    db: PgPool,
+}
+
+impl ExportService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    // Queue an export; an unfinished export of the same kind is returned instead of a new one
+    pub async fn create_export(
+        &self,
+        storage: &dyn Storage,
+        user_id: Uuid,
+        kind: ExportKind,
+    ) -> Result<ExportResponse, AppError> {
+        let mut tx = self.db.begin().await?;
+
+        // Serializes concurrent requests of one user
+        sqlx::query!("SELECT id FROM users WHERE id = $1 FOR UPDATE", user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let pending = sqlx::query_scalar!(
+            "SELECT id FROM exports WHERE user_id = $1 AND kind = $2 AND status IN ('pending', 'running')",
+            user_id,
+            kind as ExportKind
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let export_id = match pending {
+            Some(export_id) => export_id,
+            None => {
+                let export_id = sqlx::query_scalar!(
+                    "INSERT INTO exports (user_id, kind) VALUES ($1, $2) RETURNING id",
+                    user_id,
+                    kind as ExportKind
+                )
+                .fetch_one(&mut *tx)
+                .await?;
+                JobService::enqueue(&mut *tx, &Job::ExportData { export_id }, Utc::now()).await?;
+                export_id
+            }
+        };
+
+        tx.commit().await?;
+
+        self.get_export(storage, user_id, export_id).await
+    }
+
+    pub async fn list_exports(
+        &self,
+        storage: &dyn Storage,
+        user_id: Uuid,
+    ) -> Result<ExportListResponse, AppError> {
+        let exports = self.fetch(storage, user_id, None).await?;
+        Ok(ExportListResponse { exports })
+    }
+
+    pub async fn get_export(
+        &self,
+        storage: &dyn Storage,
+        user_id: Uuid,
+        export_id: Uuid,
+    ) -> Result<ExportResponse, AppError> {
+        self.fetch(storage, user_id, Some(export_id))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::NotFound("Export not found".to_string()))
+    }
+
+    // Run by the export_data job
+    pub async fn run_export(&self, storage: &dyn Storage, export_id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let Some(export) = sqlx::query!(
+            r#"
+            SELECT user_id, kind as "kind: ExportKind", status as "status: ExportStatus"
+            FROM exports WHERE id = $1
+            "#,
+            export_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        else {
+            return Ok(());
+        };
+
+        if matches!(export.status, ExportStatus::Completed | ExportStatus::Failed) {
+            return Ok(());
+        }
+
+        sqlx::query!(
+            "UPDATE exports SET status = 'running', started_at = NOW(), updated_at = NOW() WHERE id = $1",
+            export_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        // A failed export is reported rather than retried; the user can request another
+        if let Err(e) = self.build_and_store(storage, export_id, export.user_id, export.kind).await {
+            tracing::error!("Export {} failed: {}", export_id, e);
+            sqlx::query!(
+                r#"
+                UPDATE exports SET status = 'failed', error = $2, finished_at = NOW(), updated_at = NOW()
+                WHERE id = $1
+                "#,
+                export_id,
+                "The export could not be created. Please try again later."
+            )
+            .execute(&self.db)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn build_and_store(
+        &self,
+        storage: &dyn Storage,
+        export_id: Uuid,
+        user_id: Uuid,
+        kind: ExportKind,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (data, extension) = match kind {
+            ExportKind::Articles => (self.build_articles_archive(storage, user_id).await?, "zip"),
+            ExportKind::Account => (self.build_account_data(user_id).await?, "json"),
+        };
+
+        let file_key = format!("{}exports/{}.{}", PRIVATE_PREFIX, export_id, extension);
+        let file_size = data.len() as i64;
+        storage.put(&file_key, data).await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE exports
+            SET status = 'completed', file_key = $2, file_size = $3, finished_at = NOW(),
+                expires_at = NOW() + make_interval(days => $4), updated_at = NOW()
+            WHERE id = $1
+            "#,
+            export_id,
+            file_key,
+            file_size,
+            EXPORT_RETENTION_DAYS
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    // Delete archives past their expiry; run by the prune_exports job
+    pub async fn prune_expired(&self, storage: &dyn Storage) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        let expired = sqlx::query!(
+            r#"SELECT id, file_key as "file_key!" FROM exports WHERE file_key IS NOT NULL AND expires_at < NOW()"#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        for export in &expired {
+            storage.delete(&export.file_key).await?;
+            sqlx::query!(
+                "UPDATE exports SET file_key = NULL, updated_at = NOW() WHERE id = $1",
+                export.id
+            )
+            .execute(&self.db)
+            .await?;
+        }
+
+        Ok(expired.len() as u64)
+    }
+
+    // `{slug}.md` per article with YAML front matter, plus the images it uses under `images/`
+    async fn build_articles_archive(
+        &self,
+        storage: &dyn Storage,
+        user_id: Uuid,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let articles = sqlx::query!(
+            r#"
+            SELECT id, title, subtitle, slug, status as "status: ArticleStatus", tags, categories,
+                excerpt, content_html, featured_image_url, canonical_url,
+                created_at, updated_at, published_at, scheduled_at
+            FROM articles
+            WHERE author_id = $1
+            ORDER BY created_at
+            "#,
+            user_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+        // Image URL -> path inside the archive, None when it isn't one of our files
+        let mut images: HashMap<String, Option<String>> = HashMap::new();
+        let mut file_names = HashSet::new();
+
+        for article in articles {
+            let sources = IMG_SRC_REGEX
+                .captures_iter(&article.content_html)
+                .map(|captures| decode_entities(&captures[1]))
+                .chain(article.featured_image_url.clone());
+            for source in sources {
+                if images.contains_key(&source) {
+                    continue;
+                }
+                let path = match storage.key_from_url(&source) {
+                    Some(key) => match storage.get(&key).await? {
+                        Some(data) => {
+                            let name = key.rsplit('/').next().unwrap_or(&key);
+                            let path = format!("images/{}", name);
+                            files.push((path.clone(), data));
+                            Some(path)
+                        }
+                        None => None,
+                    },
+                    None => None,
+                };
+                images.insert(source, path);
+            }
+
+            let local_path = |url: &str| images.get(url).cloned().flatten().unwrap_or_else(|| url.to_string());
+
+            let mut front_matter = vec![
+                ("title", yaml_string(&article.title)),
+                ("slug", yaml_string(&article.slug)),
+                ("status", yaml_string(&format!("{:?}", article.status).to_lowercase())),
+                ("tags", yaml_list(&article.tags)),
+                ("categories", yaml_list(&article.categories.unwrap_or_default())),
+                ("created_at", yaml_date(article.created_at)),
+                ("updated_at", yaml_date(article.updated_at)),
+            ];
+            if let Some(subtitle) = &article.subtitle {
+                front_matter.insert(1, ("subtitle", yaml_string(subtitle)));
+            }
+            if let Some(published_at) = article.published_at {
+                front_matter.push(("published_at", yaml_date(published_at)));
+            }
+            if let Some(scheduled_at) = article.scheduled_at {
+                front_matter.push(("scheduled_at", yaml_date(scheduled_at)));
+            }
+            if let Some(excerpt) = &article.excerpt {
+                front_matter.push(("excerpt", yaml_string(excerpt)));
+            }
+            if let Some(url) = &article.featured_image_url {
+                front_matter.push(("featured_image", yaml_string(&local_path(url))));
+            }
+            if let Some(url) = &article.canonical_url {
+                front_matter.push(("canonical_url", yaml_string(url)));
+            }
+
+            let mut markdown = String::from("---\n");
+            for (key, value) in front_matter {
+                markdown.push_str(&format!("{}: {}\n", key, value));
+            }
+            markdown.push_str("---\n\n");
+            markdown.push_str(&html_to_markdown(&article.content_html, &local_path));
+
+            // Slugs are unique, but keep file names unique even if one was reused
+            let base = if article.slug.is_empty() { article.id.to_string() } else { article.slug.clone() };
+            let mut file_name = format!("{}.md", base);
+            let mut suffix = 2;
+            while !file_names.insert(file_name.clone()) {
+                file_name = format!("{}-{}.md", base, suffix);
+                suffix += 1;
+            }
+            files.push((file_name, markdown.into_bytes()));
+        }
+
+        let archive = tokio::task::spawn_blocking(move || build_zip(files)).await??;
+        Ok(archive)
+    }
+
+    // Everything stored about the user, one section per kind of data
+    async fn build_account_data(&self, user_id: Uuid) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let data = sqlx::query_scalar!(
+            r#"
+            SELECT json_build_object(
+                'exported_at', NOW(),
+                'profile', (
+                    SELECT row_to_json(p) FROM (
+                        SELECT id, email, username, display_name, bio, avatar_url, user_type, is_verified,
+                            membership_expires_at, followers_count, following_count, articles_count,
+                            total_claps_received, created_at, updated_at
+                        FROM users WHERE id = $1
+                    ) p
+                ),
+                'following', (
+                    SELECT COALESCE(json_agg(f ORDER BY f.followed_at), '[]') FROM (
+                        SELECT u.id as user_id, u.username, uf.created_at as followed_at
+                        FROM user_follows uf JOIN users u ON u.id = uf.following_id
+                        WHERE uf.follower_id = $1
+                    ) f
+                ),
+                'followers', (
+                    SELECT COALESCE(json_agg(f ORDER BY f.followed_at), '[]') FROM (
+                        SELECT u.id as user_id, u.username, uf.created_at as followed_at
+                        FROM user_follows uf JOIN users u ON u.id = uf.follower_id
+                        WHERE uf.following_id = $1
+                    ) f
+                ),
+                'followed_tags', (
+                    SELECT COALESCE(json_agg(t ORDER BY t.followed_at), '[]') FROM (
+                        SELECT tag, created_at as followed_at FROM tag_follows WHERE user_id = $1
+                    ) t
+                ),
+                'followed_categories', (
+                    SELECT COALESCE(json_agg(c ORDER BY c.followed_at), '[]') FROM (
+                        SELECT category, created_at as followed_at FROM category_follows WHERE user_id = $1
+                    ) c
+                ),
+                'followed_publications', (
+                    SELECT COALESCE(json_agg(p ORDER BY p.followed_at), '[]') FROM (
+                        SELECT p.id as publication_id, p.name, pf.created_at as followed_at
+                        FROM publication_follows pf JOIN publications p ON p.id = pf.publication_id
+                        WHERE pf.user_id = $1
+                    ) p
+                ),
+                'publication_memberships', (
+                    SELECT COALESCE(json_agg(m ORDER BY m.joined_at), '[]') FROM (
+                        SELECT p.id as publication_id, p.name, pm.role, pm.is_active, pm.joined_at
+                        FROM publication_members pm JOIN publications p ON p.id = pm.publication_id
+                        WHERE pm.user_id = $1
+                    ) m
+                ),
+                'articles', (
+                    SELECT COALESCE(json_agg(a ORDER BY a.created_at), '[]') FROM (
+                        SELECT id, title, subtitle, slug, status, tags, categories, canonical_url,
+                            claps_count, comments_count, views_count, reads_count,
+                            created_at, updated_at, published_at, scheduled_at
+                        FROM articles WHERE author_id = $1
+                    ) a
+                ),
+                'claps', (
+                    SELECT COALESCE(json_agg(c ORDER BY c.created_at), '[]') FROM (
+                        SELECT c.article_id, a.title as article_title, c.clap_count, c.created_at, c.updated_at
+                        FROM claps c JOIN articles a ON a.id = c.article_id
+                        WHERE c.user_id = $1
+                    ) c
+                ),
+                'comments', (
+                    SELECT COALESCE(json_agg(c ORDER BY c.created_at), '[]') FROM (
+                        SELECT id, article_id, parent_id, content, claps_count, created_at, updated_at
+                        FROM comments WHERE user_id = $1
+                    ) c
+                ),
+                'bookmarks', (
+                    SELECT COALESCE(json_agg(b ORDER BY b.created_at), '[]') FROM (
+                        SELECT b.article_id, a.title as article_title, b.created_at
+                        FROM bookmarks b JOIN articles a ON a.id = b.article_id
+                        WHERE b.user_id = $1
+                    ) b
+                ),
+                'highlights', (
+                    SELECT COALESCE(json_agg(h ORDER BY h.created_at), '[]') FROM (
+                        SELECT id, article_id, selected_text, note, start_position, end_position, created_at, updated_at
+                        FROM highlights WHERE user_id = $1
+                    ) h
+                ),
+                'reading_lists', (
+                    SELECT COALESCE(json_agg(l ORDER BY l.created_at), '[]') FROM (
+                        SELECT rl.id, rl.name, rl.description, rl.is_public, rl.created_at, rl.updated_at,
+                            (
+                                SELECT COALESCE(json_agg(json_build_object(
+                                    'article_id', rla.article_id, 'title', a.title, 'added_at', rla.added_at
+                                ) ORDER BY rla.added_at), '[]')
+                                FROM reading_list_articles rla JOIN articles a ON a.id = rla.article_id
+                                WHERE rla.reading_list_id = rl.id
+                            ) as articles
+                        FROM reading_lists rl WHERE rl.user_id = $1
+                    ) l
+                ),
+                'views', (
+                    SELECT COALESCE(json_agg(v ORDER BY v.created_at), '[]') FROM (
+                        SELECT article_id, host(ip_address) as ip_address, user_agent, reading_time_seconds,
+                            scroll_percentage, created_at
+                        FROM article_views WHERE user_id = $1
+                    ) v
+                ),
+                'notifications', (
+                    SELECT COALESCE(json_agg(n ORDER BY n.created_at), '[]') FROM (
+                        SELECT id, notification_type, actor_id, article_id, comment_id, publication_id,
+                            aggregate_count, is_read, read_at, created_at
+                        FROM notifications WHERE user_id = $1
+                    ) n
+                ),
+                'notification_preferences', (
+                    SELECT COALESCE(json_agg(n), '[]') FROM (
+                        SELECT notification_type, in_app_enabled, email_enabled, updated_at
+                        FROM notification_preferences WHERE user_id = $1
+                    ) n
+                ),
+                'digest_settings', (
+                    SELECT row_to_json(d) FROM (
+                        SELECT frequency, last_sent_at, created_at, updated_at FROM digest_settings WHERE user_id = $1
+                    ) d
+                ),
+                'newsletter_subscriptions', (
+                    SELECT COALESCE(json_agg(s ORDER BY s.created_at), '[]') FROM (
+                        SELECT author_id, publication_id, email, status, confirmed_at, unsubscribed_at, created_at
+                        FROM newsletter_subscribers
+                        WHERE user_id = $1 OR email = (SELECT email FROM users WHERE id = $1)
+                    ) s
+                ),
+                'media', (
+                    SELECT COALESCE(json_agg(m ORDER BY m.created_at), '[]') FROM (
+                        SELECT id, url, width, height, size_bytes, alt_text, caption, is_private, created_at
+                        FROM media WHERE user_id = $1
+                    ) m
+                ),
+                'imports', (
+                    SELECT COALESCE(json_agg(i ORDER BY i.created_at), '[]') FROM (
+                        SELECT id, source, status, file_name, imported_count, created_at
+                        FROM imports WHERE user_id = $1
+                    ) i
+                )
+            ) as "data!"
+            "#,
+            user_id
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(serde_json::to_vec_pretty(&data)?)
+    }
+
+    async fn fetch(
+        &self,
+        storage: &dyn Storage,
+        user_id: Uuid,
+        export_id: Option<Uuid>,
+    ) -> Result<Vec<ExportResponse>, Box<dyn Error + Send + Sync>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, kind as "kind: ExportKind", status as "status: ExportStatus", file_key, file_size, error,
+                started_at, finished_at, expires_at, created_at
+            FROM exports
+            WHERE user_id = $1 AND ($2::uuid IS NULL OR id = $2)
+            ORDER BY created_at DESC
+            LIMIT 20
+            "#,
+            user_id,
+            export_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ExportResponse {
+                id: row.id,
+                kind: row.kind,
+                status: row.status,
+                file_size: row.file_size,
+                download_url: row.file_key.map(|key| storage.signed_url(&key, DOWNLOAD_URL_TTL)),
+                error: row.error,
+                started_at: row.started_at,
+                finished_at: row.finished_at,
+                expires_at: row.expires_at,
+                created_at: row.created_at,
+            })
+            .collect())
+    }
+}
+
+fn build_zip(files: Vec<(String, Vec<u8>)>) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in files {
+        // Images are already compressed
+        let method = if name.ends_with(".md") { CompressionMethod::Deflated } else { CompressionMethod::Stored };
+        zip.start_file(name, SimpleFileOptions::default().compression_method(method))?;
+        zip.write_all(&data)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+// JSON strings are valid double-quoted YAML scalars
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn yaml_list(values: &[String]) -> String {
+    format!("[{}]", values.iter().map(|value| yaml_string(value)).collect::<Vec<_>>().join(", "))
+}
+
+fn yaml_date(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+enum Frame {
+    Root,
+    Block,
+    Heading(usize),
+    Quote,
+    Pre,
+    Code,
+    Wrap(&'static str),
+    Link(String),
+    List { ordered: bool, next: usize },
+    Item(String),
+    Caption,
+}
+
+impl Frame {
+    fn is_block(&self) -> bool {
+        !matches!(self, Frame::Code | Frame::Wrap(_) | Frame::Link(_))
+    }
+}
+
+// Converts sanitized article HTML (content_html) to CommonMark. Tables and
+// other markup without a Markdown equivalent are kept as inline HTML.
+pub fn html_to_markdown(html: &str, image_url: &dyn Fn(&str) -> String) -> String {
+    // (tag, frame, output so far)
+    let mut stack: Vec<(String, Frame, String)> = vec![(String::new(), Frame::Root, String::new())];
+    let mut position = 0;
+
+    for token in HTML_TOKEN_REGEX.captures_iter(html) {
+        let whole = token.get(0).unwrap();
+        if whole.start() < position {
+            continue;
+        }
+        let in_pre = stack.iter().any(|(_, frame, _)| matches!(frame, Frame::Pre));
+
+        let Some(tag) = token.get(2) else {
+            if whole.as_str().starts_with("<!--") {
+                continue;
+            }
+            let (_, frame, out) = stack.last_mut().unwrap();
+            let text = decode_entities(whole.as_str());
+            if in_pre {
+                out.push_str(&text);
+                continue;
+            }
+            let mut text = WHITESPACE_REGEX.replace_all(&text, " ").into_owned();
+            if !matches!(frame, Frame::Code) {
+                text = escape_markdown(&text);
+            }
+            if (frame.is_block() && out.is_empty()) || out.ends_with(char::is_whitespace) {
+                text = text.trim_start().to_string();
+            }
+            out.push_str(&text);
+            continue;
+        };
+
+        let name = tag.as_str().to_lowercase();
+        let attrs = attributes(token.get(3).map_or("", |attrs| attrs.as_str()));
+
+        if token.get(1).is_some_and(|slash| !slash.as_str().is_empty()) {
+            // Close the innermost element with this name and anything left open inside it
+            if let Some(index) = stack.iter().rposition(|(open, _, _)| *open == name) {
+                while stack.len() > index {
+                    close_frame(&mut stack);
+                }
+            }
+            continue;
+        }
+
+        let frame = match name.as_str() {
+            "p" | "div" | "section" | "article" | "figure" | "header" | "footer" | "aside" | "main" | "details"
+            | "summary" => Frame::Block,
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => Frame::Heading(name[1..].parse().unwrap_or(1)),
+            "blockquote" => Frame::Quote,
+            "pre" => Frame::Pre,
+            "code" if !in_pre => Frame::Code,
+            "strong" | "b" => Frame::Wrap("**"),
+            "em" | "i" => Frame::Wrap("*"),
+            "s" | "del" | "strike" => Frame::Wrap("~~"),
+            "a" => match attrs.get("href") {
+                Some(href) => Frame::Link(href.clone()),
+                None => continue,
+            },
+            "ul" => Frame::List { ordered: false, next: 1 },
+            "ol" => Frame::List {
+                ordered: true,
+                next: attrs.get("start").and_then(|start| start.parse().ok()).unwrap_or(1),
+            },
+            "li" => {
+                // An item starting while the previous one is still open ends it
+                let innermost = stack
+                    .iter()
+                    .rposition(|(_, frame, _)| matches!(frame, Frame::List { .. } | Frame::Item(_)));
+                if let Some(index) = innermost.filter(|&index| matches!(stack[index].1, Frame::Item(_))) {
+                    while stack.len() > index {
+                        close_frame(&mut stack);
+                    }
+                }
+                let marker = match stack.iter_mut().rev().find_map(|(_, frame, _)| match frame {
+                    Frame::List { ordered, next } => Some((ordered, next)),
+                    _ => None,
+                }) {
+                    Some((true, next)) => {
+                        *next += 1;
+                        format!("{}. ", *next - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                Frame::Item(marker)
+            }
+            "figcaption" => Frame::Caption,
+            "br" => {
+                stack.last_mut().unwrap().2.push_str("\\\n");
+                continue;
+            }
+            "hr" => {
+                push_block(&mut stack.last_mut().unwrap().2, "---");
+                continue;
+            }
+            "img" => {
+                let src = attrs.get("src").map(String::as_str).unwrap_or("");
+                if !src.is_empty() {
+                    let alt = attrs.get("alt").map(String::as_str).unwrap_or("");
+                    let image = format!("![{}]({})", escape_markdown(alt), link_destination(&image_url(src)));
+                    stack.last_mut().unwrap().2.push_str(&image);
+                }
+                continue;
+            }
+            "table" => {
+                // Copied as is, up to the matching end tag
+                let end = html[whole.start()..]
+                    .find("</table>")
+                    .map_or(html.len(), |end| whole.start() + end + "</table>".len());
+                push_block(&mut stack.last_mut().unwrap().2, &html[whole.start()..end]);
+                position = end;
+                continue;
+            }
+            _ => continue,
+        };
+        stack.push((name, frame, String::new()));
+    }
+
+    while stack.len() > 1 {
+        close_frame(&mut stack);
+    }
+    let mut markdown = stack.pop().unwrap().2.trim().to_string();
+    markdown.push('\n');
+    markdown
+}
+
+fn close_frame(stack: &mut Vec<(String, Frame, String)>) {
+    let (_, frame, content) = stack.pop().unwrap();
+    let (_, parent_frame, parent) = stack.last_mut().unwrap();
+
+    match frame {
+        Frame::Root => {}
+        Frame::Block => push_block(parent, content.trim()),
+        Frame::Heading(level) => {
+            let text = content.trim().replace("\\\n", " ").replace('\n', " ");
+            if !text.is_empty() {
+                push_block(parent, &format!("{} {}", "#".repeat(level), text));
+            }
+        }
+        Frame::Quote => {
+            let quoted: Vec<String> = content
+                .trim()
+                .lines()
+                .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                .collect();
+            push_block(parent, &quoted.join("\n"));
+        }
+        Frame::Pre => {
+            let code = content.trim_matches('\n');
+            let fence = if code.contains("```") { "~~~" } else { "```" };
+            push_block(parent, &format!("{}\n{}\n{}", fence, code, fence));
+        }
+        Frame::Code => {
+            // One backtick more than the longest run inside, padded if the code starts or ends with one
+            let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+            let ticks = "`".repeat(longest + 1);
+            let padding = if content.starts_with('`') || content.ends_with('`') { " " } else { "" };
+            parent.push_str(&format!("{}{}{}{}{}", ticks, padding, content, padding, ticks));
+        }
+        Frame::Wrap(marker) => {
+            let text = content.trim();
+            if text.is_empty() {
+                parent.push_str(&content);
+            } else {
+                // Emphasis can't start or end with whitespace
+                let leading = &content[..content.len() - content.trim_start().len()];
+                let trailing = &content[content.trim_end().len()..];
+                parent.push_str(&format!("{}{}{}{}{}", leading, marker, text, marker, trailing));
+            }
+        }
+        Frame::Link(href) => {
+            let text = content.trim();
+            if text.is_empty() {
+                return;
+            }
+            if href.is_empty() || href.starts_with("javascript:") {
+                parent.push_str(text);
+            } else {
+                parent.push_str(&format!("[{}]({})", text, link_destination(&href)));
+            }
+        }
+        Frame::List { .. } => {
+            if matches!(parent_frame, Frame::Item(_)) {
+                // Nested list: directly below the item's text
+                let trimmed = parent.trim_end().len();
+                parent.truncate(trimmed);
+                parent.push('\n');
+                parent.push_str(content.trim_end());
+            } else {
+                push_block(parent, content.trim_end());
+            }
+        }
+        Frame::Item(marker) => {
+            let indent = " ".repeat(marker.len());
+            let text = content
+                .trim()
+                .lines()
+                .enumerate()
+                .map(|(index, line)| {
+                    if index == 0 || line.is_empty() {
+                        line.to_string()
+                    } else {
+                        format!("{}{}", indent, line)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            if !parent.is_empty() && !parent.ends_with('\n') {
+                parent.push('\n');
+            }
+            parent.push_str(&format!("{}{}\n", marker, text));
+        }
+        Frame::Caption => {
+            let text = content.trim();
+            if !text.is_empty() {
+                push_block(parent, &format!("*{}*", text));
+            }
+        }
+    }
+}
+
+// Separate a block from what came before and after it with a blank line
+fn push_block(out: &mut String, block: &str) {
+    if block.is_empty() {
+        return;
+    }
+    let trimmed = out.trim_end().len();
+    out.truncate(trimmed);
+    if !out.is_empty() {
+        out.push_str("\n\n");
+    }
+    out.push_str(block);
+    out.push_str("\n\n");
+}
+
+fn attributes(attrs: &str) -> HashMap<String, String> {
+    ATTR_REGEX
+        .captures_iter(attrs)
+        .map(|captures| {
+            let value = captures.get(2).or(captures.get(3)).map_or("", |value| value.as_str());
+            (captures[1].to_lowercase(), decode_entities(value))
+        })
+        .collect()
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// Link targets with spaces or parentheses need angle brackets
+fn link_destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markdown(html: &str) -> String {
+        html_to_markdown(html, &|src| src.to_string())
+    }
+
+    #[test]
+    fn converts_blocks_and_inline_formatting() {
+        assert_eq!(
+            markdown("<h2>Title</h2><p>Some <strong>bold</strong>, <em>italic</em> and <del>gone</del> text.</p><hr><p>Next</p>"),
+            "## Title\n\nSome **bold**, *italic* and ~~gone~~ text.\n\n---\n\nNext\n"
+        );
+    }
+
+    #[test]
+    fn keeps_emphasis_markers_off_surrounding_whitespace() {
+        assert_eq!(markdown("<p>a<strong> b </strong>c</p>"), "a **b** c\n");
+        assert_eq!(markdown("<p>a<em> </em>b</p>"), "a b\n");
+    }
+
+    #[test]
+    fn converts_links_and_images() {
+        assert_eq!(
+            markdown(r#"<p><a href="https://example.com/a (1)">link</a> <a href="javascript:alert(1)">bad</a> <a>bare</a></p>"#),
+            "[link](<https://example.com/a (1)>) bad bare\n"
+        );
+        assert_eq!(
+            html_to_markdown(r#"<figure><img src="/uploads/a.png" alt="A *star*"><figcaption>Caption</figcaption></figure>"#, &|src| {
+                format!("images{}", src)
+            }),
+            "![A \\*star\\*](images/uploads/a.png)\n\n*Caption*\n"
+        );
+    }
+
+    #[test]
+    fn converts_nested_and_numbered_lists() {
+        assert_eq!(
+            markdown("<ul><li>One<ul><li>Nested</li></ul></li><li>Two</li></ul>"),
+            "- One\n  - Nested\n- Two\n"
+        );
+        assert_eq!(markdown(r#"<ol start="3"><li>Three</li><li>Four</li></ol>"#), "3. Three\n4. Four\n");
+        assert_eq!(markdown("<ol><li><p>One</p><p>More</p></li></ol>"), "1. One\n\n   More\n");
+    }
+
+    #[test]
+    fn converts_quotes_and_code() {
+        assert_eq!(markdown("<blockquote><p>One</p><p>Two</p></blockquote>"), "> One\n>\n> Two\n");
+        assert_eq!(
+            markdown("<pre><code>fn main() {\n    let x = a * b;\n}</code></pre>"),
+            "```\nfn main() {\n    let x = a * b;\n}\n```\n"
+        );
+        assert_eq!(markdown("<pre>```nested```</pre>"), "~~~\n```nested```\n~~~\n");
+        assert_eq!(markdown("<p>Use <code>a*b</code> or <code>`tick`</code></p>"), "Use `a*b` or `` `tick` ``\n");
+    }
+
+    #[test]
+    fn escapes_markdown_characters_and_decodes_entities() {
+        assert_eq!(markdown("<p>1 * 2 &lt; 3_4 [x] &amp; `y`</p>"), "1 \\* 2 \\< 3\\_4 \\[x\\] & \\`y\\`\n");
+    }
+
+    #[test]
+    fn keeps_tables_as_html() {
+        let table = "<table><tr><td><b>A</b></td></tr></table>";
+        assert_eq!(markdown(&format!("<p>Before</p>{}<p>After</p>", table)), format!("Before\n\n{}\n\nAfter\n", table));
+    }
+
+    #[test]
+    fn closes_unclosed_elements_at_the_end() {
+        assert_eq!(markdown("<p>Open <strong>bold <em>both"), "Open **bold *both***\n");
+        assert_eq!(markdown("<ul><li>One<li>Two"), "- One\n- Two\n");
+        assert_eq!(markdown("<blockquote><p>Quote"), "> Quote\n");
+    }
+
+    #[test]
+    fn end_tags_close_anything_left_open_inside() {
+        assert_eq!(markdown("<p>One <em>two</p><p>three</p>"), "One *two*\n\nthree\n");
+        // End tags that were never opened are ignored
+        assert_eq!(markdown("<p>One</em></div> two</p>"), "One two\n");
+    }
+
+    #[test]
+    fn copes_with_malformed_markup() {
+        assert_eq!(markdown("<p>a < b and c <> d</p>"), "a \\< b and c \\<> d\n");
+        assert_eq!(markdown("<p>Unclosed table</p><table><tr><td>x"), "Unclosed table\n\n<table><tr><td>x\n");
+        assert_eq!(markdown("<p>x<!-- comment --> y</p><unknown>z</unknown>"), "x y\n\nz\n");
+        assert_eq!(markdown("<p>Half a tag <a href=\"https://example.com\""), "Half a tag \\<a href=\"https://example.com\"\n");
+        assert_eq!(markdown(""), "\n");
+    }
+}
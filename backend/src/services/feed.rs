@@ -0,0 +1,334 @@
+use askama::Template;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::error::Error;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::util::{decode_entities, urlencode};
+
+// Items per feed
+const FEED_SIZE: i64 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeedFormat {
+    Rss,
+    Atom,
+    Json,
+}
+
+impl FeedFormat {
+    // Parse the trailing file name of a feed URL, e.g. `rss.xml`
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        match file_name {
+            "rss.xml" | "rss" => Some(FeedFormat::Rss),
+            "atom.xml" | "atom" => Some(FeedFormat::Atom),
+            "feed.json" | "json" => Some(FeedFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            FeedFormat::Rss => "rss.xml",
+            FeedFormat::Atom => "atom.xml",
+            FeedFormat::Json => "feed.json",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            FeedFormat::Rss => "application/rss+xml; charset=utf-8",
+            FeedFormat::Atom => "application/atom+xml; charset=utf-8",
+            FeedFormat::Json => "application/feed+json; charset=utf-8",
+        }
+    }
+}
+
+// What a feed covers
+#[derive(Debug, Clone)]
+pub enum FeedScope {
+    Site,
+    Author(String),
+    Tag(String),
+    Category(String),
+    Publication(String),
+}
+
+pub struct Feed {
+    pub title: String,
+    pub description: String,
+    pub home_page_url: String,
+    // Feed URL without the format file name
+    pub base_url: String,
+    pub updated: DateTime<Utc>,
+    pub items: Vec<FeedItem>,
+}
+
+pub struct FeedItem {
+    pub id: Uuid,
+    pub title: String,
+    pub url: String,
+    // Original URL of cross-posted articles
+    pub canonical_url: Option<String>,
+    pub summary: String,
+    // Full article, or the teaser for member-only articles
+    pub content_html: String,
+    pub author_name: String,
+    pub author_url: String,
+    pub tags: Vec<String>,
+    pub published_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Feed {
+    // Changes whenever an item is added, removed or edited
+    pub fn etag(&self, format: FeedFormat) -> String {
+        let mut hasher = DefaultHasher::new();
+        format.hash(&mut hasher);
+        self.base_url.hash(&mut hasher);
+        for item in &self.items {
+            item.id.hash(&mut hasher);
+            item.updated_at.timestamp_millis().hash(&mut hasher);
+        }
+        format!("W/\"{:016x}\"", hasher.finish())
+    }
+
+    pub fn render(&self, format: FeedFormat) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let feed_url = format!("{}/{}", self.base_url, format.file_name());
+        match format {
+            FeedFormat::Rss => Ok(RssTemplate { feed: self, feed_url: &feed_url }.render()?),
+            FeedFormat::Atom => Ok(AtomTemplate { feed: self, feed_url: &feed_url }.render()?),
+            FeedFormat::Json => Ok(serde_json::to_string_pretty(&self.to_json_feed(&feed_url))?),
+        }
+    }
+
+    // JSON Feed 1.1 (https://jsonfeed.org/version/1.1)
+    fn to_json_feed(&self, feed_url: &str) -> Value {
+        let items: Vec<Value> = self
+            .items
+            .iter()
+            .map(|item| {
+                let mut value = json!({
+                    "id": format!("urn:uuid:{}", item.id),
+                    "url": item.url,
+                    "title": item.title,
+                    "content_html": item.content_html,
+                    "summary": item.summary,
+                    "date_published": item.published_at.to_rfc3339(),
+                    "date_modified": item.updated_at.to_rfc3339(),
+                    "authors": [{ "name": item.author_name, "url": item.author_url }],
+                    "tags": item.tags
+                });
+                if let Some(canonical_url) = &item.canonical_url {
+                    value["external_url"] = json!(canonical_url);
+                }
+                value
+            })
+            .collect();
+
+        json!({
+            "version": "https://jsonfeed.org/version/1.1",
+            "title": self.title,
+            "description": self.description,
+            "home_page_url": self.home_page_url,
+            "feed_url": feed_url,
+            "language": "en",
+            "items": items
+        })
+    }
+}
+
+#[derive(Template)]
+#[template(path = "feeds/rss.xml", escape = "html")]
+struct RssTemplate<'a> {
+    feed: &'a Feed,
+    feed_url: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "feeds/atom.xml", escape = "html")]
+struct AtomTemplate<'a> {
+    feed: &'a Feed,
+    feed_url: &'a str,
+}
+
+pub struct FeedService<'a> {
+    db: PgPool,
+    config: &'a Config,
+}
+
+impl<'a> FeedService<'a> {
+    pub fn new(db: PgPool, config: &'a Config) -> Self {
+        Self { db, config }
+    }
+
+    // Latest published articles for a scope; None if the author/publication doesn't exist
+    pub async fn get_feed(&self, scope: &FeedScope) -> Result<Option<Feed>, Box<dyn Error + Send + Sync>> {
+        let frontend_url = &self.config.frontend_url;
+        let feeds_url = format!("{}/api/v1/feeds", self.config.api_url);
+
+        let mut author_id = None;
+        let mut publication_id = None;
+        let mut tag = None;
+        let mut category = None;
+
+        let (title, description, home_page_url, base_url) = match scope {
+            FeedScope::Site => (
+                "FastBlog".to_string(),
+                "Latest stories on FastBlog".to_string(),
+                frontend_url.clone(),
+                feeds_url,
+            ),
+            FeedScope::Author(username) => {
+                let Some(author) = sqlx::query!(
+                    "SELECT id, username, display_name, bio FROM users WHERE username = $1 AND is_banned = FALSE",
+                    username
+                )
+                .fetch_optional(&self.db)
+                .await?
+                else {
+                    return Ok(None);
+                };
+
+                author_id = Some(author.id);
+                let name = author.display_name.unwrap_or_else(|| author.username.clone());
+                (
+                    format!("{} on FastBlog", name),
+                    author.bio.unwrap_or_else(|| format!("Latest stories by {}", name)),
+                    format!("{}/@{}", frontend_url, author.username),
+                    format!("{}/users/{}", feeds_url, author.username),
+                )
+            }
+            FeedScope::Publication(slug) => {
+                let Some(publication) = sqlx::query!(
+                    "SELECT id, name, description, slug FROM publications WHERE slug = $1",
+                    slug
+                )
+                .fetch_optional(&self.db)
+                .await?
+                else {
+                    return Ok(None);
+                };
+
+                publication_id = Some(publication.id);
+                (
+                    publication.name.clone(),
+                    publication
+                        .description
+                        .unwrap_or_else(|| format!("Latest stories from {}", publication.name)),
+                    format!("{}/publication/{}", frontend_url, publication.slug),
+                    format!("{}/publications/{}", feeds_url, publication.slug),
+                )
+            }
+            FeedScope::Tag(name) => {
+                tag = Some(name.clone());
+                (
+                    format!("#{} on FastBlog", name),
+                    format!("Latest stories tagged {}", name),
+                    format!("{}/tag/{}", frontend_url, urlencode(name)),
+                    format!("{}/tags/{}", feeds_url, urlencode(name)),
+                )
+            }
+            FeedScope::Category(name) => {
+                category = Some(name.clone());
+                (
+                    format!("{} on FastBlog", name),
+                    format!("Latest stories in {}", name),
+                    format!("{}/category/{}", frontend_url, urlencode(name)),
+                    format!("{}/categories/{}", feeds_url, urlencode(name)),
+                )
+            }
+        };
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                a.id, a.title, a.subtitle, a.excerpt, a.content_html, a.slug, a.canonical_url, a.is_member_only,
+                COALESCE(a.tags, ARRAY[]::TEXT[]) as "tags!",
+                a.published_at as "published_at!", a.updated_at,
+                u.username, u.display_name
+            FROM articles a
+            JOIN users u ON u.id = a.author_id
+            WHERE a.status = 'published'
+                AND a.published_at IS NOT NULL
+                AND a.published_at <= NOW()
+                AND ($1::uuid IS NULL OR a.author_id = $1)
+                AND ($2::uuid IS NULL OR a.publication_id = $2)
+                AND ($3::text IS NULL OR $3 = ANY(a.tags))
+                AND ($4::text IS NULL OR $4 = ANY(a.categories))
+            ORDER BY a.published_at DESC
+            LIMIT $5
+            "#,
+            author_id,
+            publication_id,
+            tag,
+            category,
+            FEED_SIZE
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let items: Vec<FeedItem> = rows
+            .into_iter()
+            .map(|row| {
+                let url = format!("{}/article/{}", frontend_url, row.slug);
+                let summary = row
+                    .excerpt
+                    .or(row.subtitle)
+                    .map(|text| decode_entities(&text.split_whitespace().collect::<Vec<_>>().join(" ")))
+                    .unwrap_or_default();
+
+                // Member-only content stays behind the paywall
+                let content_html = if row.is_member_only {
+                    let teaser = escape_html(&summary);
+                    format!(
+                        "<p>{}</p><p><a href=\"{}\">Continue reading on FastBlog (members only)</a></p>",
+                        teaser, url
+                    )
+                } else {
+                    row.content_html
+                };
+
+                FeedItem {
+                    id: row.id,
+                    title: row.title,
+                    url,
+                    canonical_url: row.canonical_url,
+                    summary,
+                    content_html,
+                    author_name: row.display_name.unwrap_or_else(|| row.username.clone()),
+                    author_url: format!("{}/@{}", frontend_url, row.username),
+                    tags: row.tags,
+                    published_at: row.published_at,
+                    updated_at: row.updated_at.max(row.published_at),
+                }
+            })
+            .collect();
+
+        // An empty feed is as old as the epoch so conditional requests still work
+        let updated = items
+            .iter()
+            .map(|item| item.updated_at)
+            .max()
+            .unwrap_or(DateTime::<Utc>::UNIX_EPOCH);
+
+        Ok(Some(Feed {
+            title,
+            description,
+            home_page_url,
+            base_url,
+            updated,
+            items,
+        }))
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,306 @@
+use chrono::Utc;
+use image::{
+    codecs::{avif::AvifEncoder, jpeg::JpegEncoder, png::PngEncoder},
+    imageops::FilterType,
+    DynamicImage, GenericImageView, ImageFormat, ImageReader, Limits,
+};
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::Cursor;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::image::{ImageVariantResponse, UploadedImageResponse};
+use crate::services::storage::Storage;
+
+// Largest accepted upload; phone photos are routinely 5-15MB
+pub const MAX_UPLOAD_SIZE: usize = 20 * 1024 * 1024;
+// Decoded images above this are rejected (decompression bombs)
+const MAX_DIMENSION: u32 = 12_000;
+const MAX_DECODE_ALLOC: u64 = 512 * 1024 * 1024;
+
+const JPEG_QUALITY: u8 = 82;
+const AVIF_QUALITY: u8 = 60;
+// rav1e speed 1-10; higher is faster with slightly larger files
+const AVIF_SPEED: u8 = 8;
+
+// Blurhash components (x, y); 4x3 suits both square and landscape images
+const BLURHASH_COMPONENTS: (u32, u32) = (4, 3);
+
+// Sizes generated for one kind of upload
+pub struct ImageProfile {
+    // Widths in pixels, ascending; never upscaled
+    pub sizes: &'static [u32],
+    // Center-crop to a square first (avatars)
+    pub square: bool,
+}
+
+pub const AVATAR: ImageProfile = ImageProfile {
+    sizes: &[64, 128, 512],
+    square: true,
+};
+
+// Responsive widths for article images (srcset)
+pub const ARTICLE: ImageProfile = ImageProfile {
+    sizes: &[320, 640, 1024, 1600],
+    square: false,
+};
+
+pub struct ImageVariant {
+    // Requested size from the profile, used in file names
+    pub size: u32,
+    pub width: u32,
+    pub height: u32,
+    // JPEG, or PNG when the image has transparency
+    pub extension: &'static str,
+    pub data: Vec<u8>,
+}
+
+pub struct ProcessedImage {
+    // Dimensions after EXIF orientation and cropping
+    pub width: u32,
+    pub height: u32,
+    pub variants: Vec<ImageVariant>,
+    pub blurhash: String,
+    // "#rrggbb" average color, a placeholder for clients without blurhash
+    pub dominant_color: String,
+}
+
+// Decode by content, drop all metadata (EXIF, GPS, ICC) by re-encoding, and
+// build the profile's variants. CPU-bound: run it on a blocking thread.
+pub fn process(data: &[u8], profile: &ImageProfile) -> Result<ProcessedImage, AppError> {
+    let format = image::guess_format(data).map_err(|_| AppError::BadRequest("Unsupported image type".to_string()))?;
+    if !matches!(format, ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::Gif | ImageFormat::WebP) {
+        return Err(AppError::BadRequest(
+            "Unsupported image type. Allowed: JPEG, PNG, GIF, WebP".to_string(),
+        ));
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().map_err(invalid_image)?;
+    // Phone cameras store rotation in EXIF instead of rotating the pixels
+    let orientation = image::ImageDecoder::orientation(&mut decoder).map_err(invalid_image)?;
+    let mut source = DynamicImage::from_decoder(decoder).map_err(invalid_image)?;
+    source.apply_orientation(orientation);
+
+    if profile.square {
+        let (width, height) = source.dimensions();
+        let side = width.min(height);
+        source = source.crop_imm((width - side) / 2, (height - side) / 2, side, side);
+    }
+
+    let (width, height) = source.dimensions();
+    let has_alpha = source.color().has_alpha() && source.to_rgba8().pixels().any(|pixel| pixel[3] < 255);
+
+    // Largest first so each size is scaled down from the previous one
+    let mut variants = Vec::with_capacity(profile.sizes.len());
+    let mut current = source;
+    for &size in profile.sizes.iter().rev() {
+        if current.width() > size {
+            current = current.resize(size, u32::MAX, FilterType::Lanczos3);
+        }
+        variants.push(encode_variant(&current, size, has_alpha)?);
+    }
+    variants.reverse();
+
+    let (blurhash, dominant_color) = placeholder(&current)?;
+
+    Ok(ProcessedImage {
+        width,
+        height,
+        variants,
+        blurhash,
+        dominant_color,
+    })
+}
+
+fn encode_variant(image: &DynamicImage, size: u32, has_alpha: bool) -> Result<ImageVariant, Box<dyn Error + Send + Sync>> {
+    let converted = if has_alpha {
+        DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    };
+
+    let mut data = Vec::new();
+    let extension = if has_alpha {
+        converted.write_with_encoder(PngEncoder::new(&mut data))?;
+        "png"
+    } else {
+        converted.write_with_encoder(JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY))?;
+        "jpg"
+    };
+
+    Ok(ImageVariant {
+        size,
+        width: image.width(),
+        height: image.height(),
+        extension,
+        data,
+    })
+}
+
+fn placeholder(image: &DynamicImage) -> Result<(String, String), Box<dyn Error + Send + Sync>> {
+    let rgba = image.to_rgba8();
+    let (components_x, components_y) = BLURHASH_COMPONENTS;
+    let blurhash = blurhash::encode(components_x, components_y, rgba.width(), rgba.height(), rgba.as_raw())
+        .map_err(|e| format!("Failed to compute blurhash: {}", e))?;
+
+    let mut totals = [0u64; 3];
+    for pixel in rgba.pixels() {
+        for (total, channel) in totals.iter_mut().zip(pixel.0) {
+            *total += channel as u64;
+        }
+    }
+    let count = (rgba.width() as u64 * rgba.height() as u64).max(1);
+    let dominant_color = format!(
+        "#{:02x}{:02x}{:02x}",
+        totals[0] / count,
+        totals[1] / count,
+        totals[2] / count
+    );
+
+    Ok((blurhash, dominant_color))
+}
+
+fn invalid_image(e: image::ImageError) -> AppError {
+    match e {
+        image::ImageError::Limits(_) => AppError::BadRequest("Image dimensions are too large".to_string()),
+        e => AppError::BadRequest(format!("Invalid image file: {}", e)),
+    }
+}
+
+// Store every variant as `{prefix}/{id}_{size}.{ext}`.
+// Also returns the stored keys, e.g. for queueing AVIF encoding.
+pub async fn save(
+    storage: &dyn Storage,
+    processed: &ProcessedImage,
+    prefix: &str,
+    id: Uuid,
+) -> Result<(UploadedImageResponse, Vec<String>), Box<dyn Error + Send + Sync>> {
+    let mut variants = Vec::with_capacity(processed.variants.len());
+    let mut keys = Vec::with_capacity(processed.variants.len());
+    for variant in &processed.variants {
+        let key = format!("{}/{}_{}.{}", prefix, id, variant.size, variant.extension);
+
+        if let Err(e) = storage.put(&key, variant.data.clone()).await {
+            remove_variants(storage, &key).await;
+            return Err(e);
+        }
+
+        variants.push(ImageVariantResponse {
+            size: variant.size,
+            width: variant.width,
+            height: variant.height,
+            url: storage.public_url(&key),
+        });
+        keys.push(key);
+    }
+
+    let uploaded = UploadedImageResponse {
+        url: variants.last().map(|variant| variant.url.clone()).unwrap_or_default(),
+        width: processed.width,
+        height: processed.height,
+        blurhash: processed.blurhash.clone(),
+        dominant_color: processed.dominant_color.clone(),
+        variants,
+    };
+
+    Ok((uploaded, keys))
+}
+
+// Store an AVIF copy next to each variant (`x_512.jpg` -> `x_512.avif`).
+// AVIF encoding takes seconds per image, so this runs as a job on the media queue.
+pub async fn generate_avif(storage: &dyn Storage, keys: &[String]) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let mut written = 0;
+    for key in keys {
+        // Replaced or deleted in the meantime
+        let Some(data) = storage.get(key).await? else {
+            continue;
+        };
+
+        let avif = tokio::task::spawn_blocking(move || encode_avif(&data)).await??;
+        let stem = key.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(key);
+        storage.put(&format!("{}.avif", stem), avif).await?;
+        written += 1;
+    }
+
+    Ok(written)
+}
+
+fn encode_avif(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let image = image::load_from_memory(data)?;
+    let image = if image.color().has_alpha() {
+        DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    };
+
+    let mut avif = Vec::new();
+    image.write_with_encoder(AvifEncoder::new_with_speed_quality(&mut avif, AVIF_SPEED, AVIF_QUALITY))?;
+    Ok(avif)
+}
+
+// Upload id a stored file belongs to: `{id}_{size}.{ext}`, or `{id}.{ext}` for older uploads
+pub fn upload_id(file_name: &str) -> &str {
+    let stem = file_name.split('.').next().unwrap_or(file_name);
+    stem.split('_').next().unwrap_or(stem)
+}
+
+// Best-effort removal of all files of the upload `key` belongs to
+pub async fn remove_variants(storage: &dyn Storage, key: &str) {
+    let (prefix, file_name) = key.rsplit_once('/').unwrap_or(("", key));
+    let id = upload_id(file_name);
+    if id.is_empty() {
+        return;
+    }
+
+    let search = if prefix.is_empty() { id.to_string() } else { format!("{}/{}", prefix, id) };
+    let Ok(objects) = storage.list(&search).await else {
+        return;
+    };
+    for object in objects {
+        let (object_prefix, object_name) = object.key.rsplit_once('/').unwrap_or(("", &object.key));
+        if object_prefix == prefix && upload_id(object_name) == id {
+            let _ = storage.delete(&object.key).await;
+        }
+    }
+}
+
+// Delete files directly under `prefix` whose upload id isn't in `referenced`. Files younger
+// than `grace_period` are kept so an upload isn't removed before its row is saved.
+pub async fn remove_unreferenced(
+    storage: &dyn Storage,
+    prefix: &str,
+    referenced: &HashSet<String>,
+    grace_period: Duration,
+) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let cutoff = Utc::now() - grace_period;
+
+    let mut removed = 0;
+    for object in storage.list(&format!("{}/", prefix)).await? {
+        let file_name = &object.key[prefix.len() + 1..];
+        let recent = object.last_modified.is_none_or(|modified| modified > cutoff);
+
+        // Dotfiles such as .gitkeep are never uploads
+        if recent
+            || file_name.starts_with('.')
+            || file_name.contains('/')
+            || referenced.contains(upload_id(file_name))
+        {
+            continue;
+        }
+
+        storage.delete(&object.key).await?;
+        removed += 1;
+    }
+
+    Ok(removed)
+}
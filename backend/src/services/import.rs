@@ -0,0 +1,1329 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{header::LOCATION, Url};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::io::{Cursor, Read};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::models::{
+    import::{ImportItem, ImportItemStatus, ImportListResponse, ImportResponse, ImportSource, ImportStatus},
+    ArticleStatus, CreateArticleRequest,
+};
+use crate::services::{
+    article::ArticleService,
+    image::MAX_UPLOAD_SIZE,
+    jobs::{Job, JobService},
+    media::MediaService,
+    storage::{Storage, PRIVATE_PREFIX},
+};
+use crate::util::decode_entities;
+
+// Largest accepted export file
+pub const MAX_IMPORT_SIZE: usize = 100 * 1024 * 1024;
+// Larger files inside a Medium zip aren't posts
+const MAX_POST_FILE_SIZE: u64 = 10 * 1024 * 1024;
+const IMAGE_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_IMAGE_REDIRECTS: usize = 5;
+
+const MAX_TITLE_LENGTH: usize = 200;
+const MAX_SUBTITLE_LENGTH: usize = 300;
+const MAX_TAGS: usize = 10;
+const MAX_CATEGORIES: usize = 5;
+
+const WP_NAMESPACE: &str = "http://wordpress.org/export/";
+const CONTENT_NAMESPACE: &str = "http://purl.org/rss/1.0/modules/content/";
+
+lazy_static! {
+    static ref TAG_REGEX: Regex = Regex::new(r"<[^>]*>").unwrap();
+    static ref IMG_SRC_REGEX: Regex = Regex::new(r#"(?i)<img\b[^>]*?\ssrc\s*=\s*["']([^"']+)["']"#).unwrap();
+
+    // Medium export markup (one HTML file per post)
+    static ref MEDIUM_TITLE_REGEX: Regex = Regex::new(r#"(?s)<h1 class="p-name">(.*?)</h1>"#).unwrap();
+    static ref MEDIUM_SUBTITLE_REGEX: Regex =
+        Regex::new(r#"(?s)<section data-field="subtitle" class="p-summary">(.*?)</section>"#).unwrap();
+    static ref MEDIUM_BODY_REGEX: Regex =
+        Regex::new(r#"(?s)<section data-field="body" class="e-content">(.*)</section>\s*<footer>"#).unwrap();
+    static ref MEDIUM_PUBLISHED_REGEX: Regex = Regex::new(r#"<time class="dt-published" datetime="([^"]+)""#).unwrap();
+    static ref MEDIUM_CANONICAL_REGEX: Regex = Regex::new(r#"<a href="([^"]+)" class="p-canonical""#).unwrap();
+    // The body repeats the title and subtitle
+    static ref MEDIUM_HEADING_REGEX: Regex =
+        Regex::new(r#"(?s)<h3[^>]*graf--title[^>]*>.*?</h3>|<h4[^>]*graf--subtitle[^>]*>.*?</h4>"#).unwrap();
+
+    // WordPress classic editor content
+    static ref PARAGRAPH_BREAK_REGEX: Regex = Regex::new(r"\r?\n\s*\r?\n").unwrap();
+    static ref BLOCK_START_REGEX: Regex =
+        Regex::new(r"(?i)^<(h[1-6]|p|ul|ol|li|blockquote|pre|div|figure|table|hr|iframe)\b").unwrap();
+    static ref CAPTION_REGEX: Regex = Regex::new(r"(?s)\[caption[^\]]*\](.*?)\[/caption\]").unwrap();
+}
+
+// A post read from an export, before its images are rehosted
+#[derive(Debug)]
+pub struct ImportedPost {
+    pub source_id: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub html: String,
+    pub tags: Vec<String>,
+    pub categories: Vec<String>,
+    pub published: bool,
+    pub published_at: Option<DateTime<Utc>>,
+    pub canonical_url: Option<String>,
+    pub featured_image_url: Option<String>,
+}
+
+// Posts to import plus entries skipped while reading the export (pages, trash, ...)
+#[derive(Debug, Default)]
+pub struct ParsedExport {
+    pub posts: Vec<ImportedPost>,
+    pub skipped: Vec<ImportItem>,
+}
+
+enum PostOutcome {
+    Imported(Uuid),
+    Skipped(String),
+}
+
+pub struct ImportService<'a> {
+    db: PgPool,
+    config: &'a Config,
+}
+
+impl<'a> ImportService<'a> {
+    pub fn new(db: PgPool, config: &'a Config) -> Self {
+        Self { db, config }
+    }
+
+    // Store the export privately and queue the import job
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_import(
+        &self,
+        storage: &dyn Storage,
+        user_id: Uuid,
+        file_name: String,
+        data: Vec<u8>,
+        source: Option<ImportSource>,
+        site_url: Option<String>,
+        as_drafts: bool,
+    ) -> Result<ImportResponse, AppError> {
+        let source = source
+            .or_else(|| detect_source(&data))
+            .ok_or_else(|| {
+                AppError::BadRequest(
+                    "Unrecognized export format. Upload a Medium export zip, a WordPress WXR file or a Ghost JSON export"
+                        .to_string(),
+                )
+            })?;
+
+        let site_url = match site_url.as_deref().map(str::trim).filter(|url| !url.is_empty()) {
+            Some(url) => match Url::parse(url) {
+                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {
+                    Some(parsed.as_str().trim_end_matches('/').to_string())
+                }
+                _ => return Err(AppError::BadRequest("Invalid site URL".to_string())),
+            },
+            None => None,
+        };
+
+        let import_id = Uuid::new_v4();
+        let extension = match source {
+            ImportSource::Medium => "zip",
+            ImportSource::Wordpress => "xml",
+            ImportSource::Ghost => "json",
+        };
+        let file_key = format!("{}imports/{}.{}", PRIVATE_PREFIX, import_id, extension);
+        storage.put(&file_key, data).await?;
+
+        let result = async {
+            let mut tx = self.db.begin().await?;
+            sqlx::query!(
+                r#"
+                INSERT INTO imports (id, user_id, source, file_key, file_name, site_url, as_drafts)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+                import_id,
+                user_id,
+                source as ImportSource,
+                file_key,
+                file_name,
+                site_url,
+                as_drafts
+            )
+            .execute(&mut *tx)
+            .await?;
+            JobService::enqueue(&mut *tx, &Job::ImportArticles { import_id }, Utc::now()).await?;
+            tx.commit().await?;
+            Ok::<(), AppError>(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            let _ = storage.delete(&file_key).await;
+            return Err(e);
+        }
+
+        self.get_import(user_id, import_id).await
+    }
+
+    pub async fn list_imports(&self, user_id: Uuid) -> Result<ImportListResponse, AppError> {
+        let imports = self.fetch(user_id, None).await?;
+        Ok(ImportListResponse { imports })
+    }
+
+    pub async fn get_import(&self, user_id: Uuid, import_id: Uuid) -> Result<ImportResponse, AppError> {
+        self.fetch(user_id, Some(import_id))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::NotFound("Import not found".to_string()))
+    }
+
+    // Run by the import_articles job. After a crash the job resumes: posts already
+    // in the report aren't imported again.
+    pub async fn run_import(&self, storage: &dyn Storage, import_id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let Some(import) = sqlx::query!(
+            r#"
+            SELECT user_id, source as "source: ImportSource", status as "status: ImportStatus",
+                file_key, site_url, as_drafts, items
+            FROM imports WHERE id = $1
+            "#,
+            import_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        else {
+            return Ok(());
+        };
+
+        if matches!(import.status, ImportStatus::Completed | ImportStatus::Failed) {
+            return Ok(());
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE imports
+            SET status = 'running', started_at = COALESCE(started_at, NOW()), updated_at = NOW()
+            WHERE id = $1
+            "#,
+            import_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        let Some(data) = storage.get(&import.file_key).await? else {
+            return self.finish(import_id, Some("The uploaded export is no longer available")).await;
+        };
+
+        let site_url = import.site_url.clone();
+        let parsed = tokio::task::spawn_blocking(move || parse_export(import.source, &data, site_url.as_deref())).await?;
+        let parsed = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.finish(import_id, Some(&e.to_string())).await?;
+                let _ = storage.delete(&import.file_key).await;
+                return Ok(());
+            }
+        };
+
+        let done: HashSet<String> = serde_json::from_value::<Vec<ImportItem>>(import.items)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| item.source_id)
+            .collect();
+
+        sqlx::query!(
+            "UPDATE imports SET total_items = $2, updated_at = NOW() WHERE id = $1",
+            import_id,
+            (parsed.posts.len() + parsed.skipped.len()) as i32
+        )
+        .execute(&self.db)
+        .await?;
+
+        for item in parsed.skipped.iter().filter(|item| !done.contains(&item.source_id)) {
+            self.record(import_id, item).await?;
+        }
+
+        let client = image_client(self.config.import_allow_private_networks)?;
+        let mut rehosted = HashMap::new();
+        for post in parsed.posts.into_iter().filter(|post| !done.contains(&post.source_id)) {
+            let mut item = ImportItem {
+                source_id: post.source_id.clone(),
+                title: post.title.clone(),
+                status: ImportItemStatus::Imported,
+                reason: None,
+                article_id: None,
+                warnings: Vec::new(),
+            };
+
+            match self
+                .import_post(storage, &client, &mut rehosted, import.user_id, post, import.as_drafts, &mut item.warnings)
+                .await
+            {
+                Ok(PostOutcome::Imported(article_id)) => item.article_id = Some(article_id),
+                Ok(PostOutcome::Skipped(reason)) => {
+                    item.status = ImportItemStatus::Skipped;
+                    item.reason = Some(reason);
+                }
+                Err(e) => {
+                    tracing::warn!("Import {}: failed to import {}: {}", import_id, item.source_id, e);
+                    item.status = ImportItemStatus::Failed;
+                    item.reason = Some(e.to_string());
+                }
+            }
+
+            self.record(import_id, &item).await?;
+        }
+
+        self.finish(import_id, None).await?;
+        if let Err(e) = storage.delete(&import.file_key).await {
+            tracing::warn!("Failed to delete import file {}: {}", import.file_key, e);
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn import_post(
+        &self,
+        storage: &dyn Storage,
+        client: &reqwest::Client,
+        rehosted: &mut HashMap<String, Result<String, String>>,
+        user_id: Uuid,
+        post: ImportedPost,
+        as_drafts: bool,
+        warnings: &mut Vec<String>,
+    ) -> Result<PostOutcome, Box<dyn Error + Send + Sync>> {
+        let title = truncate(&post.title, MAX_TITLE_LENGTH);
+        if title.is_empty() {
+            return Ok(PostOutcome::Skipped("Post has no title".to_string()));
+        }
+        let has_text = !TAG_REGEX.replace_all(&post.html, " ").trim().is_empty();
+        if !has_text && !IMG_SRC_REGEX.is_match(&post.html) {
+            return Ok(PostOutcome::Skipped("Post has no content".to_string()));
+        }
+
+        // Stored the same way ArticleService normalizes it
+        let canonical_url = match post.canonical_url.as_deref().map(Url::parse) {
+            Some(Ok(url)) if matches!(url.scheme(), "http" | "https") => Some(url.to_string()),
+            Some(_) => {
+                warnings.push("Ignored an invalid original URL".to_string());
+                None
+            }
+            None => None,
+        };
+
+        if let Some(url) = &canonical_url {
+            let exists = sqlx::query_scalar!(
+                r#"SELECT EXISTS(SELECT 1 FROM articles WHERE author_id = $1 AND canonical_url = $2) as "exists!""#,
+                user_id,
+                url
+            )
+            .fetch_one(&self.db)
+            .await?;
+            if exists {
+                return Ok(PostOutcome::Skipped("Already imported".to_string()));
+            }
+        }
+
+        // Download every image once and point the post at our copies
+        let mut html = post.html;
+        let sources: Vec<String> = IMG_SRC_REGEX
+            .captures_iter(&html)
+            .map(|captures| captures[1].to_string())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        for source in sources {
+            if storage.key_from_url(&source).is_some() || source.starts_with("data:") {
+                continue;
+            }
+            match self.rehost_image(storage, client, rehosted, user_id, &decode_entities(&source)).await {
+                Ok(url) => {
+                    html = html
+                        .replace(&format!("\"{}\"", source), &format!("\"{}\"", url))
+                        .replace(&format!("'{}'", source), &format!("'{}'", url));
+                }
+                Err(warning) => warnings.push(warning),
+            }
+        }
+
+        let featured_image_url = match post.featured_image_url {
+            Some(url) => match self.rehost_image(storage, client, rehosted, user_id, &url).await {
+                Ok(rehosted_url) => Some(rehosted_url),
+                Err(warning) => {
+                    // Often also used in the body, already reported
+                    if !warnings.contains(&warning) {
+                        warnings.push(warning);
+                    }
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let published = post.published && !as_drafts;
+        let request = CreateArticleRequest {
+            title,
+            subtitle: post
+                .subtitle
+                .map(|subtitle| truncate(&subtitle, MAX_SUBTITLE_LENGTH))
+                .filter(|subtitle| !subtitle.is_empty()),
+            content: html,
+            excerpt: None,
+            featured_image_url,
+            publication_id: None,
+            tags: Some(post.tags.into_iter().take(MAX_TAGS).collect()),
+            categories: Some(post.categories.into_iter().take(MAX_CATEGORIES).collect()),
+            is_member_only: None,
+            paywall_position: None,
+            canonical_url,
+            scheduled_at: None,
+            status: Some(if published { ArticleStatus::Published } else { ArticleStatus::Draft }),
+        };
+
+        let article_id = ArticleService::new(self.db.clone(), self.config)
+            .import_article(user_id, request, post.published_at.filter(|_| published))
+            .await?;
+
+        Ok(PostOutcome::Imported(article_id))
+    }
+
+    // Returns the URL of our copy, or a warning for the report. Failures are
+    // remembered too, so a dead link is only tried once per import.
+    async fn rehost_image(
+        &self,
+        storage: &dyn Storage,
+        client: &reqwest::Client,
+        rehosted: &mut HashMap<String, Result<String, String>>,
+        user_id: Uuid,
+        url: &str,
+    ) -> Result<String, String> {
+        if let Some(result) = rehosted.get(url) {
+            return result.clone();
+        }
+
+        let result = match download_image(client, url, self.config.import_allow_private_networks).await {
+            Ok(data) => MediaService::new(self.db.clone())
+                .upload(storage, user_id, data, None, None, false)
+                .await
+                .map(|media| media.url)
+                .map_err(|e| format!("Image {} could not be imported: {}", url, e)),
+            Err(e) => Err(format!("Image {} could not be downloaded: {}", url, e)),
+        };
+
+        rehosted.insert(url.to_string(), result.clone());
+        result
+    }
+
+    async fn record(&self, import_id: Uuid, item: &ImportItem) -> Result<(), Box<dyn Error + Send + Sync>> {
+        sqlx::query!(
+            r#"
+            UPDATE imports
+            SET items = items || $2,
+                imported_count = imported_count + $3,
+                skipped_count = skipped_count + $4,
+                failed_count = failed_count + $5,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+            import_id,
+            serde_json::to_value(vec![item])?,
+            (item.status == ImportItemStatus::Imported) as i32,
+            (item.status == ImportItemStatus::Skipped) as i32,
+            (item.status == ImportItemStatus::Failed) as i32
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn finish(&self, import_id: Uuid, error: Option<&str>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let status = if error.is_some() { ImportStatus::Failed } else { ImportStatus::Completed };
+        sqlx::query!(
+            r#"
+            UPDATE imports
+            SET status = $2, error = $3, finished_at = NOW(), updated_at = NOW()
+            WHERE id = $1
+            "#,
+            import_id,
+            status as ImportStatus,
+            error
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    // The report is only loaded for a single import
+    async fn fetch(
+        &self,
+        user_id: Uuid,
+        import_id: Option<Uuid>,
+    ) -> Result<Vec<ImportResponse>, Box<dyn Error + Send + Sync>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, source as "source: ImportSource", status as "status: ImportStatus", file_name, as_drafts,
+                total_items, imported_count, skipped_count, failed_count,
+                CASE WHEN $2::uuid IS NULL THEN NULL ELSE items END as items,
+                error, started_at, finished_at, created_at
+            FROM imports
+            WHERE user_id = $1 AND ($2::uuid IS NULL OR id = $2)
+            ORDER BY created_at DESC
+            LIMIT 50
+            "#,
+            user_id,
+            import_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ImportResponse {
+                id: row.id,
+                source: row.source,
+                status: row.status,
+                file_name: row.file_name,
+                as_drafts: row.as_drafts,
+                total_items: row.total_items,
+                imported_count: row.imported_count,
+                skipped_count: row.skipped_count,
+                failed_count: row.failed_count,
+                items: row.items.map(|items| serde_json::from_value(items).unwrap_or_default()),
+                error: row.error,
+                started_at: row.started_at,
+                finished_at: row.finished_at,
+                created_at: row.created_at,
+            })
+            .collect())
+    }
+}
+
+// Zip: Medium, JSON: Ghost, XML: WordPress
+pub fn detect_source(data: &[u8]) -> Option<ImportSource> {
+    if data.starts_with(b"PK\x03\x04") {
+        return Some(ImportSource::Medium);
+    }
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    match data.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'{') => Some(ImportSource::Ghost),
+        Some(b'<') => Some(ImportSource::Wordpress),
+        _ => None,
+    }
+}
+
+// CPU-bound: run it on a blocking thread
+pub fn parse_export(
+    source: ImportSource,
+    data: &[u8],
+    site_url: Option<&str>,
+) -> Result<ParsedExport, Box<dyn Error + Send + Sync>> {
+    let mut export = match source {
+        ImportSource::Medium => parse_medium(data)?,
+        ImportSource::Wordpress => parse_wordpress(data)?,
+        ImportSource::Ghost => parse_ghost(data, site_url)?,
+    };
+
+    // Relative image links only work with the old site's URL
+    for post in &mut export.posts {
+        let html = IMG_SRC_REGEX.replace_all(&post.html, |captures: &Captures| {
+            let source = &captures[1];
+            match absolute_url(source, site_url) {
+                Some(url) if url != source => captures[0].replace(source, &url),
+                _ => captures[0].to_string(),
+            }
+        });
+        post.html = html.into_owned();
+        post.featured_image_url = post
+            .featured_image_url
+            .take()
+            .and_then(|url| absolute_url(&url, site_url));
+    }
+
+    Ok(export)
+}
+
+fn parse_medium(data: &[u8]) -> Result<ParsedExport, Box<dyn Error + Send + Sync>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(|_| "Invalid Medium export: not a zip file")?;
+    let mut export = ParsedExport::default();
+
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        // posts/*.html, possibly inside a top-level folder
+        let path = file.name().to_string();
+        let Some(file_name) = path.rsplit_once("posts/").map(|(_, name)| name.to_string()) else {
+            continue;
+        };
+        if !file.is_file() || file_name.contains('/') || !file_name.ends_with(".html") {
+            continue;
+        }
+
+        let mut html = String::new();
+        if file.by_ref().take(MAX_POST_FILE_SIZE).read_to_string(&mut html).is_err() {
+            export.skipped.push(skipped_item(&file_name, "", "File is not valid UTF-8 HTML"));
+            continue;
+        }
+
+        match parse_medium_post(&file_name, &html) {
+            Some(post) => export.posts.push(post),
+            None => export.skipped.push(skipped_item(&file_name, "", "Not a Medium post")),
+        }
+    }
+
+    if export.posts.is_empty() && export.skipped.is_empty() {
+        return Err("Invalid Medium export: no posts/*.html files found".into());
+    }
+
+    Ok(export)
+}
+
+fn parse_medium_post(file_name: &str, html: &str) -> Option<ImportedPost> {
+    let body = MEDIUM_BODY_REGEX.captures(html)?.get(1)?.as_str();
+    let title = MEDIUM_TITLE_REGEX.captures(html).map(|captures| plain_text(&captures[1])).unwrap_or_default();
+    let subtitle = MEDIUM_SUBTITLE_REGEX.captures(html).map(|captures| plain_text(&captures[1]));
+    // Unpublished drafts are exported as draft_*.html
+    let published = !file_name.starts_with("draft_");
+
+    Some(ImportedPost {
+        source_id: file_name.to_string(),
+        title,
+        subtitle,
+        html: MEDIUM_HEADING_REGEX.replace_all(body, "").trim().to_string(),
+        tags: Vec::new(),
+        categories: Vec::new(),
+        published,
+        published_at: MEDIUM_PUBLISHED_REGEX
+            .captures(html)
+            .and_then(|captures| DateTime::parse_from_rfc3339(&captures[1]).ok())
+            .map(|date| date.with_timezone(&Utc)),
+        canonical_url: MEDIUM_CANONICAL_REGEX
+            .captures(html)
+            .filter(|_| published)
+            .map(|captures| decode_entities(&captures[1])),
+        featured_image_url: None,
+    })
+}
+
+fn parse_wordpress(data: &[u8]) -> Result<ParsedExport, Box<dyn Error + Send + Sync>> {
+    let xml = std::str::from_utf8(data).map_err(|_| "Invalid WordPress export: not UTF-8")?;
+    let document = roxmltree::Document::parse(xml.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("Invalid WordPress export: {}", e))?;
+    let channel = document
+        .root_element()
+        .children()
+        .find(|node| node.has_tag_name("channel"))
+        .ok_or("Invalid WordPress export: no channel element")?;
+    if child(channel, "wxr_version", is_wp).is_none() {
+        return Err("Invalid WordPress export: not a WXR file".into());
+    }
+
+    let items: Vec<roxmltree::Node> = channel.children().filter(|node| node.has_tag_name("item")).collect();
+    let attachments: HashMap<String, String> = items
+        .iter()
+        .filter(|item| text(child(**item, "post_type", is_wp)) == "attachment")
+        .map(|item| (text(child(*item, "post_id", is_wp)), text(child(*item, "attachment_url", is_wp))))
+        .collect();
+
+    let mut export = ParsedExport::default();
+    for item in items {
+        let source_id = text(child(item, "post_id", is_wp));
+        let title = decode_entities(&text(child(item, "title", no_namespace)));
+
+        match text(child(item, "post_type", is_wp)).as_str() {
+            "post" => {}
+            "page" => {
+                export.skipped.push(skipped_item(&source_id, &title, "Pages are not imported"));
+                continue;
+            }
+            // Attachments, menu items, revisions, ...
+            _ => continue,
+        }
+
+        let published = match text(child(item, "status", is_wp)).as_str() {
+            "publish" => true,
+            "draft" | "pending" | "future" | "private" => false,
+            "trash" => {
+                export.skipped.push(skipped_item(&source_id, &title, "Post is in the trash"));
+                continue;
+            }
+            // auto-draft
+            _ => continue,
+        };
+
+        let mut tags = Vec::new();
+        let mut categories = Vec::new();
+        for category in item.children().filter(|node| node.has_tag_name("category")) {
+            let name = decode_entities(category.text().unwrap_or("").trim());
+            match category.attribute("domain") {
+                Some("post_tag") if !name.is_empty() => tags.push(name),
+                Some("category") if !name.is_empty() && name != "Uncategorized" => categories.push(name),
+                _ => {}
+            }
+        }
+
+        let thumbnail_id = item
+            .children()
+            .filter(|node| node.tag_name().name() == "postmeta" && is_wp(node.tag_name().namespace()))
+            .find(|meta| text(child(*meta, "meta_key", is_wp)) == "_thumbnail_id")
+            .map(|meta| text(child(meta, "meta_value", is_wp)));
+
+        let date = [text(child(item, "post_date_gmt", is_wp)), text(child(item, "post_date", is_wp))]
+            .into_iter()
+            .find_map(|date| NaiveDateTime::parse_from_str(&date, "%Y-%m-%d %H:%M:%S").ok())
+            .map(|date| date.and_utc());
+
+        let subtitle = plain_text(&text(child(item, "encoded", is_excerpt)));
+
+        export.posts.push(ImportedPost {
+            source_id,
+            title,
+            subtitle: Some(subtitle).filter(|subtitle| !subtitle.is_empty()),
+            html: autop(&captions(&text(child(item, "encoded", is_content)))),
+            tags,
+            categories,
+            published,
+            published_at: date,
+            canonical_url: Some(text(child(item, "link", no_namespace))).filter(|link| published && !link.is_empty()),
+            featured_image_url: thumbnail_id.and_then(|id| attachments.get(&id).cloned()),
+        });
+    }
+
+    Ok(export)
+}
+
+fn child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &str,
+    namespace: fn(Option<&str>) -> bool,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name && namespace(child.tag_name().namespace()))
+}
+
+fn text(node: Option<roxmltree::Node>) -> String {
+    node.and_then(|node| node.text()).unwrap_or("").trim().to_string()
+}
+
+// wp: elements; the namespace carries the WXR version (1.0 - 1.2)
+fn is_wp(namespace: Option<&str>) -> bool {
+    namespace.is_some_and(|namespace| namespace.starts_with(WP_NAMESPACE) && !namespace.ends_with("/excerpt/"))
+}
+
+fn is_excerpt(namespace: Option<&str>) -> bool {
+    namespace.is_some_and(|namespace| namespace.starts_with(WP_NAMESPACE) && namespace.ends_with("/excerpt/"))
+}
+
+fn is_content(namespace: Option<&str>) -> bool {
+    namespace == Some(CONTENT_NAMESPACE)
+}
+
+fn no_namespace(namespace: Option<&str>) -> bool {
+    namespace.is_none()
+}
+
+// `[caption]<img ...> Text[/caption]` shortcodes become figures
+fn captions(html: &str) -> String {
+    CAPTION_REGEX
+        .replace_all(html, |captures: &Captures| {
+            let inner = captures[1].trim();
+            match inner.rfind('>') {
+                Some(end) if !inner[end + 1..].trim().is_empty() => format!(
+                    "<figure>{}<figcaption>{}</figcaption></figure>",
+                    &inner[..=end],
+                    inner[end + 1..].trim()
+                ),
+                _ => format!("<figure>{}</figure>", inner),
+            }
+        })
+        .into_owned()
+}
+
+// The classic editor stores paragraphs as blank-line separated text without <p> tags
+fn autop(html: &str) -> String {
+    if html.contains("<p>") || html.contains("<p ") || html.contains("<!-- wp:") {
+        return html.to_string();
+    }
+
+    PARAGRAPH_BREAK_REGEX
+        .split(html)
+        .map(str::trim)
+        .filter(|block| !block.is_empty())
+        .map(|block| {
+            if BLOCK_START_REGEX.is_match(block) {
+                block.to_string()
+            } else {
+                format!("<p>{}</p>", block.replace('\n', "<br>\n"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn parse_ghost(data: &[u8], site_url: Option<&str>) -> Result<ParsedExport, Box<dyn Error + Send + Sync>> {
+    let root: Value = serde_json::from_slice(data).map_err(|e| format!("Invalid Ghost export: {}", e))?;
+    // {"db": [{"meta": ..., "data": ...}]}, or just {"data": ...}
+    let data = root
+        .get("db")
+        .and_then(|db| db.get(0))
+        .unwrap_or(&root)
+        .get("data")
+        .ok_or("Invalid Ghost export: no data section")?;
+    let posts = data
+        .get("posts")
+        .and_then(Value::as_array)
+        .ok_or("Invalid Ghost export: no posts")?;
+
+    let tag_names: HashMap<String, String> = array(data, "tags")
+        .iter()
+        .filter_map(|tag| Some((id_string(tag.get("id")?)?, tag.get("name")?.as_str()?.to_string())))
+        .collect();
+    let mut post_tags: HashMap<String, Vec<String>> = HashMap::new();
+    for link in array(data, "posts_tags") {
+        let (Some(post_id), Some(tag_id)) = (
+            link.get("post_id").and_then(id_string),
+            link.get("tag_id").and_then(id_string),
+        ) else {
+            continue;
+        };
+        // Tags starting with # are Ghost's internal tags
+        if let Some(name) = tag_names.get(&tag_id).filter(|name| !name.starts_with('#')) {
+            post_tags.entry(post_id).or_default().push(name.clone());
+        }
+    }
+
+    // Ghost 4+ writes its own URL as a placeholder
+    let ghost_url = |value: &str| value.replace("__GHOST_URL__", site_url.unwrap_or(""));
+
+    let mut export = ParsedExport::default();
+    for post in posts {
+        let source_id = post.get("id").and_then(id_string).unwrap_or_default();
+        let title = string(post, "title");
+
+        let is_page = post.get("type").and_then(Value::as_str) == Some("page")
+            || matches!(post.get("page"), Some(Value::Bool(true)))
+            || post.get("page").and_then(Value::as_i64) == Some(1);
+        if is_page {
+            export.skipped.push(skipped_item(&source_id, &title, "Pages are not imported"));
+            continue;
+        }
+
+        let published = match string(post, "status").as_str() {
+            "published" => true,
+            "sent" => {
+                export.skipped.push(skipped_item(&source_id, &title, "Email-only posts are not imported"));
+                continue;
+            }
+            _ => false,
+        };
+
+        let html = string(post, "html");
+        if html.is_empty() {
+            export.skipped.push(skipped_item(
+                &source_id,
+                &title,
+                "Post has no HTML in the export; export it again from Ghost 2.0 or later",
+            ));
+            continue;
+        }
+
+        let published_at = match post.get("published_at") {
+            Some(Value::String(date)) => DateTime::parse_from_rfc3339(date).ok().map(|date| date.with_timezone(&Utc)),
+            Some(Value::Number(millis)) => millis.as_i64().and_then(DateTime::from_timestamp_millis),
+            _ => None,
+        };
+
+        let slug = string(post, "slug");
+        let canonical_url = Some(string(post, "canonical_url"))
+            .filter(|url| !url.is_empty())
+            .map(|url| ghost_url(&url))
+            .or_else(|| site_url.filter(|_| published && !slug.is_empty()).map(|site| format!("{}/{}/", site, slug)))
+            .filter(|_| published);
+
+        export.posts.push(ImportedPost {
+            title,
+            subtitle: Some(string(post, "custom_excerpt")).filter(|subtitle| !subtitle.is_empty()),
+            html: ghost_url(&html),
+            tags: post_tags.remove(&source_id).unwrap_or_default(),
+            categories: Vec::new(),
+            published,
+            published_at,
+            canonical_url,
+            featured_image_url: Some(string(post, "feature_image"))
+                .filter(|url| !url.is_empty())
+                .map(|url| ghost_url(&url)),
+            source_id,
+        });
+    }
+
+    Ok(export)
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value.get(key).and_then(Value::as_array).map(Vec::as_slice).unwrap_or(&[])
+}
+
+fn string(value: &Value, key: &str) -> String {
+    value.get(key).and_then(Value::as_str).unwrap_or("").trim().to_string()
+}
+
+// Ghost ids are object-id strings; very old exports used numbers
+fn id_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+fn skipped_item(source_id: &str, title: &str, reason: &str) -> ImportItem {
+    ImportItem {
+        source_id: source_id.to_string(),
+        title: title.to_string(),
+        status: ImportItemStatus::Skipped,
+        reason: Some(reason.to_string()),
+        article_id: None,
+        warnings: Vec::new(),
+    }
+}
+
+fn plain_text(html: &str) -> String {
+    let text = TAG_REGEX.replace_all(html, " ");
+    decode_entities(&text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => text[..end].trim_end().to_string(),
+        None => text.to_string(),
+    }
+}
+
+// Absolute http(s) URL for an image reference, resolving relative links against the old site
+fn absolute_url(url: &str, site_url: Option<&str>) -> Option<String> {
+    if let Some(rest) = url.strip_prefix("//") {
+        return Some(format!("https://{}", rest));
+    }
+    match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Some(url.to_string()),
+        Ok(_) => None,
+        Err(_) => {
+            let base = Url::parse(&format!("{}/", site_url?)).ok()?;
+            base.join(url).ok().map(|joined| joined.to_string())
+        }
+    }
+}
+
+fn image_client(allow_private_networks: bool) -> reqwest::Result<reqwest::Client> {
+    let builder = reqwest::Client::builder()
+        .timeout(IMAGE_TIMEOUT)
+        // Redirects are followed by hand so every hop is checked
+        .redirect(reqwest::redirect::Policy::none())
+        .user_agent("FastBlog importer");
+
+    if allow_private_networks {
+        return builder.build();
+    }
+    // Hostnames are checked by the resolver the connection actually uses, so a second
+    // lookup can't swap in a private address. A proxy would resolve the target itself.
+    builder.no_proxy().dns_resolver(Arc::new(PublicResolver)).build()
+}
+
+/// Resolves hostnames for image downloads, refusing any that point at a private network
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if addresses.iter().any(|address| !is_public_address(address.ip())) {
+                return Err("refusing to download from a private network address".into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+async fn download_image(
+    client: &reqwest::Client,
+    url: &str,
+    allow_private_networks: bool,
+) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let mut url = Url::parse(url)?;
+
+    for _ in 0..=MAX_IMAGE_REDIRECTS {
+        check_public_host(&url, allow_private_networks)?;
+        let mut response = client.get(url.clone()).send().await?;
+
+        if response.status().is_redirection() {
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .ok_or("redirect without a location")?;
+            url = url.join(location)?;
+            continue;
+        }
+        if !response.status().is_success() {
+            return Err(format!("server responded with {}", response.status()).into());
+        }
+        if response.content_length().is_some_and(|length| length > MAX_UPLOAD_SIZE as u64) {
+            return Err("image is too large".into());
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            data.extend_from_slice(&chunk);
+            if data.len() > MAX_UPLOAD_SIZE {
+                return Err("image is too large".into());
+            }
+        }
+        return Ok(data);
+    }
+
+    Err("too many redirects".into())
+}
+
+// Exports are user-supplied, so image links must not make the server fetch internal addresses.
+// IP literals never reach the resolver, so they are checked here; hostnames are checked by PublicResolver.
+fn check_public_host(url: &Url, allow_private_networks: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("only http(s) images can be imported".into());
+    }
+    if allow_private_networks {
+        return Ok(());
+    }
+
+    let host = url.host_str().ok_or("URL has no host")?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.parse::<IpAddr>().is_ok_and(|ip| !is_public_address(ip)) {
+        return Err("refusing to download from a private network address".into());
+    }
+
+    Ok(())
+}
+
+fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || first == 0
+                // Carrier-grade NAT (100.64.0.0/10)
+                || (first == 100 && (second & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_address(IpAddr::V4(ip));
+            }
+            let first = ip.segments()[0];
+            // Unique local (fc00::/7) and link-local (fe80::/10)
+            !(ip.is_loopback() || ip.is_unspecified() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    fn zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (name, content) in files {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    const MEDIUM_POST: &str = r#"<!DOCTYPE html><html><head><title>Hello</title></head><body><article class="h-entry">
+<header><h1 class="p-name">Hello &amp; welcome</h1></header>
+<section data-field="subtitle" class="p-summary">A <em>first</em> post</section>
+<section data-field="body" class="e-content"><section class="section"><div class="section-inner">
+<h3 class="graf graf--h3 graf--title">Hello &amp; welcome</h3>
+<h4 class="graf graf--h4 graf--subtitle">A first post</h4>
+<p class="graf graf--p">Body text with <img src="https://cdn-images-1.medium.com/max/800/1.png"></p>
+</div></section></section>
+<footer><p>By <a href="https://medium.com/@writer" class="p-author h-card">Writer</a> on
+<a href="https://medium.com/p/abc"><time class="dt-published" datetime="2021-03-04T05:06:07.890Z">March 4, 2021</time></a>.</p>
+<p><a href="https://medium.com/@writer/hello-abc?a=1&amp;b=2" class="p-canonical">Canonical link</a></p></footer>
+</article></body></html>"#;
+
+    #[test]
+    fn detects_the_source_from_the_first_bytes() {
+        assert_eq!(detect_source(b"PK\x03\x04rest"), Some(ImportSource::Medium));
+        assert_eq!(detect_source(b"\xEF\xBB\xBF  {\"db\": []}"), Some(ImportSource::Ghost));
+        assert_eq!(detect_source(b"\n<?xml version=\"1.0\"?>"), Some(ImportSource::Wordpress));
+        assert_eq!(detect_source(b"title,body"), None);
+        assert_eq!(detect_source(b""), None);
+    }
+
+    #[test]
+    fn parses_medium_posts_and_drafts() {
+        let data = zip(&[
+            ("medium-export/posts/2021-03-04_Hello-abc.html", MEDIUM_POST),
+            ("medium-export/posts/draft_Hello-def.html", MEDIUM_POST),
+            ("medium-export/profile/profile.html", "<html></html>"),
+        ]);
+        let export = parse_export(ImportSource::Medium, &data, None).unwrap();
+
+        assert!(export.skipped.is_empty());
+        let [post, draft] = &export.posts[..] else {
+            panic!("expected two posts, got {:?}", export.posts);
+        };
+        assert_eq!(post.source_id, "2021-03-04_Hello-abc.html");
+        assert_eq!(post.title, "Hello & welcome");
+        assert_eq!(post.subtitle.as_deref(), Some("A first post"));
+        assert!(post.published);
+        assert_eq!(post.published_at.unwrap().to_rfc3339(), "2021-03-04T05:06:07.890+00:00");
+        assert_eq!(post.canonical_url.as_deref(), Some("https://medium.com/@writer/hello-abc?a=1&b=2"));
+        // The heading Medium repeats inside the body is dropped
+        assert!(!post.html.contains("graf--title") && !post.html.contains("graf--subtitle"));
+        assert!(post.html.contains("Body text with"));
+
+        assert!(!draft.published);
+        assert_eq!(draft.canonical_url, None);
+    }
+
+    #[test]
+    fn skips_medium_files_that_are_not_posts() {
+        let data = zip(&[
+            ("posts/post.html", MEDIUM_POST),
+            ("posts/notes.html", "<html><body>No post here</body></html>"),
+        ]);
+        let export = parse_medium(&data).unwrap();
+
+        assert_eq!(export.posts.len(), 1);
+        assert_eq!(export.skipped.len(), 1);
+        assert_eq!(export.skipped[0].source_id, "notes.html");
+    }
+
+    #[test]
+    fn rejects_invalid_medium_archives() {
+        assert!(parse_medium(b"not a zip").is_err());
+        assert!(parse_medium(&zip(&[("profile/profile.html", "<html></html>")])).is_err());
+    }
+
+    const WORDPRESS_EXPORT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0"
+    xmlns:excerpt="http://wordpress.org/export/1.2/excerpt/"
+    xmlns:content="http://purl.org/rss/1.0/modules/content/"
+    xmlns:wp="http://wordpress.org/export/1.2/">
+<channel>
+    <wp:wxr_version>1.2</wp:wxr_version>
+    <item>
+        <title>Fish &amp;amp; Chips</title>
+        <link>https://old.example.com/fish-and-chips/</link>
+        <content:encoded><![CDATA[First paragraph
+with a line break
+
+[caption id="attachment_9" width="300"]<img src="/wp-content/uploads/fish.jpg" /> A fish[/caption]
+
+<h2>Heading</h2>]]></content:encoded>
+        <excerpt:encoded><![CDATA[<b>Short</b> summary]]></excerpt:encoded>
+        <wp:post_id>7</wp:post_id>
+        <wp:post_date>2020-01-02 03:04:05</wp:post_date>
+        <wp:post_date_gmt>2020-01-02 01:04:05</wp:post_date_gmt>
+        <wp:status>publish</wp:status>
+        <wp:post_type>post</wp:post_type>
+        <category domain="category" nicename="food"><![CDATA[Food]]></category>
+        <category domain="category" nicename="uncategorized"><![CDATA[Uncategorized]]></category>
+        <category domain="post_tag" nicename="fish"><![CDATA[fish]]></category>
+        <wp:postmeta>
+            <wp:meta_key>_thumbnail_id</wp:meta_key>
+            <wp:meta_value>9</wp:meta_value>
+        </wp:postmeta>
+    </item>
+    <item>
+        <title>Fish photo</title>
+        <wp:post_id>9</wp:post_id>
+        <wp:post_type>attachment</wp:post_type>
+        <wp:attachment_url>https://old.example.com/wp-content/uploads/fish.jpg</wp:attachment_url>
+    </item>
+    <item>
+        <title>Draft</title>
+        <link>https://old.example.com/?p=8</link>
+        <content:encoded><![CDATA[<p>Not done</p>]]></content:encoded>
+        <wp:post_id>8</wp:post_id>
+        <wp:status>draft</wp:status>
+        <wp:post_type>post</wp:post_type>
+    </item>
+    <item>
+        <title>About</title>
+        <wp:post_id>2</wp:post_id>
+        <wp:status>publish</wp:status>
+        <wp:post_type>page</wp:post_type>
+    </item>
+    <item>
+        <title>Deleted</title>
+        <wp:post_id>3</wp:post_id>
+        <wp:status>trash</wp:status>
+        <wp:post_type>post</wp:post_type>
+    </item>
+    <item>
+        <title>Auto draft</title>
+        <wp:post_id>4</wp:post_id>
+        <wp:status>auto-draft</wp:status>
+        <wp:post_type>post</wp:post_type>
+    </item>
+</channel>
+</rss>"#;
+
+    #[test]
+    fn parses_wordpress_posts() {
+        let export = parse_export(ImportSource::Wordpress, WORDPRESS_EXPORT.as_bytes(), Some("https://old.example.com")).unwrap();
+
+        let [post, draft] = &export.posts[..] else {
+            panic!("expected two posts, got {:?}", export.posts);
+        };
+        assert_eq!(post.source_id, "7");
+        assert_eq!(post.title, "Fish & Chips");
+        assert_eq!(post.subtitle.as_deref(), Some("Short summary"));
+        assert_eq!(post.tags, ["fish"]);
+        assert_eq!(post.categories, ["Food"]);
+        assert!(post.published);
+        // The GMT date wins over the local one
+        assert_eq!(post.published_at.unwrap().to_rfc3339(), "2020-01-02T01:04:05+00:00");
+        assert_eq!(post.canonical_url.as_deref(), Some("https://old.example.com/fish-and-chips/"));
+        assert_eq!(post.featured_image_url.as_deref(), Some("https://old.example.com/wp-content/uploads/fish.jpg"));
+        assert_eq!(
+            post.html,
+            "<p>First paragraph<br>\nwith a line break</p>\n\
+             <figure><img src=\"https://old.example.com/wp-content/uploads/fish.jpg\" /><figcaption>A fish</figcaption></figure>\n\
+             <h2>Heading</h2>"
+        );
+
+        assert!(!draft.published);
+        assert_eq!(draft.canonical_url, None);
+        assert_eq!(draft.html, "<p>Not done</p>");
+
+        let skipped: Vec<_> = export.skipped.iter().map(|item| (item.source_id.as_str(), item.reason.as_deref())).collect();
+        assert_eq!(skipped, [("2", Some("Pages are not imported")), ("3", Some("Post is in the trash"))]);
+    }
+
+    #[test]
+    fn rejects_xml_that_is_not_a_wordpress_export() {
+        assert!(parse_wordpress(b"<rss><channel><item/></channel></rss>").is_err());
+        assert!(parse_wordpress(b"<rss><channel>").is_err());
+        assert!(parse_wordpress(b"<feed/>").is_err());
+    }
+
+    const GHOST_EXPORT: &str = r##"{"db": [{"meta": {"version": "5.0.0"}, "data": {
+        "posts": [
+            {"id": "p1", "title": "Hello", "slug": "hello", "status": "published", "type": "post",
+             "html": "<p>Hi <img src=\"__GHOST_URL__/content/images/a.png\"></p>",
+             "custom_excerpt": "Summary", "feature_image": "__GHOST_URL__/content/images/cover.png",
+             "published_at": "2022-05-06T07:08:09.000Z"},
+            {"id": "p2", "title": "Elsewhere", "slug": "elsewhere", "status": "published", "type": "post",
+             "html": "<p>Cross-posted</p>", "canonical_url": "https://other.example.com/elsewhere",
+             "published_at": 1651820889000},
+            {"id": "p3", "title": "Draft", "slug": "draft", "status": "draft", "type": "post", "html": "<p>WIP</p>"},
+            {"id": "p4", "title": "About", "status": "published", "type": "page", "html": "<p>Me</p>"},
+            {"id": "p5", "title": "Legacy page", "status": "published", "page": 1, "html": "<p>Old</p>"},
+            {"id": "p6", "title": "Newsletter", "status": "sent", "type": "post", "html": "<p>Email</p>"},
+            {"id": "p7", "title": "Mobiledoc only", "status": "published", "type": "post", "html": null}
+        ],
+        "tags": [{"id": "t1", "name": "Rust"}, {"id": "t2", "name": "#internal"}, {"id": 3, "name": "Old"}],
+        "posts_tags": [
+            {"post_id": "p1", "tag_id": "t1"}, {"post_id": "p1", "tag_id": "t2"}, {"post_id": "p1", "tag_id": 3}
+        ]
+    }}]}"##;
+
+    #[test]
+    fn parses_ghost_posts() {
+        let export = parse_export(ImportSource::Ghost, GHOST_EXPORT.as_bytes(), Some("https://blog.example.com")).unwrap();
+
+        let [post, cross_posted, draft] = &export.posts[..] else {
+            panic!("expected three posts, got {:?}", export.posts);
+        };
+        assert_eq!(post.source_id, "p1");
+        assert_eq!(post.subtitle.as_deref(), Some("Summary"));
+        assert_eq!(post.tags, ["Rust", "Old"]);
+        assert_eq!(post.html, r#"<p>Hi <img src="https://blog.example.com/content/images/a.png"></p>"#);
+        assert_eq!(post.featured_image_url.as_deref(), Some("https://blog.example.com/content/images/cover.png"));
+        assert_eq!(post.canonical_url.as_deref(), Some("https://blog.example.com/hello/"));
+        assert_eq!(post.published_at.unwrap().to_rfc3339(), "2022-05-06T07:08:09+00:00");
+
+        assert_eq!(cross_posted.canonical_url.as_deref(), Some("https://other.example.com/elsewhere"));
+        assert_eq!(cross_posted.published_at.unwrap().timestamp_millis(), 1651820889000);
+
+        assert!(!draft.published);
+        assert_eq!(draft.canonical_url, None);
+
+        let skipped: Vec<_> = export.skipped.iter().map(|item| item.source_id.as_str()).collect();
+        assert_eq!(skipped, ["p4", "p5", "p6", "p7"]);
+    }
+
+    #[test]
+    fn reads_ghost_exports_without_the_db_wrapper() {
+        let export = parse_ghost(br#"{"data": {"posts": [{"id": 1, "title": "Old", "status": "published", "html": "<p>x</p>"}]}}"#, None).unwrap();
+
+        assert_eq!(export.posts[0].source_id, "1");
+        // Without the site URL there is nothing to build a canonical link from
+        assert_eq!(export.posts[0].canonical_url, None);
+    }
+
+    #[test]
+    fn rejects_invalid_ghost_exports() {
+        assert!(parse_ghost(b"{", None).is_err());
+        assert!(parse_ghost(br#"{"db": [{"meta": {}}]}"#, None).is_err());
+        assert!(parse_ghost(br#"{"data": {"tags": []}}"#, None).is_err());
+    }
+
+    #[test]
+    fn resolves_image_urls_against_the_old_site() {
+        assert_eq!(absolute_url("//cdn.example.com/a.png", None).as_deref(), Some("https://cdn.example.com/a.png"));
+        assert_eq!(absolute_url("http://example.com/a.png", None).as_deref(), Some("http://example.com/a.png"));
+        assert_eq!(absolute_url("/a.png", Some("https://example.com")).as_deref(), Some("https://example.com/a.png"));
+        assert_eq!(absolute_url("a.png", Some("https://example.com/blog")).as_deref(), Some("https://example.com/blog/a.png"));
+        assert_eq!(absolute_url("/a.png", None), None);
+        assert_eq!(absolute_url("data:image/png;base64,AAAA", Some("https://example.com")), None);
+    }
+
+    #[test]
+    fn classifies_public_and_private_addresses() {
+        for ip in ["8.8.8.8", "100.128.0.1", "2606:4700::1111", "::ffff:8.8.8.8"] {
+            assert!(is_public_address(ip.parse().unwrap()), "{} is public", ip);
+        }
+        for ip in [
+            "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "0.0.0.0", "0.1.2.3",
+            "255.255.255.255", "100.64.0.1", "100.127.255.255", "192.0.2.1", "::1", "::", "fd00::1", "fe80::1",
+            "::ffff:127.0.0.1", "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public_address(ip.parse().unwrap()), "{} is private", ip);
+        }
+    }
+
+    #[test]
+    fn checks_ip_literal_hosts_before_downloading() {
+        let check = |url: &str, allow: bool| check_public_host(&Url::parse(url).unwrap(), allow).is_ok();
+
+        assert!(check("https://example.com/a.png", false));
+        assert!(check("https://8.8.8.8/a.png", false));
+        assert!(!check("http://127.0.0.1/a.png", false));
+        assert!(!check("http://[::1]:8080/a.png", false));
+        assert!(!check("http://[::ffff:a9fe:a9fe]/latest/meta-data", false));
+        assert!(check("http://127.0.0.1/a.png", true));
+        assert!(!check("file:///etc/passwd", true));
+    }
+}
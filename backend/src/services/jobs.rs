@@ -0,0 +1,847 @@
+use chrono::{DateTime, Utc};
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{PgExecutor, PgPool};
+use std::error::Error;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::models::job::{
+    JobListResponse, JobQueryParams, JobResponse, JobStatsResponse, JobStatus, QueueStats, RecurringJobResponse,
+};
+use crate::services::{
+    account::AccountService,
+    article::ArticleService,
+    cron::CronSchedule,
+    digest::DigestService,
+    export::ExportService,
+    image,
+    import::ImportService,
+    login_protection::LoginProtectionService,
+    mailer::{EmailMessage, Mailer},
+    media::MediaService,
+    newsletter::{self, NewsletterService},
+    storage::Storage,
+    user::UserService,
+};
+
+// How often each queue polls for due jobs when idle
+const RUNNER_INTERVAL: Duration = Duration::from_secs(5);
+// How often recurring jobs are checked
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(30);
+// How often a running job's heartbeat is refreshed
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+// A running job whose heartbeat is older than this belongs to a dead instance and is claimed again
+const STALE_HEARTBEAT_SECONDS: f64 = 120.0;
+// Completed and cancelled jobs are deleted after this many days
+const JOB_RETENTION_DAYS: i32 = 14;
+
+// Queues and how many of their jobs one instance runs at a time
+pub const QUEUES: &[(&str, usize)] = &[
+    ("default", 4),
+    ("email", 4),
+    ("media", 2),
+    ("maintenance", 1),
+    ("imports", 1),
+    ("exports", 1),
+];
+
+// Typed job payloads; stored as `kind` plus the remaining fields in `payload`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Job {
+    PublishArticle { article_id: Uuid },
+    SendEmail { message: EmailMessage },
+    SendNewsletter { delivery_id: Uuid },
+    SendDigests,
+    // Stored image variants to add AVIF copies for
+    EncodeAvif {
+        // Storage keys; jobs queued before storage was configurable hold `uploads/...` paths
+        #[serde(alias = "paths")]
+        keys: Vec<String>,
+    },
+    ImportArticles { import_id: Uuid },
+    ExportData { export_id: Uuid },
+    DeleteAccount { user_id: Uuid },
+    RecountAuthorStats,
+    CleanupOrphanedAvatars,
+    CleanupUnusedMedia,
+    PruneExports,
+    PruneFailedLogins,
+    PruneJobs,
+}
+
+impl Job {
+    pub fn queue(&self) -> &'static str {
+        match self {
+            Job::PublishArticle { .. } | Job::DeleteAccount { .. } => "default",
+            Job::SendEmail { .. } | Job::SendNewsletter { .. } | Job::SendDigests => "email",
+            Job::EncodeAvif { .. } => "media",
+            Job::ImportArticles { .. } => "imports",
+            Job::ExportData { .. } => "exports",
+            Job::RecountAuthorStats
+            | Job::CleanupOrphanedAvatars
+            | Job::CleanupUnusedMedia
+            | Job::PruneExports
+            | Job::PruneFailedLogins
+            | Job::PruneJobs => "maintenance",
+        }
+    }
+
+    fn max_attempts(&self) -> i32 {
+        match self {
+            // Mail servers can be down for a while; 8 attempts span about four hours
+            Job::SendEmail { .. } => 8,
+            Job::SendNewsletter { .. } => newsletter::MAX_ATTEMPTS,
+            // Imports resume where they stopped, so a retry doesn't duplicate posts
+            Job::ImportArticles { .. } => 3,
+            // Recurring jobs run again on their next tick anyway
+            Job::SendDigests
+            | Job::RecountAuthorStats
+            | Job::CleanupOrphanedAvatars
+            | Job::CleanupUnusedMedia
+            | Job::PruneExports
+            | Job::PruneFailedLogins
+            | Job::PruneJobs => 2,
+            _ => 5,
+        }
+    }
+
+    fn to_row(&self) -> Result<(String, Value), Box<dyn Error + Send + Sync>> {
+        let mut payload = serde_json::to_value(self)?;
+        let kind = payload
+            .as_object_mut()
+            .and_then(|fields| fields.remove("kind"))
+            .and_then(|kind| kind.as_str().map(str::to_string))
+            .ok_or("Job is missing its kind")?;
+        Ok((kind, payload))
+    }
+
+    fn from_row(kind: &str, payload: Value) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut payload = match payload {
+            Value::Object(fields) => fields,
+            _ => serde_json::Map::new(),
+        };
+        payload.insert("kind".to_string(), Value::String(kind.to_string()));
+        serde_json::from_value(Value::Object(payload)).map_err(|e| format!("Invalid '{}' job: {}", kind, e).into())
+    }
+
+    async fn execute(&self, context: &JobContext) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self {
+            Job::PublishArticle { article_id } => {
+                if ArticleService::new(context.db.clone(), &context.config).publish_scheduled(*article_id).await? {
+                    tracing::info!("Published scheduled article {}", article_id);
+                }
+            }
+            Job::SendEmail { message } => context.mailer.send(message).await?,
+            Job::SendNewsletter { delivery_id } => {
+                NewsletterService::new(context.db.clone(), &context.config)
+                    .send_delivery(context.mailer.as_ref(), *delivery_id)
+                    .await?;
+            }
+            Job::SendDigests => {
+                let sent = DigestService::new(context.db.clone(), &context.config)
+                    .send_due_digests(context.mailer.as_ref())
+                    .await?;
+                if sent > 0 {
+                    tracing::info!("Sent {} email digests", sent);
+                }
+            }
+            Job::EncodeAvif { keys } => {
+                let keys: Vec<String> = keys
+                    .iter()
+                    .map(|key| key.strip_prefix("uploads/").unwrap_or(key).to_string())
+                    .collect();
+                image::generate_avif(context.storage.as_ref(), &keys).await?;
+            }
+            Job::ImportArticles { import_id } => {
+                ImportService::new(context.db.clone(), &context.config)
+                    .run_import(context.storage.as_ref(), *import_id)
+                    .await?;
+            }
+            Job::DeleteAccount { user_id } => {
+                if AccountService::new(context.db.clone(), &context.config)
+                    .delete_account(context.storage.as_ref(), *user_id)
+                    .await?
+                {
+                    tracing::info!("Deleted account {}", user_id);
+                }
+            }
+            Job::ExportData { export_id } => {
+                ExportService::new(context.db.clone())
+                    .run_export(context.storage.as_ref(), *export_id)
+                    .await?;
+            }
+            Job::RecountAuthorStats => {
+                let updated = UserService::new(context.db.clone()).recount_author_stats().await?;
+                tracing::info!("Recounted author stats ({} users changed)", updated);
+            }
+            Job::CleanupOrphanedAvatars => {
+                let removed = UserService::new(context.db.clone())
+                    .cleanup_orphaned_avatars(context.storage.as_ref())
+                    .await?;
+                if removed > 0 {
+                    tracing::info!("Removed {} orphaned avatar files", removed);
+                }
+            }
+            Job::CleanupUnusedMedia => {
+                let (media, files) = MediaService::new(context.db.clone())
+                    .cleanup_unused(context.storage.as_ref())
+                    .await?;
+                if media > 0 || files > 0 {
+                    tracing::info!("Removed {} unused media items and {} files", media, files);
+                }
+            }
+            Job::PruneExports => {
+                let pruned = ExportService::new(context.db.clone())
+                    .prune_expired(context.storage.as_ref())
+                    .await?;
+                if pruned > 0 {
+                    tracing::info!("Deleted {} expired data exports", pruned);
+                }
+            }
+            Job::PruneFailedLogins => {
+                let pruned = LoginProtectionService::new(context.db.clone(), &context.config).prune().await?;
+                if pruned > 0 {
+                    tracing::info!("Pruned {} failed sign-in attempts", pruned);
+                }
+            }
+            Job::PruneJobs => {
+                let pruned = JobService::new(context.db.clone()).prune(JOB_RETENTION_DAYS).await?;
+                if pruned > 0 {
+                    tracing::info!("Pruned {} finished jobs", pruned);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct RecurringJob {
+    name: &'static str,
+    // Cron expression in UTC
+    schedule: &'static str,
+    job: Job,
+}
+
+fn recurring_jobs() -> Vec<RecurringJob> {
+    vec![
+        RecurringJob { name: "send_digests", schedule: "*/15 * * * *", job: Job::SendDigests },
+        RecurringJob { name: "recount_author_stats", schedule: "17 * * * *", job: Job::RecountAuthorStats },
+        RecurringJob { name: "cleanup_orphaned_avatars", schedule: "40 3 * * *", job: Job::CleanupOrphanedAvatars },
+        RecurringJob { name: "cleanup_unused_media", schedule: "50 3 * * *", job: Job::CleanupUnusedMedia },
+        RecurringJob { name: "prune_exports", schedule: "0 4 * * *", job: Job::PruneExports },
+        RecurringJob { name: "prune_failed_logins", schedule: "5 4 * * *", job: Job::PruneFailedLogins },
+        RecurringJob { name: "prune_jobs", schedule: "10 4 * * *", job: Job::PruneJobs },
+    ]
+}
+
+struct JobContext {
+    db: PgPool,
+    config: Config,
+    mailer: Arc<dyn Mailer>,
+    storage: Arc<dyn Storage>,
+}
+
+// Per-queue workers plus the recurring job scheduler; every instance runs one
+pub struct JobRunner {
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl JobRunner {
+    pub fn start(
+        db: PgPool,
+        config: Config,
+        mailer: Arc<dyn Mailer>,
+        storage: Arc<dyn Storage>,
+        shutdown: CancellationToken,
+    ) -> Self {
+        let context = Arc::new(JobContext {
+            db: db.clone(),
+            config,
+            mailer,
+            storage,
+        });
+
+        let mut tasks: Vec<JoinHandle<()>> = QUEUES
+            .iter()
+            .map(|&(queue, concurrency)| tokio::spawn(run_queue(context.clone(), queue, concurrency, shutdown.clone())))
+            .collect();
+        tasks.push(tokio::spawn(run_scheduler(db, shutdown)));
+
+        Self { tasks }
+    }
+
+    // Resolves once the shutdown token is cancelled and in-flight jobs have finished
+    pub async fn wait(self) {
+        for task in self.tasks {
+            if let Err(e) = task.await {
+                tracing::error!("Job runner task failed: {}", e);
+            }
+        }
+    }
+}
+
+struct ClaimedJob {
+    id: Uuid,
+    kind: String,
+    payload: Value,
+    attempts: i32,
+    max_attempts: i32,
+}
+
+async fn run_queue(context: Arc<JobContext>, queue: &'static str, concurrency: usize, shutdown: CancellationToken) {
+    let service = JobService::new(context.db.clone());
+    let mut in_flight = JoinSet::new();
+    let mut interval = tokio::time::interval(RUNNER_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+            // A slot freed up: look for more work right away
+            Some(result) = in_flight.join_next(), if !in_flight.is_empty() => {
+                if let Err(e) = result {
+                    tracing::error!("Job task in queue {} failed: {}", queue, e);
+                }
+            }
+        }
+
+        let free = concurrency.saturating_sub(in_flight.len());
+        if free == 0 {
+            continue;
+        }
+
+        match service.claim(queue, free as i64).await {
+            Ok(jobs) => {
+                for job in jobs {
+                    let context = context.clone();
+                    in_flight.spawn(async move { run_job(&context, job).await });
+                }
+            }
+            Err(e) => tracing::error!("Failed to claim jobs from queue {}: {}", queue, e),
+        }
+    }
+
+    // Graceful shutdown: claim nothing new but let running jobs finish
+    if !in_flight.is_empty() {
+        tracing::info!("Waiting for {} running {} jobs", in_flight.len(), queue);
+    }
+    while let Some(result) = in_flight.join_next().await {
+        if let Err(e) = result {
+            tracing::error!("Job task in queue {} failed: {}", queue, e);
+        }
+    }
+}
+
+async fn run_job(context: &JobContext, job: ClaimedJob) {
+    let service = JobService::new(context.db.clone());
+    let result = match Job::from_row(&job.kind, job.payload) {
+        // A panicking job is recorded as a failed attempt instead of staying 'running'
+        Ok(typed) => {
+            let execution = AssertUnwindSafe(typed.execute(context)).catch_unwind();
+            with_heartbeat(&service, job.id, execution)
+                .await
+                .unwrap_or_else(|_| Err("Job panicked".into()))
+        }
+        Err(e) => Err(e),
+    };
+
+    let recorded = match result {
+        Ok(()) => service.mark_completed(job.id).await,
+        Err(e) => {
+            tracing::warn!("Job {} ({}) failed (attempt {}/{}): {}", job.id, job.kind, job.attempts, job.max_attempts, e);
+            service.mark_failed(job.id, job.attempts, job.max_attempts, &e.to_string()).await
+        }
+    };
+
+    if let Err(e) = recorded {
+        tracing::error!("Failed to record result of job {}: {}", job.id, e);
+    }
+}
+
+// Keeps the job's heartbeat fresh while `execution` runs, so long imports and exports
+// aren't mistaken for jobs of a crashed instance
+async fn with_heartbeat<F: Future>(service: &JobService, job_id: Uuid, execution: F) -> F::Output {
+    tokio::pin!(execution);
+    let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+    // The first tick completes immediately; claiming already set the heartbeat
+    interval.tick().await;
+
+    loop {
+        tokio::select! {
+            output = &mut execution => return output,
+            _ = interval.tick() => {
+                if let Err(e) = service.heartbeat(job_id).await {
+                    tracing::warn!("Failed to refresh heartbeat of job {}: {}", job_id, e);
+                }
+            }
+        }
+    }
+}
+
+async fn run_scheduler(db: PgPool, shutdown: CancellationToken) {
+    let service = JobService::new(db);
+    let definitions = recurring_jobs();
+
+    if let Err(e) = service.sync_recurring(&definitions).await {
+        tracing::error!("Failed to register recurring jobs: {}", e);
+    }
+
+    let mut interval = tokio::time::interval(SCHEDULER_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+
+        match service.enqueue_due_recurring(&definitions).await {
+            Ok(0) => {}
+            Ok(queued) => tracing::debug!("Queued {} recurring jobs", queued),
+            Err(e) => tracing::error!("Recurring job run failed: {}", e),
+        }
+    }
+}
+
+pub struct JobService {
+    db: PgPool,
+}
+
+impl JobService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    // Takes an executor so jobs can be queued in the same transaction as the change they belong to
+    pub async fn enqueue<'e>(
+        executor: impl PgExecutor<'e>,
+        job: &Job,
+        run_at: DateTime<Utc>,
+    ) -> Result<Uuid, AppError> {
+        let (kind, payload) = job.to_row()?;
+
+        let job_id = sqlx::query_scalar!(
+            "INSERT INTO jobs (queue, kind, payload, run_at, max_attempts) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+            job.queue(),
+            kind,
+            payload,
+            run_at,
+            job.max_attempts()
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(job_id)
+    }
+
+    // Cancel waiting jobs identical to `job` (e.g. the publish job of a rescheduled article)
+    pub async fn cancel_pending<'e>(
+        executor: impl PgExecutor<'e>,
+        job: &Job,
+    ) -> Result<u64, AppError> {
+        let (kind, payload) = job.to_row()?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = 'cancelled', finished_at = NOW(), updated_at = NOW()
+            WHERE kind = $1 AND payload = $2 AND status IN ('pending', 'failed')
+            "#,
+            kind,
+            payload
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn claim(&self, queue: &str, limit: i64) -> Result<Vec<ClaimedJob>, Box<dyn Error + Send + Sync>> {
+        // Running jobs whose heartbeat stopped (crashed instance) are picked up again
+        let rows = sqlx::query!(
+            r#"
+            WITH due AS (
+                SELECT id FROM jobs
+                WHERE queue = $1
+                    AND ((status IN ('pending', 'failed') AND run_at <= NOW() AND attempts < max_attempts)
+                        OR (status = 'running' AND heartbeat_at < NOW() - make_interval(secs => $3)))
+                ORDER BY run_at
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE jobs j
+            SET status = 'running', attempts = j.attempts + 1, started_at = NOW(), heartbeat_at = NOW(),
+                updated_at = NOW()
+            FROM due
+            WHERE j.id = due.id
+            RETURNING j.id, j.kind, j.payload, j.attempts, j.max_attempts
+            "#,
+            queue,
+            limit,
+            STALE_HEARTBEAT_SECONDS
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ClaimedJob {
+                id: row.id,
+                kind: row.kind,
+                payload: row.payload,
+                attempts: row.attempts,
+                max_attempts: row.max_attempts,
+            })
+            .collect())
+    }
+
+    async fn heartbeat(&self, job_id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+        sqlx::query!(
+            "UPDATE jobs SET heartbeat_at = NOW() WHERE id = $1 AND status = 'running'",
+            job_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn mark_completed(&self, job_id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+        sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = 'completed', last_error = NULL, finished_at = NOW(), updated_at = NOW()
+            WHERE id = $1
+            "#,
+            job_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn mark_failed(
+        &self,
+        job_id: Uuid,
+        attempts: i32,
+        max_attempts: i32,
+        error: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let status = if attempts >= max_attempts { JobStatus::Dead } else { JobStatus::Failed };
+        // Exponential backoff: 2, 4, 8, 16 minutes
+        let backoff_minutes = 1i32 << attempts.clamp(1, 10);
+
+        sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = $2, last_error = $3,
+                run_at = CASE WHEN $2 = 'dead'::job_status THEN run_at ELSE NOW() + make_interval(mins => $4) END,
+                finished_at = CASE WHEN $2 = 'dead'::job_status THEN NOW() ELSE NULL END,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+            job_id,
+            status as JobStatus,
+            error,
+            backoff_minutes
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    // Register recurring jobs; a changed schedule takes effect from now
+    async fn sync_recurring(&self, definitions: &[RecurringJob]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for definition in definitions {
+            let next_run_at = CronSchedule::parse(definition.schedule)?
+                .next_after(Utc::now())
+                .ok_or_else(|| format!("Cron schedule '{}' never fires", definition.schedule))?;
+
+            sqlx::query!(
+                r#"
+                INSERT INTO recurring_jobs (name, schedule, next_run_at)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (name) DO UPDATE
+                SET schedule = EXCLUDED.schedule,
+                    next_run_at = CASE
+                        WHEN recurring_jobs.schedule = EXCLUDED.schedule THEN recurring_jobs.next_run_at
+                        ELSE EXCLUDED.next_run_at
+                    END,
+                    updated_at = NOW()
+                "#,
+                definition.name,
+                definition.schedule,
+                next_run_at
+            )
+            .execute(&self.db)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn enqueue_due_recurring(&self, definitions: &[RecurringJob]) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let mut tx = self.db.begin().await?;
+
+        // Only one instance gets each due row
+        let due = sqlx::query!(
+            r#"
+            SELECT r.name, j.status as "last_status?: JobStatus"
+            FROM recurring_jobs r
+            LEFT JOIN jobs j ON j.id = r.last_job_id
+            WHERE r.next_run_at <= NOW()
+            FOR UPDATE OF r SKIP LOCKED
+            "#
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut queued = 0;
+        for row in due {
+            // Removed from the code; leave the row alone
+            let Some(definition) = definitions.iter().find(|d| d.name == row.name) else {
+                continue;
+            };
+
+            let next_run_at = CronSchedule::parse(definition.schedule)?
+                .next_after(Utc::now())
+                .ok_or_else(|| format!("Cron schedule '{}' never fires", definition.schedule))?;
+
+            // Don't pile up runs while the previous one is still waiting or running
+            let previous_unfinished = matches!(
+                row.last_status,
+                Some(JobStatus::Pending | JobStatus::Running | JobStatus::Failed)
+            );
+
+            let job_id = if previous_unfinished {
+                None
+            } else {
+                queued += 1;
+                Some(Self::enqueue(&mut *tx, &definition.job, Utc::now()).await?)
+            };
+
+            sqlx::query!(
+                r#"
+                UPDATE recurring_jobs
+                SET next_run_at = $2,
+                    last_run_at = CASE WHEN $3::uuid IS NULL THEN last_run_at ELSE NOW() END,
+                    last_job_id = COALESCE($3, last_job_id),
+                    updated_at = NOW()
+                WHERE name = $1
+                "#,
+                row.name,
+                next_run_at,
+                job_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(queued)
+    }
+
+    async fn prune(&self, retention_days: i32) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM jobs
+            WHERE status IN ('completed', 'cancelled')
+                AND finished_at < NOW() - make_interval(days => $1)
+                AND id NOT IN (SELECT last_job_id FROM recurring_jobs WHERE last_job_id IS NOT NULL)
+            "#,
+            retention_days
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn list_jobs(&self, params: JobQueryParams) -> Result<JobListResponse, AppError> {
+        let limit = params.limit.unwrap_or(50).clamp(1, 200);
+        let page = params.page.unwrap_or(1).max(1);
+        let offset = (page - 1) * limit;
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                id, queue, kind, payload, status as "status: JobStatus", run_at, attempts, max_attempts,
+                last_error, started_at, finished_at, created_at, updated_at,
+                COUNT(*) OVER () as "total!"
+            FROM jobs
+            WHERE ($1::job_status IS NULL OR status = $1)
+                AND ($2::text IS NULL OR queue = $2)
+                AND ($3::text IS NULL OR kind = $3)
+            ORDER BY created_at DESC
+            LIMIT $4 OFFSET $5
+            "#,
+            params.status as Option<JobStatus>,
+            params.queue,
+            params.kind,
+            limit,
+            offset
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let total = rows.first().map(|row| row.total).unwrap_or(0);
+        let jobs = rows
+            .into_iter()
+            .map(|row| JobResponse {
+                id: row.id,
+                queue: row.queue,
+                kind: row.kind,
+                payload: redact_payload(row.payload),
+                status: row.status,
+                run_at: row.run_at,
+                attempts: row.attempts,
+                max_attempts: row.max_attempts,
+                last_error: row.last_error,
+                started_at: row.started_at,
+                finished_at: row.finished_at,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            })
+            .collect();
+
+        Ok(JobListResponse { jobs, total, page, limit })
+    }
+
+    pub async fn get_job(&self, job_id: Uuid) -> Result<JobResponse, AppError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                id, queue, kind, payload, status as "status: JobStatus", run_at, attempts, max_attempts,
+                last_error, started_at, finished_at, created_at, updated_at
+            FROM jobs
+            WHERE id = $1
+            "#,
+            job_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Job not found".to_string()))?;
+
+        Ok(JobResponse {
+            id: row.id,
+            queue: row.queue,
+            kind: row.kind,
+            payload: redact_payload(row.payload),
+            status: row.status,
+            run_at: row.run_at,
+            attempts: row.attempts,
+            max_attempts: row.max_attempts,
+            last_error: row.last_error,
+            started_at: row.started_at,
+            finished_at: row.finished_at,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+
+    // Put a dead, failed or cancelled job back in the queue with a fresh set of attempts
+    pub async fn retry_job(&self, job_id: Uuid) -> Result<JobResponse, AppError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = 'pending', attempts = 0, run_at = NOW(), started_at = NULL, finished_at = NULL, updated_at = NOW()
+            WHERE id = $1 AND status IN ('dead', 'failed', 'cancelled')
+            "#,
+            job_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            self.get_job(job_id).await?;
+            return Err(AppError::Conflict("Only dead, failed or cancelled jobs can be retried".to_string()));
+        }
+
+        self.get_job(job_id).await
+    }
+
+    pub async fn cancel_job(&self, job_id: Uuid) -> Result<JobResponse, AppError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = 'cancelled', finished_at = NOW(), updated_at = NOW()
+            WHERE id = $1 AND status IN ('pending', 'failed')
+            "#,
+            job_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            self.get_job(job_id).await?;
+            return Err(AppError::Conflict("Only pending or failed jobs can be cancelled".to_string()));
+        }
+
+        self.get_job(job_id).await
+    }
+
+    pub async fn stats(&self) -> Result<JobStatsResponse, AppError> {
+        let counts = sqlx::query!(
+            r#"
+            SELECT queue, status as "status: JobStatus", COUNT(*) as "count!"
+            FROM jobs
+            WHERE status IN ('pending', 'running', 'failed', 'dead')
+            GROUP BY queue, status
+            "#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let queues = QUEUES
+            .iter()
+            .map(|&(queue, concurrency)| {
+                let count = |status: JobStatus| {
+                    counts
+                        .iter()
+                        .find(|row| row.queue == queue && row.status == status)
+                        .map(|row| row.count)
+                        .unwrap_or(0)
+                };
+                QueueStats {
+                    queue: queue.to_string(),
+                    concurrency,
+                    pending: count(JobStatus::Pending),
+                    running: count(JobStatus::Running),
+                    failed: count(JobStatus::Failed),
+                    dead: count(JobStatus::Dead),
+                }
+            })
+            .collect();
+
+        let recurring = sqlx::query_as!(
+            RecurringJobResponse,
+            "SELECT name, schedule, next_run_at, last_run_at, last_job_id FROM recurring_jobs ORDER BY name"
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(JobStatsResponse { queues, recurring })
+    }
+}
+
+// Email bodies can be large and personal; the admin view only needs the envelope
+fn redact_payload(mut payload: Value) -> Value {
+    if let Some(message) = payload.get_mut("message").and_then(Value::as_object_mut) {
+        for field in ["html_body", "text_body"] {
+            if message.contains_key(field) {
+                message.insert(field.to_string(), Value::String("[redacted]".to_string()));
+            }
+        }
+    }
+    payload
+}
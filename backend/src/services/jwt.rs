@@ -0,0 +1,346 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm, OctetKeyPairParameters,
+        OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use once_cell::sync::OnceCell;
+use rsa::{
+    pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey},
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+    traits::PublicKeyParts,
+    RsaPrivateKey, RsaPublicKey,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+
+use crate::config::{JwtConfig, JwtKeyConfig};
+
+static KEYS: OnceCell<JwtKeys> = OnceCell::new();
+
+// Loads the configured keys once at startup so a bad key fails before the server starts
+pub fn init(config: &JwtConfig) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let keys = JwtKeys::from_config(config)?;
+    KEYS.get_or_init(|| keys);
+    Ok(())
+}
+
+pub fn keys() -> &'static JwtKeys {
+    KEYS.get().expect("JWT keys are loaded at startup")
+}
+
+struct SigningKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: EncodingKey,
+}
+
+struct VerificationKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+    // None for the development HMAC secret, which must never be published
+    jwk: Option<Jwk>,
+}
+
+// Keys session tokens are signed and verified with. Tokens carry the `kid` of their
+// signing key, so retired keys can keep verifying old tokens after a rotation.
+pub struct JwtKeys {
+    signing: SigningKey,
+    verification: Vec<VerificationKey>,
+}
+
+impl JwtKeys {
+    pub fn from_config(config: &JwtConfig) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let Some(signing_key) = &config.signing_key else {
+            tracing::warn!("JWT_PRIVATE_KEY_FILE is not set; signing tokens with the HS256 JWT_SECRET (development only)");
+            return Ok(Self {
+                signing: SigningKey {
+                    kid: None,
+                    algorithm: Algorithm::HS256,
+                    key: EncodingKey::from_secret(config.secret.as_bytes()),
+                },
+                verification: vec![VerificationKey {
+                    kid: None,
+                    algorithm: Algorithm::HS256,
+                    key: DecodingKey::from_secret(config.secret.as_bytes()),
+                    jwk: None,
+                }],
+            });
+        };
+
+        let (signing, current) = load_private_key(signing_key)?;
+        let mut verification = vec![current];
+        for key in &config.verification_keys {
+            let key = load_public_key(key)?;
+            if verification.iter().any(|existing| existing.kid == key.kid) {
+                return Err(format!("Duplicate JWT key id '{}'", key.kid.unwrap_or_default()).into());
+            }
+            verification.push(key);
+        }
+
+        tracing::info!(
+            "Signing tokens with {:?} key '{}' ({} verification keys)",
+            signing.algorithm,
+            signing.kid.as_deref().unwrap_or_default(),
+            verification.len()
+        );
+
+        Ok(Self { signing, verification })
+    }
+
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String, jsonwebtoken::errors::Error> {
+        let mut header = Header::new(self.signing.algorithm);
+        header.kid = self.signing.kid.clone();
+        encode(&header, claims, &self.signing.key)
+    }
+
+    pub fn verify<T: DeserializeOwned>(&self, token: &str) -> Result<T, Box<dyn Error + Send + Sync>> {
+        let header = decode_header(token)?;
+        // Matching the algorithm too keeps a public key from being used as an HMAC secret
+        let key = self
+            .verification
+            .iter()
+            .find(|key| key.kid == header.kid && key.algorithm == header.alg)
+            .ok_or("Token is signed with an unknown key")?;
+
+        Ok(decode::<T>(token, &key.key, &Validation::new(key.algorithm))?.claims)
+    }
+
+    // Public verification keys, served at /.well-known/jwks.json
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self.verification.iter().filter_map(|key| key.jwk.clone()).collect(),
+        }
+    }
+}
+
+fn load_private_key(config: &JwtKeyConfig) -> Result<(SigningKey, VerificationKey), Box<dyn Error + Send + Sync>> {
+    let pem = config.pem.trim();
+
+    let (algorithm, key, params) = if let Ok(private_key) =
+        RsaPrivateKey::from_pkcs8_pem(pem).or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+    {
+        (Algorithm::RS256, EncodingKey::from_rsa_pem(pem.as_bytes())?, rsa_parameters(&private_key.to_public_key()))
+    } else if let Ok(private_key) = ed25519_dalek::SigningKey::from_pkcs8_pem(pem) {
+        (Algorithm::EdDSA, EncodingKey::from_ed_pem(pem.as_bytes())?, ed25519_parameters(&private_key.verifying_key()))
+    } else {
+        return Err("JWT signing key must be an RSA or Ed25519 private key in PEM format".into());
+    };
+
+    let verification = verification_key(config.kid.clone(), algorithm, params)?;
+    let signing = SigningKey {
+        kid: verification.kid.clone(),
+        algorithm,
+        key,
+    };
+
+    Ok((signing, verification))
+}
+
+// Retired keys only need their public half, but the old private key file works too
+fn load_public_key(config: &JwtKeyConfig) -> Result<VerificationKey, Box<dyn Error + Send + Sync>> {
+    let pem = config.pem.trim();
+
+    if let Ok(public_key) = RsaPublicKey::from_public_key_pem(pem).or_else(|_| RsaPublicKey::from_pkcs1_pem(pem)) {
+        verification_key(config.kid.clone(), Algorithm::RS256, rsa_parameters(&public_key))
+    } else if let Ok(public_key) = ed25519_dalek::VerifyingKey::from_public_key_pem(pem) {
+        verification_key(config.kid.clone(), Algorithm::EdDSA, ed25519_parameters(&public_key))
+    } else {
+        load_private_key(config)
+            .map(|(_, verification)| verification)
+            .map_err(|_| "JWT verification key must be an RSA or Ed25519 key in PEM format".into())
+    }
+}
+
+fn rsa_parameters(key: &RsaPublicKey) -> AlgorithmParameters {
+    AlgorithmParameters::RSA(RSAKeyParameters {
+        key_type: RSAKeyType::RSA,
+        n: URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+        e: URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+    })
+}
+
+fn ed25519_parameters(key: &ed25519_dalek::VerifyingKey) -> AlgorithmParameters {
+    AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+        key_type: OctetKeyPairType::OctetKeyPair,
+        curve: EllipticCurve::Ed25519,
+        x: URL_SAFE_NO_PAD.encode(key.as_bytes()),
+    })
+}
+
+fn verification_key(
+    kid: Option<String>,
+    algorithm: Algorithm,
+    params: AlgorithmParameters,
+) -> Result<VerificationKey, Box<dyn Error + Send + Sync>> {
+    // Default to the RFC 7638 thumbprint, which stays the same wherever the key is deployed
+    let kid = kid.unwrap_or_else(|| thumbprint(&params));
+    let jwk = Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(match algorithm {
+                Algorithm::EdDSA => KeyAlgorithm::EdDSA,
+                _ => KeyAlgorithm::RS256,
+            }),
+            key_id: Some(kid.clone()),
+            ..Default::default()
+        },
+        algorithm: params,
+    };
+
+    Ok(VerificationKey {
+        kid: Some(kid),
+        algorithm,
+        key: DecodingKey::from_jwk(&jwk)?,
+        jwk: Some(jwk),
+    })
+}
+
+fn thumbprint(params: &AlgorithmParameters) -> String {
+    // Required members only, in lexicographic order
+    let canonical = match params {
+        AlgorithmParameters::RSA(rsa) => format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, rsa.e, rsa.n),
+        AlgorithmParameters::OctetKeyPair(okp) => format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, okp.x),
+        _ => unreachable!("only RSA and Ed25519 keys are loaded"),
+    };
+    URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::pkcs8::{spki::der::pem::LineEnding, EncodePrivateKey, EncodePublicKey};
+    use serde_json::{json, Value};
+
+    fn private_pem(seed: u8) -> String {
+        ed25519_dalek::SigningKey::from_bytes(&[seed; 32])
+            .to_pkcs8_pem(LineEnding::LF)
+            .unwrap()
+            .to_string()
+    }
+
+    fn public_pem(seed: u8) -> String {
+        ed25519_dalek::SigningKey::from_bytes(&[seed; 32])
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .unwrap()
+    }
+
+    fn key(kid: &str, pem: String) -> JwtKeyConfig {
+        JwtKeyConfig { kid: Some(kid.to_string()), pem }
+    }
+
+    fn keys(signing_key: Option<JwtKeyConfig>, verification_keys: Vec<JwtKeyConfig>) -> JwtKeys {
+        JwtKeys::from_config(&JwtConfig {
+            signing_key,
+            verification_keys,
+            secret: "development secret".to_string(),
+        })
+        .unwrap()
+    }
+
+    fn claims() -> Value {
+        json!({ "sub": "user", "exp": 4_102_444_800u64 })
+    }
+
+    #[test]
+    fn signs_with_the_signing_key_id() {
+        let keys = keys(Some(key("current", private_pem(1))), vec![]);
+        let token = keys.sign(&claims()).unwrap();
+
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.kid.as_deref(), Some("current"));
+        assert_eq!(header.alg, Algorithm::EdDSA);
+        assert_eq!(keys.verify::<Value>(&token).unwrap(), claims());
+    }
+
+    #[test]
+    fn retired_keys_keep_verifying_after_a_rotation() {
+        let old = keys(Some(key("old", private_pem(1))), vec![]);
+        let token = old.sign(&claims()).unwrap();
+
+        let rotated = keys(Some(key("new", private_pem(2))), vec![key("old", public_pem(1))]);
+        assert_eq!(rotated.verify::<Value>(&token).unwrap(), claims());
+        assert_eq!(decode_header(&rotated.sign(&claims()).unwrap()).unwrap().kid.as_deref(), Some("new"));
+
+        // Once the old key is dropped its tokens stop working
+        let retired = keys(Some(key("new", private_pem(2))), vec![]);
+        assert!(retired.verify::<Value>(&token).is_err());
+    }
+
+    #[test]
+    fn old_private_key_files_work_as_verification_keys() {
+        let token = keys(Some(key("old", private_pem(1))), vec![]).sign(&claims()).unwrap();
+        let rotated = keys(Some(key("new", private_pem(2))), vec![key("old", private_pem(1))]);
+
+        assert_eq!(rotated.verify::<Value>(&token).unwrap(), claims());
+    }
+
+    #[test]
+    fn rejects_tokens_claiming_another_keys_id() {
+        // Signed with key 1 but labelled as key 2
+        let forged = keys(Some(key("new", private_pem(1))), vec![]).sign(&claims()).unwrap();
+        let keys = keys(Some(key("new", private_pem(2))), vec![key("old", public_pem(1))]);
+
+        assert!(keys.verify::<Value>(&forged).is_err());
+    }
+
+    #[test]
+    fn rejects_hmac_tokens_for_asymmetric_key_ids() {
+        let keys = keys(Some(key("current", private_pem(1))), vec![]);
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("current".to_string());
+        let token = encode(&header, &claims(), &EncodingKey::from_secret(public_pem(1).as_bytes())).unwrap();
+
+        assert!(keys.verify::<Value>(&token).is_err());
+    }
+
+    #[test]
+    fn key_ids_default_to_the_jwk_thumbprint() {
+        let keys = keys(Some(JwtKeyConfig { kid: None, pem: private_pem(1) }), vec![]);
+        let jwk = &keys.jwks().keys[0];
+
+        assert_eq!(jwk.common.key_id.as_deref(), Some(thumbprint(&jwk.algorithm).as_str()));
+        assert_eq!(decode_header(&keys.sign(&claims()).unwrap()).unwrap().kid, jwk.common.key_id);
+    }
+
+    #[test]
+    fn thumbprint_matches_rfc_8037() {
+        let params = AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            key_type: OctetKeyPairType::OctetKeyPair,
+            curve: EllipticCurve::Ed25519,
+            x: "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo".to_string(),
+        });
+
+        assert_eq!(thumbprint(&params), "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k");
+    }
+
+    #[test]
+    fn rejects_duplicate_key_ids() {
+        let result = JwtKeys::from_config(&JwtConfig {
+            signing_key: Some(key("current", private_pem(1))),
+            verification_keys: vec![key("current", public_pem(2))],
+            secret: String::new(),
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn jwks_lists_public_keys_but_never_the_hmac_secret() {
+        let rotated = keys(Some(key("new", private_pem(2))), vec![key("old", public_pem(1))]);
+        let kids: Vec<_> = rotated.jwks().keys.into_iter().filter_map(|jwk| jwk.common.key_id).collect();
+        assert_eq!(kids, ["new", "old"]);
+
+        let development = keys(None, vec![]);
+        assert!(development.jwks().keys.is_empty());
+        let token = development.sign(&claims()).unwrap();
+        assert_eq!(decode_header(&token).unwrap().alg, Algorithm::HS256);
+        assert_eq!(development.verify::<Value>(&token).unwrap(), claims());
+    }
+}
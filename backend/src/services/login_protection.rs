@@ -0,0 +1,258 @@
+use askama::Template;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    services::{
+        jobs::{Job, JobService},
+        mailer::EmailMessage,
+    },
+};
+
+// Failed attempts older than this no longer count
+const FAILURE_WINDOW_MINUTES: i64 = 15;
+// Failures allowed before every further attempt has to wait
+const FREE_ATTEMPTS: i64 = 3;
+// Waits double with each failure (1s, 2s, 4s, ...) up to this
+const MAX_DELAY_SECONDS: i64 = 60;
+// Account failures within the window that lock the account
+const LOCKOUT_THRESHOLD: i64 = 10;
+const LOCKOUT_MINUTES: i64 = 30;
+// Failures from one IP within the window, across all accounts, before it is blocked
+const IP_FAILURE_LIMIT: i64 = 50;
+// Failed attempts are kept this long for auditing, then pruned
+const RETENTION_DAYS: i32 = 7;
+
+#[derive(Template)]
+#[template(path = "email/account_locked.html")]
+struct LockedHtmlTemplate<'a> {
+    name: &'a str,
+    attempts: i64,
+    ip_address: &'a str,
+    locked_until: &'a str,
+    security_url: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "email/account_locked.txt")]
+struct LockedTextTemplate<'a> {
+    name: &'a str,
+    attempts: i64,
+    ip_address: &'a str,
+    locked_until: &'a str,
+    security_url: &'a str,
+}
+
+pub struct LoginProtectionService<'a> {
+    db: PgPool,
+    config: &'a Config,
+}
+
+impl<'a> LoginProtectionService<'a> {
+    pub fn new(db: PgPool, config: &'a Config) -> Self {
+        Self { db, config }
+    }
+
+    // Checked before the password, so blocked attempts don't reveal whether it was right
+    pub async fn check(&self, user_id: Option<Uuid>, ip_address: &str) -> Result<(), AppError> {
+        let now = Utc::now();
+
+        if let Some(user_id) = user_id {
+            let locked_until = sqlx::query_scalar!("SELECT locked_until FROM users WHERE id = $1", user_id)
+                .fetch_optional(&self.db)
+                .await?
+                .flatten();
+            if let Some(locked_until) = locked_until.filter(|until| *until > now) {
+                return Err(throttled(seconds_until(locked_until, now), true));
+            }
+        }
+
+        let ip = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "failures!", MIN(created_at) as oldest, MAX(created_at) as latest
+            FROM failed_logins
+            WHERE ip_address = $1 AND created_at > NOW() - make_interval(mins => $2)
+            "#,
+            ip_address,
+            FAILURE_WINDOW_MINUTES as i32
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        // The IP is blocked until its oldest failure leaves the window
+        if ip.failures >= IP_FAILURE_LIMIT {
+            if let Some(oldest) = ip.oldest {
+                return Err(throttled(
+                    seconds_until(oldest + Duration::minutes(FAILURE_WINDOW_MINUTES), now),
+                    false,
+                ));
+            }
+        }
+
+        let mut next_attempt = next_attempt_at(ip.failures, ip.latest);
+        if let Some(user_id) = user_id {
+            let account = sqlx::query!(
+                r#"
+                SELECT COUNT(*) as "failures!", MAX(created_at) as latest
+                FROM failed_logins
+                WHERE user_id = $1 AND created_at > NOW() - make_interval(mins => $2)
+                "#,
+                user_id,
+                FAILURE_WINDOW_MINUTES as i32
+            )
+            .fetch_one(&self.db)
+            .await?;
+            next_attempt = next_attempt.max(next_attempt_at(account.failures, account.latest));
+        }
+
+        match next_attempt.filter(|at| *at > now) {
+            Some(at) => Err(throttled(seconds_until(at, now), false)),
+            None => Ok(()),
+        }
+    }
+
+    // Records a wrong password (or unknown account) and locks the account once it
+    // reaches the threshold, telling the owner by email
+    pub async fn record_failure(&self, user_id: Option<Uuid>, ip_address: &str) -> Result<(), AppError> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query!(
+            "INSERT INTO failed_logins (user_id, ip_address) VALUES ($1, $2)",
+            user_id,
+            ip_address
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let Some(user_id) = user_id else {
+            tx.commit().await?;
+            return Ok(());
+        };
+
+        let failures = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "failures!"
+            FROM failed_logins
+            WHERE user_id = $1 AND created_at > NOW() - make_interval(mins => $2)
+            "#,
+            user_id,
+            FAILURE_WINDOW_MINUTES as i32
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if failures >= LOCKOUT_THRESHOLD {
+            // Only the attempt that starts a lock sends an email
+            let locked = sqlx::query!(
+                r#"
+                UPDATE users
+                SET locked_until = NOW() + make_interval(mins => $2)
+                WHERE id = $1 AND (locked_until IS NULL OR locked_until <= NOW())
+                RETURNING email, username, display_name, locked_until as "locked_until!"
+                "#,
+                user_id,
+                LOCKOUT_MINUTES as i32
+            )
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            if let Some(user) = locked {
+                tracing::warn!("Locked account {} after {} failed sign-in attempts", user_id, failures);
+
+                let name = user.display_name.as_deref().unwrap_or(&user.username);
+                let locked_until = user.locked_until.format("%B %-d, %Y at %H:%M UTC").to_string();
+                let security_url = format!("{}/settings/security", self.config.frontend_url);
+                let message = EmailMessage {
+                    to: user.email,
+                    subject: "Your FastBlog account has been temporarily locked".to_string(),
+                    html_body: LockedHtmlTemplate {
+                        name,
+                        attempts: failures,
+                        ip_address,
+                        locked_until: &locked_until,
+                        security_url: &security_url,
+                    }
+                    .render()?,
+                    text_body: LockedTextTemplate {
+                        name,
+                        attempts: failures,
+                        ip_address,
+                        locked_until: &locked_until,
+                        security_url: &security_url,
+                    }
+                    .render()?,
+                    headers: Vec::new(),
+                };
+                JobService::enqueue(&mut *tx, &Job::SendEmail { message }, Utc::now()).await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // A correct password starts the account's count over; the IP's count is left alone
+    pub async fn record_success(&self, user_id: Uuid) -> Result<(), AppError> {
+        sqlx::query!("DELETE FROM failed_logins WHERE user_id = $1", user_id)
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    // Admin override: lifts the lock and forgets the account's failed attempts
+    pub async fn unlock(&self, user_id: Uuid) -> Result<(), AppError> {
+        let mut tx = self.db.begin().await?;
+
+        let result = sqlx::query!("UPDATE users SET locked_until = NULL WHERE id = $1", user_id)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("User not found".to_string()));
+        }
+
+        sqlx::query!("DELETE FROM failed_logins WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn prune(&self) -> Result<u64, AppError> {
+        let result = sqlx::query!(
+            "DELETE FROM failed_logins WHERE created_at < NOW() - make_interval(days => $1)",
+            RETENTION_DAYS
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+// Returned instead of checking the password while an account or IP has to wait
+fn throttled(retry_after: u64, locked: bool) -> AppError {
+    if locked {
+        let message = format!("Too many failed sign-in attempts; the account is locked for {} seconds", retry_after);
+        AppError::Locked { message, retry_after }
+    } else {
+        let message = format!("Too many failed sign-in attempts; try again in {} seconds", retry_after);
+        AppError::RateLimited { message, retry_after }
+    }
+}
+
+// Progressive delay: the first few failures are free, then each one doubles the wait
+fn next_attempt_at(failures: i64, latest: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    if failures < FREE_ATTEMPTS {
+        return None;
+    }
+    let exponent = (failures - FREE_ATTEMPTS).min(16) as u32;
+    let delay = 2i64.pow(exponent).min(MAX_DELAY_SECONDS);
+    latest.map(|latest| latest + Duration::seconds(delay))
+}
+
+fn seconds_until(time: DateTime<Utc>, now: DateTime<Utc>) -> u64 {
+    ((time - now).num_milliseconds() + 999).div_euclid(1000).max(1) as u64
+}
@@ -0,0 +1,107 @@
+use futures::future::BoxFuture;
+use lettre::{
+    message::{
+        header::{HeaderName, HeaderValue},
+        Mailbox, MultiPart,
+    },
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::Arc;
+
+use crate::config::{Config, SmtpConfig};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub html_body: String,
+    pub text_body: String,
+    // Extra headers, e.g. List-Unsubscribe
+    pub headers: Vec<(String, String)>,
+}
+
+// Outgoing mail delivery, shared through AppState
+pub trait Mailer: Send + Sync {
+    fn send<'a>(&'a self, message: &'a EmailMessage) -> BoxFuture<'a, Result<(), Box<dyn Error + Send + Sync>>>;
+}
+
+// SMTP when configured, otherwise emails are only written to the log
+pub fn from_config(config: &Config) -> Result<Arc<dyn Mailer>, Box<dyn Error>> {
+    match &config.smtp_config {
+        Some(smtp_config) => Ok(Arc::new(SmtpMailer::new(smtp_config)?)),
+        None => {
+            tracing::warn!("SMTP is not configured, outgoing emails will only be logged");
+            Ok(Arc::new(LogMailer))
+        }
+    }
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(config: &SmtpConfig) -> Result<Self, Box<dyn Error>> {
+        // Port 465 expects implicit TLS, everything else upgrades with STARTTLS
+        let builder = if config.port == 465 {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+        };
+
+        let transport = builder
+            .port(config.port)
+            .credentials(Credentials::new(config.username.clone(), config.password.clone()))
+            .build();
+
+        Ok(Self {
+            transport,
+            from: config.from_email.parse()?,
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send<'a>(&'a self, message: &'a EmailMessage) -> BoxFuture<'a, Result<(), Box<dyn Error + Send + Sync>>> {
+        Box::pin(async move {
+            let mut builder = Message::builder()
+                .from(self.from.clone())
+                .to(message.to.parse()?)
+                .subject(&message.subject);
+
+            for (name, value) in &message.headers {
+                let name = HeaderName::new_from_ascii(name.clone())?;
+                builder = builder.raw_header(HeaderValue::new(name, value.clone()));
+            }
+
+            let email = builder.multipart(MultiPart::alternative_plain_html(
+                message.text_body.clone(),
+                message.html_body.clone(),
+            ))?;
+
+            self.transport.send(email).await?;
+            Ok(())
+        })
+    }
+}
+
+// Development fallback
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send<'a>(&'a self, message: &'a EmailMessage) -> BoxFuture<'a, Result<(), Box<dyn Error + Send + Sync>>> {
+        Box::pin(async move {
+            tracing::info!(
+                "Email to {} ({}):\n{}",
+                message.to,
+                message.subject,
+                message.text_body
+            );
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,341 @@
+use chrono::Utc;
+use sqlx::{PgExecutor, PgPool};
+use std::collections::HashSet;
+use std::error::Error;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::{
+    image::ImageVariantResponse,
+    media::{MediaListResponse, MediaQueryParams, MediaResponse, UpdateMediaRequest},
+};
+use crate::services::{
+    image,
+    jobs::{Job, JobService},
+    storage::{Storage, PRIVATE_PREFIX},
+};
+
+// Storage key prefix of article images; private media lives under `private/articles`
+pub const MEDIA_PREFIX: &str = "articles";
+// Lifetime of the signed URLs returned for private media
+const PRIVATE_URL_TTL: Duration = Duration::from_secs(60 * 60);
+// Media no article uses is deleted after this many days
+const UNUSED_RETENTION_DAYS: i32 = 30;
+// Files without a media row younger than this may still be mid-upload
+const FILE_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+pub struct MediaService {
+    db: PgPool,
+}
+
+impl MediaService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    pub async fn upload(
+        &self,
+        storage: &dyn Storage,
+        user_id: Uuid,
+        data: Vec<u8>,
+        alt_text: Option<String>,
+        caption: Option<String>,
+        is_private: bool,
+    ) -> Result<MediaResponse, AppError> {
+        let processed = tokio::task::spawn_blocking(move || image::process(&data, &image::ARTICLE)).await??;
+        let size_bytes: usize = processed.variants.iter().map(|variant| variant.data.len()).sum();
+
+        let media_id = Uuid::new_v4();
+        let prefix = if is_private {
+            format!("{}{}", PRIVATE_PREFIX, MEDIA_PREFIX)
+        } else {
+            MEDIA_PREFIX.to_string()
+        };
+        let (uploaded, keys) = image::save(storage, &processed, &prefix, media_id).await?;
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO media (id, user_id, url, width, height, size_bytes, variants, blurhash, dominant_color, alt_text, caption, is_private)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            "#,
+            media_id,
+            user_id,
+            uploaded.url,
+            uploaded.width as i32,
+            uploaded.height as i32,
+            size_bytes as i64,
+            serde_json::to_value(&uploaded.variants)?,
+            uploaded.blurhash,
+            uploaded.dominant_color,
+            non_empty(alt_text),
+            non_empty(caption),
+            is_private
+        )
+        .execute(&self.db)
+        .await;
+
+        if let Err(e) = inserted {
+            if let Some(key) = keys.first() {
+                image::remove_variants(storage, key).await;
+            }
+            return Err(e.into());
+        }
+
+        // AVIF copies are encoded in the background
+        if let Err(e) = JobService::enqueue(&self.db, &Job::EncodeAvif { keys }, Utc::now()).await {
+            tracing::warn!("Failed to queue AVIF encoding: {}", e);
+        }
+
+        self.get_media(storage, user_id, media_id).await
+    }
+
+    pub async fn list_media(
+        &self,
+        storage: &dyn Storage,
+        user_id: Uuid,
+        params: MediaQueryParams,
+    ) -> Result<MediaListResponse, AppError> {
+        let limit = params.limit.unwrap_or(30).clamp(1, 100);
+        let page = params.page.unwrap_or(1).max(1);
+        let offset = (page - 1) * limit;
+
+        let (media, total) = self
+            .fetch(storage, user_id, None, params.unused.unwrap_or(false), limit, offset)
+            .await?;
+
+        Ok(MediaListResponse { media, total, page, limit })
+    }
+
+    pub async fn get_media(
+        &self,
+        storage: &dyn Storage,
+        user_id: Uuid,
+        media_id: Uuid,
+    ) -> Result<MediaResponse, AppError> {
+        let (media, _) = self.fetch(storage, user_id, Some(media_id), false, 1, 0).await?;
+        media
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::NotFound("Media not found".to_string()))
+    }
+
+    pub async fn update_media(
+        &self,
+        storage: &dyn Storage,
+        user_id: Uuid,
+        media_id: Uuid,
+        request: UpdateMediaRequest,
+    ) -> Result<MediaResponse, AppError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE media
+            SET alt_text = CASE WHEN $3::text IS NULL THEN alt_text ELSE NULLIF($3, '') END,
+                caption = CASE WHEN $4::text IS NULL THEN caption ELSE NULLIF($4, '') END,
+                updated_at = NOW()
+            WHERE id = $1 AND user_id = $2
+            "#,
+            media_id,
+            user_id,
+            request.alt_text.map(|alt_text| alt_text.trim().to_string()),
+            request.caption.map(|caption| caption.trim().to_string())
+        )
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Media not found".to_string()));
+        }
+
+        self.get_media(storage, user_id, media_id).await
+    }
+
+    // Media used by a published or scheduled article can't be deleted; drafts just lose the reference
+    pub async fn delete_media(
+        &self,
+        storage: &dyn Storage,
+        user_id: Uuid,
+        media_id: Uuid,
+    ) -> Result<(), AppError> {
+        let deleted = sqlx::query_scalar!(
+            r#"
+            DELETE FROM media m
+            WHERE m.id = $1 AND m.user_id = $2
+                AND NOT EXISTS (
+                    SELECT 1 FROM article_media am
+                    JOIN articles a ON a.id = am.article_id
+                    WHERE am.media_id = m.id AND a.status IN ('published', 'scheduled')
+                )
+            RETURNING m.url
+            "#,
+            media_id,
+            user_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        match deleted {
+            Some(url) => {
+                if let Some(key) = storage.key_from_url(&url) {
+                    image::remove_variants(storage, &key).await;
+                }
+                Ok(())
+            }
+            None => {
+                self.get_media(storage, user_id, media_id).await?;
+                Err(AppError::Conflict("Media is used in a published article".to_string()))
+            }
+        }
+    }
+
+    // Record which of the author's media an article's content and featured image use.
+    // Matches on `articles/{id}_` so it works whatever base URL the files are served from.
+    // Call after every write to either.
+    pub async fn sync_article_references<'e>(
+        executor: impl PgExecutor<'e>,
+        article_id: Uuid,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        sqlx::query!(
+            r#"
+            WITH current AS (
+                SELECT m.id
+                FROM articles a
+                JOIN media m ON m.user_id = a.author_id
+                WHERE a.id = $1
+                    AND (strpos(a.content, 'articles/' || m.id::text || '_') > 0
+                        OR strpos(COALESCE(a.featured_image_url, ''), 'articles/' || m.id::text || '_') > 0)
+            ),
+            removed AS (
+                DELETE FROM article_media
+                WHERE article_id = $1 AND media_id NOT IN (SELECT id FROM current)
+                RETURNING media_id
+            ),
+            touched AS (
+                UPDATE media SET last_referenced_at = NOW()
+                WHERE id IN (SELECT id FROM current) OR id IN (SELECT media_id FROM removed)
+            )
+            INSERT INTO article_media (article_id, media_id)
+            SELECT $1, id FROM current
+            ON CONFLICT DO NOTHING
+            "#,
+            article_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    // Garbage collection: media no article has used for UNUSED_RETENTION_DAYS, and files without a row.
+    // Returns (rows, files) removed.
+    pub async fn cleanup_unused(&self, storage: &dyn Storage) -> Result<(u64, usize), Box<dyn Error + Send + Sync>> {
+        let deleted = sqlx::query!(
+            r#"
+            DELETE FROM media m
+            WHERE NOT EXISTS (SELECT 1 FROM article_media am WHERE am.media_id = m.id)
+                AND GREATEST(m.updated_at, COALESCE(m.last_referenced_at, m.created_at))
+                    < NOW() - make_interval(days => $1)
+            "#,
+            UNUSED_RETENTION_DAYS
+        )
+        .execute(&self.db)
+        .await?;
+
+        let referenced: HashSet<String> = sqlx::query_scalar!("SELECT id FROM media")
+            .fetch_all(&self.db)
+            .await?
+            .into_iter()
+            .map(|id| id.to_string())
+            .collect();
+
+        let mut files = 0;
+        for prefix in [MEDIA_PREFIX.to_string(), format!("{}{}", PRIVATE_PREFIX, MEDIA_PREFIX)] {
+            files += image::remove_unreferenced(storage, &prefix, &referenced, FILE_GRACE_PERIOD).await?;
+        }
+
+        Ok((deleted.rows_affected(), files))
+    }
+
+    async fn fetch(
+        &self,
+        storage: &dyn Storage,
+        user_id: Uuid,
+        media_id: Option<Uuid>,
+        unused_only: bool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<MediaResponse>, i64), AppError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                m.id, m.url, m.width, m.height, m.size_bytes, m.variants, m.blurhash, m.dominant_color,
+                m.alt_text, m.caption, m.is_private, m.created_at, m.updated_at,
+                COUNT(am.article_id) as "usage_count!",
+                COUNT(a.id) FILTER (WHERE a.status IN ('published', 'scheduled')) as "published_count!",
+                COUNT(*) OVER () as "total!"
+            FROM media m
+            LEFT JOIN article_media am ON am.media_id = m.id
+            LEFT JOIN articles a ON a.id = am.article_id
+            WHERE m.user_id = $1 AND ($2::uuid IS NULL OR m.id = $2)
+            GROUP BY m.id
+            HAVING NOT $3 OR COUNT(am.article_id) = 0
+            ORDER BY m.created_at DESC
+            LIMIT $4 OFFSET $5
+            "#,
+            user_id,
+            media_id,
+            unused_only,
+            limit,
+            offset
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let total = rows.first().map(|row| row.total).unwrap_or(0);
+        let media = rows
+            .into_iter()
+            .map(|row| {
+                let mut url = row.url;
+                let mut variants = serde_json::from_value::<Vec<ImageVariantResponse>>(row.variants).unwrap_or_default();
+                if row.is_private {
+                    url = signed_url(storage, &url);
+                    for variant in &mut variants {
+                        variant.url = signed_url(storage, &variant.url);
+                    }
+                }
+
+                MediaResponse {
+                    id: row.id,
+                    url,
+                    width: row.width,
+                    height: row.height,
+                    size_bytes: row.size_bytes,
+                    variants,
+                    blurhash: row.blurhash,
+                    dominant_color: row.dominant_color,
+                    alt_text: row.alt_text,
+                    caption: row.caption,
+                    is_private: row.is_private,
+                    usage_count: row.usage_count,
+                    deletable: row.published_count == 0,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                }
+            })
+            .collect();
+
+        Ok((media, total))
+    }
+}
+
+// Stored URLs of private media are public-style URLs the server refuses to serve
+fn signed_url(storage: &dyn Storage, url: &str) -> String {
+    match storage.key_from_url(url) {
+        Some(key) => storage.signed_url(&key, PRIVATE_URL_TTL),
+        None => url.to_string(),
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
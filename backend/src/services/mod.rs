@@ -3,3 +3,24 @@ pub mod article;
 pub mod user;
 pub mod search;
 pub mod engagement;
+pub mod notification;
+pub mod realtime;
+pub mod mailer;
+pub mod digest;
+pub mod newsletter;
+pub mod feed;
+pub mod jobs;
+pub mod cron;
+pub mod image;
+pub mod media;
+pub mod seo;
+pub mod storage;
+pub mod import;
+pub mod export;
+pub mod account;
+pub mod oidc;
+pub mod two_factor;
+pub mod access_token;
+pub mod jwt;
+pub mod login_protection;
+pub mod rate_limit;
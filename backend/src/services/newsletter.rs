@@ -0,0 +1,641 @@
+use askama::Template;
+use chrono::Utc;
+use reqwest::Url;
+use sqlx::PgPool;
+use std::error::Error;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    models::newsletter::{
+        EmailDeliveryStatus, NewsletterDeliveryResponse, NewsletterIssueResponse, NewsletterQueryParams,
+        SubscriberListResponse, SubscriberResponse, SubscriberStatus,
+    },
+    services::{
+        jobs::{Job, JobService},
+        mailer::{EmailMessage, Mailer},
+    },
+};
+
+// Attempts before a delivery is marked failed; the max_attempts of its job
+pub const MAX_ATTEMPTS: i32 = 5;
+// Characters of article text shown in the email
+const PREVIEW_LENGTH: usize = 400;
+
+#[derive(Template)]
+#[template(path = "email/newsletter_confirm.html")]
+struct ConfirmHtmlTemplate<'a> {
+    newsletter_name: &'a str,
+    confirm_url: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "email/newsletter_confirm.txt")]
+struct ConfirmTextTemplate<'a> {
+    newsletter_name: &'a str,
+    confirm_url: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "email/newsletter_issue.html")]
+struct IssueHtmlTemplate<'a> {
+    newsletter_name: &'a str,
+    title: &'a str,
+    subtitle: &'a Option<String>,
+    author_name: &'a str,
+    reading_time_minutes: i32,
+    preview: &'a str,
+    read_url: &'a str,
+    unsubscribe_url: &'a str,
+    open_pixel_url: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "email/newsletter_issue.txt")]
+struct IssueTextTemplate<'a> {
+    newsletter_name: &'a str,
+    title: &'a str,
+    subtitle: &'a Option<String>,
+    author_name: &'a str,
+    reading_time_minutes: i32,
+    preview: &'a str,
+    read_url: &'a str,
+    unsubscribe_url: &'a str,
+}
+
+// Whose newsletter a subscriber signed up for
+#[derive(Debug, Clone, Copy)]
+pub enum NewsletterTarget {
+    Author(Uuid),
+    Publication(Uuid),
+}
+
+pub struct NewsletterService<'a> {
+    db: PgPool,
+    config: &'a Config,
+}
+
+impl<'a> NewsletterService<'a> {
+    pub fn new(db: PgPool, config: &'a Config) -> Self {
+        Self { db, config }
+    }
+
+    pub async fn resolve_author(&self, username: &str) -> Result<(NewsletterTarget, String), AppError> {
+        let author = sqlx::query!(
+            "SELECT id, username, display_name FROM users WHERE username = $1 AND is_banned = FALSE",
+            username
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Author not found".to_string()))?;
+
+        Ok((
+            NewsletterTarget::Author(author.id),
+            author.display_name.unwrap_or(author.username),
+        ))
+    }
+
+    pub async fn resolve_publication(&self, slug: &str) -> Result<(NewsletterTarget, String), AppError> {
+        let publication = sqlx::query!("SELECT id, name FROM publications WHERE slug = $1", slug)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Publication not found".to_string()))?;
+
+        Ok((NewsletterTarget::Publication(publication.id), publication.name))
+    }
+
+    // Record a pending subscription and queue the double opt-in email.
+    // Returns false when the address is already confirmed.
+    pub async fn subscribe(
+        &self,
+        target: NewsletterTarget,
+        newsletter_name: &str,
+        email: &str,
+        user_id: Option<Uuid>,
+    ) -> Result<bool, AppError> {
+        let email = email.trim().to_lowercase();
+        let (author_id, publication_id) = match target {
+            NewsletterTarget::Author(id) => (Some(id), None),
+            NewsletterTarget::Publication(id) => (None, Some(id)),
+        };
+
+        let existing = sqlx::query!(
+            r#"
+            SELECT id, status as "status: SubscriberStatus"
+            FROM newsletter_subscribers
+            WHERE lower(email) = $1
+                AND author_id IS NOT DISTINCT FROM $2
+                AND publication_id IS NOT DISTINCT FROM $3
+            "#,
+            email,
+            author_id,
+            publication_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        let token = match existing {
+            Some(subscriber) if subscriber.status == SubscriberStatus::Confirmed => return Ok(false),
+            Some(subscriber) => {
+                sqlx::query_scalar!(
+                    r#"
+                    UPDATE newsletter_subscribers
+                    SET status = 'pending', user_id = COALESCE($2, user_id), unsubscribed_at = NULL, updated_at = NOW()
+                    WHERE id = $1
+                    RETURNING token
+                    "#,
+                    subscriber.id,
+                    user_id
+                )
+                .fetch_one(&self.db)
+                .await?
+            }
+            None => {
+                sqlx::query_scalar!(
+                    r#"
+                    INSERT INTO newsletter_subscribers (author_id, publication_id, email, user_id)
+                    VALUES ($1, $2, $3, $4)
+                    RETURNING token
+                    "#,
+                    author_id,
+                    publication_id,
+                    email,
+                    user_id
+                )
+                .fetch_one(&self.db)
+                .await?
+            }
+        };
+
+        let confirm_url = format!("{}/api/v1/newsletter/confirm?token={}", self.config.api_url, token);
+        let message = EmailMessage {
+            to: email,
+            subject: format!("Confirm your subscription to {}", newsletter_name),
+            html_body: ConfirmHtmlTemplate { newsletter_name, confirm_url: &confirm_url }.render()?,
+            text_body: ConfirmTextTemplate { newsletter_name, confirm_url: &confirm_url }.render()?,
+            headers: Vec::new(),
+        };
+        JobService::enqueue(&self.db, &Job::SendEmail { message }, Utc::now()).await?;
+
+        Ok(true)
+    }
+
+    pub async fn confirm(&self, token: &str) -> Result<(), AppError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE newsletter_subscribers
+            SET status = 'confirmed', confirmed_at = COALESCE(confirmed_at, NOW()), updated_at = NOW()
+            WHERE token = $1 AND status <> 'unsubscribed'
+            "#,
+            token
+        )
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Confirmation link is invalid or expired".to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn unsubscribe(&self, token: &str) -> Result<(), AppError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE newsletter_subscribers
+            SET status = 'unsubscribed', unsubscribed_at = NOW(), updated_at = NOW()
+            WHERE token = $1
+            "#,
+            token
+        )
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Unsubscribe link is invalid".to_string()));
+        }
+
+        Ok(())
+    }
+
+    // Create the issue for a freshly published article and queue one delivery, with its
+    // send job, per confirmed subscriber of its author and publication. Does nothing on republish.
+    pub async fn queue_article(&self, article_id: Uuid) -> Result<i64, Box<dyn Error + Send + Sync>> {
+        let mut tx = self.db.begin().await?;
+
+        let issue_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO newsletter_issues (article_id)
+            SELECT id FROM articles WHERE id = $1 AND status = 'published'
+            ON CONFLICT (article_id) DO NOTHING
+            RETURNING id
+            "#,
+            article_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(issue_id) = issue_id else {
+            return Ok(0);
+        };
+
+        // Someone subscribed to both the author and the publication gets one email
+        let deliveries = sqlx::query_scalar!(
+            r#"
+            INSERT INTO newsletter_deliveries (issue_id, subscriber_id)
+            SELECT DISTINCT ON (lower(s.email)) $1::uuid, s.id
+            FROM newsletter_subscribers s
+            JOIN articles a ON a.id = $2
+            WHERE s.status = 'confirmed'
+                AND (s.author_id = a.author_id OR s.publication_id = a.publication_id)
+            ORDER BY lower(s.email), s.author_id NULLS LAST
+            RETURNING id
+            "#,
+            issue_id,
+            article_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let now = Utc::now();
+        for &delivery_id in &deliveries {
+            JobService::enqueue(&mut *tx, &Job::SendNewsletter { delivery_id }, now).await?;
+        }
+        let queued = deliveries.len() as i64;
+
+        sqlx::query!(
+            "UPDATE newsletter_issues SET recipients_count = $2 WHERE id = $1",
+            issue_id,
+            queued as i32
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(queued)
+    }
+
+    // Runs a `send_newsletter` job: sends one delivery and records how it went. Errors
+    // are returned so the job queue retries with backoff.
+    pub async fn send_delivery(&self, mailer: &dyn Mailer, delivery_id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // Nothing to do when the delivery is gone or an earlier attempt already sent it
+        let attempts = sqlx::query_scalar!(
+            r#"
+            UPDATE newsletter_deliveries
+            SET status = 'sending', attempts = attempts + 1, updated_at = NOW()
+            WHERE id = $1 AND status <> 'sent'
+            RETURNING attempts
+            "#,
+            delivery_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+        let Some(attempts) = attempts else {
+            return Ok(());
+        };
+
+        if let Err(e) = self.deliver(mailer, delivery_id).await {
+            let status = if attempts >= MAX_ATTEMPTS {
+                EmailDeliveryStatus::Failed
+            } else {
+                EmailDeliveryStatus::Queued
+            };
+            sqlx::query!(
+                "UPDATE newsletter_deliveries SET status = $2, last_error = $3, updated_at = NOW() WHERE id = $1",
+                delivery_id,
+                status as EmailDeliveryStatus,
+                e.to_string()
+            )
+            .execute(&self.db)
+            .await?;
+            return Err(e);
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE newsletter_deliveries
+            SET status = 'sent', sent_at = NOW(), last_error = NULL, updated_at = NOW()
+            WHERE id = $1
+            "#,
+            delivery_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn deliver(&self, mailer: &dyn Mailer, delivery_id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                s.email, s.token, s.status as "status: SubscriberStatus",
+                a.title, a.subtitle, a.excerpt, a.content_html, a.slug, a.reading_time_minutes,
+                u.username, u.display_name,
+                COALESCE(p.name, COALESCE(su.display_name, su.username)) as "newsletter_name!"
+            FROM newsletter_deliveries d
+            JOIN newsletter_subscribers s ON s.id = d.subscriber_id
+            JOIN newsletter_issues i ON i.id = d.issue_id
+            JOIN articles a ON a.id = i.article_id
+            JOIN users u ON u.id = a.author_id
+            LEFT JOIN publications p ON p.id = s.publication_id
+            LEFT JOIN users su ON su.id = s.author_id
+            WHERE d.id = $1
+            "#,
+            delivery_id
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        if row.status != SubscriberStatus::Confirmed {
+            return Err("Subscriber is no longer confirmed".into());
+        }
+
+        let api_url = &self.config.api_url;
+        let article_url = format!("{}/article/{}", self.config.frontend_url, row.slug);
+        let read_url = Url::parse_with_params(
+            &format!("{}/api/v1/newsletter/track/{}/click", api_url, delivery_id),
+            &[("url", &article_url)],
+        )?
+        .to_string();
+        let open_pixel_url = format!("{}/api/v1/newsletter/track/{}/open", api_url, delivery_id);
+        let unsubscribe_url = format!("{}/api/v1/newsletter/unsubscribe?token={}", api_url, row.token);
+
+        // Member-only articles never had full content here; everyone gets a teaser
+        let text = match &row.excerpt {
+            Some(excerpt) if !excerpt.trim().is_empty() => excerpt.clone(),
+            _ => ammonia::Builder::empty().clean(&row.content_html).to_string(),
+        };
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let preview = match text.char_indices().nth(PREVIEW_LENGTH) {
+            Some((index, _)) => format!("{}...", &text[..index]),
+            None => text,
+        };
+        let author_name = row.display_name.unwrap_or(row.username);
+
+        let message = EmailMessage {
+            to: row.email,
+            subject: row.title.clone(),
+            html_body: IssueHtmlTemplate {
+                newsletter_name: &row.newsletter_name,
+                title: &row.title,
+                subtitle: &row.subtitle,
+                author_name: &author_name,
+                reading_time_minutes: row.reading_time_minutes,
+                preview: &preview,
+                read_url: &read_url,
+                unsubscribe_url: &unsubscribe_url,
+                open_pixel_url: &open_pixel_url,
+            }
+            .render()?,
+            text_body: IssueTextTemplate {
+                newsletter_name: &row.newsletter_name,
+                title: &row.title,
+                subtitle: &row.subtitle,
+                author_name: &author_name,
+                reading_time_minutes: row.reading_time_minutes,
+                preview: &preview,
+                read_url: &read_url,
+                unsubscribe_url: &unsubscribe_url,
+            }
+            .render()?,
+            headers: vec![
+                ("List-Unsubscribe".to_string(), format!("<{}>", unsubscribe_url)),
+                ("List-Unsubscribe-Post".to_string(), "List-Unsubscribe=One-Click".to_string()),
+            ],
+        };
+
+        mailer.send(&message).await
+    }
+
+    pub async fn record_open(&self, delivery_id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+        sqlx::query!(
+            r#"
+            UPDATE newsletter_deliveries
+            SET open_count = open_count + 1, opened_at = COALESCE(opened_at, NOW())
+            WHERE id = $1
+            "#,
+            delivery_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    // A click implies the email was opened even when images were blocked
+    pub async fn record_click(&self, delivery_id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+        sqlx::query!(
+            r#"
+            UPDATE newsletter_deliveries
+            SET click_count = click_count + 1,
+                clicked_at = COALESCE(clicked_at, NOW()),
+                opened_at = COALESCE(opened_at, NOW())
+            WHERE id = $1
+            "#,
+            delivery_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    // Newsletter the caller manages: their own, or a publication they own or edit
+    pub async fn managed_target(
+        &self,
+        user_id: Uuid,
+        publication_id: Option<Uuid>,
+    ) -> Result<NewsletterTarget, AppError> {
+        let Some(publication_id) = publication_id else {
+            return Ok(NewsletterTarget::Author(user_id));
+        };
+
+        let can_manage = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM publications WHERE id = $1 AND owner_id = $2
+                UNION ALL
+                SELECT 1 FROM publication_members
+                WHERE publication_id = $1 AND user_id = $2 AND is_active = TRUE
+                    AND role IN ('owner', 'editor')
+            ) as "exists!"
+            "#,
+            publication_id,
+            user_id
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        if !can_manage {
+            return Err(AppError::Forbidden(
+                "Not authorized to manage this publication's newsletter".to_string(),
+            ));
+        }
+
+        Ok(NewsletterTarget::Publication(publication_id))
+    }
+
+    pub async fn list_subscribers(
+        &self,
+        target: NewsletterTarget,
+        params: NewsletterQueryParams,
+    ) -> Result<SubscriberListResponse, AppError> {
+        let limit = params.limit.unwrap_or(50).clamp(1, 200);
+        let page = params.page.unwrap_or(1).max(1);
+        let offset = (page - 1) * limit;
+        let (author_id, publication_id) = match target {
+            NewsletterTarget::Author(id) => (Some(id), None),
+            NewsletterTarget::Publication(id) => (None, Some(id)),
+        };
+
+        let subscribers = sqlx::query_as!(
+            SubscriberResponse,
+            r#"
+            SELECT id, email, status as "status: SubscriberStatus", confirmed_at, unsubscribed_at, created_at
+            FROM newsletter_subscribers
+            WHERE author_id IS NOT DISTINCT FROM $1
+                AND publication_id IS NOT DISTINCT FROM $2
+                AND ($3::subscriber_status IS NULL OR status = $3)
+            ORDER BY created_at DESC
+            LIMIT $4 OFFSET $5
+            "#,
+            author_id,
+            publication_id,
+            params.status as Option<SubscriberStatus>,
+            limit,
+            offset
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let counts = sqlx::query!(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE $3::subscriber_status IS NULL OR status = $3) as "total!",
+                COUNT(*) FILTER (WHERE status = 'confirmed') as "confirmed!"
+            FROM newsletter_subscribers
+            WHERE author_id IS NOT DISTINCT FROM $1
+                AND publication_id IS NOT DISTINCT FROM $2
+            "#,
+            author_id,
+            publication_id,
+            params.status as Option<SubscriberStatus>
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(SubscriberListResponse {
+            subscribers,
+            total: counts.total as u64,
+            confirmed_count: counts.confirmed,
+            limit,
+            offset,
+        })
+    }
+
+    // Issues for the caller's articles, or for a publication's articles
+    pub async fn list_issues(
+        &self,
+        target: NewsletterTarget,
+        params: NewsletterQueryParams,
+    ) -> Result<Vec<NewsletterIssueResponse>, AppError> {
+        let limit = params.limit.unwrap_or(20).clamp(1, 100);
+        let page = params.page.unwrap_or(1).max(1);
+        let offset = (page - 1) * limit;
+        let (author_id, publication_id) = match target {
+            NewsletterTarget::Author(id) => (Some(id), None),
+            NewsletterTarget::Publication(id) => (None, Some(id)),
+        };
+
+        let issues = sqlx::query_as!(
+            NewsletterIssueResponse,
+            r#"
+            SELECT
+                i.id, i.article_id, a.title as article_title, i.recipients_count,
+                COUNT(d.id) FILTER (WHERE d.status IN ('queued', 'sending')) as "queued_count!",
+                COUNT(d.id) FILTER (WHERE d.status = 'sent') as "sent_count!",
+                COUNT(d.id) FILTER (WHERE d.status = 'failed') as "failed_count!",
+                COUNT(d.id) FILTER (WHERE d.opened_at IS NOT NULL) as "opened_count!",
+                COUNT(d.id) FILTER (WHERE d.clicked_at IS NOT NULL) as "clicked_count!",
+                i.created_at
+            FROM newsletter_issues i
+            JOIN articles a ON a.id = i.article_id
+            LEFT JOIN newsletter_deliveries d ON d.issue_id = i.id
+            WHERE ($1::uuid IS NULL OR a.author_id = $1)
+                AND ($2::uuid IS NULL OR a.publication_id = $2)
+            GROUP BY i.id, a.title
+            ORDER BY i.created_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            author_id,
+            publication_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(issues)
+    }
+
+    pub async fn list_deliveries(
+        &self,
+        user_id: Uuid,
+        issue_id: Uuid,
+        params: NewsletterQueryParams,
+    ) -> Result<Vec<NewsletterDeliveryResponse>, AppError> {
+        let issue = sqlx::query!(
+            r#"
+            SELECT a.author_id, a.publication_id
+            FROM newsletter_issues i
+            JOIN articles a ON a.id = i.article_id
+            WHERE i.id = $1
+            "#,
+            issue_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Newsletter issue not found".to_string()))?;
+
+        if issue.author_id != user_id {
+            match issue.publication_id {
+                Some(publication_id) => {
+                    self.managed_target(user_id, Some(publication_id)).await?;
+                }
+                None => {
+                    return Err(AppError::Forbidden(
+                        "Not authorized to view this newsletter issue".to_string(),
+                    ))
+                }
+            }
+        }
+
+        let limit = params.limit.unwrap_or(50).clamp(1, 200);
+        let page = params.page.unwrap_or(1).max(1);
+        let offset = (page - 1) * limit;
+
+        let deliveries = sqlx::query_as!(
+            NewsletterDeliveryResponse,
+            r#"
+            SELECT
+                d.id, s.email, d.status as "status: EmailDeliveryStatus", d.attempts, d.last_error,
+                d.sent_at, d.opened_at, d.open_count, d.clicked_at, d.click_count
+            FROM newsletter_deliveries d
+            JOIN newsletter_subscribers s ON s.id = d.subscriber_id
+            WHERE d.issue_id = $1
+            ORDER BY s.email
+            LIMIT $2 OFFSET $3
+            "#,
+            issue_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(deliveries)
+    }
+}
@@ -0,0 +1,568 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+use std::collections::HashSet;
+use std::sync::Arc;
+use regex::Regex;
+use lazy_static::lazy_static;
+
+use crate::error::AppError;
+use crate::models::notification::{
+    NotificationActor, NotificationArticle, NotificationListResponse, NotificationPreference,
+    NotificationQueryParams, NotificationResponse, NotificationType,
+};
+use crate::services::realtime::{RealtimeEvent, RealtimeHub};
+
+lazy_static! {
+    // @username mentions; usernames follow the same rules as registration
+    static ref MENTION_REGEX: Regex = Regex::new(r"(?:^|[^\w@])@([A-Za-z0-9_]{3,30})\b").unwrap();
+}
+
+// Notification joined with its actor and article, as shown in the inbox
+#[derive(sqlx::FromRow)]
+struct NotificationRow {
+    id: Uuid,
+    notification_type: NotificationType,
+    actor_id: Option<Uuid>,
+    article_id: Option<Uuid>,
+    comment_id: Option<Uuid>,
+    publication_id: Option<Uuid>,
+    aggregate_count: i32,
+    is_read: bool,
+    read_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    actor_username: Option<String>,
+    actor_display_name: Option<String>,
+    actor_avatar_url: Option<String>,
+    article_title: Option<String>,
+    article_slug: Option<String>,
+}
+
+// Optional references attached to a notification
+#[derive(Default)]
+struct NotificationTarget {
+    article_id: Option<Uuid>,
+    comment_id: Option<Uuid>,
+    publication_id: Option<Uuid>,
+}
+
+pub struct NotificationService {
+    db: PgPool,
+    realtime: Arc<RealtimeHub>,
+}
+
+impl NotificationService {
+    pub fn new(db: PgPool, realtime: Arc<RealtimeHub>) -> Self {
+        Self { db, realtime }
+    }
+
+    // Check whether the recipient wants in-app notifications of this type
+    async fn is_enabled(
+        &self,
+        user_id: Uuid,
+        notification_type: NotificationType,
+    ) -> Result<bool, AppError> {
+        let enabled = sqlx::query_scalar!(
+            r#"
+            SELECT in_app_enabled FROM notification_preferences
+            WHERE user_id = $1 AND notification_type = $2
+            "#,
+            user_id,
+            notification_type as NotificationType
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(enabled.unwrap_or(true))
+    }
+
+    // Insert a notification unless the recipient is the actor or has muted the type
+    async fn create(
+        &self,
+        user_id: Uuid,
+        actor_id: Uuid,
+        notification_type: NotificationType,
+        target: NotificationTarget,
+    ) -> Result<Option<Uuid>, AppError> {
+        if user_id == actor_id || !self.is_enabled(user_id, notification_type).await? {
+            return Ok(None);
+        }
+
+        let notification_id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO notifications (
+                id, user_id, actor_id, notification_type, article_id, comment_id,
+                publication_id, aggregate_count, is_read, created_at, updated_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, 1, FALSE, NOW(), NOW())
+            "#,
+            notification_id,
+            user_id,
+            actor_id,
+            notification_type as NotificationType,
+            target.article_id,
+            target.comment_id,
+            target.publication_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        self.push(user_id, notification_id).await;
+
+        Ok(Some(notification_id))
+    }
+
+    // Send a notification to the recipient's live streams
+    async fn push(&self, user_id: Uuid, notification_id: Uuid) {
+        let notification = match self.get_notification(user_id, notification_id).await {
+            Ok(Some(notification)) => notification,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("Failed to load notification {} for push: {}", notification_id, e);
+                return;
+            }
+        };
+        let unread_count = self.get_unread_count(user_id).await.unwrap_or(0);
+
+        self.realtime.publish(RealtimeEvent::Notification {
+            user_id,
+            unread_count,
+            notification: Box::new(notification),
+        });
+    }
+
+    pub async fn notify_new_follower(
+        &self,
+        follower_id: Uuid,
+        following_id: Uuid,
+    ) -> Result<(), AppError> {
+        self.create(
+            following_id,
+            follower_id,
+            NotificationType::NewFollower,
+            NotificationTarget::default(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    // Claps are aggregated: while the author hasn't read the clap notification for an
+    // article, claps from new users bump its counter instead of creating new rows.
+    // Clapping again after an unclap doesn't count twice.
+    pub async fn notify_clap(
+        &self,
+        article_id: Uuid,
+        clapper_id: Uuid,
+    ) -> Result<(), AppError> {
+        let author_id = sqlx::query_scalar!(
+            "SELECT author_id FROM articles WHERE id = $1",
+            article_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Article not found".to_string()))?;
+
+        if author_id == clapper_id || !self.is_enabled(author_id, NotificationType::Clap).await? {
+            return Ok(());
+        }
+
+        let unread = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM notifications
+            WHERE user_id = $1 AND article_id = $2 AND notification_type = 'clap' AND is_read = FALSE
+            ORDER BY updated_at DESC
+            LIMIT 1
+            "#,
+            author_id,
+            article_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        let Some(notification_id) = unread else {
+            let created = self
+                .create(
+                    author_id,
+                    clapper_id,
+                    NotificationType::Clap,
+                    NotificationTarget {
+                        article_id: Some(article_id),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            if let Some(notification_id) = created {
+                self.add_actor(notification_id, clapper_id).await?;
+            }
+            return Ok(());
+        };
+
+        if !self.add_actor(notification_id, clapper_id).await? {
+            return Ok(());
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE notifications
+            SET aggregate_count = aggregate_count + 1, actor_id = $1, updated_at = NOW()
+            WHERE id = $2
+            "#,
+            clapper_id,
+            notification_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        self.push(author_id, notification_id).await;
+
+        Ok(())
+    }
+
+    // Record an actor on an aggregated notification; false if already counted
+    async fn add_actor(&self, notification_id: Uuid, actor_id: Uuid) -> Result<bool, AppError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO notification_actors (notification_id, actor_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            notification_id,
+            actor_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Notify the article author, the parent comment author (for replies) and any
+    // @mentioned users. Each recipient gets at most one notification per comment.
+    pub async fn notify_comment(
+        &self,
+        article_id: Uuid,
+        comment_id: Uuid,
+        commenter_id: Uuid,
+        parent_id: Option<Uuid>,
+        content: &str,
+    ) -> Result<(), AppError> {
+        let author_id = sqlx::query_scalar!(
+            "SELECT author_id FROM articles WHERE id = $1",
+            article_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Article not found".to_string()))?;
+
+        let target = || NotificationTarget {
+            article_id: Some(article_id),
+            comment_id: Some(comment_id),
+            ..Default::default()
+        };
+
+        let mut notified: HashSet<Uuid> = HashSet::new();
+        notified.insert(commenter_id);
+
+        if let Some(parent_id) = parent_id {
+            let parent_author = sqlx::query_scalar!(
+                "SELECT user_id FROM comments WHERE id = $1",
+                parent_id
+            )
+            .fetch_optional(&self.db)
+            .await?;
+
+            if let Some(parent_author) = parent_author {
+                if notified.insert(parent_author) {
+                    self.create(parent_author, commenter_id, NotificationType::Reply, target()).await?;
+                }
+            }
+        }
+
+        if notified.insert(author_id) {
+            self.create(author_id, commenter_id, NotificationType::Comment, target()).await?;
+        }
+
+        let usernames: Vec<String> = MENTION_REGEX
+            .captures_iter(content)
+            .map(|caps| caps[1].to_string())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        if !usernames.is_empty() {
+            let mentioned = sqlx::query_scalar!(
+                "SELECT id FROM users WHERE username = ANY($1)",
+                &usernames[..]
+            )
+            .fetch_all(&self.db)
+            .await?;
+
+            for user_id in mentioned {
+                if notified.insert(user_id) {
+                    self.create(user_id, commenter_id, NotificationType::Mention, target()).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_notifications(
+        &self,
+        user_id: Uuid,
+        params: NotificationQueryParams,
+    ) -> Result<NotificationListResponse, AppError> {
+        let limit = params.limit.unwrap_or(20).clamp(1, 100);
+        let page = params.page.unwrap_or(1).max(1);
+        let offset = (page - 1) * limit;
+        let unread_only = params.unread_only.unwrap_or(false);
+
+        let rows = sqlx::query_as!(
+            NotificationRow,
+            r#"
+            SELECT
+                n.id, n.notification_type as "notification_type: NotificationType",
+                n.actor_id, n.article_id, n.comment_id, n.publication_id,
+                n.aggregate_count, n.is_read, n.read_at, n.created_at, n.updated_at,
+                u.username as "actor_username?", u.display_name as actor_display_name,
+                u.avatar_url as actor_avatar_url,
+                a.title as "article_title?", a.slug as "article_slug?"
+            FROM notifications n
+            LEFT JOIN users u ON n.actor_id = u.id
+            LEFT JOIN articles a ON n.article_id = a.id
+            WHERE n.user_id = $1 AND ($2 = FALSE OR n.is_read = FALSE)
+            ORDER BY n.updated_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            user_id,
+            unread_only,
+            limit,
+            offset
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let total = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND ($2 = FALSE OR is_read = FALSE)",
+            user_id,
+            unread_only
+        )
+        .fetch_one(&self.db)
+        .await?
+        .unwrap_or(0);
+
+        let unread_count = self.get_unread_count(user_id).await?;
+
+        let notifications = rows.into_iter().map(Self::to_response).collect();
+
+        Ok(NotificationListResponse {
+            notifications,
+            total: total as u64,
+            unread_count,
+            limit,
+            offset,
+        })
+    }
+
+    pub async fn get_notification(
+        &self,
+        user_id: Uuid,
+        notification_id: Uuid,
+    ) -> Result<Option<NotificationResponse>, AppError> {
+        let row = sqlx::query_as!(
+            NotificationRow,
+            r#"
+            SELECT
+                n.id, n.notification_type as "notification_type: NotificationType",
+                n.actor_id, n.article_id, n.comment_id, n.publication_id,
+                n.aggregate_count, n.is_read, n.read_at, n.created_at, n.updated_at,
+                u.username as "actor_username?", u.display_name as actor_display_name,
+                u.avatar_url as actor_avatar_url,
+                a.title as "article_title?", a.slug as "article_slug?"
+            FROM notifications n
+            LEFT JOIN users u ON n.actor_id = u.id
+            LEFT JOIN articles a ON n.article_id = a.id
+            WHERE n.id = $1 AND n.user_id = $2
+            "#,
+            notification_id,
+            user_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(row.map(Self::to_response))
+    }
+
+    fn to_response(row: NotificationRow) -> NotificationResponse {
+        let actor = match (row.actor_id, row.actor_username) {
+            (Some(id), Some(username)) => Some(NotificationActor {
+                id,
+                username,
+                display_name: row.actor_display_name,
+                avatar_url: row.actor_avatar_url,
+            }),
+            _ => None,
+        };
+        let article = match (row.article_id, row.article_title, row.article_slug) {
+            (Some(id), Some(title), Some(slug)) => Some(NotificationArticle { id, title, slug }),
+            _ => None,
+        };
+        let message = Self::build_message(
+            row.notification_type,
+            actor.as_ref(),
+            article.as_ref(),
+            row.aggregate_count,
+        );
+
+        NotificationResponse {
+            id: row.id,
+            notification_type: row.notification_type,
+            message,
+            actor,
+            article,
+            comment_id: row.comment_id,
+            publication_id: row.publication_id,
+            aggregate_count: row.aggregate_count,
+            is_read: row.is_read,
+            read_at: row.read_at,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+
+    // Human readable summary, e.g. "jane and 3 others clapped for "My Article""
+    fn build_message(
+        notification_type: NotificationType,
+        actor: Option<&NotificationActor>,
+        article: Option<&NotificationArticle>,
+        aggregate_count: i32,
+    ) -> String {
+        let actor_name = actor
+            .map(|a| a.display_name.clone().unwrap_or_else(|| a.username.clone()))
+            .unwrap_or_else(|| "Someone".to_string());
+        let title = article
+            .map(|a| format!("\"{}\"", a.title))
+            .unwrap_or_else(|| "your article".to_string());
+
+        match notification_type {
+            NotificationType::NewFollower => format!("{} started following you", actor_name),
+            NotificationType::Clap => match aggregate_count {
+                1 => format!("{} clapped for {}", actor_name, title),
+                2 => format!("{} and 1 other clapped for {}", actor_name, title),
+                n => format!("{} and {} others clapped for {}", actor_name, n - 1, title),
+            },
+            NotificationType::Comment => format!("{} commented on {}", actor_name, title),
+            NotificationType::Reply => format!("{} replied to your comment on {}", actor_name, title),
+            NotificationType::Mention => format!("{} mentioned you in a comment on {}", actor_name, title),
+        }
+    }
+
+    pub async fn get_unread_count(&self, user_id: Uuid) -> Result<i64, AppError> {
+        let count = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND is_read = FALSE",
+            user_id
+        )
+        .fetch_one(&self.db)
+        .await?
+        .unwrap_or(0);
+
+        Ok(count)
+    }
+
+    pub async fn mark_as_read(
+        &self,
+        user_id: Uuid,
+        notification_id: Uuid,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE notifications
+            SET is_read = TRUE, read_at = COALESCE(read_at, NOW())
+            WHERE id = $1 AND user_id = $2
+            "#,
+            notification_id,
+            user_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Notification not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn mark_all_as_read(&self, user_id: Uuid) -> Result<u64, AppError> {
+        let result = sqlx::query!(
+            "UPDATE notifications SET is_read = TRUE, read_at = NOW() WHERE user_id = $1 AND is_read = FALSE",
+            user_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Preferences for every notification type, filling in defaults for unset ones
+    pub async fn get_preferences(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<NotificationPreference>, AppError> {
+        let stored = sqlx::query!(
+            r#"
+            SELECT notification_type as "notification_type: NotificationType", in_app_enabled, email_enabled
+            FROM notification_preferences
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(NotificationType::ALL
+            .iter()
+            .map(|notification_type| {
+                stored
+                    .iter()
+                    .find(|p| p.notification_type == *notification_type)
+                    .map(|p| NotificationPreference {
+                        notification_type: *notification_type,
+                        in_app_enabled: p.in_app_enabled,
+                        email_enabled: p.email_enabled,
+                    })
+                    .unwrap_or(NotificationPreference {
+                        notification_type: *notification_type,
+                        in_app_enabled: true,
+                        email_enabled: true,
+                    })
+            })
+            .collect())
+    }
+
+    pub async fn update_preferences(
+        &self,
+        user_id: Uuid,
+        preferences: &[NotificationPreference],
+    ) -> Result<Vec<NotificationPreference>, AppError> {
+        for preference in preferences {
+            sqlx::query!(
+                r#"
+                INSERT INTO notification_preferences (user_id, notification_type, in_app_enabled, email_enabled, updated_at)
+                VALUES ($1, $2, $3, $4, NOW())
+                ON CONFLICT (user_id, notification_type)
+                DO UPDATE SET in_app_enabled = $3, email_enabled = $4, updated_at = NOW()
+                "#,
+                user_id,
+                preference.notification_type as NotificationType,
+                preference.in_app_enabled,
+                preference.email_enabled
+            )
+            .execute(&self.db)
+            .await?;
+        }
+
+        self.get_preferences(user_id).await
+    }
+}
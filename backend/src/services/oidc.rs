@@ -0,0 +1,606 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration as ChronoDuration, Utc};
+use dashmap::DashMap;
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use lazy_static::lazy_static;
+use rand_core::{OsRng, RngCore};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::{
+    config::{Config, OidcProviderConfig},
+    database::Database,
+    error::AppError,
+    models::{
+        identity::{LinkedIdentitiesResponse, LinkedIdentity, OidcAuthorizationResponse, OidcProviderResponse},
+        two_factor::LoginResponse,
+    },
+    services::auth::AuthService,
+};
+
+// How long users have to finish signing in at the provider
+const STATE_TTL_MINUTES: i64 = 10;
+// Discovery documents and signing keys are refetched after this, or when a token
+// is signed with a key we don't know yet (key rotation)
+const METADATA_TTL: Duration = Duration::from_secs(60 * 60);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+    userinfo_endpoint: Option<String>,
+}
+
+struct CachedProvider {
+    metadata: ProviderMetadata,
+    jwks: JwkSet,
+    fetched_at: Instant,
+}
+
+lazy_static! {
+    // Keyed by issuer
+    static ref PROVIDER_CACHE: DashMap<String, Arc<CachedProvider>> = DashMap::new();
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+    access_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    nonce: Option<String>,
+    email: Option<String>,
+    // Some providers send "true" as a string
+    email_verified: Option<Value>,
+    name: Option<String>,
+    preferred_username: Option<String>,
+}
+
+impl IdTokenClaims {
+    fn verified_email(&self) -> Option<&str> {
+        let verified = matches!(&self.email_verified, Some(Value::Bool(true)))
+            || matches!(&self.email_verified, Some(Value::String(value)) if value == "true");
+        self.email.as_deref().filter(|_| verified)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UserInfo {
+    sub: String,
+    email: Option<String>,
+    email_verified: Option<Value>,
+}
+
+pub struct OidcService<'a> {
+    db: &'a Database,
+    config: &'a Config,
+}
+
+impl<'a> OidcService<'a> {
+    pub fn new(db: &'a Database, config: &'a Config) -> Self {
+        Self { db, config }
+    }
+
+    pub fn list_providers(&self) -> Vec<OidcProviderResponse> {
+        self.config
+            .oidc_providers
+            .iter()
+            .map(|provider| OidcProviderResponse {
+                id: provider.id.clone(),
+                name: provider.display_name.clone(),
+            })
+            .collect()
+    }
+
+    // Start the authorization code flow with PKCE. `link_user_id` is set when a signed-in
+    // user links the provider to their account instead of signing in.
+    pub async fn start(
+        &self,
+        provider_id: &str,
+        link_user_id: Option<Uuid>,
+    ) -> Result<OidcAuthorizationResponse, AppError> {
+        let provider = self
+            .config
+            .oidc_provider(provider_id)
+            .ok_or_else(|| AppError::NotFound("Unknown sign-in provider".to_string()))?;
+        let cached = provider_metadata(&http_client()?, provider, false).await.map_err(|e| {
+            tracing::error!("Sign-in provider {} unavailable: {}", provider.id, e);
+            AppError::BadGateway(format!("Sign-in provider unavailable: {}", e))
+        })?;
+
+        let state = random_token();
+        let code_verifier = random_token();
+        let nonce = random_token();
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        sqlx::query!("DELETE FROM oidc_login_states WHERE expires_at < NOW()")
+            .execute(&self.db.pool)
+            .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO oidc_login_states (state, provider, code_verifier, nonce, link_user_id, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            state,
+            provider.id,
+            code_verifier,
+            nonce,
+            link_user_id,
+            Utc::now() + ChronoDuration::minutes(STATE_TTL_MINUTES)
+        )
+        .execute(&self.db.pool)
+        .await?;
+
+        let authorization_url = Url::parse_with_params(
+            &cached.metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", provider.client_id.as_str()),
+                ("redirect_uri", self.config.oidc_redirect_url.as_str()),
+                ("scope", provider.scopes.as_str()),
+                ("state", state.as_str()),
+                ("nonce", nonce.as_str()),
+                ("code_challenge", code_challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| AppError::Internal(e.into()))?;
+
+        Ok(OidcAuthorizationResponse {
+            authorization_url: authorization_url.to_string(),
+            state,
+        })
+    }
+
+    // Sign in with a provider identity. Unknown identities are linked to the account with
+    // the same verified email, or get a new account.
+    pub async fn login(&self, code: &str, state: &str) -> Result<LoginResponse, AppError> {
+        let (provider, claims) = self.complete(code, state, None).await?;
+
+        let existing = sqlx::query_scalar!(
+            r#"
+            UPDATE user_identities
+            SET last_login_at = NOW(), email = COALESCE($3, email)
+            WHERE provider = $1 AND subject = $2
+            RETURNING user_id
+            "#,
+            provider.id,
+            claims.sub,
+            claims.email
+        )
+        .fetch_optional(&self.db.pool)
+        .await?;
+
+        let user_id = match existing {
+            Some(user_id) => user_id,
+            None => self.create_identity(&provider, &claims).await?,
+        };
+
+        AuthService::new(self.db).login_with_identity(user_id).await
+    }
+
+    // Finish linking a provider started with `start(provider, Some(user_id))`
+    pub async fn link(&self, user_id: Uuid, code: &str, state: &str) -> Result<LinkedIdentity, AppError> {
+        let (provider, claims) = self.complete(code, state, Some(user_id)).await?;
+
+        let owner = sqlx::query_scalar!(
+            "SELECT user_id FROM user_identities WHERE provider = $1 AND subject = $2",
+            provider.id,
+            claims.sub
+        )
+        .fetch_optional(&self.db.pool)
+        .await?;
+        match owner {
+            Some(owner) if owner != user_id => {
+                return Err(AppError::Conflict(format!(
+                    "This {} account is already linked to another user",
+                    provider.display_name
+                )));
+            }
+            Some(_) => {}
+            None => {
+                let linked = sqlx::query_scalar!(
+                    "SELECT EXISTS(SELECT 1 FROM user_identities WHERE user_id = $1 AND provider = $2)",
+                    user_id,
+                    provider.id
+                )
+                .fetch_one(&self.db.pool)
+                .await?
+                .unwrap_or(false);
+                if linked {
+                    return Err(AppError::Conflict(format!("You already linked a {} account", provider.display_name)));
+                }
+
+                sqlx::query!(
+                    "INSERT INTO user_identities (user_id, provider, subject, email) VALUES ($1, $2, $3, $4)",
+                    user_id,
+                    provider.id,
+                    claims.sub,
+                    claims.email
+                )
+                .execute(&self.db.pool)
+                .await?;
+            }
+        }
+
+        let identity = sqlx::query_as!(
+            LinkedIdentity,
+            r#"
+            SELECT id, provider, email, created_at, last_login_at
+            FROM user_identities WHERE provider = $1 AND subject = $2
+            "#,
+            provider.id,
+            claims.sub
+        )
+        .fetch_one(&self.db.pool)
+        .await?;
+
+        Ok(identity)
+    }
+
+    pub async fn list_identities(&self, user_id: Uuid) -> Result<LinkedIdentitiesResponse, AppError> {
+        let identities = sqlx::query_as!(
+            LinkedIdentity,
+            r#"
+            SELECT id, provider, email, created_at, last_login_at
+            FROM user_identities WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+            user_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        let has_password = sqlx::query_scalar!(
+            r#"SELECT password_hash IS NOT NULL as "has_password!" FROM users WHERE id = $1"#,
+            user_id
+        )
+        .fetch_optional(&self.db.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        Ok(LinkedIdentitiesResponse { identities, has_password })
+    }
+
+    // Unlink a provider, as long as the account keeps a password or another linked provider
+    pub async fn unlink(&self, user_id: Uuid, identity_id: Uuid) -> Result<(), AppError> {
+        let mut tx = self.db.pool.begin().await?;
+
+        // Serializes concurrent unlinks of the same account
+        let has_password = sqlx::query_scalar!(
+            r#"SELECT password_hash IS NOT NULL as "has_password!" FROM users WHERE id = $1 FOR UPDATE"#,
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        let identities = sqlx::query_scalar!("SELECT id FROM user_identities WHERE user_id = $1", user_id)
+            .fetch_all(&mut *tx)
+            .await?;
+        if !identities.contains(&identity_id) {
+            return Err(AppError::NotFound("Identity not found".to_string()));
+        }
+        if !has_password && identities.len() == 1 {
+            return Err(AppError::Conflict(
+                "Link another account before unlinking your only sign-in method".to_string(),
+            ));
+        }
+
+        sqlx::query!("DELETE FROM user_identities WHERE id = $1", identity_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // Redeem the authorization code and verify the ID token
+    async fn complete(
+        &self,
+        code: &str,
+        state: &str,
+        link_user_id: Option<Uuid>,
+    ) -> Result<(OidcProviderConfig, IdTokenClaims), AppError> {
+        // A state can only be used once
+        let login_state = sqlx::query!(
+            r#"
+            DELETE FROM oidc_login_states
+            WHERE state = $1 AND expires_at > NOW()
+            RETURNING provider, code_verifier, nonce, link_user_id
+            "#,
+            state
+        )
+        .fetch_optional(&self.db.pool)
+        .await?
+        .filter(|login_state| login_state.link_user_id == link_user_id)
+        .ok_or_else(|| AppError::BadRequest("Sign-in request expired or invalid".to_string()))?;
+
+        let provider = self
+            .config
+            .oidc_provider(&login_state.provider)
+            .ok_or_else(|| AppError::NotFound("Unknown sign-in provider".to_string()))?
+            .clone();
+
+        let claims = self
+            .redeem_code(&provider, code, &login_state.code_verifier, &login_state.nonce)
+            .await
+            .map_err(|e| {
+                tracing::warn!("OIDC sign-in with {} failed: {}", provider.id, e);
+                AppError::Unauthorized(format!("Sign-in failed: {}", e))
+            })?;
+
+        Ok((provider, claims))
+    }
+
+    async fn redeem_code(
+        &self,
+        provider: &OidcProviderConfig,
+        code: &str,
+        code_verifier: &str,
+        nonce: &str,
+    ) -> Result<IdTokenClaims, Box<dyn Error + Send + Sync>> {
+        let client = http_client()?;
+        let cached = provider_metadata(&client, provider, false).await?;
+
+        let response = client
+            .post(&cached.metadata.token_endpoint)
+            .basic_auth(&provider.client_id, Some(&provider.client_secret))
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", self.config.oidc_redirect_url.as_str()),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body: Value = response.json().await.unwrap_or_default();
+            let error = body["error"].as_str().unwrap_or("").to_string();
+            return Err(format!("token endpoint responded with {} {}", status, error).trim().to_string().into());
+        }
+        let tokens: TokenResponse = response.json().await?;
+        let id_token = tokens.id_token.ok_or("no ID token in the token response")?;
+
+        let mut claims = verify_id_token(&client, provider, &id_token).await?;
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err("ID token nonce mismatch".into());
+        }
+
+        // Some providers only share the email through the userinfo endpoint
+        if claims.email.is_none() {
+            if let (Some(endpoint), Some(access_token)) = (&cached.metadata.userinfo_endpoint, &tokens.access_token) {
+                let userinfo: UserInfo = client
+                    .get(endpoint)
+                    .bearer_auth(access_token)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                if userinfo.sub == claims.sub {
+                    claims.email = userinfo.email;
+                    claims.email_verified = userinfo.email_verified;
+                }
+            }
+        }
+
+        Ok(claims)
+    }
+
+    async fn create_identity(
+        &self,
+        provider: &OidcProviderConfig,
+        claims: &IdTokenClaims,
+    ) -> Result<Uuid, AppError> {
+        let email = claims
+            .email
+            .as_deref()
+            .ok_or_else(|| AppError::BadRequest(format!("{} did not share an email address", provider.display_name)))?;
+
+        let mut tx = self.db.pool.begin().await?;
+
+        let existing_user = sqlx::query!("SELECT id, is_verified FROM users WHERE LOWER(email) = LOWER($1)", email)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let user_id = match existing_user {
+            // Only an email both sides have verified proves it's the same person. An unverified
+            // local account could have been registered by someone else to hijack the provider login.
+            Some(user) if user.is_verified && claims.verified_email().is_some() => user.id,
+            Some(_) => {
+                return Err(AppError::Conflict(format!(
+                    "An account with this email already exists. Sign in with your password and link {} from your settings",
+                    provider.display_name
+                )));
+            }
+            None => {
+                let username = self.available_username(claims, email).await?;
+                let user_id = sqlx::query_scalar!(
+                    r#"
+                    INSERT INTO users (email, username, display_name, password_hash, is_verified)
+                    VALUES ($1, $2, $3, NULL, $4)
+                    RETURNING id
+                    "#,
+                    email,
+                    username,
+                    claims.name.as_deref().map(|name| name.chars().take(100).collect::<String>()),
+                    claims.verified_email().is_some()
+                )
+                .fetch_one(&mut *tx)
+                .await?;
+
+                sqlx::query!("INSERT INTO digest_settings (user_id) VALUES ($1)", user_id)
+                    .execute(&mut *tx)
+                    .await?;
+
+                user_id
+            }
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO user_identities (user_id, provider, subject, email, last_login_at)
+            VALUES ($1, $2, $3, $4, NOW())
+            "#,
+            user_id,
+            provider.id,
+            claims.sub,
+            claims.email
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                AppError::Conflict(format!("This account already has another {} account linked", provider.display_name))
+            }
+            e => AppError::from(e),
+        })?;
+
+        tx.commit().await?;
+
+        Ok(user_id)
+    }
+
+    // A username from the provider's preferred username or the email, made unique
+    async fn available_username(&self, claims: &IdTokenClaims, email: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let source = claims
+            .preferred_username
+            .as_deref()
+            .unwrap_or_else(|| email.split('@').next().unwrap_or(""));
+        let mut base: String = source
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .take(24)
+            .collect();
+        if base.len() < 3 {
+            base = format!("user_{}", base);
+        }
+
+        let mut candidate = base.clone();
+        for _ in 0..10 {
+            let taken = sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM users WHERE username = $1)", candidate)
+                .fetch_one(&self.db.pool)
+                .await?
+                .unwrap_or(false);
+            if !taken {
+                return Ok(candidate);
+            }
+            candidate = format!("{}{}", base, OsRng.next_u32() % 100_000);
+        }
+
+        Err("Could not find an available username".into())
+    }
+}
+
+fn http_client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .user_agent("FastBlog")
+        .build()
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+async fn provider_metadata(
+    client: &reqwest::Client,
+    provider: &OidcProviderConfig,
+    refresh: bool,
+) -> Result<Arc<CachedProvider>, Box<dyn Error + Send + Sync>> {
+    if !refresh {
+        if let Some(cached) = PROVIDER_CACHE.get(&provider.issuer) {
+            if cached.fetched_at.elapsed() < METADATA_TTL {
+                return Ok(cached.clone());
+            }
+        }
+    }
+
+    let metadata: ProviderMetadata = client
+        .get(format!("{}/.well-known/openid-configuration", provider.issuer))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if metadata.issuer.trim_end_matches('/') != provider.issuer {
+        return Err(format!("discovery document is for issuer {}", metadata.issuer).into());
+    }
+    let jwks: JwkSet = client
+        .get(&metadata.jwks_uri)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let cached = Arc::new(CachedProvider {
+        metadata,
+        jwks,
+        fetched_at: Instant::now(),
+    });
+    PROVIDER_CACHE.insert(provider.issuer.clone(), cached.clone());
+
+    Ok(cached)
+}
+
+async fn verify_id_token(
+    client: &reqwest::Client,
+    provider: &OidcProviderConfig,
+    id_token: &str,
+) -> Result<IdTokenClaims, Box<dyn Error + Send + Sync>> {
+    let header = decode_header(id_token)?;
+    // Only asymmetric signatures; HMAC would make the client secret a signing key
+    if !matches!(
+        header.alg,
+        Algorithm::RS256
+            | Algorithm::RS384
+            | Algorithm::RS512
+            | Algorithm::PS256
+            | Algorithm::PS384
+            | Algorithm::PS512
+            | Algorithm::ES256
+            | Algorithm::ES384
+            | Algorithm::EdDSA
+    ) {
+        return Err(format!("unsupported ID token algorithm {:?}", header.alg).into());
+    }
+
+    for refresh in [false, true] {
+        let cached = provider_metadata(client, provider, refresh).await?;
+        let jwk = match &header.kid {
+            Some(kid) => cached.jwks.find(kid),
+            None if cached.jwks.keys.len() == 1 => cached.jwks.keys.first(),
+            None => None,
+        };
+        let Some(jwk) = jwk else {
+            continue;
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&provider.client_id]);
+        validation.set_issuer(&[&provider.issuer, &cached.metadata.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let token = decode::<IdTokenClaims>(id_token, &DecodingKey::from_jwk(jwk)?, &validation)?;
+        return Ok(token.claims);
+    }
+
+    Err("ID token is signed with an unknown key".into())
+}
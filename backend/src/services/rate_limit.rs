@@ -0,0 +1,254 @@
+use chrono::Utc;
+use dashmap::DashMap;
+use once_cell::sync::{Lazy, OnceCell};
+use redis::aio::ConnectionManager;
+use std::time::Duration;
+
+// Keys in Redis are namespaced so the database can be shared
+const REDIS_PREFIX: &str = "ratelimit:";
+// How often expired in-memory entries are dropped
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+// GCRA in a single round trip: the stored value is the theoretical arrival time (TAT)
+// in milliseconds. Redis's clock is used so instances with skewed clocks agree.
+const GCRA_SCRIPT: &str = r#"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local interval = tonumber(ARGV[1])
+local period = tonumber(ARGV[2])
+local tat = tonumber(redis.call('GET', KEYS[1]) or now)
+if tat < now then
+    tat = now
+end
+local new_tat = tat + interval
+if new_tat - now > period then
+    return {0, tat, now}
+end
+redis.call('SET', KEYS[1], new_tat, 'PX', new_tat - now)
+return {1, new_tat, now}
+"#;
+
+static LIMITER: Lazy<RateLimiter> = Lazy::new(RateLimiter::new);
+
+// Process-wide limiter; counts are shared between instances once Redis is connected
+pub fn limiter() -> &'static RateLimiter {
+    &LIMITER
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    // Requests allowed per period, all of which may come in one burst
+    pub limit: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    // Time between requests at the sustained rate
+    fn interval_ms(&self) -> i64 {
+        (self.period.as_millis() as i64 / self.limit.max(1) as i64).max(1)
+    }
+
+    fn period_ms(&self) -> i64 {
+        self.period.as_millis() as i64
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    // Seconds until the full quota is available again
+    pub reset: u64,
+    // Seconds until the next request is allowed; 0 when allowed
+    pub retry_after: u64,
+}
+
+impl Decision {
+    fn from_tat(allowed: bool, tat: i64, now: i64, quota: &Quota) -> Self {
+        let interval = quota.interval_ms();
+        let backlog = (tat - now).max(0);
+        let remaining = ((quota.period_ms() - backlog) / interval).clamp(0, quota.limit as i64) as u32;
+        let retry_after = if allowed { 0 } else { tat + interval - quota.period_ms() - now };
+
+        Self {
+            allowed,
+            limit: quota.limit,
+            remaining,
+            reset: ceil_seconds(backlog),
+            retry_after: ceil_seconds(retry_after).max(if allowed { 0 } else { 1 }),
+        }
+    }
+}
+
+pub struct RateLimiter {
+    // TAT per key, in milliseconds since the epoch
+    memory: DashMap<String, i64>,
+    redis: OnceCell<ConnectionManager>,
+    script: redis::Script,
+}
+
+impl RateLimiter {
+    fn new() -> Self {
+        Self {
+            memory: DashMap::new(),
+            redis: OnceCell::new(),
+            script: redis::Script::new(GCRA_SCRIPT),
+        }
+    }
+
+    // Keep the counters in Redis so every instance enforces the same limits
+    pub async fn connect_redis(&'static self, redis_url: &str) -> Result<(), redis::RedisError> {
+        let client = redis::Client::open(redis_url)?;
+        let manager = client.get_connection_manager().await?;
+        let _ = self.redis.set(manager);
+
+        tracing::info!("Rate limits are shared through Redis");
+        Ok(())
+    }
+
+    // Drops entries whose quota has fully recovered; only needed for the in-memory backend
+    pub fn spawn_cleanup(&'static self) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let now = Utc::now().timestamp_millis();
+                self.memory.retain(|_, tat| *tat > now);
+            }
+        });
+    }
+
+    // Counts one request against `key`
+    pub async fn check(&self, key: &str, quota: &Quota) -> Decision {
+        if let Some(manager) = self.redis.get() {
+            let mut connection = manager.clone();
+            let result: Result<(i64, i64, i64), redis::RedisError> = self
+                .script
+                .key(format!("{}{}", REDIS_PREFIX, key))
+                .arg(quota.interval_ms())
+                .arg(quota.period_ms())
+                .invoke_async(&mut connection)
+                .await;
+
+            match result {
+                Ok((allowed, tat, now)) => return Decision::from_tat(allowed == 1, tat, now, quota),
+                // Better to limit per instance than not at all (or to reject everyone)
+                Err(e) => tracing::warn!("Redis rate limit check failed, using local counters: {}", e),
+            }
+        }
+
+        self.check_memory(key, quota)
+    }
+
+    fn check_memory(&self, key: &str, quota: &Quota) -> Decision {
+        self.check_memory_at(key, quota, Utc::now().timestamp_millis())
+    }
+
+    fn check_memory_at(&self, key: &str, quota: &Quota, now: i64) -> Decision {
+        let mut entry = self.memory.entry(key.to_string()).or_insert(now);
+
+        let tat = (*entry).max(now);
+        let new_tat = tat + quota.interval_ms();
+        if new_tat - now > quota.period_ms() {
+            return Decision::from_tat(false, tat, now, quota);
+        }
+
+        *entry = new_tat;
+        Decision::from_tat(true, new_tat, now, quota)
+    }
+}
+
+fn ceil_seconds(milliseconds: i64) -> u64 {
+    (milliseconds.max(0) as u64).div_ceil(1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000_000;
+
+    fn quota(limit: u32, seconds: u64) -> Quota {
+        Quota { limit, period: Duration::from_secs(seconds) }
+    }
+
+    #[test]
+    fn interval_is_the_period_spread_over_the_limit() {
+        assert_eq!(quota(5, 60).interval_ms(), 12_000);
+        assert_eq!(quota(300, 60).interval_ms(), 200);
+        // Never zero, even for limits above the period in milliseconds or a zero limit
+        assert_eq!(quota(5000, 1).interval_ms(), 1);
+        assert_eq!(quota(0, 60).interval_ms(), 60_000);
+    }
+
+    #[test]
+    fn allows_the_whole_quota_as_a_burst_then_limits() {
+        let limiter = RateLimiter::new();
+        let quota = quota(5, 60);
+
+        for remaining in (0..5).rev() {
+            let decision = limiter.check_memory_at("key", &quota, NOW);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, remaining);
+            assert_eq!(decision.retry_after, 0);
+        }
+
+        let decision = limiter.check_memory_at("key", &quota, NOW);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(decision.retry_after, 12);
+        assert_eq!(decision.reset, 60);
+    }
+
+    #[test]
+    fn recovers_one_request_per_interval() {
+        let limiter = RateLimiter::new();
+        let quota = quota(5, 60);
+        for _ in 0..5 {
+            limiter.check_memory_at("key", &quota, NOW);
+        }
+
+        assert!(!limiter.check_memory_at("key", &quota, NOW + 11_999).allowed);
+        let decision = limiter.check_memory_at("key", &quota, NOW + 12_000);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert!(!limiter.check_memory_at("key", &quota, NOW + 12_000).allowed);
+    }
+
+    #[test]
+    fn denied_requests_do_not_push_the_window_back() {
+        let limiter = RateLimiter::new();
+        let quota = quota(1, 10);
+
+        assert!(limiter.check_memory_at("key", &quota, NOW).allowed);
+        for offset in [1, 1_000, 9_999] {
+            assert!(!limiter.check_memory_at("key", &quota, NOW + offset).allowed);
+        }
+        assert!(limiter.check_memory_at("key", &quota, NOW + 10_000).allowed);
+    }
+
+    #[test]
+    fn counts_keys_separately() {
+        let limiter = RateLimiter::new();
+        let quota = quota(1, 60);
+
+        assert!(limiter.check_memory_at("a", &quota, NOW).allowed);
+        assert!(limiter.check_memory_at("b", &quota, NOW).allowed);
+        assert!(!limiter.check_memory_at("a", &quota, NOW).allowed);
+    }
+
+    #[test]
+    fn decision_rounds_partial_seconds_up() {
+        let quota = quota(3, 1);
+
+        let decision = Decision::from_tat(false, NOW + 1_000, NOW, &quota);
+        // Next slot opens at tat + interval - period = 333ms from now
+        assert_eq!(decision.retry_after, 1);
+        assert_eq!(decision.reset, 1);
+
+        let decision = Decision::from_tat(true, NOW + 334, NOW, &quota);
+        assert_eq!(decision.remaining, 2);
+        assert_eq!(decision.reset, 1);
+    }
+}
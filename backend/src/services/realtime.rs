@@ -0,0 +1,197 @@
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use once_cell::sync::OnceCell;
+use rand_core::{OsRng, RngCore};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::notification::NotificationResponse;
+
+// Redis channel used to fan events out to every backend instance
+const REDIS_CHANNEL: &str = "fastblog:realtime";
+// Events buffered per subscriber before slow clients start lagging
+const CHANNEL_CAPACITY: usize = 1024;
+// Stream tickets only need to outlive the round trip to open the EventSource
+const TICKET_TTL_SECONDS: i64 = 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RealtimeEvent {
+    // A notification was created or updated for `user_id`
+    Notification {
+        user_id: Uuid,
+        unread_count: i64,
+        notification: Box<NotificationResponse>,
+    },
+    // Live engagement counters for an article
+    ArticleCounts {
+        article_id: Uuid,
+        claps_count: i64,
+        comments_count: i32,
+    },
+}
+
+impl RealtimeEvent {
+    // SSE event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            RealtimeEvent::Notification { .. } => "notification",
+            RealtimeEvent::ArticleCounts { .. } => "article_counts",
+        }
+    }
+
+    // Whether a subscriber (user, optionally viewing an article) should receive this event
+    pub fn is_visible_to(&self, user_id: Uuid, article_id: Option<Uuid>) -> bool {
+        match self {
+            RealtimeEvent::Notification { user_id: recipient, .. } => *recipient == user_id,
+            RealtimeEvent::ArticleCounts { article_id: id, .. } => article_id == Some(*id),
+        }
+    }
+}
+
+// Shared by services (publishers) and stream handlers (subscribers) through `AppState`
+pub struct RealtimeHub {
+    sender: broadcast::Sender<RealtimeEvent>,
+    redis: OnceCell<ConnectionManager>,
+}
+
+impl Default for RealtimeHub {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+            redis: OnceCell::new(),
+        }
+    }
+}
+
+impl RealtimeHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Switch to the Redis backend: events are published to Redis and every instance
+    // (including this one) relays what it receives to its local subscribers
+    pub async fn connect_redis(self: &Arc<Self>, redis_url: &str) -> Result<(), redis::RedisError> {
+        let client = redis::Client::open(redis_url)?;
+        let manager = client.get_connection_manager().await?;
+        let _ = self.redis.set(manager);
+
+        let hub = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = hub.relay_from_redis(&client).await {
+                    tracing::error!("Realtime Redis subscription failed: {}", e);
+                }
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        });
+
+        tracing::info!("Realtime events fan out through Redis channel {}", REDIS_CHANNEL);
+        Ok(())
+    }
+
+    async fn relay_from_redis(&self, client: &redis::Client) -> Result<(), redis::RedisError> {
+        let mut pubsub = client.get_async_pubsub().await?;
+        pubsub.subscribe(REDIS_CHANNEL).await?;
+
+        let mut messages = pubsub.on_message();
+        while let Some(message) = messages.next().await {
+            let payload: String = message.get_payload()?;
+            match serde_json::from_str::<RealtimeEvent>(&payload) {
+                Ok(event) => {
+                    // No local subscribers is not an error
+                    let _ = self.sender.send(event);
+                }
+                Err(e) => tracing::warn!("Dropping malformed realtime event: {}", e),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<RealtimeEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish(&self, event: RealtimeEvent) {
+        match self.redis.get() {
+            Some(manager) => {
+                let mut connection = manager.clone();
+                tokio::spawn(async move {
+                    let payload = match serde_json::to_string(&event) {
+                        Ok(payload) => payload,
+                        Err(e) => {
+                            tracing::error!("Failed to serialize realtime event: {}", e);
+                            return;
+                        }
+                    };
+                    let result: Result<i64, redis::RedisError> =
+                        connection.publish(REDIS_CHANNEL, payload).await;
+                    if let Err(e) = result {
+                        tracing::warn!("Failed to publish realtime event to Redis: {}", e);
+                    }
+                });
+            }
+            None => {
+                let _ = self.sender.send(event);
+            }
+        }
+    }
+}
+
+// Single-use tickets for `GET /realtime/stream`. EventSource can't send an
+// Authorization header, and a session token in the query string ends up in logs.
+pub struct StreamTicketService {
+    db: PgPool,
+}
+
+impl StreamTicketService {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    pub async fn issue(&self, user_id: Uuid) -> Result<(String, DateTime<Utc>), AppError> {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let ticket = hex::encode(bytes);
+        let expires_at = Utc::now() + chrono::Duration::seconds(TICKET_TTL_SECONDS);
+
+        sqlx::query!("DELETE FROM realtime_tickets WHERE expires_at < NOW()")
+            .execute(&self.db)
+            .await?;
+        sqlx::query!(
+            "INSERT INTO realtime_tickets (ticket_hash, user_id, expires_at) VALUES ($1, $2, $3)",
+            hash_ticket(&ticket),
+            user_id,
+            expires_at
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok((ticket, expires_at))
+    }
+
+    // Consumes the ticket, so a leaked URL can't be replayed
+    pub async fn redeem(&self, ticket: &str) -> Result<Uuid, AppError> {
+        sqlx::query_scalar!(
+            "DELETE FROM realtime_tickets WHERE ticket_hash = $1 AND expires_at > NOW() RETURNING user_id",
+            hash_ticket(ticket)
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Stream ticket expired or invalid".to_string()))
+    }
+}
+
+fn hash_ticket(ticket: &str) -> String {
+    hex::encode(Sha256::digest(ticket.as_bytes()))
+}
@@ -0,0 +1,383 @@
+use askama::Template;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::models::seo::{ArticleSeoResponse, MetaTag};
+use crate::util::{decode_entities, urlencode};
+
+// The protocol allows 50,000 URLs per sitemap; smaller pages keep responses light
+const SITEMAP_PAGE_SIZE: i64 = 10_000;
+
+const SITE_NAME: &str = "FastBlog";
+
+// Published, visible articles; shared by every sitemap source below
+const PUBLISHED_ARTICLES: &str = r#"
+    SELECT a.* FROM articles a
+    JOIN users u ON u.id = a.author_id
+    WHERE a.status = 'published'
+        AND a.published_at IS NOT NULL
+        AND a.published_at <= NOW()
+        AND u.is_banned = FALSE
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SitemapKind {
+    Articles,
+    Authors,
+    Tags,
+    Publications,
+}
+
+impl SitemapKind {
+    pub const ALL: [SitemapKind; 4] = [
+        SitemapKind::Articles,
+        SitemapKind::Authors,
+        SitemapKind::Tags,
+        SitemapKind::Publications,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            SitemapKind::Articles => "articles",
+            SitemapKind::Authors => "authors",
+            SitemapKind::Tags => "tags",
+            SitemapKind::Publications => "publications",
+        }
+    }
+
+    // Parse a child sitemap file name, e.g. `articles-2.xml`
+    pub fn from_file_name(file_name: &str) -> Option<(Self, i64)> {
+        let (name, page) = file_name.strip_suffix(".xml")?.rsplit_once('-')?;
+        let page = page.parse::<i64>().ok().filter(|page| *page >= 1)?;
+        let kind = Self::ALL.into_iter().find(|kind| kind.name() == name)?;
+        Some((kind, page))
+    }
+
+    pub fn file_name(&self, page: i64) -> String {
+        format!("{}-{}.xml", self.name(), page)
+    }
+
+    // Rows of (key, lastmod), one per URL
+    fn source_sql(&self) -> String {
+        match self {
+            // Cross-posted articles are canonical elsewhere and stay out of the sitemap
+            SitemapKind::Articles => format!(
+                "SELECT p.slug AS key, MAX(GREATEST(p.updated_at, p.published_at)) AS lastmod
+                 FROM ({PUBLISHED_ARTICLES}) p
+                 WHERE p.canonical_url IS NULL
+                 GROUP BY p.slug"
+            ),
+            SitemapKind::Authors => format!(
+                "SELECT u.username AS key, GREATEST(u.updated_at, MAX(p.published_at)) AS lastmod
+                 FROM users u
+                 JOIN ({PUBLISHED_ARTICLES}) p ON p.author_id = u.id
+                 GROUP BY u.id"
+            ),
+            SitemapKind::Tags => format!(
+                "SELECT t.tag AS key, MAX(GREATEST(p.updated_at, p.published_at)) AS lastmod
+                 FROM ({PUBLISHED_ARTICLES}) p, unnest(p.tags) AS t(tag)
+                 GROUP BY t.tag"
+            ),
+            SitemapKind::Publications => format!(
+                "SELECT pub.slug AS key, GREATEST(pub.updated_at, MAX(p.published_at)) AS lastmod
+                 FROM publications pub
+                 LEFT JOIN ({PUBLISHED_ARTICLES}) p ON p.publication_id = pub.id
+                 GROUP BY pub.id"
+            ),
+        }
+    }
+
+    // Sitemap URLs must be fully escaped, slugs may contain non-ASCII letters
+    fn url(&self, frontend_url: &str, key: &str) -> String {
+        let key = urlencode(key);
+        match self {
+            SitemapKind::Articles => format!("{}/article/{}", frontend_url, key),
+            SitemapKind::Authors => format!("{}/@{}", frontend_url, key),
+            SitemapKind::Tags => format!("{}/tag/{}", frontend_url, key),
+            SitemapKind::Publications => format!("{}/publication/{}", frontend_url, key),
+        }
+    }
+}
+
+pub struct SitemapEntry {
+    pub loc: String,
+    pub lastmod: Option<DateTime<Utc>>,
+}
+
+#[derive(Template)]
+#[template(path = "sitemaps/index.xml", escape = "html")]
+struct SitemapIndexTemplate<'a> {
+    entries: &'a [SitemapEntry],
+}
+
+#[derive(Template)]
+#[template(path = "sitemaps/urlset.xml", escape = "html")]
+struct UrlSetTemplate<'a> {
+    entries: &'a [SitemapEntry],
+}
+
+pub struct SeoService<'a> {
+    db: PgPool,
+    config: &'a Config,
+}
+
+impl<'a> SeoService<'a> {
+    pub fn new(db: PgPool, config: &'a Config) -> Self {
+        Self { db, config }
+    }
+
+    // Sitemap index listing every child sitemap page with its newest lastmod
+    pub async fn sitemap_index(&self) -> Result<String, AppError> {
+        let frontend_url = &self.config.frontend_url;
+        let mut entries = Vec::new();
+
+        for kind in SitemapKind::ALL {
+            let pages = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(&format!(
+                "SELECT page, MAX(lastmod)
+                 FROM (
+                     SELECT (ROW_NUMBER() OVER (ORDER BY s.key) - 1) / $1 + 1 AS page, s.lastmod
+                     FROM ({}) s
+                 ) numbered
+                 GROUP BY page
+                 ORDER BY page",
+                kind.source_sql()
+            ))
+            .bind(SITEMAP_PAGE_SIZE)
+            .fetch_all(&self.db)
+            .await?;
+
+            entries.extend(pages.into_iter().map(|(page, lastmod)| SitemapEntry {
+                loc: format!("{}/sitemaps/{}", frontend_url, kind.file_name(page)),
+                lastmod,
+            }));
+        }
+
+        Ok(SitemapIndexTemplate { entries: &entries }.render()?)
+    }
+
+    // One page of a child sitemap; None past the last page
+    pub async fn sitemap(&self, kind: SitemapKind, page: i64) -> Result<Option<String>, AppError> {
+        let rows = sqlx::query_as::<_, (String, Option<DateTime<Utc>>)>(&format!(
+            "SELECT s.key, s.lastmod FROM ({}) s ORDER BY s.key LIMIT $1 OFFSET $2",
+            kind.source_sql()
+        ))
+        .bind(SITEMAP_PAGE_SIZE)
+        .bind((page - 1) * SITEMAP_PAGE_SIZE)
+        .fetch_all(&self.db)
+        .await?;
+
+        // An empty first page is still a valid (empty) sitemap
+        if rows.is_empty() && page > 1 {
+            return Ok(None);
+        }
+
+        let frontend_url = &self.config.frontend_url;
+        let entries: Vec<SitemapEntry> = rows
+            .into_iter()
+            .map(|(key, lastmod)| SitemapEntry {
+                loc: kind.url(frontend_url, &key),
+                lastmod,
+            })
+            .collect();
+
+        Ok(Some(UrlSetTemplate { entries: &entries }.render()?))
+    }
+
+    pub fn robots_txt(config: &Config) -> String {
+        let robots = &config.robots;
+        if !robots.allow_indexing {
+            return "User-agent: *\nDisallow: /\n".to_string();
+        }
+
+        let mut body = String::from("User-agent: *\n");
+        for path in &robots.disallow {
+            body.push_str(&format!("Disallow: {}\n", path));
+        }
+        body.push_str(&format!("\nSitemap: {}/sitemap.xml\n", config.frontend_url));
+        body
+    }
+
+    // Open Graph, Twitter Card and JSON-LD metadata for a published article
+    pub async fn article_metadata(&self, slug: &str) -> Result<ArticleSeoResponse, AppError> {
+        let article = sqlx::query!(
+            r#"
+            SELECT
+                a.title, a.subtitle, a.excerpt, a.content_html, a.slug, a.canonical_url, a.featured_image_url,
+                COALESCE(a.tags, ARRAY[]::TEXT[]) as "tags!",
+                COALESCE(a.categories, ARRAY[]::TEXT[]) as "categories!",
+                a.is_member_only, a.reading_time_minutes,
+                a.published_at as "published_at!", a.updated_at,
+                u.username, u.display_name, u.avatar_url,
+                p.name as "publication_name?", p.slug as "publication_slug?", p.logo_url as "publication_logo_url?"
+            FROM articles a
+            JOIN users u ON u.id = a.author_id
+            LEFT JOIN publications p ON p.id = a.publication_id
+            WHERE a.slug = $1
+                AND a.status = 'published'
+                AND a.published_at IS NOT NULL
+                AND a.published_at <= NOW()
+                AND u.is_banned = FALSE
+            ORDER BY a.published_at DESC
+            LIMIT 1
+            "#,
+            slug
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Article not found".to_string()))?;
+
+        let frontend_url = &self.config.frontend_url;
+        let canonical_url = article
+            .canonical_url
+            .unwrap_or_else(|| format!("{}/article/{}", frontend_url, article.slug));
+        let author_name = article.display_name.unwrap_or_else(|| article.username.clone());
+        let author_url = format!("{}/@{}", frontend_url, article.username);
+        let modified_at = article.updated_at.max(article.published_at);
+
+        let description = article
+            .excerpt
+            .or(article.subtitle)
+            .filter(|text| !text.trim().is_empty())
+            .unwrap_or_else(|| strip_tags(&article.content_html));
+        let description = truncate(&decode_entities(&normalize_whitespace(&description)), 160);
+
+        let image_url = article.featured_image_url.clone();
+
+        let mut open_graph = vec![
+            MetaTag::new("og:type", "article"),
+            MetaTag::new("og:site_name", SITE_NAME),
+            MetaTag::new("og:title", article.title.clone()),
+            MetaTag::new("og:description", description.clone()),
+            MetaTag::new("og:url", canonical_url.clone()),
+            MetaTag::new("article:published_time", article.published_at.to_rfc3339()),
+            MetaTag::new("article:modified_time", modified_at.to_rfc3339()),
+            MetaTag::new("article:author", author_url.clone()),
+        ];
+        if let Some(section) = article.categories.first() {
+            open_graph.push(MetaTag::new("article:section", section.clone()));
+        }
+        open_graph.extend(article.tags.iter().map(|tag| MetaTag::new("article:tag", tag.clone())));
+        if let Some(image_url) = &image_url {
+            open_graph.push(MetaTag::new("og:image", image_url.clone()));
+            open_graph.push(MetaTag::new("og:image:alt", article.title.clone()));
+        }
+
+        let mut twitter = vec![
+            MetaTag::new(
+                "twitter:card",
+                if image_url.is_some() { "summary_large_image" } else { "summary" },
+            ),
+            MetaTag::new("twitter:title", article.title.clone()),
+            MetaTag::new("twitter:description", description.clone()),
+            MetaTag::new("twitter:label1", "Written by"),
+            MetaTag::new("twitter:data1", author_name.clone()),
+            MetaTag::new("twitter:label2", "Reading time"),
+            MetaTag::new("twitter:data2", format!("{} min read", article.reading_time_minutes)),
+        ];
+        if let Some(image_url) = &image_url {
+            twitter.push(MetaTag::new("twitter:image", image_url.clone()));
+        }
+
+        let publisher = match (&article.publication_name, &article.publication_slug) {
+            (Some(name), Some(slug)) => json!({
+                "@type": "Organization",
+                "name": name,
+                "url": format!("{}/publication/{}", frontend_url, slug),
+                "logo": article.publication_logo_url.as_ref().map(|logo| json!({
+                    "@type": "ImageObject",
+                    "url": logo
+                }))
+            }),
+            _ => json!({
+                "@type": "Organization",
+                "name": SITE_NAME,
+                "url": frontend_url
+            }),
+        };
+
+        let json_ld = json!({
+            "@context": "https://schema.org",
+            "@type": "Article",
+            // Google truncates headlines longer than 110 characters
+            "headline": truncate(&article.title, 110),
+            "description": description,
+            "url": canonical_url,
+            "mainEntityOfPage": { "@type": "WebPage", "@id": canonical_url },
+            "image": image_url.as_ref().map(|url| vec![url]),
+            "datePublished": article.published_at.to_rfc3339(),
+            "dateModified": modified_at.to_rfc3339(),
+            "author": {
+                "@type": "Person",
+                "name": author_name,
+                "url": author_url,
+                "image": article.avatar_url
+            },
+            "publisher": publisher,
+            "keywords": article.tags,
+            "articleSection": article.categories.first(),
+            "timeRequired": format!("PT{}M", article.reading_time_minutes),
+            "isAccessibleForFree": !article.is_member_only
+        });
+
+        Ok(ArticleSeoResponse {
+            title: format!("{} | {}", article.title, SITE_NAME),
+            description,
+            canonical_url,
+            image_url,
+            open_graph,
+            twitter,
+            json_ld: strip_nulls(json_ld),
+        })
+    }
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Cut on a word boundary without splitting multi-byte characters
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let cut: String = text.chars().take(max_chars - 1).collect();
+    let cut = match cut.rfind(' ') {
+        Some(last_space) if last_space > 0 => &cut[..last_space],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation()))
+}
+
+// Optional schema.org properties are left out rather than sent as null
+fn strip_nulls(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, strip_nulls(v)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
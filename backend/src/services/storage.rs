@@ -0,0 +1,556 @@
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::fs;
+
+use crate::config::{Config, S3Config, StorageBackend};
+use crate::util::decode_entities;
+
+pub type StorageResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+// Keys under this prefix are never served publicly; use signed URLs
+pub const PRIVATE_PREFIX: &str = "private/";
+
+// Sent instead of a body hash in presigned URLs
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub key: String,
+    pub size: u64,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+// Where uploaded files live, shared through AppState. Keys are relative paths
+// such as `avatars/{id}_512.jpg`.
+pub trait Storage: Send + Sync {
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> BoxFuture<'a, StorageResult<()>>;
+
+    // None when the object doesn't exist
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StorageResult<Option<Vec<u8>>>>;
+
+    // Deleting a missing object is not an error
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StorageResult<()>>;
+
+    // Every object whose key starts with `prefix`
+    fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, StorageResult<Vec<StoredObject>>>;
+
+    fn public_url(&self, key: &str) -> String;
+
+    // Time-limited URL that works for private keys too
+    fn signed_url(&self, key: &str, expires_in: Duration) -> String;
+
+    // Checks a signature from `signed_url` when this server serves the file itself
+    fn verify_signature(&self, _key: &str, _expires: i64, _signature: &str) -> bool {
+        false
+    }
+
+    // Reverse of `public_url`; also accepts the `/uploads/...` paths stored before storage was configurable
+    fn key_from_url(&self, url: &str) -> Option<String> {
+        let base = self.public_url("");
+        let key = url
+            .strip_prefix(base.as_str())
+            .or_else(|| url.strip_prefix("/uploads/"))
+            .or_else(|| url.strip_prefix("uploads/"))?;
+        let key = key.split(['?', '#']).next().unwrap_or(key);
+        (!key.is_empty()).then(|| key.to_string())
+    }
+}
+
+// The backend selected by STORAGE_BACKEND
+pub fn from_config(config: &Config) -> Result<Arc<dyn Storage>, Box<dyn Error>> {
+    build(config, config.storage.backend)
+}
+
+// STORAGE_PUBLIC_URL only applies to the configured backend; others use their default URL
+pub fn build(config: &Config, backend: StorageBackend) -> Result<Arc<dyn Storage>, Box<dyn Error>> {
+    let settings = &config.storage;
+    let public_url = settings.public_url.clone().filter(|_| backend == settings.backend);
+
+    match backend {
+        StorageBackend::Local => Ok(Arc::new(LocalStorage::new(
+            &settings.local_root,
+            public_url.unwrap_or_else(|| format!("{}/uploads", config.api_url)),
+            config.api_url.clone(),
+            settings.signing_secret.as_bytes().to_vec(),
+        ))),
+        StorageBackend::S3 => {
+            let s3 = settings
+                .s3
+                .as_ref()
+                .ok_or("S3 storage needs S3_BUCKET, S3_ACCESS_KEY_ID and S3_SECRET_ACCESS_KEY")?;
+            Ok(Arc::new(S3Storage::new(s3, public_url)?))
+        }
+    }
+}
+
+pub fn content_type(key: &str) -> String {
+    mime_guess::from_path(key).first_or_octet_stream().to_string()
+}
+
+// Keys end up in file paths and URLs: no absolute paths, `..` or empty segments
+fn validate_key(key: &str) -> StorageResult<()> {
+    let valid = !key.is_empty()
+        && !key.contains('\\')
+        && key.split('/').all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid storage key '{}'", key).into())
+    }
+}
+
+// Copy every object to another backend; objects already there with the same size are skipped.
+// Returns (copied, skipped).
+pub async fn copy_all(from: &dyn Storage, to: &dyn Storage) -> StorageResult<(usize, usize)> {
+    let existing: HashMap<String, u64> = to
+        .list("")
+        .await?
+        .into_iter()
+        .map(|object| (object.key, object.size))
+        .collect();
+
+    let (mut copied, mut skipped) = (0, 0);
+    for object in from.list("").await? {
+        if existing.get(&object.key) == Some(&object.size) {
+            skipped += 1;
+            continue;
+        }
+        // Deleted since listing
+        let Some(data) = from.get(&object.key).await? else {
+            continue;
+        };
+        to.put(&object.key, data).await?;
+        copied += 1;
+    }
+
+    Ok((copied, skipped))
+}
+
+pub struct LocalStorage {
+    root: PathBuf,
+    public_url: String,
+    // Signed URLs point at this API's /files endpoint
+    api_url: String,
+    signing_secret: Vec<u8>,
+}
+
+impl LocalStorage {
+    pub fn new(root: &str, public_url: String, api_url: String, signing_secret: Vec<u8>) -> Self {
+        Self {
+            root: PathBuf::from(root),
+            public_url,
+            api_url,
+            signing_secret,
+        }
+    }
+
+    fn path(&self, key: &str) -> StorageResult<PathBuf> {
+        validate_key(key)?;
+        Ok(self.root.join(key))
+    }
+
+    fn signature(&self, key: &str, expires: i64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.signing_secret).expect("HMAC accepts any key length");
+        mac.update(format!("{}\n{}", key, expires).as_bytes());
+        mac
+    }
+}
+
+impl Storage for LocalStorage {
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> BoxFuture<'a, StorageResult<()>> {
+        Box::pin(async move {
+            let path = self.path(key)?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+
+            // Write then rename so readers never see a partial file
+            let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or("upload");
+            let temp = path.with_file_name(format!(".{}.tmp", file_name));
+            fs::write(&temp, data).await?;
+            if let Err(e) = fs::rename(&temp, &path).await {
+                let _ = fs::remove_file(&temp).await;
+                return Err(e.into());
+            }
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StorageResult<Option<Vec<u8>>>> {
+        Box::pin(async move {
+            match fs::read(self.path(key)?).await {
+                Ok(data) => Ok(Some(data)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StorageResult<()>> {
+        Box::pin(async move {
+            match fs::remove_file(self.path(key)?).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, StorageResult<Vec<StoredObject>>> {
+        Box::pin(async move {
+            // Walk the deepest directory the prefix names, then filter by the rest
+            let dir = prefix.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+            let mut pending = vec![self.root.join(dir)];
+            let mut objects = Vec::new();
+
+            while let Some(dir) = pending.pop() {
+                let mut entries = match fs::read_dir(&dir).await {
+                    Ok(entries) => entries,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e.into()),
+                };
+
+                while let Some(entry) = entries.next_entry().await? {
+                    let metadata = entry.metadata().await?;
+                    let path = entry.path();
+                    // Dotfiles are .gitkeep and in-progress writes, not uploads
+                    if entry.file_name().to_string_lossy().starts_with('.') {
+                        continue;
+                    }
+                    if metadata.is_dir() {
+                        pending.push(path);
+                        continue;
+                    }
+
+                    let Some(key) = relative_key(&self.root, &path) else {
+                        continue;
+                    };
+                    if key.starts_with(prefix) {
+                        objects.push(StoredObject {
+                            key,
+                            size: metadata.len(),
+                            last_modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+                        });
+                    }
+                }
+            }
+
+            Ok(objects)
+        })
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_url, key)
+    }
+
+    // Served by GET /api/v1/files/{key}, which checks the signature
+    fn signed_url(&self, key: &str, expires_in: Duration) -> String {
+        let expires = Utc::now().timestamp() + expires_in.as_secs() as i64;
+        let signature = hex::encode(self.signature(key, expires).finalize().into_bytes());
+        format!(
+            "{}/api/v1/files/{}?expires={}&signature={}",
+            self.api_url,
+            uri_encode(key, false),
+            expires,
+            signature
+        )
+    }
+
+    fn verify_signature(&self, key: &str, expires: i64, signature: &str) -> bool {
+        if expires < Utc::now().timestamp() {
+            return false;
+        }
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+        self.signature(key, expires).verify_slice(&signature).is_ok()
+    }
+}
+
+fn relative_key(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let segments: Option<Vec<&str>> = relative.components().map(|part| part.as_os_str().to_str()).collect();
+    Some(segments?.join("/"))
+}
+
+// S3-compatible object storage (AWS, MinIO, R2, ...) over plain HTTP with SigV4 signing
+pub struct S3Storage {
+    client: reqwest::Client,
+    // Scheme and host requests go to, e.g. `http://localhost:9000`
+    origin: String,
+    host: String,
+    // `/bucket` for path-style addressing, empty for virtual-hosted buckets
+    bucket_path: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    public_url: String,
+}
+
+impl S3Storage {
+    pub fn new(config: &S3Config, public_url: Option<String>) -> Result<Self, Box<dyn Error>> {
+        let endpoint = Url::parse(&config.endpoint)?;
+        let endpoint_host = endpoint.host_str().ok_or("S3_ENDPOINT has no host")?;
+        let host_name = if config.path_style {
+            endpoint_host.to_string()
+        } else {
+            format!("{}.{}", config.bucket, endpoint_host)
+        };
+        let host = match endpoint.port() {
+            Some(port) => format!("{}:{}", host_name, port),
+            None => host_name,
+        };
+        let origin = format!("{}://{}", endpoint.scheme(), host);
+        let bucket_path = if config.path_style {
+            format!("/{}", uri_encode(&config.bucket, true))
+        } else {
+            String::new()
+        };
+
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(Duration::from_secs(60)).build()?,
+            public_url: public_url.unwrap_or_else(|| format!("{}{}", origin, bucket_path)),
+            origin,
+            host,
+            bucket_path,
+            region: config.region.clone(),
+            access_key_id: config.access_key_id.clone(),
+            secret_access_key: config.secret_access_key.clone(),
+        })
+    }
+
+    // Presigned GET; S3 checks the signature and expiry itself
+    fn presign(&self, key: &str, now: DateTime<Utc>, expires_in: Duration) -> String {
+        let path = self.object_path(key);
+        let credential = format!("{}/{}", self.access_key_id, self.scope(now));
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        // S3 caps presigned URLs at seven days
+        let expires = expires_in.as_secs().clamp(1, 7 * 24 * 60 * 60).to_string();
+
+        let query = canonical_query(&[
+            ("X-Amz-Algorithm", "AWS4-HMAC-SHA256"),
+            ("X-Amz-Credential", &credential),
+            ("X-Amz-Date", &amz_date),
+            ("X-Amz-Expires", &expires),
+            ("X-Amz-SignedHeaders", "host"),
+        ]);
+        let canonical_request = format!("GET\n{}\n{}\nhost:{}\n\nhost\n{}", path, query, self.host, UNSIGNED_PAYLOAD);
+
+        format!(
+            "{}{}?{}&X-Amz-Signature={}",
+            self.origin,
+            path,
+            query,
+            self.sign(now, &canonical_request)
+        )
+    }
+
+    fn object_path(&self, key: &str) -> String {
+        format!("{}/{}", self.bucket_path, uri_encode(key, false))
+    }
+
+    fn signing_key(&self, date: &str) -> Vec<u8> {
+        let secret = format!("AWS4{}", self.secret_access_key);
+        let key = hmac_sha256(secret.as_bytes(), date.as_bytes());
+        let key = hmac_sha256(&key, self.region.as_bytes());
+        let key = hmac_sha256(&key, b"s3");
+        hmac_sha256(&key, b"aws4_request")
+    }
+
+    fn scope(&self, now: DateTime<Utc>) -> String {
+        format!("{}/{}/s3/aws4_request", now.format("%Y%m%d"), self.region)
+    }
+
+    fn sign(&self, now: DateTime<Utc>, canonical_request: &str) -> String {
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            now.format("%Y%m%dT%H%M%SZ"),
+            self.scope(now),
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let date = now.format("%Y%m%d").to_string();
+        hex::encode(hmac_sha256(&self.signing_key(&date), string_to_sign.as_bytes()))
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+        content_type: Option<&str>,
+    ) -> StorageResult<reqwest::Response> {
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let query = canonical_query(query);
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method, path, query, self.host, payload_hash, amz_date, payload_hash
+        );
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            self.access_key_id,
+            self.scope(now),
+            self.sign(now, &canonical_request)
+        );
+
+        let url = if query.is_empty() {
+            format!("{}{}", self.origin, path)
+        } else {
+            format!("{}{}?{}", self.origin, path, query)
+        };
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization);
+        if let Some(content_type) = content_type {
+            request = request.header("content-type", content_type);
+        }
+
+        Ok(request.body(body).send().await?)
+    }
+
+    async fn checked(response: reqwest::Response, action: &str) -> StorageResult<reqwest::Response> {
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        Err(format!("S3 {} failed with {}: {}", action, status, xml_value(&body, "Message").unwrap_or(&body)).into())
+    }
+}
+
+impl Storage for S3Storage {
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> BoxFuture<'a, StorageResult<()>> {
+        Box::pin(async move {
+            validate_key(key)?;
+            let response = self
+                .send(Method::PUT, &self.object_path(key), &[], data, Some(&content_type(key)))
+                .await?;
+            Self::checked(response, "upload").await?;
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StorageResult<Option<Vec<u8>>>> {
+        Box::pin(async move {
+            validate_key(key)?;
+            let response = self.send(Method::GET, &self.object_path(key), &[], Vec::new(), None).await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            let response = Self::checked(response, "download").await?;
+            Ok(Some(response.bytes().await?.to_vec()))
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StorageResult<()>> {
+        Box::pin(async move {
+            validate_key(key)?;
+            let response = self.send(Method::DELETE, &self.object_path(key), &[], Vec::new(), None).await?;
+            if response.status() != StatusCode::NOT_FOUND {
+                Self::checked(response, "delete").await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, StorageResult<Vec<StoredObject>>> {
+        Box::pin(async move {
+            let path = if self.bucket_path.is_empty() { "/" } else { self.bucket_path.as_str() };
+            let mut objects = Vec::new();
+            let mut continuation: Option<String> = None;
+
+            loop {
+                let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+                if let Some(token) = continuation.as_deref() {
+                    query.push(("continuation-token", token));
+                }
+
+                let response = self.send(Method::GET, path, &query, Vec::new(), None).await?;
+                let body = Self::checked(response, "list").await?.text().await?;
+
+                for entry in body.split("<Contents>").skip(1) {
+                    let Some(key) = xml_value(entry, "Key") else {
+                        continue;
+                    };
+                    objects.push(StoredObject {
+                        key: decode_entities(key),
+                        size: xml_value(entry, "Size").and_then(|size| size.parse().ok()).unwrap_or(0),
+                        last_modified: xml_value(entry, "LastModified")
+                            .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+                            .map(|date| date.with_timezone(&Utc)),
+                    });
+                }
+
+                match xml_value(&body, "NextContinuationToken") {
+                    Some(token) if xml_value(&body, "IsTruncated") == Some("true") => {
+                        continuation = Some(decode_entities(token));
+                    }
+                    _ => break,
+                }
+            }
+
+            Ok(objects)
+        })
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_url, key)
+    }
+
+    fn signed_url(&self, key: &str, expires_in: Duration) -> String {
+        self.presign(key, Utc::now(), expires_in)
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// SigV4 URI encoding: everything but unreserved characters, optionally keeping `/`
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            b'/' if !encode_slash => "/".to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn canonical_query(params: &[(&str, &str)]) -> String {
+    let mut encoded: Vec<(String, String)> = params
+        .iter()
+        .map(|(name, value)| (uri_encode(name, true), uri_encode(value, true)))
+        .collect();
+    encoded.sort();
+    encoded
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+// First `<tag>value</tag>` in an S3 XML response
+fn xml_value<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&format!("</{}>", tag))? + start;
+    Some(&xml[start..end])
+}
@@ -1,8 +1,18 @@
 use sqlx::PgPool;
 use uuid::Uuid;
-use anyhow::Result;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
 
+use crate::config::Config;
+use crate::error::AppError;
 use crate::models::user::{User, UserResponse, UserType};
+use crate::services::{image, notification::NotificationService, realtime::RealtimeHub, storage::Storage};
+
+// Storage key prefix of uploaded avatars
+pub const AVATAR_PREFIX: &str = "avatars";
+// Unreferenced avatars younger than this may still be mid-upload
+const AVATAR_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
 
 #[derive(Clone)]
 pub struct UserService {
@@ -14,7 +24,7 @@ impl UserService {
         Self { db }
     }
 
-    pub async fn get_user_by_id(&self, user_id: &Uuid) -> Result<Option<User>> {
+    pub async fn get_user_by_id(&self, user_id: &Uuid) -> Result<Option<User>, AppError> {
         let user = sqlx::query_as!(
             User,
             r#"
@@ -42,7 +52,7 @@ impl UserService {
         Ok(user)
     }
 
-    pub async fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
+    pub async fn get_user_by_username(&self, username: &str) -> Result<Option<User>, AppError> {
         let user = sqlx::query_as!(
             User,
             r#"
@@ -70,7 +80,7 @@ impl UserService {
         Ok(user)
     }
 
-    pub async fn update_avatar(&self, user_id: &Uuid, avatar_url: &str) -> Result<()> {
+    pub async fn update_avatar(&self, user_id: &Uuid, avatar_url: &str) -> Result<(), AppError> {
         let avatar_url = if avatar_url.is_empty() { None } else { Some(avatar_url) };
         
         sqlx::query!(
@@ -89,7 +99,7 @@ impl UserService {
         user_id: &Uuid,
         display_name: Option<&str>,
         bio: Option<&str>,
-    ) -> Result<()> {
+    ) -> Result<(), AppError> {
         sqlx::query!(
             r#"
             UPDATE users 
@@ -109,7 +119,7 @@ impl UserService {
         Ok(())
     }
 
-    pub async fn get_user_profile(&self, username: &str) -> Result<Option<UserResponse>> {
+    pub async fn get_user_profile(&self, config: &Config, username: &str) -> Result<Option<UserResponse>, AppError> {
         #[derive(sqlx::FromRow)]
         struct UserProfileRow {
             id: Uuid,
@@ -165,7 +175,7 @@ impl UserService {
             // Handle avatar URL - if it's a relative path, make it absolute
             let avatar_url = if let Some(url) = &user.avatar_url {
                 if url.starts_with("uploads/") {
-                    Some(format!("http://localhost:3001/{}", url))
+                    Some(format!("{}/{}", config.api_url, url))
                 } else {
                     Some(url.clone())
                 }
@@ -194,9 +204,14 @@ impl UserService {
         }
     }
 
-    pub async fn follow_user(&self, follower_id: &Uuid, following_id: &Uuid) -> Result<()> {
+    pub async fn follow_user(
+        &self,
+        realtime: &Arc<RealtimeHub>,
+        follower_id: &Uuid,
+        following_id: &Uuid,
+    ) -> Result<(), AppError> {
         // Insert follow relationship
-        sqlx::query!(
+        let inserted = sqlx::query!(
             r#"
             INSERT INTO user_follows (follower_id, following_id, created_at)
             VALUES ($1, $2, NOW())
@@ -223,10 +238,18 @@ impl UserService {
         .execute(&self.db)
         .await?;
 
+        // Let the followed user know (only for new follows)
+        if inserted.rows_affected() > 0 {
+            let notification_service = NotificationService::new(self.db.clone(), realtime.clone());
+            if let Err(e) = notification_service.notify_new_follower(*follower_id, *following_id).await {
+                tracing::warn!("Failed to create follower notification: {}", e);
+            }
+        }
+
         Ok(())
     }
 
-    pub async fn unfollow_user(&self, follower_id: &Uuid, following_id: &Uuid) -> Result<()> {
+    pub async fn unfollow_user(&self, follower_id: &Uuid, following_id: &Uuid) -> Result<(), AppError> {
         // Delete follow relationship
         let result = sqlx::query!(
             "DELETE FROM user_follows WHERE follower_id = $1 AND following_id = $2",
@@ -256,7 +279,7 @@ impl UserService {
         Ok(())
     }
 
-    pub async fn is_following(&self, follower_id: &Uuid, following_id: &Uuid) -> Result<bool> {
+    pub async fn is_following(&self, follower_id: &Uuid, following_id: &Uuid) -> Result<bool, AppError> {
         let exists = sqlx::query_scalar!(
             "SELECT EXISTS(SELECT 1 FROM user_follows WHERE follower_id = $1 AND following_id = $2)",
             follower_id,
@@ -272,9 +295,10 @@ impl UserService {
     // Based on: users with most followers, users with most articles, users followed by people you follow
     pub async fn get_user_recommendations(
         &self,
+        config: &Config,
         user_id: &Uuid,
         limit: Option<i64>,
-    ) -> Result<Vec<UserResponse>> {
+    ) -> Result<Vec<UserResponse>, AppError> {
         let limit = limit.unwrap_or(10).min(50) as i64;
 
         // Get users that:
@@ -322,7 +346,7 @@ impl UserService {
             // Handle avatar URL
             let avatar_url = if let Some(url) = &user.avatar_url {
                 if url.starts_with("uploads/") {
-                    Some(format!("http://localhost:3001/{}", url))
+                    Some(format!("{}/{}", config.api_url, url))
                 } else {
                     Some(url.clone())
                 }
@@ -350,4 +374,47 @@ impl UserService {
 
         Ok(recommendations)
     }
+
+    // Recompute the denormalized article and clap counters that triggers keep in sync
+    pub async fn recount_author_stats(&self) -> Result<u64, AppError> {
+        let result = sqlx::query!(
+            r#"
+            WITH stats AS (
+                SELECT
+                    u.id,
+                    (SELECT COUNT(*) FROM articles a WHERE a.author_id = u.id AND a.status = 'published')::int as articles,
+                    (SELECT COALESCE(SUM(c.clap_count), 0) FROM claps c JOIN articles a ON a.id = c.article_id
+                        WHERE a.author_id = u.id)::bigint as claps
+                FROM users u
+            )
+            UPDATE users u
+            SET articles_count = stats.articles, total_claps_received = stats.claps
+            FROM stats
+            WHERE u.id = stats.id
+                AND (u.articles_count <> stats.articles OR u.total_claps_received <> stats.claps)
+            "#
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Delete avatar files (all size variants) no user points at anymore
+    pub async fn cleanup_orphaned_avatars(&self, storage: &dyn Storage) -> Result<usize, AppError> {
+        let referenced: HashSet<String> = sqlx::query_scalar!("SELECT avatar_url FROM users WHERE avatar_url IS NOT NULL")
+            .fetch_all(&self.db)
+            .await?
+            .into_iter()
+            .flatten()
+            .filter_map(|url| storage.key_from_url(&url))
+            .filter_map(|key| {
+                key.strip_prefix(AVATAR_PREFIX)
+                    .and_then(|file_name| file_name.strip_prefix('/'))
+                    .map(|file_name| image::upload_id(file_name).to_string())
+            })
+            .collect();
+
+        Ok(image::remove_unreferenced(storage, AVATAR_PREFIX, &referenced, AVATAR_GRACE_PERIOD).await?)
+    }
 }